@@ -13,6 +13,9 @@ use crate::parser::FLOAT;
 #[cfg(not(feature = "no_index"))]
 use crate::engine::Array;
 
+#[cfg(not(feature = "no_index"))]
+use crate::{result::EvalAltResult, token::Position};
+
 #[cfg(not(feature = "no_object"))]
 use crate::engine::Map;
 
@@ -36,12 +39,15 @@ use crate::stdlib::sync::{RwLockReadGuard, RwLockWriteGuard};
 use crate::stdlib::collections::HashMap;
 
 #[cfg(not(feature = "no_index"))]
-use crate::stdlib::vec::Vec;
+use crate::stdlib::{format, iter::FromIterator, vec::Vec};
 
 #[cfg(not(feature = "no_std"))]
 #[cfg(not(target_arch = "wasm32"))]
 use crate::stdlib::time::Instant;
 
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
 #[cfg(not(feature = "no_std"))]
 #[cfg(target_arch = "wasm32")]
 use instant::Instant;
@@ -151,6 +157,8 @@ pub enum Union {
     Int(INT),
     #[cfg(not(feature = "no_float"))]
     Float(FLOAT),
+    #[cfg(feature = "decimal")]
+    Decimal(Box<Decimal>),
     #[cfg(not(feature = "no_index"))]
     Array(Box<Array>),
     #[cfg(not(feature = "no_object"))]
@@ -303,6 +311,8 @@ impl Dynamic {
             Union::Int(_) => TypeId::of::<INT>(),
             #[cfg(not(feature = "no_float"))]
             Union::Float(_) => TypeId::of::<FLOAT>(),
+            #[cfg(feature = "decimal")]
+            Union::Decimal(_) => TypeId::of::<Decimal>(),
             #[cfg(not(feature = "no_index"))]
             Union::Array(_) => TypeId::of::<Array>(),
             #[cfg(not(feature = "no_object"))]
@@ -337,6 +347,8 @@ impl Dynamic {
             Union::Int(_) => type_name::<INT>(),
             #[cfg(not(feature = "no_float"))]
             Union::Float(_) => type_name::<FLOAT>(),
+            #[cfg(feature = "decimal")]
+            Union::Decimal(_) => "decimal",
             #[cfg(not(feature = "no_index"))]
             Union::Array(_) => "array",
             #[cfg(not(feature = "no_object"))]
@@ -384,6 +396,10 @@ pub(crate) fn map_std_type_name(name: &str) -> &str {
         if name == type_name::<Instant>() {
             return "timestamp";
         }
+        #[cfg(feature = "decimal")]
+        if name == type_name::<Decimal>() {
+            return "decimal";
+        }
 
         name
     }
@@ -399,6 +415,8 @@ impl fmt::Display for Dynamic {
             Union::Int(value) => fmt::Display::fmt(value, f),
             #[cfg(not(feature = "no_float"))]
             Union::Float(value) => fmt::Display::fmt(value, f),
+            #[cfg(feature = "decimal")]
+            Union::Decimal(value) => fmt::Display::fmt(value, f),
             #[cfg(not(feature = "no_index"))]
             Union::Array(value) => fmt::Debug::fmt(value, f),
             #[cfg(not(feature = "no_object"))]
@@ -438,6 +456,8 @@ impl fmt::Debug for Dynamic {
             Union::Int(value) => fmt::Debug::fmt(value, f),
             #[cfg(not(feature = "no_float"))]
             Union::Float(value) => fmt::Debug::fmt(value, f),
+            #[cfg(feature = "decimal")]
+            Union::Decimal(value) => fmt::Display::fmt(value, f),
             #[cfg(not(feature = "no_index"))]
             Union::Array(value) => fmt::Debug::fmt(value, f),
             #[cfg(not(feature = "no_object"))]
@@ -477,6 +497,8 @@ impl Clone for Dynamic {
             Union::Int(value) => Self(Union::Int(value)),
             #[cfg(not(feature = "no_float"))]
             Union::Float(value) => Self(Union::Float(value)),
+            #[cfg(feature = "decimal")]
+            Union::Decimal(ref value) => Self(Union::Decimal(value.clone())),
             #[cfg(not(feature = "no_index"))]
             Union::Array(ref value) => Self(Union::Array(value.clone())),
             #[cfg(not(feature = "no_object"))]
@@ -616,6 +638,14 @@ impl Dynamic {
             }
         }
 
+        #[cfg(feature = "decimal")]
+        {
+            boxed = match unsafe_cast_box::<_, Decimal>(boxed) {
+                Ok(decimal) => return (*decimal).into(),
+                Err(val) => val,
+            }
+        }
+
         Self(Union::Variant(Box::new(boxed)))
     }
 
@@ -760,6 +790,14 @@ impl Dynamic {
             };
         }
 
+        #[cfg(feature = "decimal")]
+        if TypeId::of::<T>() == TypeId::of::<Decimal>() {
+            return match self.0 {
+                Union::Decimal(value) => unsafe_cast_box::<_, T>(value).ok().map(|v| *v),
+                _ => None,
+            };
+        }
+
         if TypeId::of::<T>() == TypeId::of::<()>() {
             return match self.0 {
                 Union::Unit(value) => unsafe_try_cast(value),
@@ -775,6 +813,51 @@ impl Dynamic {
         }
     }
 
+    /// Convert the `Dynamic` value into specific type.
+    ///
+    /// Casting to a `Dynamic` just returns as is, but if it contains a shared value,
+    /// it is cloned into a `Dynamic` with a normal value.
+    ///
+    /// Returns the original `Dynamic` value as `Err` if types mismatched, so that the value can
+    /// be cast to another type.
+    ///
+    /// # Panics or Deadlocks
+    ///
+    /// Under the `sync` feature, this call may deadlock, or [panic](https://doc.rust-lang.org/std/sync/struct.RwLock.html#panics-1).
+    /// Otherwise, this call panics if the data is currently borrowed for write.
+    ///
+    /// These normally shouldn't occur since most operations in Rhai is single-threaded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Dynamic;
+    ///
+    /// let x = Dynamic::from(42_u32);
+    ///
+    /// let x = x.try_cast_result::<String>().unwrap_err();
+    ///
+    /// assert_eq!(x.try_cast_result::<u32>().unwrap(), 42);
+    /// ```
+    #[inline(always)]
+    pub fn try_cast_result<T: Variant>(self) -> Result<T, Self> {
+        if TypeId::of::<T>() == TypeId::of::<Dynamic>() {
+            return Ok(self.try_cast::<T>().unwrap());
+        }
+
+        let mut target_type_id = TypeId::of::<T>();
+
+        if target_type_id == TypeId::of::<String>() {
+            target_type_id = TypeId::of::<ImmutableString>();
+        }
+
+        if self.type_id() == target_type_id {
+            Ok(self.try_cast::<T>().unwrap())
+        } else {
+            Err(self)
+        }
+    }
+
     /// Convert the `Dynamic` value into a specific type.
     ///
     /// Casting to a `Dynamic` just returns as is, but if it contains a shared value,
@@ -839,6 +922,47 @@ impl Dynamic {
         }
     }
 
+    /// Clone the `Dynamic` deeply, replacing all shared values within by independent copies.
+    ///
+    /// Cloning a `Dynamic` normally _shares_ (instead of copies) shared values, such as variables
+    /// captured by closures, meaning that the clone and the original refer to the same data.
+    ///
+    /// Mutating a deep clone never affects the original value.  Shared values nested inside
+    /// arrays, object maps and curried arguments of function pointers are also copied.
+    ///
+    /// # Panics or Deadlocks When Value is Shared
+    ///
+    /// Under the `sync` feature, this call may deadlock, or [panic](https://doc.rust-lang.org/std/sync/struct.RwLock.html#panics-1).
+    /// Otherwise, this call panics if the data is currently borrowed for write.
+    pub fn deep_clone(&self) -> Self {
+        match &self.0 {
+            Union::Str(value) => Self(Union::Str(value.to_string().into())),
+            #[cfg(not(feature = "no_index"))]
+            Union::Array(value) => Self(Union::Array(Box::new(
+                value.iter().map(Self::deep_clone).collect(),
+            ))),
+            #[cfg(not(feature = "no_object"))]
+            Union::Map(value) => Self(Union::Map(Box::new(
+                value
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.deep_clone()))
+                    .collect(),
+            ))),
+            Union::FnPtr(value) => Self(Union::FnPtr(Box::new(FnPtr::new_unchecked(
+                value.fn_name(),
+                value.curry().iter().map(Self::deep_clone).collect(),
+            )))),
+            #[cfg(not(feature = "no_closure"))]
+            Union::Shared(cell) => {
+                #[cfg(not(feature = "sync"))]
+                return cell.borrow().deep_clone();
+                #[cfg(feature = "sync")]
+                return cell.read().unwrap().deep_clone();
+            }
+            _ => self.clone(),
+        }
+    }
+
     /// Flatten the `Dynamic`.
     ///
     /// If the `Dynamic` is not a shared value, it returns itself.
@@ -1027,6 +1151,13 @@ impl Dynamic {
                 _ => None,
             };
         }
+        #[cfg(feature = "decimal")]
+        if TypeId::of::<T>() == TypeId::of::<Decimal>() {
+            return match &self.0 {
+                Union::Decimal(value) => <dyn Any>::downcast_ref::<T>(value.as_ref()),
+                _ => None,
+            };
+        }
         if TypeId::of::<T>() == TypeId::of::<()>() {
             return match &self.0 {
                 Union::Unit(value) => <dyn Any>::downcast_ref::<T>(value),
@@ -1111,6 +1242,13 @@ impl Dynamic {
                 _ => None,
             };
         }
+        #[cfg(feature = "decimal")]
+        if TypeId::of::<T>() == TypeId::of::<Decimal>() {
+            return match &mut self.0 {
+                Union::Decimal(value) => <dyn Any>::downcast_mut::<T>(value.as_mut()),
+                _ => None,
+            };
+        }
         if TypeId::of::<T>() == TypeId::of::<()>() {
             return match &mut self.0 {
                 Union::Unit(value) => <dyn Any>::downcast_mut::<T>(value),
@@ -1154,6 +1292,21 @@ impl Dynamic {
         }
     }
 
+    /// Cast the `Dynamic` as a `Decimal` and return it.
+    /// Returns the name of the actual type if the cast fails.
+    ///
+    /// Exported under the `decimal` feature only.
+    #[cfg(feature = "decimal")]
+    #[inline(always)]
+    pub fn as_decimal(&self) -> Result<Decimal, &'static str> {
+        match &self.0 {
+            Union::Decimal(n) => Ok(**n),
+            #[cfg(not(feature = "no_closure"))]
+            Union::Shared(_) => self.read_lock().map(|v| *v).ok_or_else(|| self.type_name()),
+            _ => Err(self.type_name()),
+        }
+    }
+
     /// Cast the `Dynamic` as a `bool` and return it.
     /// Returns the name of the actual type if the cast fails.
     #[inline(always)]
@@ -1231,6 +1384,54 @@ impl Dynamic {
             _ => Err(self.type_name()),
         }
     }
+
+    /// Convert the `Dynamic` holding an `Array` into a `Vec<T>`, casting each element into `T`.
+    ///
+    /// Returns an error if the `Dynamic` is not an `Array`, or naming the index of the first
+    /// element that is not of type `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Dynamic;
+    ///
+    /// let x: Dynamic = vec![1_u8, 2, 3].into();
+    ///
+    /// assert_eq!(x.into_typed_array::<u8>().unwrap(), vec![1, 2, 3]);
+    ///
+    /// let x: Dynamic = vec![Dynamic::from(1_u8), Dynamic::from(true)].into();
+    ///
+    /// assert!(x.into_typed_array::<u8>().is_err());
+    /// ```
+    #[cfg(not(feature = "no_index"))]
+    pub fn into_typed_array<T: Variant + Clone>(self) -> Result<Vec<T>, Box<EvalAltResult>> {
+        let typ = self.type_name();
+
+        let array = self.try_cast::<Array>().ok_or_else(|| {
+            EvalAltResult::ErrorMismatchDataType(
+                map_std_type_name(typ).into(),
+                "array".into(),
+                Position::none(),
+            )
+        })?;
+
+        array
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let typ = item.type_name();
+
+                item.try_cast::<T>().ok_or_else(|| {
+                    EvalAltResult::ErrorMismatchDataType(
+                        format!("array element {} is {}", index, map_std_type_name(typ)),
+                        map_std_type_name(type_name::<T>()).into(),
+                        Position::none(),
+                    )
+                    .into()
+                })
+            })
+            .collect()
+    }
 }
 
 impl From<()> for Dynamic {
@@ -1280,6 +1481,15 @@ impl<T: Variant + Clone> From<Vec<T>> for Dynamic {
     }
 }
 #[cfg(not(feature = "no_index"))]
+impl<T: Into<Dynamic>> FromIterator<T> for Dynamic {
+    #[inline(always)]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(Union::Array(Box::new(
+            iter.into_iter().map(Into::into).collect(),
+        )))
+    }
+}
+#[cfg(not(feature = "no_index"))]
 impl<T: Variant + Clone> From<&[T]> for Dynamic {
     #[inline(always)]
     fn from(value: &[T]) -> Self {
@@ -1319,3 +1529,10 @@ impl From<Instant> for Dynamic {
         Self(Union::TimeStamp(Box::new(value)))
     }
 }
+#[cfg(feature = "decimal")]
+impl From<Decimal> for Dynamic {
+    #[inline(always)]
+    fn from(value: Decimal) -> Self {
+        Self(Union::Decimal(Box::new(value)))
+    }
+}
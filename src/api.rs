@@ -1,9 +1,11 @@
 //! Module that defines the extern API of `Engine`.
 
 use crate::any::{Dynamic, Variant};
+use crate::calc_fn_hash;
 use crate::engine::{Engine, EvalContext, Imports, State};
 use crate::error::ParseError;
-use crate::fn_native::{FnCallArgs, NativeCallContext, SendSync};
+use crate::fn_native::{FnCallArgs, NativeCallContext, SendSync, Shared};
+use crate::fn_register::RegisterFn;
 use crate::optimize::OptimizationLevel;
 use crate::parser::AST;
 use crate::result::EvalAltResult;
@@ -18,26 +20,45 @@ use crate::{
 
 #[cfg(not(feature = "no_object"))]
 use crate::{
-    engine::{make_getter, make_setter, Map},
-    error::ParseErrorType,
+    engine::{make_getter, make_setter, Map, FN_GET_FALLBACK},
+    error::{LexError, ParseErrorType},
     token::Token,
 };
 
 #[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
-use crate::fn_register::{RegisterFn, RegisterResultFn};
+use crate::fn_register::RegisterResultFn;
 
 #[cfg(not(feature = "no_function"))]
 use crate::{fn_args::FuncArgs, fn_call::ensure_no_data_race, module::Module, StaticVec};
 
+#[cfg(not(feature = "no_function"))]
+use crate::utils::calc_source_hash;
+
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_closure"))]
+use crate::engine::bind_global_vars;
+
 #[cfg(not(feature = "no_optimize"))]
 use crate::optimize::optimize_into_ast;
 
+#[cfg(feature = "async")]
+use crate::fn_native::FnFuture;
+
 use crate::stdlib::{
     any::{type_name, TypeId},
     boxed::Box,
+    hash::{Hash, Hasher},
+    iter::empty,
     string::String,
+    vec::Vec,
 };
 
+#[cfg(not(feature = "no_std"))]
+use crate::stdlib::collections::hash_map::DefaultHasher;
+
+#[cfg(feature = "no_std")]
+use ahash::AHasher;
+
 #[cfg(not(feature = "no_optimize"))]
 use crate::stdlib::mem;
 
@@ -45,6 +66,14 @@ use crate::stdlib::mem;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::stdlib::{fs::File, io::prelude::*, path::PathBuf};
 
+#[cfg(not(feature = "no_std"))]
+#[cfg(not(target_arch = "wasm32"))]
+use crate::stdlib::time::{Duration, Instant};
+
+#[cfg(not(feature = "no_std"))]
+#[cfg(target_arch = "wasm32")]
+use instant::{Duration, Instant};
+
 /// Engine public API
 impl Engine {
     /// Register a function of the `Engine`.
@@ -73,6 +102,99 @@ impl Engine {
             + 'static,
     ) -> &mut Self {
         self.global_module.set_raw_fn(name, arg_types, func);
+        self.compile_cache_generation += 1;
+        self
+    }
+
+    /// Register a native function that has read access to the calling `Scope`,
+    /// via `NativeCallContext::scope`.
+    ///
+    /// Arguments are passed by value in a mutable array of `Dynamic`, and are not type-checked.
+    /// The function is only matched by name and number of arguments, and only when called
+    /// in normal function-call style (i.e. not as a method call nor as an operator).
+    /// Script-defined functions with the same name and number of parameters take precedence.
+    ///
+    /// Only functions registered via this method can see the variables of the calling `Scope`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Dynamic, Engine};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// // Look up a variable by name in the calling scope.
+    /// engine.register_scope_fn("get_var", 1, |context, args| {
+    ///     let name = args[0].as_str().map_err(|typ| typ.to_string())?;
+    ///     Ok(context.scope().and_then(|s| s.get_value::<Dynamic>(name)).unwrap_or_default())
+    /// });
+    ///
+    /// assert_eq!(engine.eval::<i64>(r#"let x = 42; get_var("x")"#)?, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn register_scope_fn<T: Variant + Clone>(
+        &mut self,
+        name: &str,
+        num_params: usize,
+        func: impl Fn(NativeCallContext, &mut [Dynamic]) -> Result<T, Box<EvalAltResult>>
+            + SendSync
+            + 'static,
+    ) -> &mut Self {
+        let hash_fn = calc_fn_hash(empty(), name, num_params, empty());
+        let func = move |context: NativeCallContext, args: &mut [Dynamic]| {
+            func(context, args).map(Dynamic::from)
+        };
+        self.scope_functions.insert(hash_fn, Box::new(func));
+        self.compile_cache_generation += 1;
+        self
+    }
+
+    /// Register a function taking two arguments of different types, together with the same
+    /// function taking the arguments in the reversed order.
+    ///
+    /// This is useful for registering operators that are commutative, such as `+` or `*` between
+    /// a custom type and a primary type, so that both `x + 1` and `1 + x` work.
+    ///
+    /// If both arguments are of the same type, only one version is registered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, ImmutableString};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// // Register '*' between a string and an integer, in both orders.
+    /// engine.register_commutative_fn("*", |s: ImmutableString, n: i64| s.repeat(n as usize));
+    ///
+    /// assert_eq!(engine.eval::<String>(r#""ab" * 2"#)?, "abab");
+    /// assert_eq!(engine.eval::<String>(r#"2 * "ab""#)?, "abab");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn register_commutative_fn<A, B, R>(
+        &mut self,
+        name: &str,
+        func: impl Fn(A, B) -> R + SendSync + 'static,
+    ) -> &mut Self
+    where
+        A: Variant + Clone,
+        B: Variant + Clone,
+        R: Variant + Clone,
+    {
+        let func = Shared::new(func);
+
+        if TypeId::of::<A>() != TypeId::of::<B>() {
+            let func = func.clone();
+            self.register_fn(name, move |b: B, a: A| func(a, b));
+        }
+
+        self.register_fn(name, move |a: A, b: B| func(a, b));
         self
     }
 
@@ -278,6 +400,61 @@ impl Engine {
         self.register_result_fn(&make_getter(name), callback)
     }
 
+    /// Register a fallback getter function for a registered type with the `Engine`.
+    ///
+    /// The fallback getter is called with the name of the property whenever a property of the
+    /// type is accessed via the dot operator (e.g. `obj.prop`) and no specific getter has been
+    /// registered for that property.  This is useful for types with properties that are only
+    /// known at runtime (e.g. dynamic records).
+    ///
+    /// The function signature must start with `&mut self` and not `&self`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::{Engine, Dynamic, EvalAltResult, RegisterFn};
+    ///
+    /// #[derive(Clone)]
+    /// struct Record {
+    ///     fields: Vec<(String, i64)>
+    /// }
+    ///
+    /// impl Record {
+    ///     fn new() -> Self { Self { fields: vec![("foo".into(), 1), ("bar".into(), 2)] } }
+    ///     // Even a getter must start with `&mut self` and not `&self`.
+    ///     fn get_field(&mut self, name: &str) -> Result<Dynamic, Box<EvalAltResult>> {
+    ///         match self.fields.iter().find(|(n, _)| n == name) {
+    ///             Some((_, value)) => Ok((*value).into()),
+    ///             None => Err(format!("no field named '{}'", name).into())
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// let mut engine = Engine::new();
+    ///
+    /// // Register the custom type.
+    /// engine.register_type::<Record>();
+    ///
+    /// engine.register_fn("new_record", Record::new);
+    ///
+    /// // Register a fallback getter for all properties.
+    /// engine.register_type_getter_fallback(Record::get_field);
+    ///
+    /// assert_eq!(engine.eval::<i64>("let r = new_record(); r.foo + r.bar")?, 3);
+    /// assert!(engine.eval::<i64>("let r = new_record(); r.baz").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_object"))]
+    #[inline(always)]
+    pub fn register_type_getter_fallback<T: Variant + Clone>(
+        &mut self,
+        callback: impl Fn(&mut T, &str) -> Result<Dynamic, Box<EvalAltResult>> + SendSync + 'static,
+    ) -> &mut Self {
+        self.register_result_fn(FN_GET_FALLBACK, callback)
+    }
+
     /// Register a setter function for a member of a registered type with the `Engine`.
     ///
     /// # Example
@@ -779,6 +956,8 @@ impl Engine {
 
     /// Compile a string into an `AST`, which can be used later for evaluation.
     ///
+    /// Use `compile_with_scope` to fold constants defined in a `Scope` into the `AST`.
+    ///
     /// # Example
     ///
     /// ```
@@ -907,8 +1086,108 @@ impl Engine {
         scripts: &[&str],
         optimization_level: OptimizationLevel,
     ) -> Result<AST, ParseError> {
+        // Constants in a non-empty scope may be folded into the AST, so skip the cache
+        let key = if scope.is_empty() && self.compile_cache_enabled() {
+            let key = self.compile_cache_key(scripts, optimization_level);
+
+            #[cfg(not(feature = "sync"))]
+            let cached = self.compile_cache.borrow_mut().get(key, scripts);
+            #[cfg(feature = "sync")]
+            let cached = self.compile_cache.write().unwrap().get(key, scripts);
+
+            if let Some(ast) = cached {
+                return Ok(ast);
+            }
+
+            Some(key)
+        } else {
+            None
+        };
+
         let stream = self.lex(scripts, None);
-        self.parse(&mut stream.peekable(), scope, optimization_level)
+        let mut ast = self.parse(
+            &mut stream.peekable(),
+            #[cfg(not(feature = "no_function"))]
+            calc_source_hash(scripts),
+            scope,
+            optimization_level,
+        )?;
+
+        if self.retain_source {
+            ast.set_source_text(&scripts.concat());
+        }
+
+        if let Some(key) = key {
+            #[cfg(not(feature = "sync"))]
+            self.compile_cache
+                .borrow_mut()
+                .insert(key, scripts, ast.clone());
+            #[cfg(feature = "sync")]
+            self.compile_cache
+                .write()
+                .unwrap()
+                .insert(key, scripts, ast.clone());
+        }
+
+        Ok(ast)
+    }
+
+    /// Is the compile cache enabled?
+    #[inline(always)]
+    fn compile_cache_enabled(&self) -> bool {
+        #[cfg(not(feature = "sync"))]
+        return self.compile_cache.borrow().is_enabled();
+        #[cfg(feature = "sync")]
+        return self.compile_cache.read().unwrap().is_enabled();
+    }
+
+    /// Calculate the compile cache key of a list of scripts.
+    ///
+    /// The key includes all `Engine` settings that affect how scripts are parsed and optimized.
+    fn compile_cache_key(&self, scripts: &[&str], optimization_level: OptimizationLevel) -> u64 {
+        fn new_hasher() -> impl Hasher {
+            #[cfg(feature = "no_std")]
+            let s: AHasher = Default::default();
+            #[cfg(not(feature = "no_std"))]
+            let s = DefaultHasher::new();
+            s
+        }
+
+        // Entries in hash maps and sets are not ordered, so combine their hashes with XOR
+        fn hash_unordered<T: Hash>(items: impl Iterator<Item = T>, s: &mut impl Hasher) {
+            items
+                .map(|item| {
+                    let mut s = new_hasher();
+                    item.hash(&mut s);
+                    s.finish()
+                })
+                .fold(0_u64, |acc, h| acc ^ h)
+                .hash(s)
+        }
+
+        let mut s = new_hasher();
+
+        scripts.iter().for_each(|script| script.hash(&mut s));
+
+        optimization_level.hash(&mut s);
+        self.strict_conditions.hash(&mut s);
+        self.allow_while_true.hash(&mut s);
+        self.strict_semicolons.hash(&mut s);
+        self.strict_function_calls.hash(&mut s);
+        self.allow_nested_functions.hash(&mut s);
+        self.allow_comparison_chains.hash(&mut s);
+        self.allow_statement_expression.hash(&mut s);
+        self.retain_source.hash(&mut s);
+        #[cfg(not(feature = "unchecked"))]
+        self.limits_set.hash(&mut s);
+
+        hash_unordered(self.disabled_symbols.iter(), &mut s);
+        hash_unordered(self.custom_keywords.iter(), &mut s);
+        hash_unordered(self.precedence_overrides.iter(), &mut s);
+        // Registered functions, packages and custom syntax cannot be hashed, so track changes instead
+        self.compile_cache_generation.hash(&mut s);
+
+        s.finish()
     }
 
     /// Read the contents of a file into a string.
@@ -1008,17 +1287,11 @@ impl Engine {
     ///
     /// The JSON string must be an object hash.  It cannot be a simple JavaScript primitive.
     ///
-    /// Set `has_null` to `true` in order to map `null` values to `()`.
-    /// Setting it to `false` will cause a _variable not found_ error during parsing.
-    ///
-    /// # JSON With Sub-Objects
+    /// Nested JSON objects and arrays are parsed into `Map` and `Array` values respectively,
+    /// while JSON `null` values map to `()`.
     ///
-    /// This method assumes no sub-objects in the JSON string.  That is because the syntax
-    /// of a JSON sub-object (or object hash), `{ .. }`, is different from Rhai's syntax, `#{ .. }`.
-    /// Parsing a JSON string with sub-objects will cause a syntax error.
-    ///
-    /// If it is certain that the character `{` never appears in any text string within the JSON object,
-    /// then globally replace `{` with `#{` before calling this method.
+    /// Set `has_comments` to `true` to allow `//` and `/* ... */` comments within the JSON string.
+    /// Setting it to `false` will cause any comment to be reported as a parse error.
     ///
     /// # Example
     ///
@@ -1028,9 +1301,14 @@ impl Engine {
     ///
     /// let engine = Engine::new();
     ///
-    /// let map = engine.parse_json(
-    ///     r#"{"a":123, "b":42, "c":{"x":false, "y":true}, "d":null}"#
-    ///         .replace("{", "#{").as_str(), true)?;
+    /// let map = engine.parse_json(r#"
+    ///     {
+    ///         "a": 123,
+    ///         "b": 42,    // comments are allowed
+    ///         "c": { "x": false, "y": true },
+    ///         "d": null
+    ///     }
+    /// "#, true)?;
     ///
     /// assert_eq!(map.len(), 4);
     /// assert_eq!(map["a"].as_int().unwrap(), 123);
@@ -1043,43 +1321,57 @@ impl Engine {
     /// # }
     /// ```
     #[cfg(not(feature = "no_object"))]
-    pub fn parse_json(&self, json: &str, has_null: bool) -> Result<Map, Box<EvalAltResult>> {
+    pub fn parse_json(&self, json: &str, has_comments: bool) -> Result<Map, Box<EvalAltResult>> {
         let mut scope = Default::default();
 
-        // Trims the JSON string and add a '#' in front
+        // The JSON string must start with an object hash
         let json_text = json.trim_start();
-        let scripts = if json_text.starts_with(Token::MapStart.syntax().as_ref()) {
-            [json_text, ""]
-        } else if json_text.starts_with(Token::LeftBrace.syntax().as_ref()) {
-            ["#", json_text]
-        } else {
+
+        if !json_text.starts_with(Token::MapStart.syntax().as_ref())
+            && !json_text.starts_with(Token::LeftBrace.syntax().as_ref())
+        {
             return Err(ParseErrorType::MissingToken(
                 Token::LeftBrace.syntax().into(),
                 "to start a JSON object hash".into(),
             )
             .into_err(Position::new(1, (json.len() - json_text.len() + 1) as u16))
             .into());
-        };
+        }
+
+        let scripts = [json];
 
         let stream = self.lex(
             &scripts,
-            if has_null {
-                Some(Box::new(|token| match token {
-                    // If `null` is present, make sure `null` is treated as a variable
-                    Token::Reserved(s) if s == "null" => Token::Identifier(s),
-                    _ => token,
-                }))
-            } else {
-                None
-            },
+            Some(Box::new(move |token| match token {
+                // A JSON object hash is a Rhai object map
+                Token::LeftBrace => Token::MapStart,
+                // Make sure `null` is treated as a variable
+                Token::Reserved(s) if s == "null" => Token::Identifier(s),
+                // Comments are only allowed when requested
+                token if token.is_comment() && !has_comments => Token::LexError(Box::new(
+                    LexError::ImproperSymbol("comments are not allowed in JSON".to_string()),
+                )),
+                _ => token,
+            })),
         );
-        let ast =
-            self.parse_global_expr(&mut stream.peekable(), &scope, OptimizationLevel::None)?;
+
+        // Comments must be kept in the token stream in order to be rejected
+        let stream = if has_comments {
+            stream
+        } else {
+            stream.keep_comments()
+        };
+
+        let ast = self.parse_global_expr(
+            &mut stream.peekable(),
+            #[cfg(not(feature = "no_function"))]
+            calc_source_hash(&scripts),
+            &scope,
+            OptimizationLevel::None,
+        )?;
 
         // Handle null - map to ()
-        if has_null {
-            scope.push_constant("null", ());
-        }
+        scope.push_constant("null", ());
 
         self.eval_ast_with_scope(&mut scope, &ast)
     }
@@ -1156,10 +1448,22 @@ impl Engine {
     ) -> Result<AST, ParseError> {
         let scripts = [script];
         let stream = self.lex(&scripts, None);
-        {
+        let mut ast = {
             let mut peekable = stream.peekable();
-            self.parse_global_expr(&mut peekable, scope, self.optimization_level)
+            self.parse_global_expr(
+                &mut peekable,
+                #[cfg(not(feature = "no_function"))]
+                calc_source_hash(&scripts),
+                scope,
+                self.optimization_level,
+            )?
+        };
+
+        if self.retain_source {
+            ast.set_source_text(script);
         }
+
+        Ok(ast)
     }
 
     /// Evaluate a script file.
@@ -1233,6 +1537,112 @@ impl Engine {
         self.eval_with_scope(&mut Default::default(), script)
     }
 
+    /// Evaluate a string, capturing the text output of all `print` and `debug` calls
+    /// (instead of sending them to the callbacks set via `on_print` and `on_debug`).
+    ///
+    /// Returns the result together with the captured `print` and `debug` outputs, in that order.
+    ///
+    /// Calls can be nested (e.g. from within a registered function), with each call capturing
+    /// only its own output.  Output is captured per `Engine`, so do not share an `Engine` among
+    /// multiple threads running `eval_captured` at the same time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let (result, prints, debugs) =
+    ///     engine.eval_captured::<i64>(r#"print("hello"); debug("world"); 42"#)?;
+    ///
+    /// assert_eq!(result, 42);
+    /// assert_eq!(prints, ["hello"]);
+    /// assert_eq!(debugs, [r#""world""#]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn eval_captured<T: Variant + Clone>(
+        &self,
+        script: &str,
+    ) -> Result<(T, Vec<String>, Vec<String>), Box<EvalAltResult>> {
+        #[cfg(not(feature = "sync"))]
+        self.captured_output.borrow_mut().push(Default::default());
+        #[cfg(feature = "sync")]
+        self.captured_output
+            .write()
+            .unwrap()
+            .push(Default::default());
+
+        let result = self.eval::<T>(script);
+
+        #[cfg(not(feature = "sync"))]
+        let (prints, debugs) = self.captured_output.borrow_mut().pop().unwrap();
+        #[cfg(feature = "sync")]
+        let (prints, debugs) = self.captured_output.write().unwrap().pop().unwrap();
+
+        result.map(|value| (value, prints, debugs))
+    }
+
+    /// Evaluate a string, terminating the script with `EvalAltResult::ErrorTerminated`
+    /// if it runs for longer than `timeout`.
+    ///
+    /// The deadline is checked at every operation, in addition to the progress callback
+    /// (if any) registered via `on_progress`, which is still called and left unchanged.
+    ///
+    /// Not available under `no_std`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use std::time::Duration;
+    /// use rhai::{Engine, EvalAltResult};
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// assert_eq!(engine.eval_with_timeout::<i64>("40 + 2", Duration::from_secs(5))?, 42);
+    ///
+    /// let result = engine.eval_with_timeout::<()>("loop {}", Duration::from_millis(10));
+    /// assert!(matches!(*result.unwrap_err(), EvalAltResult::ErrorTerminated(_)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_std"))]
+    pub fn eval_with_timeout<T: Variant + Clone>(
+        &self,
+        script: &str,
+        timeout: Duration,
+    ) -> Result<T, Box<EvalAltResult>> {
+        let mut scope = Default::default();
+        let ast = self.compile_with_scope_and_optimization_level(
+            &scope,
+            &[script],
+            self.optimization_level,
+        )?;
+
+        let state = State {
+            deadline: Some(Instant::now() + timeout),
+            ..Default::default()
+        };
+        let mut mods = Default::default();
+
+        let (result, _) =
+            self.eval_ast_with_state(&mut scope, &mut mods, state, &ast, &[ast.lib()])?;
+
+        let typ = self.map_type_name(result.type_name());
+
+        result.try_cast::<T>().ok_or_else(|| {
+            EvalAltResult::ErrorMismatchOutputType(
+                self.map_type_name(type_name::<T>()).into(),
+                typ.into(),
+                Position::none(),
+            )
+            .into()
+        })
+    }
+
     /// Evaluate a string with own scope.
     ///
     /// # Example
@@ -1319,11 +1729,65 @@ impl Engine {
         let stream = self.lex(&scripts, None);
 
         // No need to optimize a lone expression
-        let ast = self.parse_global_expr(&mut stream.peekable(), scope, OptimizationLevel::None)?;
+        let ast = self.parse_global_expr(
+            &mut stream.peekable(),
+            #[cfg(not(feature = "no_function"))]
+            calc_source_hash(&scripts),
+            scope,
+            OptimizationLevel::None,
+        )?;
 
         self.eval_ast_with_scope(scope, &ast)
     }
 
+    /// Evaluate a string containing a single statement with own scope.
+    ///
+    /// Variables and constants declared by the statement are kept in the scope, so this can be used
+    /// to execute statements incrementally (e.g. in a REPL).  Functions defined by the statement
+    /// and modules imported by it are not retained.
+    ///
+    /// A parse error is returned if the string contains more than one statement.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, Scope};
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let mut scope = Scope::new();
+    ///
+    /// engine.eval_stmt_with_scope(&mut scope, "let x = 40;")?;
+    /// engine.eval_stmt_with_scope(&mut scope, "x += 1")?;
+    ///
+    /// assert_eq!(engine.eval_stmt_with_scope(&mut scope, "x + 1")?.cast::<i64>(), 42);
+    ///
+    /// assert!(engine.eval_stmt_with_scope(&mut scope, "x += 1; x").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn eval_stmt_with_scope(
+        &self,
+        scope: &mut Scope,
+        stmt_source: &str,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let scripts = [stmt_source];
+        let stream = self.lex(&scripts, None);
+        let ast = self.parse_global_stmt(
+            &mut stream.peekable(),
+            #[cfg(not(feature = "no_function"))]
+            calc_source_hash(&scripts),
+            scope,
+            self.optimization_level,
+        )?;
+
+        let mut mods = Default::default();
+        let (result, _) = self.eval_ast_with_scope_raw(scope, &mut mods, &ast)?;
+        Ok(result)
+    }
+
     /// Evaluate an `AST`.
     ///
     /// # Example
@@ -1397,6 +1861,69 @@ impl Engine {
         });
     }
 
+    /// Evaluate the statements of an `AST` with own scope, resolving function calls against the
+    /// script-defined functions of another `AST` (in addition to its own functions).
+    ///
+    /// This avoids merging a large library of functions into every `AST` that uses it.
+    /// Functions defined in `statements_ast` take precedence over those in `functions_ast`.
+    /// The statements of `functions_ast` are not evaluated.
+    ///
+    /// Not available under the `no_function` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # #[cfg(not(feature = "no_function"))]
+    /// # {
+    /// use rhai::{Engine, Scope};
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// // Compile a library of functions once
+    /// let lib = engine.compile("fn add(x, y) { x + y }")?;
+    ///
+    /// // Compile a script that uses the library
+    /// let ast = engine.compile("add(x, 2)")?;
+    ///
+    /// let mut scope = Scope::new();
+    /// scope.push("x", 40_i64);
+    ///
+    /// assert_eq!(engine.eval_ast_with_functions::<i64>(&ast, &lib, &mut scope)?, 42);
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_function"))]
+    #[inline]
+    pub fn eval_ast_with_functions<T: Variant + Clone>(
+        &self,
+        statements_ast: &AST,
+        functions_ast: &AST,
+        scope: &mut Scope,
+    ) -> Result<T, Box<EvalAltResult>> {
+        let mut mods = Default::default();
+
+        let (result, _) = self.eval_ast_with_state(
+            scope,
+            &mut mods,
+            Default::default(),
+            statements_ast,
+            &[statements_ast.lib(), functions_ast.lib()],
+        )?;
+
+        let typ = self.map_type_name(result.type_name());
+
+        return result.try_cast::<T>().ok_or_else(|| {
+            EvalAltResult::ErrorMismatchOutputType(
+                self.map_type_name(type_name::<T>()).into(),
+                typ.into(),
+                Position::none(),
+            )
+            .into()
+        });
+    }
+
     /// Evaluate an `AST` with own scope.
     #[inline(always)]
     pub(crate) fn eval_ast_with_scope_raw<'a>(
@@ -1405,7 +1932,7 @@ impl Engine {
         mods: &mut Imports,
         ast: &'a AST,
     ) -> Result<(Dynamic, u64), Box<EvalAltResult>> {
-        self.eval_statements(scope, mods, ast.statements(), &[ast.lib()])
+        self.eval_ast_with_state(scope, mods, Default::default(), ast, &[ast.lib()])
     }
 
     /// Evaluate a file, but throw away the result and only return error (if any).
@@ -1447,7 +1974,13 @@ impl Engine {
     ) -> Result<(), Box<EvalAltResult>> {
         let scripts = [script];
         let stream = self.lex(&scripts, None);
-        let ast = self.parse(&mut stream.peekable(), scope, self.optimization_level)?;
+        let ast = self.parse(
+            &mut stream.peekable(),
+            #[cfg(not(feature = "no_function"))]
+            calc_source_hash(&scripts),
+            scope,
+            self.optimization_level,
+        )?;
         self.consume_ast_with_scope(scope, &ast)
     }
 
@@ -1467,7 +2000,7 @@ impl Engine {
         ast: &AST,
     ) -> Result<(), Box<EvalAltResult>> {
         let mut mods = Default::default();
-        self.eval_statements(scope, &mut mods, ast.statements(), &[ast.lib()])
+        self.eval_ast_with_state(scope, &mut mods, Default::default(), ast, &[ast.lib()])
             .map(|_| ())
     }
 
@@ -1515,11 +2048,78 @@ impl Engine {
         ast: &AST,
         name: &str,
         args: A,
+    ) -> Result<T, Box<EvalAltResult>> {
+        self.call_fn_internal(scope, ast, name, args, 0)
+    }
+
+    /// Call a script function defined in an `AST` with multiple arguments,
+    /// limiting the number of operations it may perform.
+    /// Arguments are passed as a tuple.
+    ///
+    /// If the function performs more than `max_operations` operations (0 for unlimited),
+    /// the call fails with `EvalAltResult::ErrorTooManyOperations`.
+    /// The maximum number of operations set on the `Engine` still applies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # #[cfg(not(feature = "no_function"))]
+    /// # {
+    /// use rhai::{Engine, EvalAltResult, Scope};
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let ast = engine.compile(r"
+    ///     fn spin() { loop {} }
+    /// ")?;
+    ///
+    /// let mut scope = Scope::new();
+    ///
+    /// let result = engine.call_fn_with_max_operations::<_, ()>(&mut scope, &ast, "spin", 1000, ());
+    ///
+    /// assert!(matches!(*result.expect_err("should error"), EvalAltResult::ErrorTooManyOperations(_)));
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_function"))]
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub fn call_fn_with_max_operations<A: FuncArgs, T: Variant + Clone>(
+        &self,
+        scope: &mut Scope,
+        ast: &AST,
+        name: &str,
+        max_operations: u64,
+        args: A,
+    ) -> Result<T, Box<EvalAltResult>> {
+        self.call_fn_internal(scope, ast, name, args, max_operations)
+    }
+
+    /// Call a script function defined in an `AST` with multiple arguments,
+    /// limiting the number of operations it may perform (0 for unlimited).
+    #[cfg(not(feature = "no_function"))]
+    #[inline]
+    fn call_fn_internal<A: FuncArgs, T: Variant + Clone>(
+        &self,
+        scope: &mut Scope,
+        ast: &AST,
+        name: &str,
+        args: A,
+        max_operations: u64,
     ) -> Result<T, Box<EvalAltResult>> {
         let mut arg_values = args.into_vec();
         let mut args: StaticVec<_> = arg_values.as_mut().iter_mut().collect();
 
-        let result = self.call_fn_dynamic_raw(scope, ast.lib(), name, &mut None, args.as_mut())?;
+        let result = self.call_fn_dynamic_raw(
+            scope,
+            ast.lib(),
+            name,
+            &mut None,
+            args.as_mut(),
+            max_operations,
+        )?;
 
         let typ = self.map_type_name(result.type_name());
 
@@ -1594,10 +2194,49 @@ impl Engine {
     ) -> Result<Dynamic, Box<EvalAltResult>> {
         let mut args: StaticVec<_> = arg_values.as_mut().iter_mut().collect();
 
-        self.call_fn_dynamic_raw(scope, lib.as_ref(), name, &mut this_ptr, args.as_mut())
+        self.call_fn_dynamic_raw(scope, lib.as_ref(), name, &mut this_ptr, args.as_mut(), 0)
     }
 
-    /// Call a script function defined in an `AST` with multiple `Dynamic` arguments.
+    /// Call a script function defined in an `AST` with multiple `Dynamic` arguments
+    /// and optionally a value for binding to the 'this' pointer, limiting the number
+    /// of operations it may perform.
+    ///
+    /// If the function performs more than `max_operations` operations (0 for unlimited),
+    /// the call fails with `EvalAltResult::ErrorTooManyOperations`.
+    /// The maximum number of operations set on the `Engine` still applies.
+    ///
+    /// ## WARNING
+    ///
+    /// All the arguments are _consumed_, meaning that they're replaced by `()`.
+    /// This is to avoid unnecessarily cloning the arguments.
+    /// Do not use the arguments after this call. If they are needed afterwards,
+    /// clone them _before_ calling this function.
+    #[cfg(not(feature = "no_function"))]
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub fn call_fn_dynamic_with_max_operations(
+        &self,
+        scope: &mut Scope,
+        lib: impl AsRef<Module>,
+        name: &str,
+        mut this_ptr: Option<&mut Dynamic>,
+        max_operations: u64,
+        mut arg_values: impl AsMut<[Dynamic]>,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let mut args: StaticVec<_> = arg_values.as_mut().iter_mut().collect();
+
+        self.call_fn_dynamic_raw(
+            scope,
+            lib.as_ref(),
+            name,
+            &mut this_ptr,
+            args.as_mut(),
+            max_operations,
+        )
+    }
+
+    /// Call a script function defined in an `AST` with multiple `Dynamic` arguments,
+    /// limiting the number of operations it may perform (0 for unlimited).
     ///
     /// ## WARNING
     ///
@@ -1614,14 +2253,19 @@ impl Engine {
         name: &str,
         this_ptr: &mut Option<&mut Dynamic>,
         args: &mut FnCallArgs,
+        max_operations: u64,
     ) -> Result<Dynamic, Box<EvalAltResult>> {
         let fn_def = lib
             .get_script_fn(name, args.len(), true)
             .ok_or_else(|| EvalAltResult::ErrorFunctionNotFound(name.into(), Position::none()))?;
 
         let mut state = State::new();
+        state.max_operations = max_operations;
         let mut mods = Default::default();
 
+        #[cfg(not(feature = "no_closure"))]
+        bind_global_vars(scope, &mut state, &[lib]);
+
         // Check for data race.
         if cfg!(not(feature = "no_closure")) {
             ensure_no_data_race(name, args, false)?;
@@ -1672,6 +2316,58 @@ impl Engine {
         optimize_into_ast(self, scope, stmt, lib, optimization_level)
     }
 
+    /// Specialize an `AST` by binding some variables to fixed values.
+    ///
+    /// All variables in `bindings` are treated as constants (whether or not they are pushed as
+    /// constants), and the optimizer folds them into a new `AST`.  Uses of the bound variables
+    /// are replaced by their values where possible, while all other variables and impure
+    /// function calls are left intact.  The original `AST` is untouched.
+    ///
+    /// The `Engine`'s optimization level is used, or `OptimizationLevel::Simple` if optimization
+    /// is turned off.
+    ///
+    /// Variables assigned to by the script should not be bound.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, Scope};
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let ast = engine.compile("if verbose { x * 2 } else { x }")?;
+    ///
+    /// let mut bindings = Scope::new();
+    /// bindings.push("verbose", false);
+    ///
+    /// let ast = engine.specialize_ast(&ast, &bindings);
+    ///
+    /// // The 'if' statement is gone, only 'x' remains
+    /// let mut scope = Scope::new();
+    /// scope.push("x", 42_i64);
+    ///
+    /// assert_eq!(engine.eval_ast_with_scope::<i64>(&mut scope, &ast)?, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_optimize"))]
+    #[inline]
+    pub fn specialize_ast(&self, ast: &AST, bindings: &Scope) -> AST {
+        let mut scope = Scope::new();
+
+        bindings.iter().for_each(|(name, _, value)| {
+            scope.push_constant_dynamic(name.to_string(), value);
+        });
+
+        let optimization_level = match self.optimization_level {
+            OptimizationLevel::None => OptimizationLevel::Simple,
+            level => level,
+        };
+
+        self.optimize_ast(&scope, ast.clone(), optimization_level)
+    }
+
     /// Provide a callback that will be invoked before each variable access.
     ///
     /// ## Return Value of Callback
@@ -1715,6 +2411,94 @@ impl Engine {
         self
     }
 
+    /// Provide a callback that will be invoked as a last resort for a binary operator
+    /// (built-in or custom) when no function is defined for the types of its operands.
+    ///
+    /// The callback receives the operator and both operands.
+    /// Built-in operator implementations and registered functions always take precedence.
+    ///
+    /// ## Return Value of Callback
+    ///
+    /// Return `Ok(None)` to continue with the normal function-not-found error.  
+    /// Return `Ok(Some(Dynamic))` as the result of the operation.
+    ///
+    /// ## Errors in Callback
+    ///
+    /// Return `Err(...)` if there is an error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// // Treat '+' between a number and a boolean as adding 0 or 1.
+    /// engine.on_undefined_operator(|op, x, y| {
+    ///     match (op, x.as_int(), y.as_bool()) {
+    ///         ("+", Ok(x), Ok(y)) => Ok(Some((x + y as i64).into())),
+    ///         _ => Ok(None)
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(engine.eval::<i64>("41 + true")?, 42);
+    /// assert!(engine.eval::<i64>("41 - true").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn on_undefined_operator(
+        &mut self,
+        callback: impl Fn(&str, &mut Dynamic, &mut Dynamic) -> Result<Option<Dynamic>, Box<EvalAltResult>>
+            + SendSync
+            + 'static,
+    ) -> &mut Self {
+        self.undefined_operator = Some(Box::new(callback));
+        self
+    }
+
+    /// Register a callback that is invoked on each entry into and exit from a script-defined function.
+    ///
+    /// The callback receives the name of the function, its number of parameters, and
+    /// `true` when the function is entered or `false` when it exits (whether successfully or not).
+    ///
+    /// Not available under the `no_function` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # use std::sync::RwLock;
+    /// # use std::sync::Arc;
+    /// use rhai::Engine;
+    ///
+    /// let result = Arc::new(RwLock::new(Vec::new()));
+    /// let logger = result.clone();
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.on_fn_call(move |name, num_params, enter| {
+    ///     let prefix = if enter { ">" } else { "<" };
+    ///     logger.write().unwrap().push(format!("{} {}/{}", prefix, name, num_params));
+    /// });
+    ///
+    /// engine.consume("fn foo(x) { bar() } fn bar() {} foo(42);")?;
+    ///
+    /// assert_eq!(*result.read().unwrap(), ["> foo/1", "> bar/0", "< bar/0", "< foo/1"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_function"))]
+    #[inline(always)]
+    pub fn on_fn_call(
+        &mut self,
+        callback: impl Fn(&str, usize, bool) + SendSync + 'static,
+    ) -> &mut Self {
+        self.on_fn_call = Some(Box::new(callback));
+        self
+    }
+
     /// Register a callback for script evaluation progress.
     ///
     /// # Example
@@ -1758,6 +2542,56 @@ impl Engine {
         self
     }
 
+    /// Provide an executor that blocks on the future of an async function until it completes.
+    /// Exported under the `async` feature only.
+    ///
+    /// Async functions registered via `register_async_fn` or `register_async_result_fn` are
+    /// called synchronously by scripts, with the `Engine` blocking on their futures via this
+    /// executor.  Usually this is the `block_on` method of an async runtime.
+    ///
+    /// Calling an async function without an executor raises an error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # use std::future::Future;
+    /// # use std::task::{Context, Poll, Waker};
+    /// # fn block_on<F: Future>(fut: F) -> F::Output {
+    /// #     let mut fut = Box::pin(fut);
+    /// #     let mut cx = Context::from_waker(Waker::noop());
+    /// #     loop {
+    /// #         if let Poll::Ready(value) = fut.as_mut().poll(&mut cx) {
+    /// #             return value;
+    /// #         }
+    /// #     }
+    /// # }
+    /// use rhai::{Engine, RegisterAsyncFn};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.register_async_fn("answer", || async { 42_i64 });
+    ///
+    /// // No executor yet
+    /// assert!(engine.eval::<i64>("answer()").is_err());
+    ///
+    /// // Block on futures with the executor of an async runtime
+    /// engine.on_block_on(|fut| block_on(fut));
+    ///
+    /// assert_eq!(engine.eval::<i64>("answer()")?, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "async")]
+    #[inline(always)]
+    pub fn on_block_on(
+        &mut self,
+        callback: impl Fn(FnFuture) -> Result<Dynamic, Box<EvalAltResult>> + SendSync + 'static,
+    ) -> &mut Self {
+        self.block_on = Some(Box::new(callback));
+        self
+    }
+
     /// Override default action of `print` (print to stdout using `println!`)
     ///
     /// # Example
@@ -1817,4 +2651,30 @@ impl Engine {
         self.debug = Box::new(callback);
         self
     }
+
+    /// Calculate the `u64` hash key of a function given its name and number of arguments,
+    /// without any module qualifiers.
+    ///
+    /// This is the same hash key that Rhai uses to look up script-defined functions,
+    /// so it can be used to key external caches compatibly, for example in a `HashMap`
+    /// built with `StraightHasherBuilder`.
+    ///
+    /// The hash key is stable for the same version of Rhai built with the same Rust compiler.
+    /// It should not be persisted across builds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Engine;
+    ///
+    /// let hash = Engine::calc_fn_hash("foo", 2);
+    ///
+    /// assert_eq!(hash, Engine::calc_fn_hash("foo", 2));
+    /// assert_ne!(hash, Engine::calc_fn_hash("foo", 1));
+    /// assert_ne!(hash, Engine::calc_fn_hash("bar", 2));
+    /// ```
+    #[inline(always)]
+    pub fn calc_fn_hash(name: &str, num_args: usize) -> u64 {
+        calc_fn_hash(empty(), name, num_args, empty())
+    }
 }
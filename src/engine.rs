@@ -2,7 +2,10 @@
 
 use crate::any::{map_std_type_name, Dynamic, Union, Variant};
 use crate::fn_call::run_builtin_op_assignment;
-use crate::fn_native::{Callback, FnPtr, OnVarCallback};
+use crate::fn_native::{
+    Callback, FnPtr, FnWithScope, Locked, OnFnCallCallback, OnOptimizeExprCallback,
+    OnParseTokenCallback, OnUndefinedOperatorCallback, OnVarCallback,
+};
 use crate::module::{Module, ModuleRef};
 use crate::optimize::OptimizationLevel;
 use crate::packages::{Package, PackagesCollection, StandardPackage};
@@ -12,15 +15,18 @@ use crate::result::EvalAltResult;
 use crate::scope::{EntryType as ScopeEntryType, Scope};
 use crate::syntax::CustomSyntax;
 use crate::token::Position;
+use crate::utils::CompileCache;
 use crate::{calc_fn_hash, StaticVec};
 
+#[cfg(feature = "async")]
+use crate::fn_native::OnBlockOnCallback;
+
 #[cfg(not(feature = "no_index"))]
 use crate::parser::INT;
 
 #[cfg(not(feature = "no_module"))]
 use crate::module::ModuleResolver;
 
-#[cfg(not(feature = "no_std"))]
 #[cfg(not(feature = "no_module"))]
 use crate::module::resolvers;
 
@@ -49,6 +55,14 @@ use crate::stdlib::any::TypeId;
 #[cfg(not(feature = "no_closure"))]
 use crate::stdlib::mem;
 
+#[cfg(not(feature = "no_std"))]
+#[cfg(not(target_arch = "wasm32"))]
+use crate::stdlib::time::Instant;
+
+#[cfg(not(feature = "no_std"))]
+#[cfg(target_arch = "wasm32")]
+use instant::Instant;
+
 /// Variable-sized array of `Dynamic` values.
 ///
 /// Not available under the `no_index` feature.
@@ -108,16 +122,22 @@ pub const KEYWORD_IS_DEF_VAR: &str = "is_def_var";
 pub const KEYWORD_IS_DEF_FN: &str = "is_def_fn";
 pub const KEYWORD_THIS: &str = "this";
 pub const FN_TO_STRING: &str = "to_string";
+pub const FN_CLONE: &str = "clone";
 #[cfg(not(feature = "no_object"))]
 pub const FN_GET: &str = "get$";
 #[cfg(not(feature = "no_object"))]
 pub const FN_SET: &str = "set$";
+#[cfg(not(feature = "no_object"))]
+pub const FN_GET_FALLBACK: &str = "get$fallback$";
 #[cfg(not(feature = "no_index"))]
 pub const FN_IDX_GET: &str = "index$get$";
 #[cfg(not(feature = "no_index"))]
 pub const FN_IDX_SET: &str = "index$set$";
 #[cfg(not(feature = "no_function"))]
 pub const FN_ANONYMOUS: &str = "anon$";
+#[cfg(not(feature = "no_function"))]
+pub const FN_LOCAL: &str = "local$";
+pub const FN_CONCAT: &str = "concat$";
 pub const MARKER_EXPR: &str = "$expr$";
 pub const MARKER_BLOCK: &str = "$block$";
 pub const MARKER_IDENT: &str = "$ident$";
@@ -372,7 +392,7 @@ impl<T: Into<Dynamic>> From<T> for Target<'_> {
 /// ## WARNING
 ///
 /// This type is volatile and may change.
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct State {
     /// Normally, access to variables are parsed with a relative offset into the scope to avoid a lookup.
     /// In some situation, e.g. after running an `eval` statement, subsequent offsets become mis-aligned.
@@ -383,8 +403,20 @@ pub struct State {
     pub scope_level: usize,
     /// Number of operations performed.
     pub operations: u64,
+    /// Maximum number of operations allowed for this evaluation (0 for unlimited).
+    /// The limit set on the `Engine` still applies.
+    pub max_operations: u64,
     /// Number of modules loaded.
     pub modules: usize,
+    /// Time after which this evaluation is terminated, if any.
+    /// Not available under `no_std`.
+    #[cfg(not(feature = "no_std"))]
+    pub deadline: Option<Instant>,
+    /// Global variables declared via `global` by script-defined functions, mapped to their
+    /// types and shared values (if bound).
+    #[cfg(not(feature = "no_function"))]
+    #[cfg(not(feature = "no_closure"))]
+    pub globals: HashMap<String, Option<(ScopeEntryType, Dynamic)>>,
 }
 
 impl State {
@@ -416,6 +448,8 @@ pub struct Limits {
     pub max_function_expr_depth: usize,
     /// Maximum number of operations allowed to run (0 = unlimited).
     pub max_operations: u64,
+    /// Maximum number of variables declared within a scope during parsing (0 = unlimited).
+    pub max_variables: usize,
     /// Maximum number of modules allowed to load.
     /// Not available under `no_module`.
     #[cfg(not(feature = "no_module"))]
@@ -430,6 +464,10 @@ pub struct Limits {
     /// Not available under `no_object`.
     #[cfg(not(feature = "no_object"))]
     pub max_map_size: usize,
+    /// Maximum levels of nesting of array/map literals during parsing (0 = unlimited).
+    /// Not available under both `no_index` and `no_object`.
+    #[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
+    pub max_data_depth: usize,
 }
 
 /// Context of a script evaluation process.
@@ -455,7 +493,7 @@ impl<'e, 'x, 'px, 'a, 's, 'm, 'pm, 't, 'pt> EvalContext<'e, 'x, 'px, 'a, 's, 'm,
     #[cfg(feature = "internals")]
     #[cfg(not(feature = "no_module"))]
     #[inline(always)]
-    pub fn imports(&self) -> &'a Imports {
+    pub fn imports(&self) -> &Imports {
         self.mods
     }
     /// Get an iterator over the namespaces containing definition of all script-defined functions.
@@ -509,6 +547,9 @@ pub struct Engine {
     /// A module resolution service.
     #[cfg(not(feature = "no_module"))]
     pub(crate) module_resolver: Option<Box<dyn ModuleResolver>>,
+    /// Modules registered directly into the Engine via `register_static_module`.
+    #[cfg(not(feature = "no_module"))]
+    pub(crate) static_modules: resolvers::StaticModuleResolver,
 
     /// A hashmap mapping type names to pretty-print names.
     pub(crate) type_names: HashMap<String, String>,
@@ -516,11 +557,24 @@ pub struct Engine {
     /// A hashset containing symbols to disable.
     pub(crate) disabled_symbols: HashSet<String>,
     /// A hashset containing custom keywords and precedence to recognize.
-    pub(crate) custom_keywords: HashMap<String, Option<u8>>,
+    pub(crate) custom_keywords: HashMap<String, Option<(u8, bool)>>,
+    /// A hashmap overriding the precedence of built-in operators.
+    pub(crate) precedence_overrides: HashMap<String, u8>,
     /// Custom syntax.
     pub(crate) custom_syntax: HashMap<ImmutableString, CustomSyntax>,
     /// Callback closure for resolving variable access.
     pub(crate) resolve_var: Option<OnVarCallback>,
+    /// Callback closure for tracing script function calls.
+    pub(crate) on_fn_call: Option<OnFnCallCallback>,
+    /// Callback closure for binary operators not defined for the types of their operands.
+    pub(crate) undefined_operator: Option<OnUndefinedOperatorCallback>,
+    /// Native functions with read access to the calling scope.
+    pub(crate) scope_functions: HashMap<u64, FnWithScope>,
+    /// Callback closure for remapping tokens during parsing.
+    pub(crate) token_mapper: Option<OnParseTokenCallback>,
+    /// Callback closure for blocking on the futures of async functions.
+    #[cfg(feature = "async")]
+    pub(crate) block_on: Option<OnBlockOnCallback>,
 
     /// Callback closure for implementing the `print` command.
     pub(crate) print: Callback<str, ()>,
@@ -528,9 +582,40 @@ pub struct Engine {
     pub(crate) debug: Callback<str, ()>,
     /// Callback closure for progress reporting.
     pub(crate) progress: Option<Callback<u64, bool>>,
+    /// Stack of `print` and `debug` outputs captured by `eval_captured`, innermost last.
+    pub(crate) captured_output: Locked<Vec<(Vec<String>, Vec<String>)>>,
 
     /// Optimize the AST after compilation.
     pub(crate) optimization_level: OptimizationLevel,
+    /// Custom optimization passes run on each expression after the built-in optimizations.
+    pub(crate) optimizer_passes: Vec<OnOptimizeExprCallback>,
+
+    /// Raise parse errors on `if` and `while` guards that are constant booleans.
+    pub(crate) strict_conditions: bool,
+    /// Exempt `while true` from strict conditions.
+    pub(crate) allow_while_true: bool,
+    /// Raise parse errors on empty statements (i.e. stray semicolons).
+    pub(crate) strict_semicolons: bool,
+    /// Raise parse errors on calls to known functions with the wrong number of arguments.
+    pub(crate) strict_function_calls: bool,
+    /// Retain the source text of scripts in compiled `AST`'s.
+    pub(crate) retain_source: bool,
+    /// Keep nested function call errors separate to capture the script call stack.
+    pub(crate) capture_call_stack: bool,
+    /// Fall back to script-defined free functions for method calls with no matching method.
+    pub(crate) fallback_to_free_fn: bool,
+    /// Allow function definitions nested inside blocks and other functions.
+    pub(crate) allow_nested_functions: bool,
+    /// Parse chains of comparison operators (e.g. `a < b < c`) as in mathematical notation.
+    pub(crate) allow_comparison_chains: bool,
+    /// Allow statement blocks as expressions in contexts where they are normally disallowed.
+    pub(crate) allow_statement_expression: bool,
+    /// Cache of compiled `AST`'s.
+    pub(crate) compile_cache: Locked<CompileCache>,
+    /// Generation of the `Engine` state that affects compilation but cannot be hashed
+    /// (e.g. registered functions, packages, custom syntax, optimizer passes).
+    /// Bumped on every change so that stale cached `AST`'s are never returned.
+    pub(crate) compile_cache_generation: u64,
 
     /// Max limits.
     #[cfg(not(feature = "unchecked"))]
@@ -576,6 +661,66 @@ pub fn is_anonymous_fn(fn_name: &str) -> bool {
     fn_name.starts_with(FN_ANONYMOUS)
 }
 
+/// Get the name of a function as written in the script, stripping the decoration
+/// added to functions defined inside a block or another function.
+#[cfg(not(feature = "no_function"))]
+pub fn unmangle_local_fn(fn_name: &str) -> &str {
+    if fn_name.starts_with(FN_LOCAL) {
+        let name = &fn_name[FN_LOCAL.len()..];
+        name.rfind('$').map_or(name, |i| &name[..i])
+    } else {
+        fn_name
+    }
+}
+
+/// Calculate the offset into a collection of `len` items from an index.
+/// A negative index counts from the end.
+///
+/// Returns `None` if a negative index is out of bounds.
+#[cfg(not(feature = "no_index"))]
+#[inline(always)]
+fn calc_index(len: usize, index: INT) -> Option<usize> {
+    if index >= 0 {
+        Some(index as usize)
+    } else {
+        index
+            .checked_abs()
+            .and_then(|n| len.checked_sub(n as usize))
+    }
+}
+
+/// Turn a variable in the global `Scope` into a shared value, returning its type and a shared
+/// reference to it.
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_closure"))]
+fn share_global_var(scope: &mut Scope, name: &str) -> Option<(ScopeEntryType, Dynamic)> {
+    scope.get_index(name).map(|(index, typ)| {
+        let (val, _) = scope.get_mut(index);
+
+        if !val.is_shared() {
+            // Replace the variable with a shared value.
+            *val = mem::take(val).into_shared();
+        }
+
+        (typ, val.clone())
+    })
+}
+
+/// Bind all variables in the global `Scope` that are declared via `global` within
+/// script-defined functions.
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_closure"))]
+pub fn bind_global_vars(scope: &mut Scope, state: &mut State, lib: &[&Module]) {
+    for (_, _, _, fn_def) in lib.iter().flat_map(|m| m.iter_script_fn()) {
+        for name in fn_def.globals.iter() {
+            if !state.globals.contains_key(name) {
+                let global = share_global_var(scope, name);
+                state.globals.insert(name.clone(), global);
+            }
+        }
+    }
+}
+
 /// Print/debug to stdout
 fn default_print(_s: &str) {
     #[cfg(not(feature = "no_std"))]
@@ -657,14 +802,23 @@ impl Engine {
             #[cfg(not(feature = "no_module"))]
             #[cfg(any(feature = "no_std", target_arch = "wasm32",))]
             module_resolver: None,
+            #[cfg(not(feature = "no_module"))]
+            static_modules: Default::default(),
 
             type_names: Default::default(),
             disabled_symbols: Default::default(),
+            precedence_overrides: Default::default(),
             custom_keywords: Default::default(),
             custom_syntax: Default::default(),
 
             // variable resolver
             resolve_var: None,
+            on_fn_call: None,
+            undefined_operator: None,
+            scope_functions: Default::default(),
+            token_mapper: None,
+            #[cfg(feature = "async")]
+            block_on: None,
 
             // default print/debug implementations
             print: Box::new(default_print),
@@ -672,6 +826,7 @@ impl Engine {
 
             // progress callback
             progress: None,
+            captured_output: Default::default(),
 
             // optimization level
             optimization_level: if cfg!(feature = "no_optimize") {
@@ -679,6 +834,20 @@ impl Engine {
             } else {
                 OptimizationLevel::Simple
             },
+            optimizer_passes: Default::default(),
+
+            strict_conditions: false,
+            strict_semicolons: false,
+            strict_function_calls: false,
+            allow_while_true: true,
+            retain_source: false,
+            capture_call_stack: false,
+            fallback_to_free_fn: false,
+            allow_nested_functions: false,
+            allow_comparison_chains: false,
+            allow_statement_expression: false,
+            compile_cache: Default::default(),
+            compile_cache_generation: 0,
 
             #[cfg(not(feature = "unchecked"))]
             limits_set: Limits {
@@ -687,6 +856,7 @@ impl Engine {
                 #[cfg(not(feature = "no_function"))]
                 max_function_expr_depth: MAX_FUNCTION_EXPR_DEPTH,
                 max_operations: 0,
+                max_variables: 0,
                 #[cfg(not(feature = "no_module"))]
                 max_modules: usize::MAX,
                 max_string_size: 0,
@@ -694,6 +864,8 @@ impl Engine {
                 max_array_size: 0,
                 #[cfg(not(feature = "no_object"))]
                 max_map_size: 0,
+                #[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
+                max_data_depth: 0,
             },
         };
 
@@ -714,23 +886,47 @@ impl Engine {
 
             #[cfg(not(feature = "no_module"))]
             module_resolver: None,
+            #[cfg(not(feature = "no_module"))]
+            static_modules: Default::default(),
 
             type_names: Default::default(),
             disabled_symbols: Default::default(),
+            precedence_overrides: Default::default(),
             custom_keywords: Default::default(),
             custom_syntax: Default::default(),
 
             resolve_var: None,
+            on_fn_call: None,
+            undefined_operator: None,
+            scope_functions: Default::default(),
+            token_mapper: None,
+            #[cfg(feature = "async")]
+            block_on: None,
 
             print: Box::new(|_| {}),
             debug: Box::new(|_| {}),
             progress: None,
+            captured_output: Default::default(),
 
             optimization_level: if cfg!(feature = "no_optimize") {
                 OptimizationLevel::None
             } else {
                 OptimizationLevel::Simple
             },
+            optimizer_passes: Default::default(),
+
+            strict_conditions: false,
+            strict_semicolons: false,
+            strict_function_calls: false,
+            allow_while_true: true,
+            retain_source: false,
+            capture_call_stack: false,
+            fallback_to_free_fn: false,
+            allow_nested_functions: false,
+            allow_comparison_chains: false,
+            allow_statement_expression: false,
+            compile_cache: Default::default(),
+            compile_cache_generation: 0,
 
             #[cfg(not(feature = "unchecked"))]
             limits_set: Limits {
@@ -739,6 +935,7 @@ impl Engine {
                 #[cfg(not(feature = "no_function"))]
                 max_function_expr_depth: MAX_FUNCTION_EXPR_DEPTH,
                 max_operations: 0,
+                max_variables: 0,
                 #[cfg(not(feature = "no_module"))]
                 max_modules: usize::MAX,
                 max_string_size: 0,
@@ -746,6 +943,8 @@ impl Engine {
                 max_array_size: 0,
                 #[cfg(not(feature = "no_object"))]
                 max_map_size: 0,
+                #[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
+                max_data_depth: 0,
             },
         }
     }
@@ -878,8 +1077,8 @@ impl Engine {
         let is_ref = target.is_ref();
 
         let next_chain = match rhs {
-            Expr::Index(_) => ChainType::Index,
-            Expr::Dot(_) => ChainType::Dot,
+            Expr::Index(_, _) => ChainType::Index,
+            Expr::Dot(_, _) => ChainType::Dot,
             _ => ChainType::None,
         };
 
@@ -893,7 +1092,7 @@ impl Engine {
 
                 match rhs {
                     // xxx[idx].expr... | xxx[idx][expr]...
-                    Expr::Dot(x) | Expr::Index(x) => {
+                    Expr::Dot(x, _) | Expr::Index(x, _) => {
                         let idx_pos = x.lhs.position();
                         let idx_val = idx_val.as_value();
                         let obj_ptr = &mut self.get_indexed_mut(
@@ -943,12 +1142,13 @@ impl Engine {
                                 EvalAltResult::ErrorFunctionNotFound(fn_sig, _)
                                     if fn_sig.ends_with("]=") =>
                                 {
-                                    EvalAltResult::ErrorIndexingType(
+                                    Box::new(EvalAltResult::ErrorIndexingType(
                                         self.map_type_name(val_type_name).into(),
                                         Position::none(),
-                                    )
+                                    ))
                                 }
-                                err => err,
+                                // Errors raised by the indexer itself point to the index expression
+                                err => Box::new(err).fill_position(pos),
                             })?;
                         }
 
@@ -1001,14 +1201,19 @@ impl Engine {
                     // xxx.id = ???
                     Expr::Property(x) if new_val.is_some() => {
                         let ((_, _, setter), pos) = x.as_ref();
-                        let mut new_val = new_val;
-                        let mut args = [target.as_mut(), &mut new_val.as_mut().unwrap().0];
+                        let (mut new_val, assign_pos) = new_val.unwrap();
+                        let mut args = [target.as_mut(), &mut new_val];
                         self.exec_fn_call(
                             state, lib, setter, 0, &mut args, is_ref, true, false, None, &None,
                             level,
                         )
                         .map(|(v, _)| (v, true))
-                        .map_err(|err| err.fill_position(*pos))
+                        .map_err(|err| match *err {
+                            // No setter - point to the property
+                            EvalAltResult::ErrorFunctionNotFound(_, _) => err.fill_position(*pos),
+                            // Errors raised by the setter itself point to the assignment
+                            _ => err.fill_position(assign_pos),
+                        })
                     }
                     // xxx.id
                     Expr::Property(x) => {
@@ -1022,7 +1227,7 @@ impl Engine {
                         .map_err(|err| err.fill_position(*pos))
                     }
                     // {xxx:map}.sub_lhs[expr] | {xxx:map}.sub_lhs.expr
-                    Expr::Index(x) | Expr::Dot(x) if target.is::<Map>() => {
+                    Expr::Index(x, _) | Expr::Dot(x, _) if target.is::<Map>() => {
                         let mut val = match &x.lhs {
                             Expr::Property(p) => {
                                 let ((prop, _, _), pos) = p.as_ref();
@@ -1057,7 +1262,7 @@ impl Engine {
                         .map_err(|err| err.fill_position(x.pos))
                     }
                     // xxx.sub_lhs[expr] | xxx.sub_lhs.expr
-                    Expr::Index(x) | Expr::Dot(x) => {
+                    Expr::Index(x, _) | Expr::Dot(x, _) => {
                         match &x.lhs {
                             // xxx.prop[expr] | xxx.prop.expr
                             Expr::Property(p) => {
@@ -1164,8 +1369,8 @@ impl Engine {
             },
             chain_type,
         ) = match expr {
-            Expr::Index(x) => (x.as_ref(), ChainType::Index),
-            Expr::Dot(x) => (x.as_ref(), ChainType::Dot),
+            Expr::Index(x, _) => (x.as_ref(), ChainType::Index),
+            Expr::Dot(x, _) => (x.as_ref(), ChainType::Dot),
             _ => unreachable!(),
         };
 
@@ -1258,7 +1463,7 @@ impl Engine {
             }
             Expr::FnCall(_) => unreachable!(),
             Expr::Property(_) => idx_values.push(IndexChainValue::None),
-            Expr::Index(x) | Expr::Dot(x) => {
+            Expr::Index(x, _) | Expr::Dot(x, _) => {
                 let BinaryExpr { lhs, rhs, .. } = x.as_ref();
 
                 // Evaluate in left-to-right order
@@ -1280,8 +1485,8 @@ impl Engine {
 
                 // Push in reverse order
                 let chain_type = match expr {
-                    Expr::Index(_) => ChainType::Index,
-                    Expr::Dot(_) => ChainType::Dot,
+                    Expr::Index(_, _) => ChainType::Index,
+                    Expr::Dot(_, _) => ChainType::Dot,
                     _ => unreachable!(),
                 };
                 self.eval_indexed_chain(
@@ -1330,15 +1535,10 @@ impl Engine {
 
                 let arr_len = arr.len();
 
-                if index >= 0 {
-                    arr.get_mut(index as usize)
-                        .map(Target::from)
-                        .ok_or_else(|| {
-                            EvalAltResult::ErrorArrayBounds(arr_len, index, idx_pos).into()
-                        })
-                } else {
-                    EvalAltResult::ErrorArrayBounds(arr_len, index, idx_pos).into()
-                }
+                calc_index(arr_len, index)
+                    .and_then(move |offset| arr.get_mut(offset))
+                    .map(Target::from)
+                    .ok_or_else(|| EvalAltResult::ErrorArrayBounds(arr_len, index, idx_pos).into())
             }
 
             #[cfg(not(feature = "no_object"))]
@@ -1369,15 +1569,13 @@ impl Engine {
                     .as_int()
                     .map_err(|err| self.make_type_mismatch_err::<INT>(err, idx_pos))?;
 
-                if index >= 0 {
-                    let offset = index as usize;
-                    let ch = s.chars().nth(offset).ok_or_else(|| {
-                        EvalAltResult::ErrorStringBounds(chars_len, index, idx_pos)
-                    })?;
-                    Ok(Target::StringChar(val, offset, ch.into()))
-                } else {
-                    EvalAltResult::ErrorStringBounds(chars_len, index, idx_pos).into()
-                }
+                let offset = calc_index(chars_len, index)
+                    .ok_or_else(|| EvalAltResult::ErrorStringBounds(chars_len, index, idx_pos))?;
+                let ch = s
+                    .chars()
+                    .nth(offset)
+                    .ok_or_else(|| EvalAltResult::ErrorStringBounds(chars_len, index, idx_pos))?;
+                Ok(Target::StringChar(val, offset, ch.into()))
             }
 
             #[cfg(not(feature = "no_index"))]
@@ -1396,7 +1594,8 @@ impl Engine {
                             Position::none(),
                         ))
                     }
-                    _ => err,
+                    // Errors raised by the indexer itself point to the index expression
+                    _ => err.fill_position(idx_pos),
                 })
             }
 
@@ -1491,6 +1690,8 @@ impl Engine {
             Expr::IntegerConstant(x) => Ok(x.0.into()),
             #[cfg(not(feature = "no_float"))]
             Expr::FloatConstant(x) => Ok(x.0.into()),
+            #[cfg(feature = "decimal")]
+            Expr::DecimalConstant(x) => Ok(x.0.into()),
             Expr::StringConstant(x) => Ok(x.0.to_string().into()),
             Expr::CharConstant(x) => Ok(x.0.into()),
             Expr::FnPointer(x) => Ok(FnPtr::new_unchecked(x.0.clone(), Default::default()).into()),
@@ -1513,13 +1714,13 @@ impl Engine {
 
             // lhs[idx_expr]
             #[cfg(not(feature = "no_index"))]
-            Expr::Index(_) => {
+            Expr::Index(_, _) => {
                 self.eval_dot_index_chain(scope, mods, state, lib, this_ptr, expr, level, None)
             }
 
             // lhs.dot_rhs
             #[cfg(not(feature = "no_object"))]
-            Expr::Dot(_) => {
+            Expr::Dot(_, _) => {
                 self.eval_dot_index_chain(scope, mods, state, lib, this_ptr, expr, level, None)
             }
 
@@ -1540,6 +1741,24 @@ impl Engine {
                     .collect::<Result<HashMap<_, _>, _>>()?,
             )))),
 
+            #[cfg(not(feature = "no_object"))]
+            Expr::ComputedMap(x) => {
+                let mut map = Map::new();
+
+                // Later keys overwrite earlier ones
+                for (key_expr, expr) in x.0.iter() {
+                    let key = self.eval_expr(scope, mods, state, lib, this_ptr, key_expr, level)?;
+                    let key: ImmutableString = match key.0 {
+                        Union::Str(s) => s,
+                        _ => key.to_string().into(),
+                    };
+                    let value = self.eval_expr(scope, mods, state, lib, this_ptr, expr, level)?;
+                    map.insert(key, value);
+                }
+
+                Ok(Dynamic(Union::Map(Box::new(map))))
+            }
+
             // Normal function call
             Expr::FnCall(x) if x.1.is_none() => {
                 let ((name, native, cap_scope, pos), _, hash, args_expr, def_val) = x.as_ref();
@@ -1710,13 +1929,15 @@ impl Engine {
 
                                 // Overriding exact implementation
                                 if func.is_plugin_fn() {
-                                    func.get_plugin_fn().call((self, lib).into(), args)?;
+                                    func.get_plugin_fn().call((self, lib).into(), args)
                                 } else {
-                                    func.get_native_fn()((self, lib).into(), args)?;
+                                    func.get_native_fn()((self, lib).into(), args)
                                 }
+                                .map_err(|err| err.fill_position(*op_pos))?;
                             }
                             // Built-in op-assignment function
-                            _ if run_builtin_op_assignment(op, lhs_ptr.as_mut(), &rhs_val)?
+                            _ if run_builtin_op_assignment(op, lhs_ptr.as_mut(), &rhs_val)
+                                .map_err(|err| err.fill_position(*op_pos))?
                                 .is_some() => {}
                             // Not built-in: expand to `var = var op rhs`
                             _ => {
@@ -1755,7 +1976,7 @@ impl Engine {
 
                 let _new_val = if op.is_empty() {
                     // Normal assignment
-                    Some((rhs_val, rhs_expr.position()))
+                    Some((rhs_val, *op_pos))
                 } else {
                     // Op-assignment - always map to `lhs = lhs op rhs`
                     let op = &op[..op.len() - 1]; // extract operator without =
@@ -1771,7 +1992,7 @@ impl Engine {
                         .map(|(v, _)| v)
                         .map_err(|err| err.fill_position(*op_pos))?;
 
-                    Some((result, rhs_expr.position()))
+                    Some((result, *op_pos))
                 };
 
                 // Must be either `var[index] op= val` or `var.prop op= val`
@@ -1780,7 +2001,7 @@ impl Engine {
                     Expr::Variable(_) => unreachable!(),
                     // idx_lhs[idx_expr] op= rhs
                     #[cfg(not(feature = "no_index"))]
-                    Expr::Index(_) => {
+                    Expr::Index(_, _) => {
                         self.eval_dot_index_chain(
                             scope, mods, state, lib, this_ptr, lhs_expr, level, _new_val,
                         )?;
@@ -1788,7 +2009,7 @@ impl Engine {
                     }
                     // dot_lhs.dot_rhs op= rhs
                     #[cfg(not(feature = "no_object"))]
-                    Expr::Dot(_) => {
+                    Expr::Dot(_, _) => {
                         self.eval_dot_index_chain(
                             scope, mods, state, lib, this_ptr, lhs_expr, level, _new_val,
                         )?;
@@ -1874,51 +2095,73 @@ impl Engine {
 
             // For loop
             Stmt::For(expr, x, _) => {
-                let (name, stmt) = x.as_ref();
+                let (name, value_name, stmt) = x.as_ref();
                 let iter_obj = self.eval_expr(scope, mods, state, lib, this_ptr, expr, level)?;
                 let iter_type = iter_obj.type_id();
 
-                let func = self
+                let iter: Box<dyn Iterator<Item = (Dynamic, Option<Dynamic>)>> = if value_name
+                    .is_some()
+                {
+                    // for (key, value) in map
+                    #[cfg(not(feature = "no_object"))]
+                    match iter_obj.try_cast::<Map>() {
+                        Some(map) => Box::new(map.into_iter().map(|(k, v)| (k.into(), Some(v)))),
+                        None => return Err(self.make_type_mismatch_err::<Map>("", expr.position())),
+                    }
+
+                    #[cfg(feature = "no_object")]
+                    unreachable!()
+                } else if let Some(func) = self
                     .global_module
                     .get_iter(iter_type)
-                    .or_else(|| self.packages.get_iter(iter_type));
+                    .or_else(|| self.packages.get_iter(iter_type))
+                {
+                    Box::new(func(iter_obj).map(|v| (v, None)))
+                } else {
+                    return EvalAltResult::ErrorFor(expr.position()).into();
+                };
 
-                if let Some(func) = func {
-                    // Add the loop variable
-                    let var_name = unsafe_cast_var_name_to_lifetime(name, &state);
+                // Add the loop variables
+                let var_name = unsafe_cast_var_name_to_lifetime(name, &state);
+                scope.push(var_name, ());
+                let index = scope.len() - 1;
+
+                if let Some(value_name) = value_name {
+                    let var_name = unsafe_cast_var_name_to_lifetime(value_name, &state);
                     scope.push(var_name, ());
-                    let index = scope.len() - 1;
-                    state.scope_level += 1;
+                }
+
+                let num_vars = scope.len() - index;
+                state.scope_level += 1;
 
-                    for iter_value in func(iter_obj) {
-                        let (loop_var, _) = scope.get_mut(index);
+                for (iter_value, iter_value2) in iter {
+                    for (offset, value) in once(iter_value).chain(iter_value2).enumerate() {
+                        let (loop_var, _) = scope.get_mut(index + offset);
 
-                        let value = iter_value.flatten();
+                        let value = value.flatten();
                         if cfg!(not(feature = "no_closure")) && loop_var.is_shared() {
                             *loop_var.write_lock().unwrap() = value;
                         } else {
                             *loop_var = value;
                         }
+                    }
 
-                        self.inc_operations(state)
-                            .map_err(|err| err.fill_position(stmt.position()))?;
+                    self.inc_operations(state)
+                        .map_err(|err| err.fill_position(stmt.position()))?;
 
-                        match self.eval_stmt(scope, mods, state, lib, this_ptr, stmt, level) {
-                            Ok(_) => (),
-                            Err(err) => match *err {
-                                EvalAltResult::LoopBreak(false, _) => (),
-                                EvalAltResult::LoopBreak(true, _) => break,
-                                _ => return Err(err),
-                            },
-                        }
+                    match self.eval_stmt(scope, mods, state, lib, this_ptr, stmt, level) {
+                        Ok(_) => (),
+                        Err(err) => match *err {
+                            EvalAltResult::LoopBreak(false, _) => (),
+                            EvalAltResult::LoopBreak(true, _) => break,
+                            _ => return Err(err),
+                        },
                     }
-
-                    state.scope_level -= 1;
-                    scope.rewind(scope.len() - 1);
-                    Ok(Default::default())
-                } else {
-                    EvalAltResult::ErrorFor(expr.position()).into()
                 }
+
+                state.scope_level -= 1;
+                scope.rewind(scope.len() - num_vars);
+                Ok(Default::default())
             }
 
             // Continue statement
@@ -1941,7 +2184,8 @@ impl Engine {
                         mut err @ EvalAltResult::ErrorRuntime(_, _) | mut err
                             if err.is_catchable() =>
                         {
-                            let value = if let EvalAltResult::ErrorRuntime(ref x, _) = err {
+                            // Values thrown inside called functions are caught unchanged
+                            let value = if let Some(x) = err.thrown_value() {
                                 x.clone()
                             } else {
                                 err.set_position(Position::none());
@@ -2018,9 +2262,36 @@ impl Engine {
                 };
                 let var_name = unsafe_cast_var_name_to_lifetime(&var_def.0, &state);
                 scope.push_dynamic_value(var_name, entry_type, val, false);
+
+                // Re-bind a global variable that is shadowed at global level
+                #[cfg(not(feature = "no_function"))]
+                #[cfg(not(feature = "no_closure"))]
+                if state.scope_level == 0 {
+                    if let Some(global) = state.globals.get_mut(var_def.0.as_str()) {
+                        *global = share_global_var(scope, &var_def.0);
+                    }
+                }
+
                 Ok(Default::default())
             }
 
+            // Global variable declaration
+            #[cfg(not(feature = "no_function"))]
+            #[cfg(not(feature = "no_closure"))]
+            Stmt::Global(x, _) => {
+                let (name, pos) = x.as_ref();
+
+                match state.globals.get(name) {
+                    Some(Some((typ, value))) => {
+                        let (typ, value) = (*typ, value.clone());
+                        let var_name = unsafe_cast_var_name_to_lifetime(name, &state);
+                        scope.push_dynamic_value(var_name, typ, value, false);
+                        Ok(Default::default())
+                    }
+                    _ => EvalAltResult::ErrorVariableNotFound(name.into(), *pos).into(),
+                }
+            }
+
             // Import statement
             #[cfg(not(feature = "no_module"))]
             Stmt::Import(expr, alias, _pos) => {
@@ -2030,29 +2301,34 @@ impl Engine {
                     return EvalAltResult::ErrorTooManyModules(*_pos).into();
                 }
 
-                if let Some(path) = self
-                    .eval_expr(scope, mods, state, lib, this_ptr, &expr, level)?
-                    .try_cast::<ImmutableString>()
-                {
-                    if let Some(resolver) = &self.module_resolver {
-                        let mut module = resolver.resolve(self, &path, expr.position())?;
+                // The path may be any expression evaluating to a string
+                let path = self.eval_expr(scope, mods, state, lib, this_ptr, &expr, level)?;
+                let typ = path.type_name();
+                let path = path.try_cast::<ImmutableString>().ok_or_else(|| {
+                    self.make_type_mismatch_err::<ImmutableString>(typ, expr.position())
+                })?;
 
-                        if let Some(name_def) = alias {
-                            module.index_all_sub_modules();
-                            mods.push((name_def.0.clone(), module));
-                        }
+                // Modules registered directly into the Engine take precedence
+                let resolver = if self.static_modules.contains_path(&path) {
+                    Some(&self.static_modules as &dyn ModuleResolver)
+                } else {
+                    self.module_resolver.as_ref().map(|r| r.as_ref())
+                };
 
-                        state.modules += 1;
+                if let Some(resolver) = resolver {
+                    let mut module = resolver.resolve(self, &path, expr.position())?;
 
-                        Ok(Default::default())
-                    } else {
-                        Err(
-                            EvalAltResult::ErrorModuleNotFound(path.to_string(), expr.position())
-                                .into(),
-                        )
+                    if let Some(name_def) = alias {
+                        module.update_fn_metadata(&name_def.0);
+                        module.index_all_sub_modules();
+                        mods.push((name_def.0.clone(), module));
                     }
+
+                    state.modules += 1;
+
+                    Ok(Default::default())
                 } else {
-                    Err(self.make_type_mismatch_err::<ImmutableString>("", expr.position()))
+                    Err(EvalAltResult::ErrorModuleNotFound(path.to_string(), expr.position()).into())
                 }
             }
 
@@ -2239,7 +2515,9 @@ impl Engine {
 
         #[cfg(not(feature = "unchecked"))]
         // Guard against too many operations
-        if self.max_operations() > 0 && state.operations > self.max_operations() {
+        if (self.max_operations() > 0 && state.operations > self.max_operations())
+            || (state.max_operations > 0 && state.operations > state.max_operations)
+        {
             return EvalAltResult::ErrorTooManyOperations(Position::none()).into();
         }
 
@@ -2251,6 +2529,14 @@ impl Engine {
             }
         }
 
+        // Terminate script if the deadline has passed
+        #[cfg(not(feature = "no_std"))]
+        if let Some(deadline) = state.deadline {
+            if Instant::now() >= deadline {
+                return EvalAltResult::ErrorTerminated(Position::none()).into();
+            }
+        }
+
         Ok(())
     }
 
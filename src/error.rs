@@ -1,7 +1,7 @@
 //! Module containing error definitions for the parsing process.
 
 use crate::result::EvalAltResult;
-use crate::token::Position;
+use crate::token::{is_keyword_function, Position};
 
 use crate::stdlib::{
     boxed::Box,
@@ -23,6 +23,8 @@ pub enum LexError {
     UnexpectedInput(String),
     /// A string literal is not terminated before a new-line or EOF.
     UnterminatedString,
+    /// A block comment is not terminated before EOF.
+    UnterminatedComment,
     /// An identifier is in an invalid format.
     StringTooLong(usize),
     /// An string/character/numeric escape sequence is in an invalid format.
@@ -48,6 +50,7 @@ impl fmt::Display for LexError {
             Self::MalformedChar(s) => write!(f, "Invalid character: '{}'", s),
             Self::MalformedIdentifier(s) => write!(f, "Variable name is not proper: '{}'", s),
             Self::UnterminatedString => write!(f, "Open string is not terminated"),
+            Self::UnterminatedComment => write!(f, "Open block comment is not terminated"),
             Self::StringTooLong(max) => write!(
                 f,
                 "Length of string literal exceeds the maximum limit ({})",
@@ -112,6 +115,8 @@ pub enum ParseErrorType {
     ExprExpected(String),
     /// Defining a function `fn` in an appropriate place (e.g. inside another function).
     ///
+    /// Does not appear for functions inside blocks when enabled via `Engine::set_allow_nested_functions`.
+    ///
     /// Never appears under the `no_function` feature.
     WrongFnDefinition,
     /// Missing a function name after the `fn` keyword.
@@ -130,6 +135,11 @@ pub enum ParseErrorType {
     ///
     /// Never appears under the `no_function` feature.
     FnMissingBody(String),
+    /// A function is called with a number of arguments that does not match any function of the same name.
+    /// Wrapped values are the function name and the number of arguments.
+    ///
+    /// Only appears when strict function calls are enabled via `Engine::set_strict_function_calls`.
+    FnWrongArgsCount(String, usize),
     /// An export statement has duplicated names.
     ///
     /// Never appears under the `no_module` feature.
@@ -138,6 +148,10 @@ pub enum ParseErrorType {
     ///
     /// Never appears under the `no_module` feature.
     WrongExport,
+    /// Global variable declaration not inside a function.
+    ///
+    /// Never appears under the `no_function` or `no_closure` feature.
+    WrongGlobal,
     /// Assignment to an a constant variable. Wrapped value is the constant variable name.
     AssignmentToConstant(String),
     /// Assignment to an inappropriate LHS (left-hand-side) expression.
@@ -147,10 +161,27 @@ pub enum ParseErrorType {
     ///
     /// Never appears under the `unchecked` feature.
     ExprTooDeep,
+    /// Array/map literals nesting beyond the maximum levels.
+    ///
+    /// Never appears under the `unchecked` feature.
+    DataTooDeep,
     /// Literal exceeding the maximum size. Wrapped values are the data type name and the maximum size.
     ///
     /// Never appears under the `unchecked` feature.
     LiteralTooLarge(String, usize),
+    /// Too many variables declared within a scope.
+    ///
+    /// Never appears under the `unchecked` feature.
+    TooManyVariables,
+    /// The guard condition of an `if` or `while` statement is a constant boolean.
+    /// Wrapped value is the constant.
+    ///
+    /// Only appears when strict conditions are enabled via `Engine::set_strict_conditions`.
+    ConstantCondition(bool),
+    /// An empty statement, i.e. a semicolon that does not terminate a statement.
+    ///
+    /// Only appears when strict semicolons are enabled via `Engine::set_strict_semicolons`.
+    EmptyStatement,
     /// Break statement not inside a loop.
     LoopBreak,
 }
@@ -181,13 +212,19 @@ impl ParseErrorType {
             Self::FnMissingParams(_) => "Expecting parameters in function declaration",
             Self::FnDuplicatedParam(_,_) => "Duplicated parameters in function declaration",
             Self::FnMissingBody(_) => "Expecting body statement block for function declaration",
+            Self::FnWrongArgsCount(_, _) => "Function called with the wrong number of arguments",
             Self::WrongFnDefinition => "Function definitions must be at global level and cannot be inside a block or another function",
             Self::DuplicatedExport(_) => "Duplicated variable/function in export statement",
             Self::WrongExport => "Export statement can only appear at global level",
+            Self::WrongGlobal => "Global variable declaration can only appear inside a function",
             Self::AssignmentToConstant(_) => "Cannot assign to a constant value",
             Self::AssignmentToInvalidLHS(_) => "Expression cannot be assigned to",
             Self::ExprTooDeep => "Expression exceeds maximum complexity",
+            Self::DataTooDeep => "Array/map literal exceeds maximum levels of nesting",
             Self::LiteralTooLarge(_, _) => "Literal exceeds maximum limit",
+            Self::TooManyVariables => "Too many variables defined",
+            Self::ConstantCondition(_) => "Condition is a constant",
+            Self::EmptyStatement => "Unexpected ';' - empty statement",
             Self::LoopBreak => "Break statement should only be used inside a loop"
         }
     }
@@ -224,6 +261,10 @@ impl fmt::Display for ParseErrorType {
                 write!(f, "Duplicated parameter '{}' for function '{}'", arg, s)
             }
 
+            Self::FnWrongArgsCount(s, n) => {
+                write!(f, "No function '{}' takes {} argument(s)", s, n)
+            }
+
             Self::DuplicatedExport(s) => write!(
                 f,
                 "Duplicated variable/function '{}' in export statement",
@@ -241,7 +282,17 @@ impl fmt::Display for ParseErrorType {
             Self::LiteralTooLarge(typ, max) => {
                 write!(f, "{} exceeds the maximum limit ({})", typ, max)
             }
-            Self::Reserved(s) => write!(f, "'{}' is a reserved keyword", s),
+            Self::Reserved(s) if is_keyword_function(s) => write!(
+                f,
+                "'{}' is a reserved keyword; did you mean to call it as a function?",
+                s
+            ),
+            Self::Reserved(s) => write!(
+                f,
+                "'{}' is a reserved keyword and cannot be used as a name",
+                s
+            ),
+            Self::ConstantCondition(value) => write!(f, "Condition is always {}", value),
             _ => f.write_str(self.desc()),
         }
     }
@@ -2,15 +2,15 @@
 
 use crate::any::Dynamic;
 use crate::engine::{
-    search_imports, Engine, Imports, State, KEYWORD_DEBUG, KEYWORD_EVAL, KEYWORD_FN_PTR,
-    KEYWORD_FN_PTR_CALL, KEYWORD_FN_PTR_CURRY, KEYWORD_IS_DEF_FN, KEYWORD_IS_DEF_VAR,
-    KEYWORD_PRINT, KEYWORD_TYPE_OF,
+    search_imports, Engine, Imports, State, FN_CLONE, FN_CONCAT, KEYWORD_DEBUG, KEYWORD_EVAL,
+    KEYWORD_FN_PTR, KEYWORD_FN_PTR_CALL, KEYWORD_FN_PTR_CURRY, KEYWORD_IS_DEF_FN,
+    KEYWORD_IS_DEF_VAR, KEYWORD_PRINT, KEYWORD_TYPE_OF,
 };
 use crate::error::ParseErrorType;
-use crate::fn_native::{FnCallArgs, FnPtr};
+use crate::fn_native::{FnCallArgs, FnPtr, NativeCallContext};
 use crate::module::{Module, ModuleRef};
 use crate::optimize::OptimizationLevel;
-use crate::parser::{Expr, ImmutableString, Stmt, INT};
+use crate::parser::{Expr, ImmutableString, Stmt, AST, INT};
 use crate::result::EvalAltResult;
 use crate::scope::Scope;
 use crate::stdlib::ops::Deref;
@@ -26,18 +26,21 @@ use crate::{
 #[cfg(not(feature = "no_float"))]
 use crate::parser::FLOAT;
 
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
 #[cfg(not(feature = "no_index"))]
 use crate::engine::{FN_IDX_GET, FN_IDX_SET};
 
 #[cfg(not(feature = "no_object"))]
-use crate::engine::{Map, Target, FN_GET, FN_SET};
+use crate::engine::{Map, Target, FN_GET, FN_GET_FALLBACK, FN_SET};
 
 #[cfg(not(feature = "no_closure"))]
 use crate::engine::KEYWORD_IS_SHARED;
 
 #[cfg(not(feature = "no_closure"))]
 #[cfg(not(feature = "no_function"))]
-use crate::scope::Entry as ScopeEntry;
+use crate::{engine::bind_global_vars, scope::Entry as ScopeEntry};
 
 use crate::stdlib::{
     any::{type_name, TypeId},
@@ -46,7 +49,7 @@ use crate::stdlib::{
     format,
     iter::{empty, once},
     mem,
-    string::ToString,
+    string::{String, ToString},
     vec::Vec,
 };
 
@@ -169,6 +172,42 @@ pub fn ensure_no_data_race(
 }
 
 impl Engine {
+    /// Generate the signature of a function call for error messages, consisting of the
+    /// (possibly qualified) function name followed by the types of the arguments,
+    /// e.g. `foo(i64, string)` or `ttt::hidden()`.
+    pub(crate) fn gen_call_signature(&self, name: &str, args: &[&mut Dynamic]) -> String {
+        format!(
+            "{}({})",
+            name,
+            args.iter()
+                .map(|a| if a.is::<ImmutableString>() {
+                    "&str | ImmutableString | String"
+                } else {
+                    self.map_type_name((*a).type_name())
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    /// Capture the text output of `print` or `debug` if `eval_captured` is running.
+    ///
+    /// Returns `false` if the output is not captured.
+    fn capture_output(&self, text: &str, is_print: bool) -> bool {
+        #[cfg(not(feature = "sync"))]
+        let mut captured = self.captured_output.borrow_mut();
+        #[cfg(feature = "sync")]
+        let mut captured = self.captured_output.write().unwrap();
+
+        match captured.last_mut() {
+            Some((prints, _)) if is_print => prints.push(text.into()),
+            Some((_, debugs)) => debugs.push(text.into()),
+            None => return false,
+        }
+
+        true
+    }
+
     /// Call a native Rust function registered with the `Engine`.
     /// Position in `EvalAltResult` is `None` and must be set afterwards.
     ///
@@ -193,9 +232,15 @@ impl Engine {
         // Search for the native function
         // First search registered functions (can override packages)
         // Then search packages
+        // Finally search variadic functions matching the number of arguments
         let func = //lib.get_fn(hash_fn, pub_only)
             self.global_module.get_fn(hash_fn, pub_only)
-                .or_else(|| self.packages.get_fn(hash_fn, pub_only));
+                .or_else(|| self.packages.get_fn(hash_fn, pub_only))
+                .or_else(|| {
+                    let hash_name = calc_fn_hash(empty(), fn_name, 0, empty());
+                    self.global_module.get_variadic_fn(hash_name, args.len())
+                        .or_else(|| self.packages.get_variadic_fn(hash_name, args.len()))
+                });
 
         if let Some(func) = func {
             assert!(func.is_native());
@@ -218,28 +263,26 @@ impl Engine {
 
             // See if the function match print/debug (which requires special processing)
             return Ok(match fn_name {
-                KEYWORD_PRINT => (
-                    (self.print)(result.as_str().map_err(|typ| {
+                KEYWORD_PRINT | KEYWORD_DEBUG => {
+                    let text = result.as_str().map_err(|typ| {
                         EvalAltResult::ErrorMismatchOutputType(
                             self.map_type_name(type_name::<ImmutableString>()).into(),
-                            typ.into(),
+                            self.map_type_name(typ).into(),
                             Position::none(),
                         )
-                    })?)
-                    .into(),
-                    false,
-                ),
-                KEYWORD_DEBUG => (
-                    (self.debug)(result.as_str().map_err(|typ| {
-                        EvalAltResult::ErrorMismatchOutputType(
-                            self.map_type_name(type_name::<ImmutableString>()).into(),
-                            typ.into(),
-                            Position::none(),
-                        )
-                    })?)
-                    .into(),
-                    false,
-                ),
+                    })?;
+                    let is_print = fn_name == KEYWORD_PRINT;
+
+                    if !self.capture_output(text, is_print) {
+                        if is_print {
+                            (self.print)(text)
+                        } else {
+                            (self.debug)(text)
+                        }
+                    }
+
+                    (().into(), false)
+                }
                 _ => (result, func.is_method()),
             });
         }
@@ -250,6 +293,17 @@ impl Engine {
                 Some(v) => return Ok((v, false)),
                 None => (),
             }
+
+            // Last resort - see if the undefined operator callback handles it
+            if let Some(callback) = &self.undefined_operator {
+                if self.is_binary_operator(fn_name) {
+                    let (x, y) = args.split_at_mut(1);
+
+                    if let Some(v) = callback(fn_name, x[0], y[0])? {
+                        return Ok((v, false));
+                    }
+                }
+            }
         }
 
         // Return default value (if any)
@@ -257,6 +311,39 @@ impl Engine {
             return Ok((val.clone(), false));
         }
 
+        // Getter function not found - try the fallback getter for the type
+        #[cfg(not(feature = "no_object"))]
+        if let Some(prop) = extract_prop_from_getter(fn_name) {
+            let hash_fallback = calc_fn_hash(
+                empty(),
+                FN_GET_FALLBACK,
+                2,
+                [args[0].type_id(), TypeId::of::<ImmutableString>()]
+                    .iter()
+                    .cloned(),
+            );
+
+            if self.global_module.contains_fn(hash_fallback, pub_only)
+                || self.packages.contains_fn(hash_fallback, pub_only)
+            {
+                let mut prop: Dynamic = ImmutableString::from(prop).into();
+                let mut args = [&mut *args[0], &mut prop];
+
+                return self
+                    .call_native_fn(
+                        state,
+                        lib,
+                        FN_GET_FALLBACK,
+                        hash_fallback,
+                        &mut args,
+                        is_ref,
+                        pub_only,
+                        &None,
+                    )
+                    .map(|(v, _)| (v, false));
+            }
+        }
+
         // Getter function not found?
         if let Some(prop) = extract_prop_from_getter(fn_name) {
             return EvalAltResult::ErrorDotExpr(
@@ -313,22 +400,8 @@ impl Engine {
         }
 
         // Raise error
-        EvalAltResult::ErrorFunctionNotFound(
-            format!(
-                "{} ({})",
-                fn_name,
-                args.iter()
-                    .map(|name| if name.is::<ImmutableString>() {
-                        "&str | ImmutableString | String"
-                    } else {
-                        self.map_type_name((*name).type_name())
-                    })
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            ),
-            Position::none(),
-        )
-        .into()
+        EvalAltResult::ErrorFunctionNotFound(self.gen_call_signature(fn_name, args), Position::none())
+            .into()
     }
 
     /// Call a script-defined function.
@@ -362,6 +435,10 @@ impl Engine {
             ));
         }
 
+        if let Some(callback) = &self.on_fn_call {
+            callback(&fn_def.name, fn_def.params.len(), true);
+        }
+
         let orig_scope_level = state.scope_level;
         state.scope_level += 1;
 
@@ -401,6 +478,15 @@ impl Engine {
             .or_else(|err| match *err {
                 // Convert return statement to return value
                 EvalAltResult::Return(x, _) => Ok(x),
+                // Keep nested calls separate to capture the call stack
+                EvalAltResult::ErrorInFunctionCall(_, _, _) if self.capture_call_stack => {
+                    EvalAltResult::ErrorInFunctionCall(
+                        fn_def.name.to_string(),
+                        err,
+                        Position::none(),
+                    )
+                    .into()
+                }
                 EvalAltResult::ErrorInFunctionCall(name, err, _) => {
                     EvalAltResult::ErrorInFunctionCall(
                         format!("{} > {}", fn_def.name, name),
@@ -425,6 +511,10 @@ impl Engine {
         mods.truncate(prev_mods_len);
         state.scope_level = orig_scope_level;
 
+        if let Some(callback) = &self.on_fn_call {
+            callback(&fn_def.name, fn_def.params.len(), false);
+        }
+
         result
     }
 
@@ -525,6 +615,13 @@ impl Engine {
                 ))
             }
 
+            // clone
+            FN_CLONE
+                if args.len() == 1 && !self.has_override(lib, hash_fn, hash_script, pub_only) =>
+            {
+                Ok((args[0].deep_clone(), false))
+            }
+
             // Fn/eval - reaching this point it must be a method-style call, mostly like redirected
             //           by a function pointer so it isn't caught at parse time.
             KEYWORD_FN_PTR | KEYWORD_EVAL
@@ -635,28 +732,59 @@ impl Engine {
         }
     }
 
-    /// Evaluate a list of statements.
-    #[inline]
-    pub(crate) fn eval_statements<'a>(
+    /// Evaluate a list of statements with an initial `State`.
+    pub(crate) fn eval_statements_with_state<'a>(
         &self,
         scope: &mut Scope,
         mods: &mut Imports,
+        mut state: State,
         statements: impl IntoIterator<Item = &'a Stmt>,
         lib: &[&Module],
     ) -> Result<(Dynamic, u64), Box<EvalAltResult>> {
-        let mut state = State::new();
+        #[cfg(not(feature = "no_function"))]
+        #[cfg(not(feature = "no_closure"))]
+        bind_global_vars(scope, &mut state, lib);
+
+        #[cfg(not(feature = "no_optimize"))]
+        let scope_len = scope.len();
 
-        statements
+        let result = statements
             .into_iter()
             .try_fold(().into(), |_, stmt| {
                 self.eval_stmt(scope, mods, &mut state, lib, &mut None, stmt, 0)
             })
             .or_else(|err| match *err {
+                // A `return` at global level stops the script, yielding the value as its result
                 EvalAltResult::Return(out, _) => Ok(out),
                 EvalAltResult::LoopBreak(_, _) => unreachable!(),
                 _ => Err(err),
             })
-            .map(|v| (v, state.operations))
+            .map(|v| (v, state.operations));
+
+        // Constants synthesized to share literals are not part of the script
+        #[cfg(not(feature = "no_optimize"))]
+        scope.remove_shared_literals(scope_len);
+
+        result
+    }
+
+    /// Evaluate the global statements of an `AST` with an initial `State`.
+    ///
+    /// Errors are tagged with the source name of the statement that raised them.
+    pub(crate) fn eval_ast_with_state(
+        &self,
+        scope: &mut Scope,
+        mods: &mut Imports,
+        state: State,
+        ast: &AST,
+        lib: &[&Module],
+    ) -> Result<(Dynamic, u64), Box<EvalAltResult>> {
+        // Count the statements run, so the last one is the statement that failed
+        let mut count = 0_usize;
+        let statements = ast.statements().iter().inspect(|_| count += 1);
+
+        self.eval_statements_with_state(scope, mods, state, statements, lib)
+            .map_err(|err| err.in_source(ast.source_at(count.saturating_sub(1))))
     }
 
     /// Evaluate a text string as a script - used primarily for 'eval'.
@@ -699,8 +827,15 @@ impl Engine {
             return Err(ParseErrorType::WrongFnDefinition.into());
         }
 
-        // Evaluate the AST
-        let (result, operations) = self.eval_statements(scope, mods, ast.statements(), lib)?;
+        // Evaluate the AST, keeping the deadline (if any)
+        let new_state = State {
+            #[cfg(not(feature = "no_std"))]
+            deadline: state.deadline,
+            ..Default::default()
+        };
+
+        let (result, operations) =
+            self.eval_statements_with_state(scope, mods, new_state, ast.statements(), lib)?;
 
         state.operations += operations;
         self.inc_operations(state)?;
@@ -846,8 +981,34 @@ impl Engine {
                 .collect::<StaticVec<_>>();
             let args = arg_values.as_mut();
 
+            let is_method = true;
+
+            // No method found - fall back to a script-defined free function
+            // taking the object as the first argument
+            #[cfg(not(feature = "no_function"))]
+            let (hash, is_method) = if self.fallback_to_free_fn && !native {
+                let hash_fn = calc_fn_hash(
+                    empty(),
+                    _fn_name,
+                    args.len(),
+                    args.iter().map(|a| a.type_id()),
+                );
+                let hash_free = calc_fn_hash(empty(), _fn_name, args.len(), empty());
+
+                if !self.has_override(lib, hash_fn, hash, pub_only)
+                    && (lib.iter().any(|&m| m.contains_fn(hash_free, pub_only))
+                        || self.packages.contains_fn(hash_free, pub_only))
+                {
+                    (hash_free, false)
+                } else {
+                    (hash, is_method)
+                }
+            } else {
+                (hash, is_method)
+            };
+
             self.exec_fn_call(
-                state, lib, _fn_name, hash, args, is_ref, true, pub_only, None, def_val, level,
+                state, lib, _fn_name, hash, args, is_ref, is_method, pub_only, None, def_val, level,
             )
         }?;
 
@@ -859,6 +1020,49 @@ impl Engine {
         Ok((result, updated))
     }
 
+    /// Concatenate a list of values as if via a chain of `+` operations.
+    /// Position in `EvalAltResult` is `None` and must be set afterwards.
+    ///
+    /// When all the values are strings, the result is built with a single allocation.
+    /// Otherwise, each value is added in turn via the `+` operator.
+    fn concat_values(
+        &self,
+        state: &mut State,
+        lib: &[&Module],
+        values: StaticVec<Dynamic>,
+        level: usize,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let string_type = TypeId::of::<ImmutableString>();
+
+        if values.iter().all(|v| v.as_str().is_ok())
+            && !self.has_override_by_name_and_arguments(lib, "+", [string_type, string_type], false)
+        {
+            self.inc_operations(state)?;
+
+            let len = values.iter().map(|v| v.as_str().unwrap().len()).sum();
+            let mut result = String::with_capacity(len);
+            values
+                .iter()
+                .for_each(|v| result.push_str(v.as_str().unwrap()));
+
+            return Ok(result.into());
+        }
+
+        let mut values = values.into_iter();
+        let mut result = values.next().unwrap_or_default();
+
+        for mut value in values {
+            let args = &mut [&mut result, &mut value];
+            result = self
+                .exec_fn_call(
+                    state, lib, "+", 0, args, false, false, false, None, &None, level,
+                )?
+                .0;
+        }
+
+        Ok(result)
+    }
+
     /// Call a function in normal function-call style.
     /// Position in `EvalAltResult` is `None` and must be set afterwards.
     pub(crate) fn make_function_call(
@@ -879,6 +1083,16 @@ impl Engine {
     ) -> Result<Dynamic, Box<EvalAltResult>> {
         let args_expr = args_expr.as_ref();
 
+        // Handle a chain of string concatenations rewritten by the optimizer
+        if name == FN_CONCAT {
+            let values = args_expr
+                .iter()
+                .map(|expr| self.eval_expr(scope, mods, state, lib, this_ptr, expr, level))
+                .collect::<Result<_, _>>()?;
+
+            return self.concat_values(state, lib, values, level);
+        }
+
         // Handle Fn()
         if name == KEYWORD_FN_PTR && args_expr.len() == 1 {
             let hash_fn = calc_fn_hash(empty(), name, 1, once(TypeId::of::<ImmutableString>()));
@@ -1039,6 +1253,28 @@ impl Engine {
             }
         }
 
+        // Handle native functions with read access to the calling scope
+        if !native && !self.scope_functions.is_empty() {
+            let args_len = args_expr.len() + curry.len();
+            let hash_fn = calc_fn_hash(empty(), name, args_len, empty());
+
+            if let Some(func) = self.scope_functions.get(&hash_fn) {
+                // Script-defined functions take precedence
+                if !lib.iter().any(|&m| m.contains_fn(hash_script, pub_only)) {
+                    let mut values = curry;
+                    for expr in args_expr {
+                        values
+                            .push(self.eval_expr(scope, mods, state, lib, this_ptr, expr, level)?);
+                    }
+
+                    self.inc_operations(state)?;
+
+                    let context = NativeCallContext::new_with_scope(self, lib, scope);
+                    return func(context, values.as_mut());
+                }
+            }
+        }
+
         // Normal function call - except for Fn, curry, call and eval (handled above)
         let mut arg_values: StaticVec<_>;
         let mut args: StaticVec<_>;
@@ -1189,7 +1425,12 @@ impl Engine {
                 // 3) The final hash is the XOR of the two hashes.
                 let hash_qualified_fn = hash_script ^ hash_fn_args;
 
-                module.get_qualified_fn(hash_qualified_fn)
+                module.get_qualified_fn(hash_qualified_fn).or_else(|| {
+                    // Finally search variadic functions matching the number of arguments
+                    let qualifiers = modules.iter().map(|(m, _)| m.as_str());
+                    let hash_qualified_name = calc_fn_hash(qualifiers, name, 0, empty());
+                    module.get_qualified_variadic_fn(hash_qualified_name, args.len())
+                })
             }
             r => r,
         };
@@ -1230,19 +1471,7 @@ impl Engine {
             Some(_) => unreachable!(),
             None if def_val.is_some() => Ok(def_val.unwrap().into()),
             None => EvalAltResult::ErrorFunctionNotFound(
-                format!(
-                    "{}{} ({})",
-                    modules,
-                    name,
-                    args.iter()
-                        .map(|a| if a.is::<ImmutableString>() {
-                            "&str | ImmutableString | String"
-                        } else {
-                            self.map_type_name((*a).type_name())
-                        })
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                ),
+                self.gen_call_signature(&module.qualified_name(modules, name), args.as_ref()),
                 Position::none(),
             )
             .into(),
@@ -1281,6 +1510,18 @@ pub fn run_builtin_binary_op(
                 _ => (),
             }
         }
+
+        // Integers mixed with decimals are promoted to decimals.
+        // Decimals are never mixed with floating-point numbers.
+        #[cfg(feature = "decimal")]
+        if args_type == TypeId::of::<Decimal>() && second_type == TypeId::of::<INT>() {
+            let y = Decimal::from(y.clone().cast::<INT>());
+            return run_builtin_binary_op(op, x, &y.into());
+        } else if args_type == TypeId::of::<INT>() && second_type == TypeId::of::<Decimal>() {
+            let x = Decimal::from(x.clone().cast::<INT>());
+            return run_builtin_binary_op(op, &x.into(), y);
+        }
+
         return Ok(None);
     }
 
@@ -1396,6 +1637,74 @@ pub fn run_builtin_binary_op(
         }
     }
 
+    #[cfg(feature = "decimal")]
+    if args_type == TypeId::of::<Decimal>() {
+        use crate::packages::arithmetic::decimal_functions::*;
+
+        let x = x.clone().cast::<Decimal>();
+        let y = y.clone().cast::<Decimal>();
+
+        match op {
+            "+" => return add(x, y).map(Some),
+            "-" => return subtract(x, y).map(Some),
+            "*" => return multiply(x, y).map(Some),
+            "/" => return divide(x, y).map(Some),
+            "%" => return modulo(x, y).map(Some),
+            "==" => return Ok(Some((x == y).into())),
+            "!=" => return Ok(Some((x != y).into())),
+            ">" => return Ok(Some((x > y).into())),
+            ">=" => return Ok(Some((x >= y).into())),
+            "<" => return Ok(Some((x < y).into())),
+            "<=" => return Ok(Some((x <= y).into())),
+            _ => (),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Build in common unary operator implementations to avoid the cost of calling a registered function.
+pub fn run_builtin_unary_op(op: &str, x: &Dynamic) -> Result<Option<Dynamic>, Box<EvalAltResult>> {
+    use crate::packages::arithmetic::signed_basic::INT::functions::*;
+
+    let args_type = x.type_id();
+
+    if args_type == TypeId::of::<INT>() {
+        let x = x.clone().cast::<INT>();
+
+        match op {
+            "-" => return neg(x).map(Some),
+            _ => (),
+        }
+    } else if args_type == TypeId::of::<bool>() {
+        let x = x.clone().cast::<bool>();
+
+        match op {
+            "!" => return Ok(Some((!x).into())),
+            _ => (),
+        }
+    }
+
+    #[cfg(not(feature = "no_float"))]
+    if args_type == TypeId::of::<FLOAT>() {
+        let x = x.clone().cast::<FLOAT>();
+
+        match op {
+            "-" => return Ok(Some((-x).into())),
+            _ => (),
+        }
+    }
+
+    #[cfg(feature = "decimal")]
+    if args_type == TypeId::of::<Decimal>() {
+        let x = x.clone().cast::<Decimal>();
+
+        match op {
+            "-" => return Ok(Some((-x).into())),
+            _ => (),
+        }
+    }
+
     Ok(None)
 }
 
@@ -1503,5 +1812,22 @@ pub fn run_builtin_op_assignment(
         }
     }
 
+    #[cfg(feature = "decimal")]
+    if args_type == TypeId::of::<Decimal>() {
+        use crate::packages::arithmetic::decimal_functions::*;
+
+        let y = y.clone().cast::<Decimal>();
+        let mut x = x.write_lock::<Decimal>().unwrap();
+
+        match op {
+            "+=" => return Ok(Some(*x = add(*x, y)?.as_decimal().unwrap())),
+            "-=" => return Ok(Some(*x = subtract(*x, y)?.as_decimal().unwrap())),
+            "*=" => return Ok(Some(*x = multiply(*x, y)?.as_decimal().unwrap())),
+            "/=" => return Ok(Some(*x = divide(*x, y)?.as_decimal().unwrap())),
+            "%=" => return Ok(Some(*x = modulo(*x, y)?.as_decimal().unwrap())),
+            _ => (),
+        }
+    }
+
     Ok(None)
 }
@@ -3,10 +3,12 @@
 use crate::any::Dynamic;
 use crate::engine::{Engine, EvalContext};
 use crate::module::Module;
-use crate::parser::{FnAccess, ScriptFnDef};
+use crate::optimize::OptimizeContext;
+use crate::parser::{Expr, FnAccess, ScriptFnDef};
 use crate::plugin::PluginFunction;
 use crate::result::EvalAltResult;
-use crate::token::{is_valid_identifier, Position};
+use crate::scope::Scope;
+use crate::token::{is_valid_identifier, Position, Token};
 use crate::utils::ImmutableString;
 use crate::{calc_fn_hash, StaticVec};
 
@@ -15,6 +17,9 @@ use crate::engine::FN_ANONYMOUS;
 
 use crate::stdlib::{boxed::Box, convert::TryFrom, fmt, iter::empty, mem, string::String};
 
+#[cfg(feature = "async")]
+use crate::stdlib::{future::Future, pin::Pin};
+
 #[cfg(feature = "sync")]
 use crate::stdlib::sync::{Arc, RwLock};
 #[cfg(not(feature = "sync"))]
@@ -34,6 +39,24 @@ pub trait SendSync {}
 #[cfg(not(feature = "sync"))]
 impl<T> SendSync for T {}
 
+/// Trait that maps to `Send` only under the `sync` feature.
+#[cfg(feature = "async")]
+#[cfg(feature = "sync")]
+pub trait SendOnly: Send {}
+/// Trait that maps to `Send` only under the `sync` feature.
+#[cfg(feature = "async")]
+#[cfg(feature = "sync")]
+impl<T: Send> SendOnly for T {}
+
+/// Trait that maps to `Send` only under the `sync` feature.
+#[cfg(feature = "async")]
+#[cfg(not(feature = "sync"))]
+pub trait SendOnly {}
+/// Trait that maps to `Send` only under the `sync` feature.
+#[cfg(feature = "async")]
+#[cfg(not(feature = "sync"))]
+impl<T> SendOnly for T {}
+
 /// Immutable reference-counted container.
 #[cfg(not(feature = "sync"))]
 pub type Shared<T> = Rc<T>;
@@ -53,6 +76,7 @@ pub type Locked<T> = RwLock<T>;
 pub struct NativeCallContext<'e, 'm, 'pm: 'm> {
     engine: &'e Engine,
     lib: &'m [&'pm Module],
+    scope: Option<&'m Scope<'m>>,
 }
 
 impl<'e, 'm, 'pm: 'm, M: AsRef<[&'pm Module]> + ?Sized> From<(&'e Engine, &'m M)>
@@ -62,11 +86,25 @@ impl<'e, 'm, 'pm: 'm, M: AsRef<[&'pm Module]> + ?Sized> From<(&'e Engine, &'m M)
         Self {
             engine: value.0,
             lib: value.1.as_ref(),
+            scope: None,
         }
     }
 }
 
 impl<'e, 'm, 'pm> NativeCallContext<'e, 'm, 'pm> {
+    /// Create a new `NativeCallContext` with read access to the calling `Scope`.
+    #[inline(always)]
+    pub(crate) fn new_with_scope(
+        engine: &'e Engine,
+        lib: &'m [&'pm Module],
+        scope: &'m Scope<'m>,
+    ) -> Self {
+        Self {
+            engine,
+            lib,
+            scope: Some(scope),
+        }
+    }
     /// The current `Engine`.
     #[inline(always)]
     pub fn engine(&self) -> &'e Engine {
@@ -77,6 +115,14 @@ impl<'e, 'm, 'pm> NativeCallContext<'e, 'm, 'pm> {
     pub fn iter_namespaces(&self) -> impl Iterator<Item = &'pm Module> + 'm {
         self.lib.iter().cloned()
     }
+    /// The calling `Scope`.
+    ///
+    /// This is only available to functions registered via `Engine::register_scope_fn`.
+    /// It is `None` for all other functions.
+    #[inline(always)]
+    pub fn scope(&self) -> Option<&'m Scope<'m>> {
+        self.scope
+    }
 }
 
 /// Consume a `Shared` resource and return a mutable reference to the wrapped value.
@@ -113,7 +159,36 @@ pub type FnCallArgs<'a> = [&'a mut Dynamic];
 pub struct FnPtr(ImmutableString, StaticVec<Dynamic>);
 
 impl FnPtr {
-    /// Create a new function pointer.
+    /// Create a new function pointer, with curried arguments (if any).
+    ///
+    /// Returns an error if the function name is not a valid identifier.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Dynamic, FnPtr};
+    ///
+    /// let mut fn_ptr = FnPtr::new("foo", vec![Dynamic::from(1_i64)])?;
+    /// fn_ptr.add_curry(Dynamic::from(2_i64));
+    ///
+    /// assert_eq!(fn_ptr.fn_name(), "foo");
+    /// assert_eq!(fn_ptr.curry().len(), 2);
+    ///
+    /// assert!(FnPtr::new("42", vec![]).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn new<S: Into<ImmutableString>>(
+        name: S,
+        curry: impl IntoIterator<Item = Dynamic>,
+    ) -> Result<Self, Box<EvalAltResult>> {
+        let mut fn_ptr = Self::try_from(name.into())?;
+        fn_ptr.1.extend(curry);
+        Ok(fn_ptr)
+    }
+    /// Create a new function pointer without checking its name.
     #[inline(always)]
     pub(crate) fn new_unchecked<S: Into<ImmutableString>>(
         name: S,
@@ -141,6 +216,12 @@ impl FnPtr {
     pub fn curry(&self) -> &[Dynamic] {
         self.1.as_ref()
     }
+    /// Add a new curried argument, to be passed after all existing curried arguments.
+    #[inline(always)]
+    pub fn add_curry(&mut self, value: Dynamic) -> &mut Self {
+        self.1.push(value);
+        self
+    }
     /// Does this function pointer refer to an anonymous function?
     #[cfg(not(feature = "no_function"))]
     #[inline(always)]
@@ -277,6 +358,76 @@ pub type OnVarCallback = Box<
         + 'static,
 >;
 
+/// A callback function for script function calls.
+#[cfg(not(feature = "sync"))]
+pub type OnFnCallCallback = Box<dyn Fn(&str, usize, bool) + 'static>;
+/// A callback function for script function calls.
+#[cfg(feature = "sync")]
+pub type OnFnCallCallback = Box<dyn Fn(&str, usize, bool) + Send + Sync + 'static>;
+
+/// A callback function for binary operators not defined for the types of their operands.
+#[cfg(not(feature = "sync"))]
+pub type OnUndefinedOperatorCallback = Box<
+    dyn Fn(&str, &mut Dynamic, &mut Dynamic) -> Result<Option<Dynamic>, Box<EvalAltResult>>
+        + 'static,
+>;
+/// A callback function for binary operators not defined for the types of their operands.
+#[cfg(feature = "sync")]
+pub type OnUndefinedOperatorCallback = Box<
+    dyn Fn(&str, &mut Dynamic, &mut Dynamic) -> Result<Option<Dynamic>, Box<EvalAltResult>>
+        + Send
+        + Sync
+        + 'static,
+>;
+
+/// A native Rust function with read access to the calling `Scope`.
+#[cfg(not(feature = "sync"))]
+pub type FnWithScope =
+    Box<dyn Fn(NativeCallContext, &mut [Dynamic]) -> Result<Dynamic, Box<EvalAltResult>> + 'static>;
+/// A native Rust function with read access to the calling `Scope`.
+#[cfg(feature = "sync")]
+pub type FnWithScope = Box<
+    dyn Fn(NativeCallContext, &mut [Dynamic]) -> Result<Dynamic, Box<EvalAltResult>>
+        + Send
+        + Sync
+        + 'static,
+>;
+
+/// A callback function for mapping tokens during parsing.
+#[cfg(not(feature = "sync"))]
+pub type OnParseTokenCallback = Box<dyn Fn(Token, Position) -> Token + 'static>;
+/// A callback function for mapping tokens during parsing.
+#[cfg(feature = "sync")]
+pub type OnParseTokenCallback = Box<dyn Fn(Token, Position) -> Token + Send + Sync + 'static>;
+
+/// A callback function for custom optimization passes over expressions.
+#[cfg(not(feature = "sync"))]
+pub type OnOptimizeExprCallback = Box<dyn Fn(Expr, &OptimizeContext) -> Expr + 'static>;
+/// A callback function for custom optimization passes over expressions.
+#[cfg(feature = "sync")]
+pub type OnOptimizeExprCallback =
+    Box<dyn Fn(Expr, &OptimizeContext) -> Expr + Send + Sync + 'static>;
+
+/// A boxed future returned by an async function registered via `register_async_fn`.
+#[cfg(feature = "async")]
+#[cfg(not(feature = "sync"))]
+pub type FnFuture = Pin<Box<dyn Future<Output = Result<Dynamic, Box<EvalAltResult>>> + 'static>>;
+/// A boxed future returned by an async function registered via `register_async_fn`.
+#[cfg(feature = "async")]
+#[cfg(feature = "sync")]
+pub type FnFuture =
+    Pin<Box<dyn Future<Output = Result<Dynamic, Box<EvalAltResult>>> + Send + 'static>>;
+
+/// A callback function for blocking on the future of an async function until it completes.
+#[cfg(feature = "async")]
+#[cfg(not(feature = "sync"))]
+pub type OnBlockOnCallback = Box<dyn Fn(FnFuture) -> Result<Dynamic, Box<EvalAltResult>> + 'static>;
+/// A callback function for blocking on the future of an async function until it completes.
+#[cfg(feature = "async")]
+#[cfg(feature = "sync")]
+pub type OnBlockOnCallback =
+    Box<dyn Fn(FnFuture) -> Result<Dynamic, Box<EvalAltResult>> + Send + Sync + 'static>;
+
 /// A type encapsulating a function callable by Rhai.
 #[derive(Clone)]
 pub enum CallableFunction {
@@ -4,13 +4,38 @@
 
 use crate::any::{Dynamic, DynamicWriteLock, Variant};
 use crate::engine::Engine;
-use crate::fn_native::{CallableFunction, FnAny, FnCallArgs, NativeCallContext, SendSync};
-use crate::parser::FnAccess;
+use crate::fn_native::{CallableFunction, FnAny, FnCallArgs, FnPtr, NativeCallContext, SendSync};
+use crate::parser::{FnAccess, INT};
 use crate::r#unsafe::unsafe_cast_box;
 use crate::result::EvalAltResult;
+use crate::token::Position;
 use crate::utils::ImmutableString;
 
-use crate::stdlib::{any::TypeId, boxed::Box, mem, string::String};
+use crate::stdlib::{
+    any::{Any, TypeId},
+    boxed::Box,
+    error::Error,
+    mem,
+    string::{String, ToString},
+};
+
+#[cfg(not(feature = "no_index"))]
+use crate::stdlib::vec::Vec;
+
+#[cfg(not(feature = "no_float"))]
+use crate::parser::FLOAT;
+
+#[cfg(not(feature = "no_index"))]
+use crate::engine::Array;
+
+#[cfg(not(feature = "no_object"))]
+use crate::engine::Map;
+
+#[cfg(feature = "async")]
+use crate::fn_native::{FnFuture, SendOnly};
+
+#[cfg(feature = "async")]
+use crate::stdlib::future::Future;
 
 /// Trait to register custom functions with the `Engine`.
 pub trait RegisterFn<FN, ARGS, RET> {
@@ -72,6 +97,175 @@ pub trait RegisterResultFn<FN, ARGS> {
     ///         .expect_err("expecting division by zero error!");
     /// ```
     fn register_result_fn(&mut self, name: &str, f: FN) -> &mut Self;
+
+    /// Register a custom function returning a `Dynamic` value whose type is decided at runtime.
+    ///
+    /// The returned `Dynamic` is passed to the script untouched.
+    /// This is the same as `register_result_fn`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, Dynamic, RegisterResultFn};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.register_dynamic_fn("get", |x: i64| {
+    ///     Ok(if x > 0 { Dynamic::from(x) } else { Dynamic::from("none") })
+    /// });
+    ///
+    /// assert_eq!(engine.eval::<i64>("get(42)")?, 42);
+    /// assert_eq!(engine.eval::<String>("get(0)")?, "none");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    fn register_dynamic_fn(&mut self, name: &str, f: FN) -> &mut Self {
+        self.register_result_fn(name, f)
+    }
+}
+
+/// Trait to register fallible custom functions returning `Result<T, E>` with the `Engine`,
+/// where `E` is any error type.
+pub trait RegisterErrorFn<FN, ARGS, RET> {
+    /// Register a custom fallible function with the `Engine`.
+    ///
+    /// Errors are raised in scripts as `EvalAltResult::ErrorRuntime` holding the text
+    /// of the error (via its `Display` implementation).
+    /// Errors of type `Box<EvalAltResult>` are passed through unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::{Engine, RegisterErrorFn};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// // You must use the trait rhai::RegisterErrorFn to get this method.
+    /// engine.register_error_fn("parse_num", |s: &str| s.parse::<i64>());
+    ///
+    /// assert_eq!(engine.eval::<i64>(r#"parse_num("42")"#).unwrap(), 42);
+    ///
+    /// engine.eval::<i64>(r#"parse_num("hello")"#)
+    ///         .expect_err("expecting parse error!");
+    /// ```
+    fn register_error_fn(&mut self, name: &str, f: FN) -> &mut Self;
+}
+
+/// Trait to register custom functions taking all their arguments as a single tuple with the `Engine`.
+///
+/// Scripts call such a function with the elements of the tuple as separate arguments.
+pub trait RegisterTupleFn<FN, ARGS, RET> {
+    /// Register a custom function taking its arguments as a tuple with the `Engine`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, RegisterTupleFn};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// // You must use the trait rhai::RegisterTupleFn to get this method.
+    /// engine.register_tuple_fn("repeat", |(s, n): (String, i64)| s.repeat(n as usize));
+    ///
+    /// assert_eq!(engine.eval::<String>(r#"repeat("ab", 3)"#)?, "ababab");
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn register_tuple_fn(&mut self, name: &str, f: FN) -> &mut Self;
+}
+
+/// Trait to register custom async functions with the `Engine`.
+///
+/// Exported under the `async` feature only.
+///
+/// Scripts call async functions synchronously.  The `Engine` blocks on the returned future
+/// until it completes, via the executor provided by `Engine::on_block_on`.
+#[cfg(feature = "async")]
+pub trait RegisterAsyncFn<FN, ARGS, RET> {
+    /// Register a custom async function with the `Engine`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # use std::future::Future;
+    /// # use std::task::{Context, Poll, Waker};
+    /// # fn block_on<F: Future>(fut: F) -> F::Output {
+    /// #     let mut fut = Box::pin(fut);
+    /// #     let mut cx = Context::from_waker(Waker::noop());
+    /// #     loop {
+    /// #         if let Poll::Ready(value) = fut.as_mut().poll(&mut cx) {
+    /// #             return value;
+    /// #         }
+    /// #     }
+    /// # }
+    /// use rhai::{Engine, RegisterAsyncFn};
+    ///
+    /// async fn fetch(id: i64) -> i64 {
+    ///     id * 2
+    /// }
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// // Use the executor of an async runtime to block on futures
+    /// engine.on_block_on(|fut| block_on(fut));
+    ///
+    /// // You must use the trait rhai::RegisterAsyncFn to get this method.
+    /// engine.register_async_fn("fetch", fetch);
+    ///
+    /// assert_eq!(engine.eval::<i64>("fetch(21)")?, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn register_async_fn(&mut self, name: &str, f: FN) -> &mut Self;
+}
+
+/// Trait to register fallible custom async functions, whose futures resolve to
+/// `Result<Dynamic, Box<EvalAltResult>>`, with the `Engine`.
+///
+/// Exported under the `async` feature only.
+#[cfg(feature = "async")]
+pub trait RegisterAsyncResultFn<FN, ARGS> {
+    /// Register a custom fallible async function with the `Engine`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::future::Future;
+    /// # use std::task::{Context, Poll, Waker};
+    /// # fn block_on<F: Future>(fut: F) -> F::Output {
+    /// #     let mut fut = Box::pin(fut);
+    /// #     let mut cx = Context::from_waker(Waker::noop());
+    /// #     loop {
+    /// #         if let Poll::Ready(value) = fut.as_mut().poll(&mut cx) {
+    /// #             return value;
+    /// #         }
+    /// #     }
+    /// # }
+    /// use rhai::{Engine, Dynamic, RegisterAsyncResultFn, EvalAltResult};
+    ///
+    /// async fn fetch(id: i64) -> Result<Dynamic, Box<EvalAltResult>> {
+    ///     if id < 0 {
+    ///         Err("invalid id!".into())
+    ///     } else {
+    ///         Ok((id * 2).into())
+    ///     }
+    /// }
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.on_block_on(|fut| block_on(fut));
+    ///
+    /// // You must use the trait rhai::RegisterAsyncResultFn to get this method.
+    /// engine.register_async_result_fn("fetch", fetch);
+    ///
+    /// engine.eval::<i64>("fetch(-1)")
+    ///         .expect_err("expecting invalid id error!");
+    /// ```
+    fn register_async_result_fn(&mut self, name: &str, f: FN) -> &mut Self;
 }
 
 // These types are used to build a unique _marker_ tuple type for each combination
@@ -116,6 +310,45 @@ pub fn by_value<T: Variant + Clone>(data: &mut Dynamic) -> T {
     }
 }
 
+/// Convert an `Array` into a `Vec<T>` of a standard type.
+#[cfg(not(feature = "no_index"))]
+macro_rules! map_vec {
+    ($data:ident => $($t:ty),*) => {
+        $(
+            if TypeId::of::<T>() == TypeId::of::<Vec<$t>>() {
+                let values = mem::take($data).into_typed_array::<$t>()?;
+                return Ok(*unsafe_cast_box::<Vec<$t>, T>(Box::new(values)).ok().unwrap());
+            }
+        )*
+    };
+}
+
+/// Dereference into value, converting an `Array` into a `Vec<T>` of a standard type.
+///
+/// Fails when an element of the `Array` is not of the required type.
+#[inline(always)]
+pub fn try_by_value<T: Variant + Clone>(data: &mut Dynamic) -> Result<T, Box<EvalAltResult>> {
+    #[cfg(not(feature = "no_index"))]
+    {
+        map_vec!(data => INT, bool, char, ImmutableString, String, FnPtr);
+        #[cfg(not(feature = "no_float"))]
+        map_vec!(data => FLOAT);
+        map_vec!(data => Array);
+        #[cfg(not(feature = "no_object"))]
+        map_vec!(data => Map);
+    }
+
+    Ok(by_value(data))
+}
+
+/// Dereference into DynamicWriteLock, never failing.
+#[inline(always)]
+pub fn try_by_ref<T: Variant + Clone>(
+    data: &mut Dynamic,
+) -> Result<DynamicWriteLock<'_, T>, Box<EvalAltResult>> {
+    Ok(by_ref(data))
+}
+
 /// This macro creates a closure wrapping a registered function.
 macro_rules! make_func {
 	($fn:ident : $map:expr ; $($par:ident => $let:stmt => $convert:expr => $arg:expr),*) => {
@@ -131,7 +364,7 @@ macro_rules! make_func {
 
 			let mut _drain = args.iter_mut();
 			$($let)*
-			$($par = ($convert)(_drain.next().unwrap()); )*
+			$($par = ($convert)(_drain.next().unwrap())?; )*
 
             // Call the function with each parameter value
 			let r = $fn($($arg),*);
@@ -142,9 +375,31 @@ macro_rules! make_func {
 	};
 }
 
+/// Map an `Option<T>` of a standard type into `T` (or `()` for `None`).
+macro_rules! map_option {
+    ($data:ident => $($t:ty),*) => {
+        $(
+            if TypeId::of::<T>() == TypeId::of::<Option<$t>>() {
+                let value = *unsafe_cast_box::<T, Option<$t>>(Box::new($data)).ok().unwrap();
+                return Ok(value.map_or_else(|| ().into(), Dynamic::from));
+            }
+        )*
+    };
+}
+
 /// To Dynamic mapping function.
+///
+/// `Option<T>` (where `T` is a standard type) maps to `T` for `Some`, and `()` for `None`.
 #[inline(always)]
 pub fn map_dynamic<T: Variant + Clone>(data: T) -> Result<Dynamic, Box<EvalAltResult>> {
+    map_option!(data => INT, bool, char, ImmutableString, String, FnPtr, Dynamic);
+    #[cfg(not(feature = "no_float"))]
+    map_option!(data => FLOAT);
+    #[cfg(not(feature = "no_index"))]
+    map_option!(data => Array);
+    #[cfg(not(feature = "no_object"))]
+    map_option!(data => Map);
+
     Ok(data.into_dynamic())
 }
 
@@ -156,11 +411,64 @@ pub fn map_result(
     data
 }
 
-/// Remap `&str` | `String` to `ImmutableString`.
+/// To Dynamic mapping function for results with any error type.
+///
+/// `Box<EvalAltResult>` errors are passed through unchanged.
+/// Other errors map to `EvalAltResult::ErrorRuntime` holding the text of the error.
+#[inline(always)]
+pub fn map_error_result<T: Variant + Clone, E: Error + 'static>(
+    data: Result<T, E>,
+) -> Result<Dynamic, Box<EvalAltResult>> {
+    match data {
+        Ok(value) => map_dynamic(value),
+        Err(err) => match (Box::new(err) as Box<dyn Any>).downcast::<Box<EvalAltResult>>() {
+            Ok(err) => Err(*err),
+            Err(err) => {
+                let err = err.downcast::<E>().ok().unwrap();
+                Err(EvalAltResult::ErrorRuntime(err.to_string().into(), Position::none()).into())
+            }
+        },
+    }
+}
+
+/// Block on the future of an async function using the `Engine`'s executor.
+#[cfg(feature = "async")]
+fn block_on(ctx: NativeCallContext, fut: FnFuture) -> Result<Dynamic, Box<EvalAltResult>> {
+    match ctx.engine().block_on.as_ref() {
+        Some(block_on) => block_on(fut),
+        None => {
+            Err("No executor is set up via `Engine::on_block_on` to call async functions".into())
+        }
+    }
+}
+
+/// Remap `Vec<T>` of a standard type to `Array`.
+#[cfg(not(feature = "no_index"))]
+macro_rules! map_vec_type_id {
+    ($id:ident => $($t:ty),*) => {
+        $(
+            if $id == TypeId::of::<Vec<$t>>() {
+                return TypeId::of::<Array>();
+            }
+        )*
+    };
+}
+
+/// Remap `&str` | `String` to `ImmutableString`, and `Vec<T>` of a standard type to `Array`.
 #[inline(always)]
 fn map_type_id<T: 'static>() -> TypeId {
     let id = TypeId::of::<T>();
 
+    #[cfg(not(feature = "no_index"))]
+    {
+        map_vec_type_id!(id => INT, bool, char, ImmutableString, String, FnPtr);
+        #[cfg(not(feature = "no_float"))]
+        map_vec_type_id!(id => FLOAT);
+        map_vec_type_id!(id => Array);
+        #[cfg(not(feature = "no_object"))]
+        map_vec_type_id!(id => Map);
+    }
+
     if id == TypeId::of::<&str>() {
         TypeId::of::<ImmutableString>()
     } else if id == TypeId::of::<String>() {
@@ -173,6 +481,7 @@ fn map_type_id<T: 'static>() -> TypeId {
 macro_rules! def_register {
     () => {
         def_register!(imp from_pure :);
+        def_register!(imp_async :);
     };
     (imp $abi:ident : $($par:ident => $arg:expr => $mark:ty => $param:ty => $let:stmt => $clone:expr),*) => {
     //   ^ function ABI type
@@ -193,6 +502,7 @@ macro_rules! def_register {
                     &[$(map_type_id::<$par>()),*],
                     CallableFunction::$abi(make_func!(f : map_dynamic ; $($par => $let => $clone => $arg),*))
                 );
+                self.compile_cache_generation += 1;
                 self
             }
         }
@@ -208,18 +518,116 @@ macro_rules! def_register {
                     &[$(map_type_id::<$par>()),*],
                     CallableFunction::$abi(make_func!(f : map_result ; $($par => $let => $clone => $arg),*))
                 );
+                self.compile_cache_generation += 1;
+                self
+            }
+        }
+
+        impl<
+            $($par: Variant + Clone,)*
+            FN: Fn($($param),*) -> Result<RET, ERR> + SendSync + 'static,
+            RET: Variant + Clone,
+            ERR: Error + 'static
+        > RegisterErrorFn<FN, ($($mark,)*), RET> for Engine
+        {
+            #[inline]
+            fn register_error_fn(&mut self, name: &str, f: FN) -> &mut Self {
+                self.global_module.set_fn(name, FnAccess::Public,
+                    &[$(map_type_id::<$par>()),*],
+                    CallableFunction::$abi(make_func!(f : map_error_result ; $($par => $let => $clone => $arg),*))
+                );
+                self.compile_cache_generation += 1;
                 self
             }
         }
 
         //def_register!(imp_pop $($par => $mark => $param),*);
     };
+    (imp_tuple : $($par:ident),*) => {
+    //             ^ tuple element generic type name (A, B, C etc.), all passed by value
+        impl<
+            $($par: Variant + Clone,)*
+            FN: Fn(($($par,)*)) -> RET + SendSync + 'static,
+            RET: Variant + Clone
+        > RegisterTupleFn<FN, ($($par,)*), RET> for Engine
+        {
+            #[inline]
+            fn register_tuple_fn(&mut self, name: &str, f: FN) -> &mut Self {
+                self.global_module.set_fn(name, FnAccess::Public,
+                    &[$(map_type_id::<$par>()),*],
+                    CallableFunction::from_pure(Box::new(move |_: NativeCallContext, args: &mut FnCallArgs| {
+                        // The arguments are assumed to be of the correct number and types!
+                        let mut _drain = args.iter_mut();
+                        $(let $par = try_by_value::<$par>(_drain.next().unwrap())?;)*
+
+                        map_dynamic(f(($($par,)*)))
+                    }) as Box<FnAny>)
+                );
+                self.compile_cache_generation += 1;
+                self
+            }
+        }
+    };
+    (imp_async : $($par:ident),*) => {
+    //             ^ function parameter generic type name (A, B, C etc.), all passed by value
+        #[cfg(feature = "async")]
+        impl<
+            $($par: Variant + Clone,)*
+            FN: Fn($($par),*) -> FUT + SendSync + 'static,
+            FUT: Future<Output = RET> + SendOnly + 'static,
+            RET: Variant + Clone
+        > RegisterAsyncFn<FN, ($($par,)*), RET> for Engine
+        {
+            #[inline]
+            fn register_async_fn(&mut self, name: &str, f: FN) -> &mut Self {
+                self.global_module.set_fn(name, FnAccess::Public,
+                    &[$(map_type_id::<$par>()),*],
+                    CallableFunction::from_pure(Box::new(move |ctx: NativeCallContext, args: &mut FnCallArgs| {
+                        // The arguments are assumed to be of the correct number and types!
+                        let mut _drain = args.iter_mut();
+                        $(let $par = try_by_value::<$par>(_drain.next().unwrap())?;)*
+
+                        let fut = f($($par),*);
+                        block_on(ctx, Box::pin(async move { map_dynamic(fut.await) }))
+                    }) as Box<FnAny>)
+                );
+                self.compile_cache_generation += 1;
+                self
+            }
+        }
+
+        #[cfg(feature = "async")]
+        impl<
+            $($par: Variant + Clone,)*
+            FN: Fn($($par),*) -> FUT + SendSync + 'static,
+            FUT: Future<Output = Result<Dynamic, Box<EvalAltResult>>> + SendOnly + 'static,
+        > RegisterAsyncResultFn<FN, ($($par,)*)> for Engine
+        {
+            #[inline]
+            fn register_async_result_fn(&mut self, name: &str, f: FN) -> &mut Self {
+                self.global_module.set_fn(name, FnAccess::Public,
+                    &[$(map_type_id::<$par>()),*],
+                    CallableFunction::from_pure(Box::new(move |ctx: NativeCallContext, args: &mut FnCallArgs| {
+                        // The arguments are assumed to be of the correct number and types!
+                        let mut _drain = args.iter_mut();
+                        $(let $par = try_by_value::<$par>(_drain.next().unwrap())?;)*
+
+                        block_on(ctx, Box::pin(f($($par),*)))
+                    }) as Box<FnAny>)
+                );
+                self.compile_cache_generation += 1;
+                self
+            }
+        }
+    };
     ($p0:ident $(, $p:ident)*) => {
-        def_register!(imp from_pure   : $p0 => $p0      => $p0      => $p0      => let $p0     => by_value $(, $p => $p => $p => $p => let $p => by_value)*);
-        def_register!(imp from_method : $p0 => &mut $p0  => Mut<$p0> => &mut $p0 => let mut $p0 => by_ref   $(, $p => $p => $p => $p => let $p => by_value)*);
+        def_register!(imp from_pure   : $p0 => $p0      => $p0      => $p0      => let $p0     => try_by_value $(, $p => $p => $p => $p => let $p => try_by_value)*);
+        def_register!(imp from_method : $p0 => &mut $p0  => Mut<$p0> => &mut $p0 => let mut $p0 => try_by_ref   $(, $p => $p => $p => $p => let $p => try_by_value)*);
+        def_register!(imp_async : $p0 $(, $p)*);
+        def_register!(imp_tuple : $p0 $(, $p)*);
         //                ^ CallableFunction
         // handle the first parameter                                              ^ first parameter passed through
-        //                                                                                                     ^ others passed by value (by_value)
+        //                                                                                                         ^ others passed by value (try_by_value)
 
         // Currently does not support first argument which is a reference, as there will be
         // conflicting implementations since &T: Any and T: Any cannot be distinguished
@@ -86,13 +86,19 @@ pub use any::Dynamic;
 pub use engine::{Engine, EvalContext};
 pub use error::{ParseError, ParseErrorType};
 pub use fn_native::{FnPtr, NativeCallContext};
-pub use fn_register::{RegisterFn, RegisterResultFn};
+pub use fn_register::{RegisterErrorFn, RegisterFn, RegisterResultFn, RegisterTupleFn};
 pub use module::Module;
 pub use parser::{ImmutableString, AST, INT};
 pub use result::EvalAltResult;
 pub use scope::Scope;
 pub use syntax::Expression;
-pub use token::Position;
+pub use token::{Position, Span};
+pub use utils::{StraightHasher, StraightHasherBuilder};
+
+#[cfg(feature = "async")]
+pub use fn_native::FnFuture;
+#[cfg(feature = "async")]
+pub use fn_register::{RegisterAsyncFn, RegisterAsyncResultFn};
 
 #[cfg(feature = "internals")]
 pub use utils::calc_fn_hash;
@@ -119,6 +125,12 @@ pub use engine::Map;
 #[cfg(not(feature = "no_float"))]
 pub use parser::FLOAT;
 
+/// The fixed-precision decimal type.
+///
+/// Only available under the `decimal` feature.
+#[cfg(feature = "decimal")]
+pub use rust_decimal::Decimal;
+
 #[cfg(not(feature = "no_module"))]
 pub use module::ModuleResolver;
 
@@ -159,6 +171,11 @@ pub use engine::{Imports, Limits, State as EvalState};
 #[deprecated(note = "this type is volatile and may change")]
 pub use module::ModuleRef;
 
+#[cfg(feature = "internals")]
+#[cfg(not(feature = "no_optimize"))]
+#[deprecated(note = "this type is volatile and may change")]
+pub use optimize::OptimizeContext;
+
 /// _[INTERNALS]_ Alias to [`smallvec::SmallVec<[T; 4]>`](https://crates.io/crates/smallvec),
 /// which is a specialized `Vec` backed by a small, fixed-size array when there are <= 4 items stored.
 /// Exported under the `internals` feature only.
@@ -10,11 +10,14 @@ use crate::utils::{ImmutableString, StraightHasherBuilder};
 use crate::{calc_fn_hash, StaticVec};
 
 #[cfg(not(feature = "no_function"))]
-use crate::{fn_native::Shared, parser::ScriptFnDef};
+use crate::{error::ParseError, fn_native::Shared, parser::ScriptFnDef};
+
+#[cfg(any(not(feature = "no_function"), not(feature = "no_module")))]
+use crate::engine::Engine;
 
 #[cfg(not(feature = "no_module"))]
 use crate::{
-    engine::{Engine, Imports},
+    engine::Imports,
     parser::AST,
     scope::{Entry as ScopeEntry, Scope},
 };
@@ -35,6 +38,7 @@ use crate::stdlib::{
     collections::HashMap,
     fmt, format,
     iter::empty,
+    mem,
     num::NonZeroUsize,
     ops::{Add, AddAssign, Deref, DerefMut},
     string::{String, ToString},
@@ -49,6 +53,9 @@ pub type FuncInfo = (
     CallableFunction,
 );
 
+/// Information on a variadic Rust function: name, minimum and maximum number of arguments plus the function.
+pub type VariadicFnInfo = (String, usize, usize, CallableFunction);
+
 /// An imported module, which may contain variables, sub-modules,
 /// external Rust functions, and script-defined functions.
 ///
@@ -74,8 +81,17 @@ pub struct Module {
     /// including those in sub-modules.
     all_functions: HashMap<u64, CallableFunction, StraightHasherBuilder>,
 
+    /// Variadic Rust functions, keyed by the hash of the function name.
+    variadic_functions: HashMap<u64, StaticVec<VariadicFnInfo>, StraightHasherBuilder>,
+
+    /// Flattened collection of all variadic Rust functions, including those in sub-modules.
+    all_variadic_functions: HashMap<u64, StaticVec<VariadicFnInfo>, StraightHasherBuilder>,
+
     /// Is the module indexed?
     indexed: bool,
+
+    /// Fully-qualified namespace the module is resolved under, if any.
+    namespace: Option<String>,
 }
 
 impl fmt::Debug for Module {
@@ -109,6 +125,7 @@ impl Clone for Module {
         Self {
             all_variables: self.all_variables.clone(),
             all_functions: self.all_functions.clone(),
+            all_variadic_functions: self.all_variadic_functions.clone(),
             indexed: self.indexed,
             ..self.do_clone(false)
         }
@@ -172,6 +189,8 @@ impl Module {
     pub fn is_empty(&self) -> bool {
         self.functions.is_empty()
             && self.all_functions.is_empty()
+            && self.variadic_functions.is_empty()
+            && self.all_variadic_functions.is_empty()
             && self.variables.is_empty()
             && self.all_variables.is_empty()
             && self.modules.is_empty()
@@ -192,7 +211,9 @@ impl Module {
             },
             variables: self.variables.clone(),
             functions: self.functions.clone(),
+            variadic_functions: self.variadic_functions.clone(),
             type_iterators: self.type_iterators.clone(),
+            namespace: self.namespace.clone(),
             ..Default::default()
         }
     }
@@ -306,6 +327,54 @@ impl Module {
         hash_script
     }
 
+    /// Compile a script-defined function from the statements in its body and set it into the module.
+    /// Returns a hash key.
+    ///
+    /// The body is compiled with the settings of the `Engine`, as if it were written
+    /// inside `fn name(params) { ... }` in a script.  Any anonymous functions (e.g. closures)
+    /// defined within the body are encapsulated together with the function.
+    ///
+    /// If there is an existing function of the same name and number of arguments, it is replaced.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, FnAccess, Module};
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let mut module = Module::new();
+    /// let hash = module.add_script_fn("add", FnAccess::Public, &["x", "y"], "x + y", &engine)?;
+    /// assert!(module.contains_fn(hash, true));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_function"))]
+    pub fn add_script_fn(
+        &mut self,
+        name: &str,
+        access: FnAccess,
+        params: &[&str],
+        body: &str,
+        engine: &Engine,
+    ) -> Result<u64, ParseError> {
+        let ast = engine.parse_script_fn(name, access, params, body, engine.optimization_level)?;
+
+        // Encapsulate the environment, which includes any anonymous functions defined within the body
+        let ast_lib: Shared<Module> = ast.lib().clone().into();
+
+        let mut fn_def = ast
+            .lib()
+            .get_script_fn(name, params.len(), false)
+            .unwrap()
+            .as_ref()
+            .clone();
+        fn_def.lib = Some(ast_lib);
+
+        Ok(self.set_script_fn(fn_def.into()))
+    }
+
     /// Get a script-defined function in the module based on name and number of parameters.
     #[cfg(not(feature = "no_function"))]
     #[inline(always)]
@@ -315,14 +384,12 @@ impl Module {
         num_params: usize,
         public_only: bool,
     ) -> Option<&Shared<ScriptFnDef>> {
-        self.functions
-            .values()
-            .find(|(fn_name, access, num, _, _)| {
-                (!public_only || *access == FnAccess::Public)
-                    && *num == num_params
-                    && fn_name == name
-            })
-            .map(|(_, _, _, _, f)| f.get_shared_fn_def())
+        // None + function name + number of arguments.
+        let hash_script = calc_fn_hash(empty(), name, num_params, empty());
+
+        self.get_fn(hash_script, public_only)
+            .filter(|f| f.is_script())
+            .map(CallableFunction::get_shared_fn_def)
     }
 
     /// Does a sub-module exist in the module?
@@ -551,6 +618,81 @@ impl Module {
         )
     }
 
+    /// Set a variadic Rust function into the module, taking any number of arguments
+    /// between `min_args` and `max_args` (inclusive) of any type.
+    ///
+    /// Variadic functions are only considered when there is no registered function matching
+    /// the exact number and types of arguments in a function call.
+    ///
+    /// If there is an existing variadic function of the same name with an overlapping range of
+    /// number of arguments, the new function takes precedence.
+    ///
+    /// ## WARNING - Low Level API
+    ///
+    /// This function is very low level.
+    ///
+    /// Arguments are passed in as a mutable slice of `Dynamic` values, which can be consumed
+    /// (e.g. via `std::mem::take`).  The function is _pure_, meaning that a variable passed as the
+    /// first argument in a method-call style is never modified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Module;
+    ///
+    /// let mut module = Module::new();
+    /// module.set_variadic_fn("sum", 1, 10, |args| {
+    ///     Ok(args.iter().map(|v| v.as_int().unwrap_or(0)).sum::<i64>())
+    /// });
+    ///
+    /// assert!(module.contains_variadic_fn("sum", 3));
+    /// assert!(!module.contains_variadic_fn("sum", 0));
+    /// ```
+    pub fn set_variadic_fn<T: Variant + Clone>(
+        &mut self,
+        name: impl Into<String>,
+        min_args: usize,
+        max_args: usize,
+        func: impl Fn(&mut [Dynamic]) -> Result<T, Box<EvalAltResult>> + SendSync + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+
+        let f = move |_: NativeCallContext, args: &mut FnCallArgs| {
+            let mut values: StaticVec<_> = args.iter_mut().map(|v| mem::take(*v)).collect();
+            func(values.as_mut()).map(Dynamic::from)
+        };
+
+        let hash_name = calc_fn_hash(empty(), &name, 0, empty());
+        let func = CallableFunction::from_pure(Box::new(f));
+
+        self.variadic_functions
+            .entry(hash_name)
+            .or_insert_with(Default::default)
+            .insert(0, (name, min_args, max_args, func));
+
+        self.indexed = false;
+        self
+    }
+
+    /// Does a variadic Rust function exist in the module that accepts the specified number of arguments?
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Module;
+    ///
+    /// let mut module = Module::new();
+    /// module.set_variadic_fn("sum", 2, 5, |args| Ok(args.len() as i64));
+    ///
+    /// assert!(module.contains_variadic_fn("sum", 2));
+    /// assert!(!module.contains_variadic_fn("sum", 6));
+    /// ```
+    #[inline(always)]
+    pub fn contains_variadic_fn(&self, name: &str, num_args: usize) -> bool {
+        self.get_variadic_fn(calc_fn_hash(empty(), name, 0, empty()), num_args)
+            .is_some()
+    }
+
     /// Set a Rust function taking no parameters into the module, returning a hash key.
     ///
     /// If there is a similar existing Rust function, it is replaced.
@@ -1111,6 +1253,43 @@ impl Module {
         }
     }
 
+    /// Get a variadic Rust function that accepts the specified number of arguments.
+    ///
+    /// The `u64` hash is calculated by the function `crate::calc_fn_hash` on the function name
+    /// with no qualifiers and zero number of arguments.
+    #[inline]
+    pub(crate) fn get_variadic_fn(
+        &self,
+        hash_name: u64,
+        num_args: usize,
+    ) -> Option<&CallableFunction> {
+        self.variadic_functions.get(&hash_name).and_then(|list| {
+            list.iter()
+                .find(|(_, min, max, _)| *min <= num_args && num_args <= *max)
+                .map(|(_, _, _, f)| f)
+        })
+    }
+
+    /// Get a modules-qualified variadic Rust function that accepts the specified number of arguments.
+    ///
+    /// The `u64` hash is calculated by the function `crate::calc_fn_hash` on the qualifiers plus the
+    /// function name and zero number of arguments, and must match the hash calculated by
+    /// `index_all_sub_modules`.
+    #[inline]
+    pub(crate) fn get_qualified_variadic_fn(
+        &self,
+        hash_qualified_name: u64,
+        num_args: usize,
+    ) -> Option<&CallableFunction> {
+        self.all_variadic_functions
+            .get(&hash_qualified_name)
+            .and_then(|list| {
+                list.iter()
+                    .find(|(_, min, max, _)| *min <= num_args && num_args <= *max)
+                    .map(|(_, _, _, f)| f)
+            })
+    }
+
     /// Get a modules-qualified function.
     /// Name and Position in `EvalAltResult` are None and must be set afterwards.
     ///
@@ -1128,8 +1307,11 @@ impl Module {
         self.modules.extend(other.modules.into_iter());
         self.variables.extend(other.variables.into_iter());
         self.functions.extend(other.functions.into_iter());
+        self.variadic_functions
+            .extend(other.variadic_functions.into_iter());
         self.type_iterators.extend(other.type_iterators.into_iter());
         self.all_functions.clear();
+        self.all_variadic_functions.clear();
         self.all_variables.clear();
         self.indexed = false;
         self
@@ -1145,8 +1327,11 @@ impl Module {
         });
         self.variables.extend(other.variables.into_iter());
         self.functions.extend(other.functions.into_iter());
+        self.variadic_functions
+            .extend(other.variadic_functions.into_iter());
         self.type_iterators.extend(other.type_iterators.into_iter());
         self.all_functions.clear();
+        self.all_variadic_functions.clear();
         self.all_variables.clear();
         self.indexed = false;
         self
@@ -1169,10 +1354,16 @@ impl Module {
         other.functions.iter().for_each(|(&k, v)| {
             self.functions.entry(k).or_insert_with(|| v.clone());
         });
+        other.variadic_functions.iter().for_each(|(&k, v)| {
+            self.variadic_functions
+                .entry(k)
+                .or_insert_with(|| v.clone());
+        });
         other.type_iterators.iter().for_each(|(&k, &v)| {
             self.type_iterators.entry(k).or_insert(v);
         });
         self.all_functions.clear();
+        self.all_variadic_functions.clear();
         self.all_variables.clear();
         self.indexed = false;
         self
@@ -1215,9 +1406,16 @@ impl Module {
                 })
                 .map(|(&k, v)| (k, v.clone())),
         );
+        self.variadic_functions.extend(
+            other
+                .variadic_functions
+                .iter()
+                .map(|(&k, v)| (k, v.clone())),
+        );
 
         self.type_iterators.extend(other.type_iterators.iter());
         self.all_functions.clear();
+        self.all_variadic_functions.clear();
         self.all_variables.clear();
         self.indexed = false;
         self
@@ -1236,6 +1434,7 @@ impl Module {
         });
 
         self.all_functions.clear();
+        self.all_variadic_functions.clear();
         self.all_variables.clear();
         self.indexed = false;
         self
@@ -1253,17 +1452,30 @@ impl Module {
 
     /// Get an iterator to the variables in the module.
     #[inline(always)]
-    pub fn iter_var(&self) -> impl Iterator<Item = (&String, &Dynamic)> {
-        self.variables.iter()
+    pub fn iter_var(&self) -> impl Iterator<Item = (&str, &Dynamic)> {
+        self.variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Get an iterator to the sub-modules in the module.
+    #[inline(always)]
+    pub fn iter_sub_module(&self) -> impl Iterator<Item = (&str, &Module)> {
+        self.modules.iter().map(|(name, m)| (name.as_str(), m))
     }
 
     /// Get an iterator to the functions in the module.
-    #[cfg(not(feature = "no_optimize"))]
     #[inline(always)]
     pub(crate) fn iter_fn(&self) -> impl Iterator<Item = &FuncInfo> {
         self.functions.values()
     }
 
+    /// Get an iterator to the variadic functions in the module.
+    #[inline(always)]
+    pub(crate) fn iter_variadic_fn(&self) -> impl Iterator<Item = &VariadicFnInfo> {
+        self.variadic_functions.values().flat_map(|v| v.iter())
+    }
+
     /// Get an iterator over all script-defined functions in the module.
     ///
     /// Function metadata includes:
@@ -1365,7 +1577,8 @@ impl Module {
             });
 
         // Modules left in the scope become sub-modules
-        mods.into_iter().for_each(|(alias, m)| {
+        mods.into_iter().for_each(|(alias, mut m)| {
+            m.update_fn_metadata(alias.as_str());
             module.modules.insert(alias.to_string(), m);
         });
 
@@ -1397,11 +1610,12 @@ impl Module {
             qualifiers: &mut Vec<&'a str>,
             variables: &mut Vec<(u64, Dynamic)>,
             functions: &mut Vec<(u64, CallableFunction)>,
+            variadic_functions: &mut Vec<(u64, StaticVec<VariadicFnInfo>)>,
         ) {
             module.modules.iter().for_each(|(name, m)| {
                 // Index all the sub-modules first.
                 qualifiers.push(name);
-                index_module(m, qualifiers, variables, functions);
+                index_module(m, qualifiers, variables, functions, variadic_functions);
                 qualifiers.pop();
             });
 
@@ -1445,23 +1659,70 @@ impl Module {
                         functions.push((hash_qualified_script, func.clone()));
                     }
                 });
+            // Index all variadic Rust functions
+            module
+                .variadic_functions
+                .iter()
+                .filter_map(|(_, list)| list.first().map(|(name, _, _, _)| (name, list)))
+                .for_each(|(name, list)| {
+                    // Qualifiers + function name + no parameters.
+                    let hash_qualified_name =
+                        calc_fn_hash(qualifiers.iter().cloned(), name, 0, empty());
+                    variadic_functions.push((hash_qualified_name, list.clone()));
+                });
         }
 
         if !self.indexed {
             let mut qualifiers: Vec<_> = Default::default();
             let mut variables: Vec<_> = Default::default();
             let mut functions: Vec<_> = Default::default();
+            let mut variadic_functions: Vec<_> = Default::default();
 
             qualifiers.push("root");
 
-            index_module(self, &mut qualifiers, &mut variables, &mut functions);
+            index_module(
+                self,
+                &mut qualifiers,
+                &mut variables,
+                &mut functions,
+                &mut variadic_functions,
+            );
 
             self.all_variables = variables.into_iter().collect();
             self.all_functions = functions.into_iter().collect();
+            self.all_variadic_functions = variadic_functions.into_iter().collect();
             self.indexed = true;
         }
     }
 
+    /// Set the namespace the module is resolved under, plus those of all its sub-modules.
+    ///
+    /// Qualified functions not found in the module are reported under this namespace.
+    #[cfg(not(feature = "no_module"))]
+    pub(crate) fn update_fn_metadata(&mut self, namespace: &str) {
+        let separator = Token::DoubleColon.syntax();
+
+        self.modules.iter_mut().for_each(|(name, m)| {
+            m.update_fn_metadata(&format!("{}{}{}", namespace, separator, name));
+        });
+        self.namespace = Some(namespace.into());
+    }
+
+    /// Get the fully-qualified name of an item referred to via `modules` in this root module.
+    ///
+    /// Falls back to the path as written when the module has no namespace.
+    pub(crate) fn qualified_name(&self, modules: &ModuleRef, name: &str) -> String {
+        modules
+            .iter()
+            .skip(1)
+            .try_fold(self, |m, (sub, _)| m.get_sub_module(sub))
+            .and_then(|m| m.namespace.as_ref())
+            .map_or_else(
+                || format!("{}{}", modules, name),
+                |ns| format!("{}{}{}", ns, Token::DoubleColon.syntax(), name),
+            )
+    }
+
     /// Does a type iterator exist in the module?
     pub fn contains_iter(&self, id: TypeId) -> bool {
         self.type_iterators.contains_key(&id)
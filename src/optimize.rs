@@ -2,30 +2,45 @@
 
 use crate::any::Dynamic;
 use crate::engine::{
-    Engine, KEYWORD_DEBUG, KEYWORD_EVAL, KEYWORD_IS_DEF_FN, KEYWORD_IS_DEF_VAR, KEYWORD_PRINT,
-    KEYWORD_TYPE_OF,
+    Engine, EvalContext, FN_CONCAT, KEYWORD_DEBUG, KEYWORD_EVAL, KEYWORD_FN_PTR_CALL,
+    KEYWORD_IS_DEF_FN, KEYWORD_IS_DEF_VAR, KEYWORD_PRINT, KEYWORD_TYPE_OF,
 };
-use crate::fn_call::run_builtin_binary_op;
+use crate::fn_call::{run_builtin_binary_op, run_builtin_unary_op};
 use crate::module::Module;
-use crate::parser::{map_dynamic_to_expr, BinaryExpr, Expr, ScriptFnDef, Stmt, AST};
+use crate::parser::{
+    map_dynamic_to_expr, walk_expr_mut, walk_stmt, walk_stmt_mut, ASTNode, BinaryExpr, CustomExpr,
+    Expr, ScriptFnDef, Stmt, AST, INT,
+};
 use crate::scope::{Entry as ScopeEntry, Scope};
+use crate::syntax::FnCustomSyntaxEval;
 use crate::token::{is_valid_identifier, Position};
+use crate::utils::ImmutableString;
 use crate::{calc_fn_hash, StaticVec};
 
-#[cfg(not(feature = "no_function"))]
 use crate::parser::ReturnType;
 
-#[cfg(feature = "internals")]
-use crate::parser::CustomExpr;
+#[cfg(not(feature = "no_index"))]
+use crate::engine::Array;
 
 use crate::stdlib::{
+    any::TypeId,
     boxed::Box,
-    iter::empty,
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+    iter::{empty, once},
+    mem,
     string::{String, ToString},
     vec,
     vec::Vec,
 };
 
+#[cfg(not(feature = "no_std"))]
+use crate::stdlib::collections::hash_map::DefaultHasher;
+
+#[cfg(feature = "no_std")]
+use ahash::AHasher;
+
 /// Level of optimization performed.
 ///
 /// Not available under the `no_optimize` feature.
@@ -66,13 +81,18 @@ struct State<'a> {
     /// Has the AST been changed during this pass?
     changed: bool,
     /// Collection of constants to use for eager function evaluations.
-    constants: Vec<(String, Expr)>,
+    /// `None` marks a variable that shadows a constant of the same name.
+    constants: Vec<(String, Option<Expr>)>,
     /// An `Engine` instance for eager function evaluation.
     engine: &'a Engine,
     /// Library of script-defined functions.
     lib: &'a [&'a Module],
     /// Optimization level.
     optimization_level: OptimizationLevel,
+    /// Is the current statement inside a `try` block?
+    in_try_block: bool,
+    /// Variables that may be shared with (and modified by) closures.
+    shared: Vec<String>,
 }
 
 impl<'a> State<'a> {
@@ -85,6 +105,8 @@ impl<'a> State<'a> {
             engine,
             lib,
             optimization_level: level,
+            in_try_block: false,
+            shared: vec![],
         }
     }
     /// Reset the state from dirty to clean.
@@ -105,7 +127,7 @@ impl<'a> State<'a> {
     /// Does a constant exist?
     #[inline(always)]
     pub fn contains_constant(&self, name: &str) -> bool {
-        self.constants.iter().any(|(n, _)| n == name)
+        self.find_constant(name).is_some()
     }
     /// Prune the list of constants back to a specified size.
     #[inline(always)]
@@ -115,14 +137,21 @@ impl<'a> State<'a> {
     /// Add a new constant to the list.
     #[inline(always)]
     pub fn push_constant(&mut self, name: &str, value: Expr) {
-        self.constants.push((name.into(), value))
+        self.constants.push((name.into(), Some(value)))
+    }
+    /// Hide a constant behind a variable of the same name.
+    #[inline(always)]
+    pub fn shadow_constant(&mut self, name: &str) {
+        if self.contains_constant(name) {
+            self.constants.push((name.into(), None))
+        }
     }
     /// Look up a constant from the list.
     #[inline]
     pub fn find_constant(&self, name: &str) -> Option<&Expr> {
         for (n, expr) in self.constants.iter().rev() {
             if n == name {
-                return Some(expr);
+                return expr.as_ref();
             }
         }
 
@@ -130,6 +159,46 @@ impl<'a> State<'a> {
     }
 }
 
+/// _[INTERNALS]_ Context of an optimization pass registered via `Engine::register_optimizer_pass`.
+/// Exported under the `internals` feature only.
+///
+/// ## WARNING
+///
+/// This type is volatile and may change.
+#[allow(dead_code)]
+pub struct OptimizeContext<'a> {
+    /// Collection of constants in scope.
+    constants: &'a [(String, Option<Expr>)],
+    /// Optimization level.
+    optimization_level: OptimizationLevel,
+    /// Has the expression been changed by the optimization pass?
+    changed: Cell<bool>,
+}
+
+#[allow(dead_code)]
+impl OptimizeContext<'_> {
+    /// Find the value of a constant in scope, if any.
+    #[inline(always)]
+    pub fn find_constant(&self, name: &str) -> Option<&Expr> {
+        self.constants
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, expr)| expr.as_ref())
+    }
+    /// Get the current optimization level.
+    #[inline(always)]
+    pub fn optimization_level(&self) -> OptimizationLevel {
+        self.optimization_level
+    }
+    /// Signal that the expression has been changed, so that the optimizer runs another pass
+    /// over the `AST` to optimize it further.
+    #[inline(always)]
+    pub fn set_dirty(&self) {
+        self.changed.set(true);
+    }
+}
+
 /// Call a registered function
 fn call_fn_with_constant_arguments(
     state: &State,
@@ -160,14 +229,534 @@ fn call_fn_with_constant_arguments(
         .map(|(v, _)| v)
 }
 
+/// Call a pure custom syntax with constant inputs
+fn call_custom_syntax_with_constant_inputs(
+    state: &State,
+    func: &FnCustomSyntaxEval,
+    inputs: &[Expr],
+) -> Option<Dynamic> {
+    let mut scope = Scope::new();
+
+    let result = {
+        let mut context = EvalContext {
+            engine: state.engine,
+            scope: &mut scope,
+            mods: &mut Default::default(),
+            state: &mut Default::default(),
+            lib: state.lib,
+            this_ptr: &mut None,
+            level: 0,
+        };
+        let inputs: StaticVec<_> = inputs.iter().map(Into::into).collect();
+        func(&mut context, &inputs).ok()?
+    };
+
+    // Bail out if the custom syntax has tampered with the scope
+    if scope.is_empty() {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+/// Concatenate (`+`) or repeat (`*`) constant arrays.
+///
+/// Returns `None` if the operands do not match, or if the result would exceed the maximum array
+/// size (in which case the error is left for run-time).
+#[cfg(not(feature = "no_index"))]
+fn fold_array_op(_engine: &Engine, op: &str, args: StaticVec<Dynamic>) -> Option<Dynamic> {
+    let mut args = args.into_iter();
+    let list = args.next()?.try_cast::<Array>()?;
+    let arg = args.next()?;
+
+    let check_size = |_len: usize| -> Option<()> {
+        #[cfg(not(feature = "unchecked"))]
+        if _engine.max_array_size() > 0 && _len > _engine.max_array_size() {
+            return None;
+        }
+        Some(())
+    };
+
+    let result: Array = match op {
+        "+" => {
+            let other = arg.try_cast::<Array>()?;
+            check_size(list.len() + other.len())?;
+            list.into_iter().chain(other).collect()
+        }
+        "*" => {
+            let times = arg.as_int().ok()?.max(0) as usize;
+            check_size(list.len().checked_mul(times)?)?;
+            let mut result = Array::with_capacity(list.len() * times);
+            (0..times).for_each(|_| result.extend(list.iter().cloned()));
+            result
+        }
+        _ => return None,
+    };
+
+    Some(result.into())
+}
+
+/// Can a call to a registered function change its first argument (i.e. is it a method
+/// taking `&mut` first parameter)?
+///
+/// Operators are assumed to never change their operands.
+fn may_mutate_first_arg(state: &State, fn_name: &str, num_args: usize) -> bool {
+    if !is_valid_identifier(fn_name.chars()) {
+        return false;
+    }
+
+    once(&state.engine.global_module)
+        .chain(state.engine.packages.iter())
+        .flat_map(Module::iter_fn)
+        .any(|(name, _, params, _, func)| {
+            name == fn_name && *params == num_args && func.is_method()
+        })
+}
+
+/// Is an expression free of side effects, assuming that all registered functions are pure?
+///
+/// All variables referenced by the expression are collected.
+fn is_side_effect_free<'e>(expr: &'e Expr, state: &State, vars: &mut Vec<&'e str>) -> bool {
+    // These functions have side effects or depend on the environment
+    const IMPURE_KEYWORDS: &[&str] = &[
+        KEYWORD_PRINT,
+        KEYWORD_DEBUG,
+        KEYWORD_EVAL,
+        KEYWORD_FN_PTR_CALL,
+        KEYWORD_IS_DEF_FN,
+        KEYWORD_IS_DEF_VAR,
+    ];
+
+    match expr {
+        Expr::Expr(x) => is_side_effect_free(x, state, vars),
+
+        Expr::Variable(x) if x.1.is_none() => {
+            vars.push(&(x.0).0);
+            true
+        }
+
+        Expr::Array(x) => x.0.iter().all(|e| is_side_effect_free(e, state, vars)),
+        Expr::Map(x) => x.0.iter().all(|(_, e)| is_side_effect_free(e, state, vars)),
+        Expr::ComputedMap(x) => x.0.iter().all(|(k, e)| {
+            is_side_effect_free(k, state, vars) && is_side_effect_free(e, state, vars)
+        }),
+
+        Expr::Index(x, _) | Expr::And(x) | Expr::Or(x) | Expr::In(x) => {
+            is_side_effect_free(&x.lhs, state, vars) && is_side_effect_free(&x.rhs, state, vars)
+        }
+
+        // Property access (but not method calls)
+        Expr::Dot(x, _) => match x.rhs {
+            Expr::Property(_) => is_side_effect_free(&x.lhs, state, vars),
+            _ => false,
+        },
+
+        Expr::FnCall(x) => {
+            let ((name, _, capture, _), modules, _, args, _) = x.as_ref();
+
+            #[cfg(not(feature = "no_function"))]
+            let has_script_fn = state
+                .lib
+                .iter()
+                .any(|&m| m.get_script_fn(name, args.len(), false).is_some());
+            #[cfg(feature = "no_function")]
+            let has_script_fn = false;
+
+            modules.is_none()
+                && !*capture
+                && !IMPURE_KEYWORDS.contains(&name.as_ref())
+                // Script-defined functions may have side effects
+                && !has_script_fn
+                // A variable as the first argument may be changed
+                && (args.is_empty()
+                    || args[0].get_variable_access(true).is_none()
+                    || !may_mutate_first_arg(state, name, args.len()))
+                && args.iter().all(|e| is_side_effect_free(e, state, vars))
+        }
+
+        _ => expr.is_constant(),
+    }
+}
+
+/// Collected information on a loop body for hoisting loop-invariant expressions.
+#[derive(Debug, Default)]
+struct LoopScan<'e> {
+    /// Variables declared within the loop body.
+    declared: Vec<&'e str>,
+    /// Variables that may be modified within the loop body.
+    mutated: Vec<&'e str>,
+    /// Does the loop body contain code that makes the analysis unreliable?
+    bail: bool,
+}
+
+/// Does a dot/index chain contain a method call (which may change the object)?
+fn has_method_call(expr: &Expr) -> bool {
+    match expr {
+        Expr::Dot(x, _) => {
+            matches!(x.rhs, Expr::FnCall(_)) || has_method_call(&x.lhs) || has_method_call(&x.rhs)
+        }
+        Expr::Index(x, _) => has_method_call(&x.lhs) || has_method_call(&x.rhs),
+        _ => false,
+    }
+}
+
+/// Get the variable at the root of a dot/index chain.
+fn chain_root(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::Dot(x, _) | Expr::Index(x, _) => chain_root(&x.lhs),
+        Expr::Variable(x) => Some(&(x.0).0),
+        _ => None,
+    }
+}
+
+/// Scan a statement or an expression within a loop body.
+fn scan_loop_node<'e>(node: ASTNode<'e>, state: &State, scan: &mut LoopScan<'e>) {
+    match node {
+        ASTNode::Stmt(Stmt::Let(name, _, _)) | ASTNode::Stmt(Stmt::Const(name, _, _)) => {
+            scan.declared.push(&name.0)
+        }
+        ASTNode::Stmt(Stmt::Assignment(x, _)) => scan.mutated.extend(chain_root(&x.0)),
+        ASTNode::Stmt(Stmt::For(_, x, _)) => {
+            scan.declared.push(&x.0);
+            scan.declared.extend(x.1.as_deref());
+        }
+        ASTNode::Stmt(Stmt::TryCatch(x)) => scan
+            .declared
+            .extend(x.1.as_ref().map(|(name, _)| name.as_str())),
+        #[cfg(not(feature = "no_closure"))]
+        ASTNode::Stmt(Stmt::Share(name, _)) => scan.mutated.push(name),
+        #[cfg(not(feature = "no_function"))]
+        #[cfg(not(feature = "no_closure"))]
+        ASTNode::Stmt(Stmt::Global(name, _)) => {
+            scan.declared.push(&name.0);
+            scan.mutated.push(&name.0);
+        }
+        // Method calls may change the object, or call a function pointer
+        // which changes any variable it captures
+        ASTNode::Expr(expr @ Expr::Dot(_, _)) | ASTNode::Expr(expr @ Expr::Index(_, _)) => {
+            if has_method_call(expr) {
+                scan.bail = true;
+            }
+        }
+        ASTNode::Expr(Expr::FnCall(x)) => {
+            let ((name, _, capture, _), modules, _, args, _) = x.as_ref();
+
+            #[cfg(not(feature = "no_function"))]
+            let has_script_fn = state
+                .lib
+                .iter()
+                .any(|&m| m.get_script_fn(name, args.len(), false).is_some());
+            #[cfg(feature = "no_function")]
+            let has_script_fn = false;
+
+            if name == KEYWORD_EVAL
+                || name == KEYWORD_FN_PTR_CALL
+                || *capture
+                || modules.is_some()
+                || has_script_fn
+            {
+                // 'eval' can do anything, while function pointers, script-defined functions and
+                // module functions can change any variable shared with a closure
+                scan.bail = true;
+            } else if let Some(var) = args.first().and_then(|e| e.get_variable_access(true)) {
+                // Functions are called with the first argument by reference
+                if may_mutate_first_arg(state, name, args.len()) {
+                    scan.mutated.push(var);
+                }
+            }
+        }
+        // Custom syntax can access the scope directly
+        ASTNode::Expr(Expr::Custom(_)) => scan.bail = true,
+        _ => (),
+    }
+}
+
+/// Can any script-defined function modify global variables (via `global`)?
+fn has_global_decls(_state: &State) -> bool {
+    #[cfg(not(feature = "no_function"))]
+    #[cfg(not(feature = "no_closure"))]
+    return _state.lib.iter().any(|m| {
+        m.iter_script_fn()
+            .any(|(_, _, _, fn_def)| !fn_def.globals.is_empty())
+    });
+
+    #[cfg(any(feature = "no_function", feature = "no_closure"))]
+    return false;
+}
+
+/// Hoist leading `let`/`const` statements with side-effect-free, loop-invariant values out of
+/// the body of a loop.
+///
+/// The statements are hoisted only if they are always run in the first iteration of the loop,
+/// so it is up to the caller to make sure that the loop body runs at least once.
+///
+/// `loop_vars` are the variables defined by the loop itself, and `outer` are the expressions of
+/// the loop (e.g. the condition) that must not be affected by the hoisted variables.
+///
+/// Returns the hoisted statements and the remaining loop body, or the original loop body if
+/// nothing can be hoisted.
+fn hoist_loop_invariants(
+    body: Stmt,
+    loop_vars: &[&str],
+    outer: &[&Expr],
+    state: &State,
+) -> Result<(Vec<Stmt>, Stmt), Stmt> {
+    let (mut statements, pos) = match body {
+        Stmt::Block(statements, pos) => (statements, pos),
+        body => return Err(body),
+    };
+
+    // Any function call may modify global variables
+    if has_global_decls(state) {
+        return Err(Stmt::Block(statements, pos));
+    }
+
+    let mut outer_vars = Vec::new();
+
+    if !outer
+        .iter()
+        .all(|e| is_side_effect_free(e, state, &mut outer_vars))
+    {
+        return Err(Stmt::Block(statements, pos));
+    }
+
+    let mut num_hoisted = 0;
+
+    {
+        let mut scan: LoopScan = Default::default();
+        let mut path = Vec::new();
+        statements.iter().all(|s| {
+            walk_stmt(s, &mut path, &mut |path| {
+                scan_loop_node(*path.last().unwrap(), state, &mut scan);
+                !scan.bail
+            })
+        });
+
+        if scan.bail {
+            return Err(Stmt::Block(statements, pos));
+        }
+
+        let mut hoisted = Vec::new();
+
+        for stmt in statements.iter() {
+            let (name, expr) = match stmt {
+                Stmt::Let(name, Some(expr), _) | Stmt::Const(name, Some(expr), _) => {
+                    (name.0.as_str(), expr)
+                }
+                _ => break,
+            };
+
+            // The variable itself must never change nor be shadowed
+            if scan.mutated.contains(&name)
+                || state.shared.iter().any(|v| v == name)
+                || loop_vars.contains(&name)
+                || outer_vars.contains(&name)
+                || scan.declared.iter().filter(|&&n| n == name).count() > 1
+            {
+                break;
+            }
+
+            // The value must not depend on anything that changes within the loop
+            let mut vars = Vec::new();
+
+            if !is_side_effect_free(expr, state, &mut vars)
+                || vars.iter().any(|v| {
+                    scan.mutated.contains(v)
+                        || state.shared.iter().any(|s| s == v)
+                        || loop_vars.contains(v)
+                        || (scan.declared.contains(v) && !hoisted.contains(v))
+                })
+            {
+                break;
+            }
+
+            hoisted.push(name);
+            num_hoisted += 1;
+        }
+    }
+
+    if num_hoisted == 0 {
+        return Err(Stmt::Block(statements, pos));
+    }
+
+    let body = statements.split_off(num_hoisted);
+    Ok((statements, Stmt::Block(body, pos)))
+}
+
+/// Does an expression always yield a non-empty iterator for a `for` loop?
+fn is_non_empty_iterable(expr: &Expr, state: &State) -> bool {
+    match expr {
+        #[cfg(not(feature = "no_index"))]
+        Expr::Array(x) => !x.0.is_empty(),
+
+        // range(from, to)
+        Expr::FnCall(x) if x.1.is_none() && (x.0).0 == "range" && x.3.len() == 2 => {
+            match (&x.3[0], &x.3[1]) {
+                (Expr::IntegerConstant(from), Expr::IntegerConstant(to)) if from.0 < to.0 => {
+                    // Make sure that `range` is not overridden
+                    let arg_types = [TypeId::of::<INT>(), TypeId::of::<INT>()];
+                    let hash_fn = calc_fn_hash(empty(), "range", 2, arg_types.iter().cloned());
+
+                    #[cfg(not(feature = "no_function"))]
+                    let has_script_fn = state
+                        .lib
+                        .iter()
+                        .any(|&m| m.get_script_fn("range", 2, false).is_some());
+                    #[cfg(feature = "no_function")]
+                    let has_script_fn = false;
+
+                    !has_script_fn && !state.engine.global_module.contains_fn(hash_fn, false)
+                }
+                _ => false,
+            }
+        }
+
+        _ => false,
+    }
+}
+
+/// Clear the cached stack offsets of all variables within a statement, forcing them
+/// to be searched by name.
+///
+/// This is necessary when statements are moved such that the layout of the stack changes.
+fn clear_var_indices_stmt(stmt: &mut Stmt) {
+    walk_stmt_mut(stmt, &mut clear_var_index);
+}
+
+/// Clear the cached stack offsets of all variables within an expression, forcing them
+/// to be searched by name.
+pub(crate) fn clear_var_indices_expr(expr: &mut Expr) {
+    walk_expr_mut(expr, &mut clear_var_index);
+}
+
+/// Clear the cached stack offset of a variable.
+fn clear_var_index(expr: &mut Expr) {
+    if let Expr::Variable(x) = expr {
+        x.3 = None;
+    }
+}
+
+/// Collect the names of all variables captured by closures within a statement.
+#[cfg(not(feature = "no_closure"))]
+fn collect_shared_vars(stmt: &Stmt, shared: &mut Vec<String>) {
+    walk_stmt(stmt, &mut Vec::new(), &mut |path| {
+        if let Some(ASTNode::Stmt(Stmt::Share(name, _))) = path.last() {
+            shared.push(name.clone());
+        }
+        true
+    });
+}
+
+/// Can a statement raise an exception that is catchable by `try` ... `catch`?
+///
+/// Unlike `Stmt::is_pure`, this does not care about side effects, only about errors.
+/// It is conservative: any function call, property access, indexing operation or access to a
+/// variable not declared in the script counts as possibly throwing.
+fn can_throw_stmt(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Noop(_) | Stmt::Continue(_) | Stmt::Break(_) => false,
+        Stmt::Let(_, expr, _) | Stmt::Const(_, expr, _) => expr.iter().any(can_throw_expr),
+        // Only a normal assignment to a variable declared in the script is safe
+        Stmt::Assignment(x, _) => match &x.0 {
+            Expr::Variable(v) if v.1.is_none() && v.3.is_some() && x.1.is_empty() => {
+                can_throw_expr(&x.2)
+            }
+            _ => true,
+        },
+        // The guard must be a boolean value
+        Stmt::IfThenElse(condition, x, _) => {
+            !is_bool_expr(condition)
+                || can_throw_stmt(&x.0)
+                || x.1.iter().any(can_throw_stmt)
+        }
+        Stmt::While(condition, block, _) => !is_bool_expr(condition) || can_throw_stmt(block),
+        Stmt::Loop(block, _) => can_throw_stmt(block),
+        Stmt::Block(statements, _) => statements.iter().any(can_throw_stmt),
+        // Errors in the try block are caught
+        Stmt::TryCatch(x) => can_throw_stmt(&(x.2).0),
+        Stmt::Expr(expr) => can_throw_expr(expr),
+        Stmt::ReturnWithVal((ReturnType::Return, _), expr, _) => expr.iter().any(can_throw_expr),
+        Stmt::ReturnWithVal((ReturnType::Exception, _), _, _) => true,
+        #[cfg(not(feature = "no_closure"))]
+        Stmt::Share(_, _) => false,
+        // Iterating a value may fail, and global variables, imports and exports may not be found
+        _ => true,
+    }
+}
+
+/// Can an expression raise an exception that is catchable by `try` ... `catch`?
+///
+/// See `can_throw_stmt`.
+fn can_throw_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Expr(x) => can_throw_expr(x),
+        Expr::Stmt(x) => can_throw_stmt(&x.0),
+        Expr::Variable(x) => x.1.is_some() || x.3.is_none(),
+        Expr::Array(x) => x.0.iter().any(can_throw_expr),
+        Expr::Map(x) => x.0.iter().any(|(_, e)| can_throw_expr(e)),
+        // Both operands must be boolean values
+        Expr::And(x) | Expr::Or(x) => {
+            !is_bool_expr(&x.lhs)
+                || !is_bool_expr(&x.rhs)
+                || can_throw_expr(&x.lhs)
+                || can_throw_expr(&x.rhs)
+        }
+        Expr::FnCall(_)
+        | Expr::Dot(_, _)
+        | Expr::Index(_, _)
+        | Expr::In(_)
+        | Expr::ComputedMap(_)
+        | Expr::Custom(_) => true,
+        _ => !expr.is_constant(),
+    }
+}
+
+/// Does an expression always evaluate to a boolean value?
+fn is_bool_expr(expr: &Expr) -> bool {
+    expr.get_type_id() == Some(TypeId::of::<bool>())
+}
+
+/// Binary operators with a compound assignment form (e.g. `+` and `+=`).
+const OP_ASSIGNMENT_OPERATORS: &[&str] = &["+", "-", "*", "/", "%", "~", "<<", ">>", "&", "|", "^"];
+
+/// Are two expressions the same non-qualified variable?
+fn is_same_variable(expr1: &Expr, expr2: &Expr) -> bool {
+    match (expr1, expr2) {
+        (Expr::Variable(x1), Expr::Variable(x2)) => {
+            x1.1.is_none() && x2.1.is_none() && (x1.0).0 == (x2.0).0
+        }
+        _ => false,
+    }
+}
+
 /// Optimize a statement.
 fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
     match stmt {
         // id op= expr
-        Stmt::Assignment(x, pos) => Stmt::Assignment(
-            Box::new((optimize_expr(x.0, state), x.1, optimize_expr(x.2, state))),
-            pos,
-        ),
+        Stmt::Assignment(x, pos) => {
+            let (lhs, op, rhs) = *x;
+            let lhs = optimize_expr(lhs, state);
+
+            match optimize_expr(rhs, state) {
+                // id = id op expr -> id op= expr
+                Expr::FnCall(mut x)
+                    if op.is_empty()
+                        && (x.0).1
+                        && x.1.is_none()
+                        && x.3.len() == 2
+                        && OP_ASSIGNMENT_OPERATORS.contains(&(x.0).0.as_ref())
+                        && is_same_variable(&lhs, &x.3[0])
+                        // The operand must not change the variable before it is read
+                        && x.3[1].is_pure() =>
+                {
+                    state.set_dirty();
+                    let op = format!("{}=", (x.0).0);
+                    let rhs = x.3.pop().unwrap();
+                    Stmt::Assignment(Box::new((lhs, op.into(), rhs)), pos)
+                }
+                rhs => Stmt::Assignment(Box::new((lhs, op, rhs)), pos),
+            }
+        }
         // if false { if_block } -> Noop
         Stmt::IfThenElse(Expr::False(pos), x, _) if x.1.is_none() => {
             state.set_dirty();
@@ -195,11 +784,19 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
             }
         }
         // if expr { if_block }
-        Stmt::IfThenElse(condition, x, pos) if x.1.is_none() => Stmt::IfThenElse(
-            optimize_expr(condition, state),
-            Box::new((optimize_stmt(x.0, state, true), None)),
-            pos,
-        ),
+        Stmt::IfThenElse(condition, x, pos) if x.1.is_none() => {
+            match optimize_expr(condition, state) {
+                // The condition is folded into a constant - prune the branch in the same pass
+                condition @ Expr::True(_) | condition @ Expr::False(_) => {
+                    optimize_stmt(Stmt::IfThenElse(condition, x, pos), state, preserve_result)
+                }
+                condition => Stmt::IfThenElse(
+                    condition,
+                    Box::new((optimize_stmt(x.0, state, true), None)),
+                    pos,
+                ),
+            }
+        }
         // if false { if_block } else { else_block } -> else_block
         Stmt::IfThenElse(Expr::False(_), x, _) if x.1.is_some() => {
             optimize_stmt(x.1.unwrap(), state, true)
@@ -207,17 +804,23 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
         // if true { if_block } else { else_block } -> if_block
         Stmt::IfThenElse(Expr::True(_), x, _) => optimize_stmt(x.0, state, true),
         // if expr { if_block } else { else_block }
-        Stmt::IfThenElse(condition, x, pos) => Stmt::IfThenElse(
-            optimize_expr(condition, state),
-            Box::new((
-                optimize_stmt(x.0, state, true),
-                match optimize_stmt(x.1.unwrap(), state, true) {
-                    Stmt::Noop(_) => None, // Noop -> no else block
-                    stmt => Some(stmt),
-                },
-            )),
-            pos,
-        ),
+        Stmt::IfThenElse(condition, x, pos) => match optimize_expr(condition, state) {
+            // The condition is folded into a constant - prune the branches in the same pass
+            condition @ Expr::True(_) | condition @ Expr::False(_) => {
+                optimize_stmt(Stmt::IfThenElse(condition, x, pos), state, preserve_result)
+            }
+            condition => Stmt::IfThenElse(
+                condition,
+                Box::new((
+                    optimize_stmt(x.0, state, true),
+                    match optimize_stmt(x.1.unwrap(), state, true) {
+                        Stmt::Noop(_) => None, // Noop -> no else block
+                        stmt => Some(stmt),
+                    },
+                )),
+                pos,
+            ),
+        },
 
         // while false { block } -> Noop
         Stmt::While(Expr::False(pos), _, _) => {
@@ -242,6 +845,26 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
                     }
                     Stmt::Block(statements, pos)
                 }
+                // while expr { let x = invariant; block } -> if expr { let x = invariant; while expr { block } }
+                // The condition is run one more time, so it must be free of side effects.
+                stmt if state.optimization_level == OptimizationLevel::Full => {
+                    let condition = optimize_expr(condition, state);
+
+                    match hoist_loop_invariants(stmt, &[], &[&condition], state) {
+                        Ok((mut statements, block)) => {
+                            state.set_dirty();
+                            let mut guard = condition.clone();
+                            clear_var_indices_expr(&mut guard);
+                            statements.push(Stmt::While(guard, Box::new(block), pos));
+                            Stmt::IfThenElse(
+                                condition,
+                                Box::new((Stmt::Block(statements, pos), None)),
+                                pos,
+                            )
+                        }
+                        Err(block) => Stmt::While(condition, Box::new(block), pos),
+                    }
+                }
                 // while expr { block }
                 stmt => Stmt::While(optimize_expr(condition, state), Box::new(stmt), pos),
             }
@@ -254,22 +877,73 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
                 state.set_dirty();
                 Stmt::Noop(pos)
             }
+            // loop { let x = invariant; block } -> { let x = invariant; loop { block } }
+            stmt if state.optimization_level == OptimizationLevel::Full => {
+                match hoist_loop_invariants(stmt, &[], &[], state) {
+                    Ok((mut statements, block)) => {
+                        state.set_dirty();
+                        statements.push(Stmt::Loop(Box::new(block), pos));
+                        Stmt::Block(statements, pos)
+                    }
+                    Err(block) => Stmt::Loop(Box::new(block), pos),
+                }
+            }
             // loop { block }
             stmt => Stmt::Loop(Box::new(stmt), pos),
         },
         // for id in expr { block }
         Stmt::For(iterable, x, pos) => {
-            let (var_name, block) = *x;
-            Stmt::For(
-                optimize_expr(iterable, state),
-                Box::new((var_name, optimize_stmt(block, state, false))),
-                pos,
-            )
+            let (var_name, value_name, block) = *x;
+            let mut iterable = optimize_expr(iterable, state);
+
+            // Loop variables shadow constants within the body
+            let orig_constants_len = state.constants.len();
+            state.shadow_constant(&var_name);
+            value_name
+                .iter()
+                .for_each(|name| state.shadow_constant(name));
+            let mut block = optimize_stmt(block, state, false);
+            state.restore_constants(orig_constants_len);
+
+            // for id in [ ... ] { let x = invariant; block } -> { let x = invariant; for id in [ ... ] { block } }
+            // The loop body must run at least once.
+            if state.optimization_level == OptimizationLevel::Full
+                && is_non_empty_iterable(&iterable, state)
+            {
+                let mut loop_vars = vec![var_name.as_str()];
+                loop_vars.extend(value_name.as_deref());
+
+                match hoist_loop_invariants(block, &loop_vars, &[&iterable], state) {
+                    Ok((mut statements, mut body)) => {
+                        state.set_dirty();
+                        // Variables are now pushed in a different order, so stack offsets are invalid
+                        statements.iter_mut().for_each(clear_var_indices_stmt);
+                        clear_var_indices_stmt(&mut body);
+                        clear_var_indices_expr(&mut iterable);
+                        statements.push(Stmt::For(
+                            iterable,
+                            Box::new((var_name, value_name, body)),
+                            pos,
+                        ));
+                        return Stmt::Block(statements, pos);
+                    }
+                    Err(body) => block = body,
+                }
+            }
+
+            Stmt::For(iterable, Box::new((var_name, value_name, block)), pos)
         }
         // let id = expr;
-        Stmt::Let(name, Some(expr), pos) => Stmt::Let(name, Some(optimize_expr(expr, state)), pos),
+        Stmt::Let(name, Some(expr), pos) => {
+            let expr = optimize_expr(expr, state);
+            state.shadow_constant(&name.0);
+            Stmt::Let(name, Some(expr), pos)
+        }
         // let id;
-        stmt @ Stmt::Let(_, None, _) => stmt,
+        Stmt::Let(name, None, pos) => {
+            state.shadow_constant(&name.0);
+            Stmt::Let(name, None, pos)
+        }
         // import expr as var;
         #[cfg(not(feature = "no_module"))]
         Stmt::Import(expr, alias, pos) => Stmt::Import(optimize_expr(expr, state), alias, pos),
@@ -278,6 +952,8 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
             let orig_len = statements.len(); // Original number of statements in the block, for change detection
             let orig_constants_len = state.constants.len(); // Original number of constants in the state, for restore later
 
+            let mut constants_removed = false;
+
             // Optimize each statement in the block
             let mut result: Vec<_> = statements
                 .into_iter()
@@ -286,6 +962,7 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
                     Stmt::Const(name, Some(expr), pos) if expr.is_literal() => {
                         state.set_dirty();
                         state.push_constant(&name.0, expr);
+                        constants_removed = true;
                         Stmt::Noop(pos) // No need to keep constants
                     }
                     Stmt::Const(name, Some(expr), pos) if expr.is_literal() => {
@@ -295,6 +972,7 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
                     Stmt::Const(name, None, pos) => {
                         state.set_dirty();
                         state.push_constant(&name.0, Expr::Unit(name.1));
+                        constants_removed = true;
                         Stmt::Noop(pos) // No need to keep constants
                     }
                     // Optimize the statement
@@ -302,10 +980,18 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
                 })
                 .collect();
 
+            // Removing constants changes the layout of the stack,
+            // so variables declared before them must be searched by name
+            if constants_removed {
+                result.iter_mut().for_each(clear_var_indices_stmt);
+            }
+
             // Remove all raw expression statements that are pure except for the very last statement
             let last_stmt = if preserve_result { result.pop() } else { None };
 
-            result.retain(|stmt| !stmt.is_pure());
+            // Inside a try block, keep statements that may throw so the catch block still runs
+            let in_try_block = state.in_try_block;
+            result.retain(|stmt| !stmt.is_pure() || (in_try_block && can_throw_stmt(stmt)));
 
             if let Some(stmt) = last_stmt {
                 result.push(stmt);
@@ -313,18 +999,27 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
 
             // Remove all let/import statements at the end of a block - the new variables will go away anyway.
             // But be careful only remove ones that have no initial values or have values that are pure expressions,
-            // otherwise there may be side effects.
+            // otherwise there may be side effects.  A let statement with an impure initial value is turned
+            // into a simple expression statement, so the block no longer needs to hold a new variable.
             let mut removed = false;
 
-            while let Some(expr) = result.pop() {
-                match expr {
-                    Stmt::Let(_, expr, _) => {
-                        removed = expr.as_ref().map(Expr::is_pure).unwrap_or(true)
+            while let Some(stmt) = result.pop() {
+                match stmt {
+                    Stmt::Let(_, None, _) => removed = true,
+                    Stmt::Let(_, Some(expr), _)
+                        if expr.is_pure() && !(in_try_block && can_throw_expr(&expr)) =>
+                    {
+                        removed = true
+                    }
+                    Stmt::Let(_, Some(expr), _) => {
+                        removed = true;
+                        result.push(Stmt::Expr(expr));
+                        break;
                     }
                     #[cfg(not(feature = "no_module"))]
-                    Stmt::Import(expr, _, _) => removed = expr.is_pure(),
-                    _ => {
-                        result.push(expr);
+                    Stmt::Import(expr, _, _) if expr.is_pure() => removed = true,
+                    stmt => {
+                        result.push(stmt);
                         break;
                     }
                 }
@@ -389,8 +1084,8 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
             }
         }
         // try { block } catch ( var ) { block }
-        Stmt::TryCatch(x) if (x.0).0.is_pure() => {
-            // If try block is pure, there will never be any exceptions
+        Stmt::TryCatch(x) if !can_throw_stmt(&(x.0).0) => {
+            // If try block cannot throw, there will never be any exceptions
             state.set_dirty();
             let pos = (x.0).0.position();
             let mut statements: Vec<_> = Default::default();
@@ -401,10 +1096,22 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
         // try { block } catch ( var ) { block }
         Stmt::TryCatch(x) => {
             let ((try_block, try_pos), var_name, (catch_block, catch_pos)) = *x;
+            let in_try_block = mem::replace(&mut state.in_try_block, true);
+            let try_block = optimize_stmt(try_block, state, false);
+            state.in_try_block = in_try_block;
+
+            // The catch variable shadows constants within the catch block
+            let orig_constants_len = state.constants.len();
+            var_name
+                .iter()
+                .for_each(|(name, _)| state.shadow_constant(name));
+            let catch_block = optimize_stmt(catch_block, state, false);
+            state.restore_constants(orig_constants_len);
+
             Stmt::TryCatch(Box::new((
-                (optimize_stmt(try_block, state, false), try_pos),
+                (try_block, try_pos),
                 var_name,
-                (optimize_stmt(catch_block, state, false), catch_pos),
+                (catch_block, catch_pos),
             )))
         }
         // expr;
@@ -423,8 +1130,114 @@ fn optimize_stmt(stmt: Stmt, state: &mut State, preserve_result: bool) -> Stmt {
     }
 }
 
+/// Collect the operands of a left-associative chain of `+` operations (or a previously-rewritten
+/// string concatenation).
+fn collect_concat_operands(expr: Expr, operands: &mut StaticVec<Expr>) {
+    match expr {
+        Expr::FnCall(x)
+            if x.1.is_none()
+                && !(x.0).2
+                && (((x.0).0 == "+" && x.3.len() == 2) || (x.0).0 == FN_CONCAT) =>
+        {
+            let is_concat = (x.0).0 == FN_CONCAT;
+            let mut args = x.3.into_iter();
+
+            if is_concat {
+                operands.extend(args);
+            } else {
+                collect_concat_operands(args.next().unwrap(), operands);
+                operands.push(args.next().unwrap());
+            }
+        }
+        expr => operands.push(expr),
+    }
+}
+
+/// Rewrite a chain of `+` operations starting with a string constant,
+/// e.g. `"x = " + x + ", y = " + y`, into a single call that concatenates all the operands,
+/// avoiding the allocation of intermediate strings.
+///
+/// Only chains of at least three side-effect-free operands are rewritten, so that evaluating all
+/// the operands before concatenation is not observable.
+fn optimize_string_concat(expr: Expr, state: &mut State) -> Expr {
+    let pos = match &expr {
+        Expr::FnCall(x)
+            if x.1.is_none()
+                && !(x.0).2
+                && (x.0).0 == "+"
+                && x.3.len() == 2
+                && matches!(&x.3[0], Expr::FnCall(y) if (y.0).0 == "+" || (y.0).0 == FN_CONCAT) =>
+        {
+            (x.0).3
+        }
+        _ => return expr,
+    };
+
+    // Overloaded string operators (can override built-in)
+    if state.engine.has_override_by_name_and_arguments(
+        state.lib,
+        "+",
+        [
+            TypeId::of::<ImmutableString>(),
+            TypeId::of::<ImmutableString>(),
+        ],
+        false,
+    ) {
+        return expr;
+    }
+
+    let mut operands: StaticVec<_> = Default::default();
+    collect_concat_operands(expr.clone(), &mut operands);
+
+    if operands.len() < 3
+        || !matches!(operands[0], Expr::StringConstant(_))
+        || !operands.iter().all(Expr::is_pure)
+    {
+        return expr;
+    }
+
+    state.set_dirty();
+
+    let hash = calc_fn_hash(empty(), FN_CONCAT, operands.len(), empty());
+    Expr::FnCall(Box::new((
+        (FN_CONCAT.into(), true, false, pos),
+        None,
+        hash,
+        operands,
+        None,
+    )))
+}
+
 /// Optimize an expression.
 fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
+    let expr = optimize_expr_builtin(expr, state);
+
+    if state.engine.optimizer_passes.is_empty() {
+        return expr;
+    }
+
+    // Run optimization passes registered with the engine
+    let context = OptimizeContext {
+        constants: &state.constants,
+        optimization_level: state.optimization_level,
+        changed: Cell::new(false),
+    };
+
+    let expr = state
+        .engine
+        .optimizer_passes
+        .iter()
+        .fold(expr, |expr, pass| pass(expr, &context));
+
+    if context.changed.get() {
+        state.set_dirty();
+    }
+
+    expr
+}
+
+/// Optimize an expression with the built-in optimizations.
+fn optimize_expr_builtin(expr: Expr, state: &mut State) -> Expr {
     // These keywords are handled specially
     const DONT_EVAL_KEYWORDS: &[&str] = &[
         KEYWORD_PRINT,      // side effects
@@ -447,7 +1260,12 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
             // { expr } -> expr
             Stmt::Expr(expr) => {
                 state.set_dirty();
-                optimize_expr(expr, state)
+                let mut expr = optimize_expr(expr, state);
+                // A constant takes the place of the statement block
+                if expr.is_constant() {
+                    expr.set_position(x.1);
+                }
+                expr
             }
             // { stmt }
             stmt => Expr::Stmt(Box::new((optimize_stmt(stmt, state, true), x.1))),
@@ -455,7 +1273,7 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
 
         // lhs.rhs
         #[cfg(not(feature = "no_object"))]
-        Expr::Dot(x) => match (x.lhs, x.rhs) {
+        Expr::Dot(x, is_optional) => match (x.lhs, x.rhs) {
             // map.string
             (Expr::Map(m), Expr::Property(p)) if m.0.iter().all(|(_, x)| x.is_pure()) => {
                 let ((prop, _, _), _) = p.as_ref();
@@ -472,12 +1290,12 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
                 lhs: optimize_expr(lhs, state),
                 rhs: optimize_expr(rhs, state),
                 pos: x.pos
-            }))
+            }), is_optional)
         }
 
         // lhs[rhs]
         #[cfg(not(feature = "no_index"))]
-        Expr::Index(x) => match (x.lhs, x.rhs) {
+        Expr::Index(x, is_optional) => match (x.lhs, x.rhs) {
             // array[int]
             (Expr::Array(mut a), Expr::IntegerConstant(i))
                 if i.0 >= 0 && (i.0 as usize) < a.0.len() && a.0.iter().all(Expr::is_pure) =>
@@ -510,7 +1328,7 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
                 lhs: optimize_expr(lhs, state),
                 rhs: optimize_expr(rhs, state),
                 pos: x.pos
-            })),
+            }), is_optional),
         },
         // [ items .. ]
         #[cfg(not(feature = "no_index"))]
@@ -522,25 +1340,30 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
         Expr::Map(m) => Expr::Map(Box::new((m.0
                             .into_iter().map(|((key, pos), expr)| ((key, pos), optimize_expr(expr, state)))
                             .collect(), m.1))),
+        // #{ (key): value .. }
+        #[cfg(not(feature = "no_object"))]
+        Expr::ComputedMap(m) => Expr::ComputedMap(Box::new((m.0
+                            .into_iter().map(|(key, expr)| (optimize_expr(key, state), optimize_expr(expr, state)))
+                            .collect(), m.1))),
         // lhs in rhs
         Expr::In(x) => match (x.lhs, x.rhs) {
             // "xxx" in "xxxxx"
             (Expr::StringConstant(a), Expr::StringConstant(b)) => {
                 state.set_dirty();
-                if b.0.contains(a.0.as_str()) { Expr::True(a.1) } else { Expr::False(a.1) }
+                if b.0.contains(a.0.as_str()) { Expr::True(x.pos) } else { Expr::False(x.pos) }
             }
             // 'x' in "xxxxx"
             (Expr::CharConstant(a), Expr::StringConstant(b)) => {
                 state.set_dirty();
-                if b.0.contains(a.0) { Expr::True(a.1) } else { Expr::False(a.1) }
+                if b.0.contains(a.0) { Expr::True(x.pos) } else { Expr::False(x.pos) }
             }
             // "xxx" in #{...}
             (Expr::StringConstant(a), Expr::Map(b)) => {
                 state.set_dirty();
                 if b.0.iter().find(|((name, _), _)| *name == a.0).is_some() {
-                    Expr::True(a.1)
+                    Expr::True(x.pos)
                 } else {
-                    Expr::False(a.1)
+                    Expr::False(x.pos)
                 }
             }
             // 'x' in #{...}
@@ -549,9 +1372,9 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
                 let ch = a.0.to_string();
 
                 if b.0.iter().find(|((name, _), _)| name == &ch).is_some() {
-                    Expr::True(a.1)
+                    Expr::True(x.pos)
                 } else {
-                    Expr::False(a.1)
+                    Expr::False(x.pos)
                 }
             }
             // lhs in rhs
@@ -569,9 +1392,9 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
                 rhs
             }
             // false && rhs -> false
-            (Expr::False(pos), _) => {
+            (Expr::False(_), _) => {
                 state.set_dirty();
-                Expr::False(pos)
+                Expr::False(x.pos)
             }
             // lhs && true -> lhs
             (lhs, Expr::True(_)) => {
@@ -593,9 +1416,9 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
                 rhs
             }
             // true || rhs -> true
-            (Expr::True(pos), _) => {
+            (Expr::True(_), _) => {
                 state.set_dirty();
-                Expr::True(pos)
+                Expr::True(x.pos)
             }
             // lhs || false
             (lhs, Expr::False(_)) => {
@@ -610,12 +1433,105 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
             })),
         },
 
+        // is_def_fn("name", num_params) where the function is defined in the script
+        #[cfg(not(feature = "no_function"))]
+        Expr::FnCall(x)
+                if x.1.is_none() // Non-qualified
+                && state.optimization_level == OptimizationLevel::Full // full optimizations
+                && (x.0).0 == KEYWORD_IS_DEF_FN
+                && matches!(
+                    x.3.as_slice(),
+                    [Expr::StringConstant(name), Expr::IntegerConstant(num_params)]
+                        if num_params.0 >= 0
+                        // Script-defined functions cannot be removed at runtime
+                        && state.lib.iter().any(|&m| m.get_script_fn(&name.0, num_params.0 as usize, false).is_some())
+                )
+                && !state.engine.has_override_by_name_and_arguments(state.lib, KEYWORD_IS_DEF_FN,
+                                                [TypeId::of::<ImmutableString>(), TypeId::of::<INT>()], false)
+        => {
+            state.set_dirty();
+            Expr::True((x.0).3)
+        }
+
         // Do not call some special keywords
         Expr::FnCall(mut x) if DONT_EVAL_KEYWORDS.contains(&(x.0).0.as_ref()) => {
             x.3 = x.3.into_iter().map(|a| optimize_expr(a, state)).collect();
             Expr::FnCall(x)
         }
 
+        // type_of(constant)
+        Expr::FnCall(x)
+                if x.1.is_none() // Non-qualified
+                && !state.optimization_level.is_none() // optimizations on
+                && (x.0).0 == KEYWORD_TYPE_OF
+                && x.3.len() == 1 // single argument
+                && x.3[0].is_constant() // argument is a constant
+                && x.3[0].get_type_id().is_some() // type of argument is known
+                && !state.engine.has_override_by_name_and_arguments(state.lib, KEYWORD_TYPE_OF, [x.3[0].get_type_id().unwrap()], false)
+        => {
+            let pos = (x.0).3;
+            let type_name = state.engine.map_type_name(x.3[0].get_constant_value().unwrap().type_name()).to_string();
+            state.set_dirty();
+            Expr::StringConstant(Box::new((type_name.into(), pos)))
+        }
+
+        // Call built-in unary operators
+        Expr::FnCall(x)
+                if x.1.is_none() // Non-qualified
+                && state.optimization_level == OptimizationLevel::Simple // simple optimizations
+                && x.3.len() == 1 // unary call
+                && x.3[0].is_constant() // argument is a constant
+                && ((x.0).0 == "!" || (x.0).0 == "-")
+        => {
+            let ((name, _, _, pos), _, _, args, _) = x.as_ref();
+
+            let arg_value = args[0].get_constant_value().unwrap();
+            let hash_fn = calc_fn_hash(empty(), name, 1, once(arg_value.type_id()));
+
+            // Search for overloaded operators (can override built-in).
+            // The standard packages implement these operators, so only check registered functions.
+            if !state.engine.global_module.contains_fn(hash_fn, false) {
+                if let Some(expr) = run_builtin_unary_op(name, &arg_value)
+                                        .ok().flatten()
+                                        .and_then(|result| map_dynamic_to_expr(result, *pos))
+                {
+                    state.set_dirty();
+                    return expr;
+                }
+            }
+
+            Expr::FnCall(x)
+        }
+
+        // Fold array concatenation and repetition
+        #[cfg(not(feature = "no_index"))]
+        Expr::FnCall(x)
+                if x.1.is_none() // Non-qualified
+                && state.optimization_level == OptimizationLevel::Simple // simple optimizations
+                && x.3.len() == 2 // binary call
+                && matches!(x.3[0], Expr::Array(_)) // array on the left
+                && x.3.iter().all(Expr::is_constant) // all arguments are constants
+                && ((x.0).0 == "+" || (x.0).0 == "*")
+        => {
+            let ((name, _, _, pos), _, _, args, _) = x.as_ref();
+
+            let arg_values: StaticVec<_> = args.iter().map(|e| e.get_constant_value().unwrap()).collect();
+            let hash_fn = calc_fn_hash(empty(), name, 2, arg_values.iter().map(Dynamic::type_id));
+
+            // Search for overloaded operators (can override built-in).
+            // The standard packages implement these operators, so only check registered functions.
+            if !state.engine.global_module.contains_fn(hash_fn, false) {
+                if let Some(expr) = fold_array_op(state.engine, name, arg_values)
+                                        .and_then(|result| map_dynamic_to_expr(result, *pos))
+                {
+                    state.set_dirty();
+                    return expr;
+                }
+            }
+
+            Expr::FnCall(x)
+        }
+
         // Call built-in operators
         Expr::FnCall(mut x)
                 if x.1.is_none() // Non-qualified
@@ -693,7 +1609,9 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
         // id(args ..) -> optimize function call arguments
         Expr::FnCall(mut x) => {
             x.3 = x.3.into_iter().map(|a| optimize_expr(a, state)).collect();
-            Expr::FnCall(x)
+
+            // "string" + expr + expr ... -> concat$("string", expr, expr, ...)
+            optimize_string_concat(Expr::FnCall(x), state)
         }
 
         // constant-name
@@ -708,13 +1626,30 @@ fn optimize_expr(expr: Expr, state: &mut State) -> Expr {
         }
 
         // Custom syntax
-        #[cfg(feature = "internals")]
-        Expr::Custom(x) => Expr::Custom(Box::new((
-            CustomExpr(
-                (x.0).0.into_iter().map(|expr| optimize_expr(expr, state)).collect(),
-                (x.0).1),
-            x.1
-        ))),
+        Expr::Custom(x) => {
+            let CustomExpr { keywords, func, pure, pos } = *x;
+
+            // Leave variables alone because they may be identifiers in the custom syntax
+            let keywords: StaticVec<_> = keywords.into_iter().map(|expr| match expr {
+                expr @ Expr::Variable(_) => expr,
+                expr => optimize_expr(expr, state),
+            }).collect();
+
+            // Eagerly evaluate pure custom syntax with all constant inputs
+            if pure
+                && state.optimization_level == OptimizationLevel::Full // full optimizations
+                && keywords.iter().all(Expr::is_constant) // all inputs are constants
+            {
+                if let Some(expr) = call_custom_syntax_with_constant_inputs(&state, func.as_ref(), &keywords)
+                                        .and_then(|result| map_dynamic_to_expr(result, pos))
+                {
+                    state.set_dirty();
+                    return expr;
+                }
+            }
+
+            Expr::Custom(Box::new(CustomExpr { keywords, func, pure, pos }))
+        }
 
         // All other expressions - skip
         expr => expr,
@@ -727,6 +1662,7 @@ fn optimize(
     scope: &Scope,
     lib: &[&Module],
     level: OptimizationLevel,
+    shared: Vec<String>,
 ) -> Vec<Stmt> {
     // If optimization level is None then skip optimizing
     if level == OptimizationLevel::None {
@@ -736,6 +1672,20 @@ fn optimize(
     // Set up the state
     let mut state = State::new(engine, lib, level);
 
+    // Variables captured by closures, or shared values from the scope, may be modified
+    // by any call to a closure
+    state.shared = shared;
+    #[cfg(not(feature = "no_closure"))]
+    {
+        statements
+            .iter()
+            .for_each(|s| collect_shared_vars(s, &mut state.shared));
+        scope
+            .to_iter()
+            .filter(|ScopeEntry { value, .. }| value.is_shared())
+            .for_each(|ScopeEntry { name, .. }| state.shared.push(name.to_string()));
+    }
+
     // Add constants from the scope into the state
     scope
         .to_iter()
@@ -829,6 +1779,230 @@ fn optimize(
     result
 }
 
+/// Prefix of the names of constants synthesized to share identical literals.
+/// It is not a valid identifier, so the names never clash with variables in a script.
+pub(crate) const SHARED_LITERAL_PREFIX: &str = "lit$";
+
+/// Is the expression a non-empty array or object map literal that is fully constant?
+fn is_shareable_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::Array(x) => !x.0.is_empty() && expr.is_constant(),
+        Expr::Map(x) => !x.0.is_empty() && expr.is_constant(),
+        _ => false,
+    }
+}
+
+/// Calculate a hash of a constant literal, ignoring positions and the order of map properties,
+/// so that structurally identical literals have the same hash.
+fn hash_literal(expr: &Expr) -> u64 {
+    fn normalize(expr: &Expr) -> Expr {
+        match expr {
+            Expr::Array(x) => Expr::Array(Box::new((
+                x.0.iter().map(normalize).collect(),
+                Position::none(),
+            ))),
+            Expr::Map(x) => {
+                let mut items: StaticVec<_> =
+                    x.0.iter()
+                        .map(|((k, _), v)| ((k.clone(), Position::none()), normalize(v)))
+                        .collect();
+                items.sort_by(|((a, _), _), ((b, _), _)| a.cmp(b));
+                Expr::Map(Box::new((items, Position::none())))
+            }
+            expr => {
+                let mut expr = expr.clone();
+                expr.set_position(Position::none());
+                expr
+            }
+        }
+    }
+
+    #[cfg(feature = "no_std")]
+    let mut s: AHasher = Default::default();
+    #[cfg(not(feature = "no_std"))]
+    let mut s = DefaultHasher::new();
+
+    normalize(expr).hash(&mut s);
+    s.finish()
+}
+
+/// Are two constant literals structurally identical, ignoring positions and the order of
+/// map properties?
+fn literals_eq(a: &Expr, b: &Expr) -> bool {
+    match (a, b) {
+        (Expr::Expr(a), b) | (b, Expr::Expr(a)) => literals_eq(a, b),
+
+        (Expr::IntegerConstant(x), Expr::IntegerConstant(y)) => x.0 == y.0,
+        // Compare the bits so that 0.0 and -0.0 are kept apart
+        #[cfg(not(feature = "no_float"))]
+        (Expr::FloatConstant(x), Expr::FloatConstant(y)) => x.0.to_bits() == y.0.to_bits(),
+        // Decimals with the same value but different scales (e.g. 1.5 and 1.50) print differently
+        #[cfg(feature = "decimal")]
+        (Expr::DecimalConstant(x), Expr::DecimalConstant(y)) => {
+            x.0 == y.0 && x.0.scale() == y.0.scale()
+        }
+        (Expr::CharConstant(x), Expr::CharConstant(y)) => x.0 == y.0,
+        (Expr::StringConstant(x), Expr::StringConstant(y))
+        | (Expr::FnPointer(x), Expr::FnPointer(y)) => x.0 == y.0,
+        (Expr::True(_), Expr::True(_))
+        | (Expr::False(_), Expr::False(_))
+        | (Expr::Unit(_), Expr::Unit(_)) => true,
+
+        (Expr::Array(x), Expr::Array(y)) => {
+            x.0.len() == y.0.len() && x.0.iter().zip(y.0.iter()).all(|(a, b)| literals_eq(a, b))
+        }
+        (Expr::Map(x), Expr::Map(y)) => {
+            x.0.len() == y.0.len()
+                && x.0.iter().all(|((name, _), a)| {
+                    y.0.iter().any(|((n, _), b)| n == name && literals_eq(a, b))
+                })
+        }
+
+        _ => false,
+    }
+}
+
+/// Call `f` on each shareable literal within a statement.
+///
+/// Only literals whose values are always copied are visited.  Literals that may be passed by
+/// reference (e.g. the first argument of a function call or the object of a method call) are
+/// skipped because they may be modified in place.
+fn visit_shareable_literals_stmt(stmt: &mut Stmt, f: &mut impl FnMut(&mut Expr)) {
+    match stmt {
+        Stmt::Let(_, expr, _) | Stmt::Const(_, expr, _) => expr
+            .iter_mut()
+            .for_each(|e| visit_shareable_literals_expr(e, f)),
+        Stmt::Assignment(x, _) => visit_shareable_literals_expr(&mut x.2, f),
+        Stmt::IfThenElse(condition, x, _) => {
+            visit_shareable_literals_expr(condition, f);
+            visit_shareable_literals_stmt(&mut x.0, f);
+            x.1.iter_mut()
+                .for_each(|s| visit_shareable_literals_stmt(s, f));
+        }
+        Stmt::While(condition, block, _) => {
+            visit_shareable_literals_expr(condition, f);
+            visit_shareable_literals_stmt(block, f);
+        }
+        Stmt::Loop(block, _) => visit_shareable_literals_stmt(block, f),
+        Stmt::For(iterable, x, _) => {
+            visit_shareable_literals_expr(iterable, f);
+            visit_shareable_literals_stmt(&mut x.2, f);
+        }
+        Stmt::Block(statements, _) => statements
+            .iter_mut()
+            .for_each(|s| visit_shareable_literals_stmt(s, f)),
+        Stmt::TryCatch(x) => {
+            visit_shareable_literals_stmt(&mut (x.0).0, f);
+            visit_shareable_literals_stmt(&mut (x.2).0, f);
+        }
+        Stmt::Expr(expr) | Stmt::ReturnWithVal(_, Some(expr), _) => {
+            visit_shareable_literals_expr(expr, f)
+        }
+        _ => (),
+    }
+}
+
+/// Call `f` on each shareable literal within an expression.
+fn visit_shareable_literals_expr(expr: &mut Expr, f: &mut impl FnMut(&mut Expr)) {
+    if is_shareable_literal(expr) {
+        return f(expr);
+    }
+
+    match expr {
+        Expr::Expr(x) => visit_shareable_literals_expr(x, f),
+        Expr::Stmt(x) => visit_shareable_literals_stmt(&mut x.0, f),
+        // The first argument may be passed by reference
+        Expr::FnCall(x) => {
+            x.3.iter_mut()
+                .skip(1)
+                .for_each(|e| visit_shareable_literals_expr(e, f))
+        }
+        Expr::Array(x) => {
+            x.0.iter_mut()
+                .for_each(|e| visit_shareable_literals_expr(e, f))
+        }
+        Expr::Map(x) => {
+            x.0.iter_mut()
+                .for_each(|(_, e)| visit_shareable_literals_expr(e, f))
+        }
+        Expr::ComputedMap(x) => x.0.iter_mut().for_each(|(k, e)| {
+            visit_shareable_literals_expr(k, f);
+            visit_shareable_literals_expr(e, f);
+        }),
+        Expr::In(x) | Expr::And(x) | Expr::Or(x) => {
+            visit_shareable_literals_expr(&mut x.lhs, f);
+            visit_shareable_literals_expr(&mut x.rhs, f);
+        }
+        // Dot/index chains operate on their objects by reference
+        _ => (),
+    }
+}
+
+/// Replace structurally identical constant array and object map literals that occur more than
+/// once with a single constant, defined at the beginning of the script.
+///
+/// The constants are removed from the `Scope` after the script is run.
+fn share_literals(mut statements: Vec<Stmt>) -> Vec<Stmt> {
+    // Literals with the same hash are only candidates - group them by structural equality,
+    // so that a hash collision never substitutes one literal for another
+    let mut groups: HashMap<u64, Vec<(Expr, usize)>> = Default::default();
+
+    statements.iter_mut().for_each(|stmt| {
+        visit_shareable_literals_stmt(stmt, &mut |expr| {
+            let group = groups.entry(hash_literal(expr)).or_default();
+
+            match group
+                .iter_mut()
+                .find(|(literal, _)| literals_eq(literal, expr))
+            {
+                Some((_, count)) => *count += 1,
+                None => group.push((expr.clone(), 1)),
+            }
+        })
+    });
+
+    let mut constants: Vec<Stmt> = Default::default();
+    let mut defined: HashSet<(u64, usize)> = Default::default();
+
+    statements.iter_mut().for_each(|stmt| {
+        visit_shareable_literals_stmt(stmt, &mut |expr| {
+            let hash = hash_literal(expr);
+            let group = &groups[&hash];
+            let index = group
+                .iter()
+                .position(|(literal, _)| literals_eq(literal, expr))
+                .unwrap();
+
+            if group[index].1 < 2 {
+                return;
+            }
+
+            let name = format!("{}{:016x}_{}", SHARED_LITERAL_PREFIX, hash, index);
+            let pos = expr.position();
+            let literal = mem::replace(
+                expr,
+                Expr::Variable(Box::new(((name.clone(), pos), None, 0, None))),
+            );
+
+            // Define the constant upon its first occurrence
+            if defined.insert((hash, index)) {
+                constants.push(Stmt::Const(
+                    Box::new((name, Position::none())),
+                    Some(literal),
+                    Position::none(),
+                ));
+            }
+        })
+    });
+
+    if constants.is_empty() {
+        statements
+    } else {
+        constants.extend(statements);
+        constants
+    }
+}
+
 /// Optimize an AST.
 pub fn optimize_into_ast(
     engine: &Engine,
@@ -861,6 +2035,8 @@ pub fn optimize_into_ast(
                         params: fn_def.params.clone(),
                         #[cfg(not(feature = "no_closure"))]
                         externals: fn_def.externals.clone(),
+                        #[cfg(not(feature = "no_closure"))]
+                        globals: fn_def.globals.clone(),
                         pos: fn_def.pos,
                         lib: None,
                     }
@@ -875,9 +2051,21 @@ pub fn optimize_into_ast(
                 .map(|mut fn_def| {
                     let pos = fn_def.body.position();
 
+                    // Captured variables are shared with the closure's creator
+                    #[cfg(not(feature = "no_closure"))]
+                    let shared = fn_def.externals.iter().cloned().collect();
+                    #[cfg(feature = "no_closure")]
+                    let shared = Vec::new();
+
                     // Optimize the function body
-                    let mut body =
-                        optimize(vec![fn_def.body], engine, &Scope::new(), &[&lib2], level);
+                    let mut body = optimize(
+                        vec![fn_def.body],
+                        engine,
+                        &Scope::new(),
+                        &[&lib2],
+                        level,
+                        shared,
+                    );
 
                     // {} -> Noop
                     fn_def.body = match body.pop().unwrap_or_else(|| Stmt::Noop(pos)) {
@@ -913,7 +2101,13 @@ pub fn optimize_into_ast(
         match level {
             OptimizationLevel::None => statements,
             OptimizationLevel::Simple | OptimizationLevel::Full => {
-                optimize(statements, engine, &scope, &[&lib], level)
+                let statements = optimize(statements, engine, &scope, &[&lib], level, Vec::new());
+
+                if level == OptimizationLevel::Full {
+                    share_literals(statements)
+                } else {
+                    statements
+                }
             }
         },
         lib,
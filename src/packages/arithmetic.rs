@@ -13,6 +13,9 @@ use crate::parser::FLOAT;
 #[cfg(not(feature = "no_float"))]
 use num_traits::float::Float;
 
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
 use crate::stdlib::{format, string::String};
 
 #[inline(always)]
@@ -67,7 +70,12 @@ macro_rules! gen_arithmetic_functions {
                 #[rhai_fn(name = "%", return_raw)]
                 pub fn modulo(x: $arg_type, y: $arg_type) -> Result<Dynamic, Box<EvalAltResult>> {
                     if cfg!(not(feature = "unchecked")) {
-                        x.checked_rem(y).ok_or_else(|| make_err(format!("Modulo division by zero or overflow: {} % {}", x, y))).map(Dynamic::from)
+                        // Detect division by zero
+                        if y == 0 {
+                            Err(make_err(format!("Modulo division by zero: {} % {}", x, y)))
+                        } else {
+                            x.checked_rem(y).ok_or_else(|| make_err(format!("Modulo division overflow: {} % {}", x, y))).map(Dynamic::from)
+                        }
                     } else {
                         Ok(Dynamic::from(x % y))
                     }
@@ -197,6 +205,10 @@ def_package!(crate:ArithmeticPackage:"Basic arithmetic", lib, {
         combine_with_exported_module!(lib, "f32", f32_functions);
         combine_with_exported_module!(lib, "f64", f64_functions);
     }
+
+    // Basic arithmetic for fixed-precision decimals
+    #[cfg(feature = "decimal")]
+    combine_with_exported_module!(lib, "decimal", decimal_functions);
 });
 
 gen_arithmetic_functions!(arith_basic => INT);
@@ -308,3 +320,84 @@ mod f64_functions {
         }
     }
 }
+
+#[cfg(feature = "decimal")]
+#[export_module]
+pub mod decimal_functions {
+    #[rhai_fn(name = "+", return_raw)]
+    pub fn add(x: Decimal, y: Decimal) -> Result<Dynamic, Box<EvalAltResult>> {
+        if cfg!(not(feature = "unchecked")) {
+            x.checked_add(y)
+                .ok_or_else(|| make_err(format!("Addition overflow: {} + {}", x, y)))
+                .map(Dynamic::from)
+        } else {
+            Ok(Dynamic::from(x + y))
+        }
+    }
+    #[rhai_fn(name = "-", return_raw)]
+    pub fn subtract(x: Decimal, y: Decimal) -> Result<Dynamic, Box<EvalAltResult>> {
+        if cfg!(not(feature = "unchecked")) {
+            x.checked_sub(y)
+                .ok_or_else(|| make_err(format!("Subtraction overflow: {} - {}", x, y)))
+                .map(Dynamic::from)
+        } else {
+            Ok(Dynamic::from(x - y))
+        }
+    }
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn multiply(x: Decimal, y: Decimal) -> Result<Dynamic, Box<EvalAltResult>> {
+        if cfg!(not(feature = "unchecked")) {
+            x.checked_mul(y)
+                .ok_or_else(|| make_err(format!("Multiplication overflow: {} * {}", x, y)))
+                .map(Dynamic::from)
+        } else {
+            Ok(Dynamic::from(x * y))
+        }
+    }
+    #[rhai_fn(name = "/", return_raw)]
+    pub fn divide(x: Decimal, y: Decimal) -> Result<Dynamic, Box<EvalAltResult>> {
+        if cfg!(not(feature = "unchecked")) {
+            // Detect division by zero
+            if y.is_zero() {
+                Err(make_err(format!("Division by zero: {} / {}", x, y)))
+            } else {
+                x.checked_div(y)
+                    .ok_or_else(|| make_err(format!("Division overflow: {} / {}", x, y)))
+                    .map(Dynamic::from)
+            }
+        } else {
+            Ok(Dynamic::from(x / y))
+        }
+    }
+    #[rhai_fn(name = "%", return_raw)]
+    pub fn modulo(x: Decimal, y: Decimal) -> Result<Dynamic, Box<EvalAltResult>> {
+        if cfg!(not(feature = "unchecked")) {
+            // Detect division by zero
+            if y.is_zero() {
+                Err(make_err(format!("Modulo division by zero: {} % {}", x, y)))
+            } else {
+                x.checked_rem(y)
+                    .ok_or_else(|| make_err(format!("Modulo division overflow: {} % {}", x, y)))
+                    .map(Dynamic::from)
+            }
+        } else {
+            Ok(Dynamic::from(x % y))
+        }
+    }
+    #[rhai_fn(name = "-")]
+    pub fn neg(x: Decimal) -> Decimal {
+        -x
+    }
+    pub fn abs(x: Decimal) -> Decimal {
+        x.abs()
+    }
+    pub fn sign(x: Decimal) -> INT {
+        if x.is_zero() {
+            0
+        } else if x.is_sign_negative() {
+            -1
+        } else {
+            1
+        }
+    }
+}
@@ -106,6 +106,36 @@ mod array_functions {
         x.extend(y);
         x
     }
+    #[rhai_fn(name = "*", return_raw)]
+    pub fn repeat(
+        _context: NativeCallContext,
+        list: Array,
+        times: INT,
+    ) -> Result<Dynamic, Box<EvalAltResult>> {
+        let times = if times > 0 { times as usize } else { 0 };
+
+        // Check if array will be over max size limit
+        #[cfg(not(feature = "unchecked"))]
+        if _context.engine().max_array_size() > 0
+            && list.len().saturating_mul(times) > _context.engine().max_array_size()
+        {
+            return EvalAltResult::ErrorDataTooLarge(
+                "Size of array".to_string(),
+                _context.engine().max_array_size(),
+                list.len().saturating_mul(times),
+                Position::none(),
+            )
+            .into();
+        }
+
+        let mut result = Array::with_capacity(list.len() * times);
+
+        for _ in 0..times {
+            result.extend(list.iter().cloned());
+        }
+
+        Ok(result.into())
+    }
     pub fn pop(list: &mut Array) -> Dynamic {
         list.pop().unwrap_or_else(|| ().into())
     }
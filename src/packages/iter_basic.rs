@@ -2,6 +2,7 @@ use crate::any::Variant;
 use crate::def_package;
 use crate::parser::INT;
 use crate::result::EvalAltResult;
+use crate::token::Position;
 
 use crate::stdlib::{
     boxed::Box,
@@ -13,21 +14,24 @@ fn get_range<T: Variant + Clone>(from: T, to: T) -> Result<Range<T>, Box<EvalAlt
 }
 
 // Register range function with step
+// A positive step counts up towards the end value, a negative step counts down.
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 struct StepRange<T>(T, T, T)
 where
     for<'a> &'a T: Add<&'a T, Output = T>,
-    T: Variant + Clone + PartialOrd;
+    T: Variant + Clone + PartialOrd + Default;
 
 impl<T> Iterator for StepRange<T>
 where
     for<'a> &'a T: Add<&'a T, Output = T>,
-    T: Variant + Clone + PartialOrd,
+    T: Variant + Clone + PartialOrd + Default,
 {
     type Item = T;
 
     fn next(&mut self) -> Option<T> {
-        if self.0 < self.1 {
+        let zero = T::default();
+
+        if (self.2 > zero && self.0 < self.1) || (self.2 < zero && self.0 > self.1) {
             let v = self.0.clone();
             self.0 = &v + &self.2;
             Some(v)
@@ -40,8 +44,17 @@ where
 fn get_step_range<T>(from: T, to: T, step: T) -> Result<StepRange<T>, Box<EvalAltResult>>
 where
     for<'a> &'a T: Add<&'a T, Output = T>,
-    T: Variant + Clone + PartialOrd,
+    T: Variant + Clone + PartialOrd + Default,
 {
+    // A zero step would never reach the end value
+    if step == T::default() {
+        return EvalAltResult::ErrorArithmetic(
+            "Step value of range cannot be zero".into(),
+            Position::none(),
+        )
+        .into();
+    }
+
     Ok(StepRange::<T>(from, to, step))
 }
 
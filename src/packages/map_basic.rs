@@ -6,11 +6,19 @@ use crate::engine::Map;
 use crate::parser::{ImmutableString, INT};
 use crate::plugin::*;
 
+use crate::stdlib::{any::TypeId, boxed::Box};
+
 #[cfg(not(feature = "no_index"))]
 use crate::engine::Array;
 
 def_package!(crate:BasicMapPackage:"Basic object map utilities.", lib, {
     combine_with_exported_module!(lib, "map", map_functions);
+
+    // Register map iterator - iterates through the keys
+    lib.set_iter(
+        TypeId::of::<Map>(),
+        |map: Dynamic| Box::new(map.cast::<Map>().into_iter().map(|(k, _)| k.into()))
+    );
 });
 
 #[export_module]
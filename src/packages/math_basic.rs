@@ -15,9 +15,12 @@ use crate::result::EvalAltResult;
 #[cfg(not(feature = "no_float"))]
 use num_traits::float::Float;
 
-#[cfg(not(feature = "no_float"))]
+#[cfg(any(not(feature = "no_float"), feature = "decimal"))]
 use crate::stdlib::format;
 
+#[cfg(feature = "decimal")]
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+
 #[allow(dead_code)]
 #[cfg(feature = "only_i32")]
 pub const MAX_INT: INT = i32::MAX;
@@ -78,6 +81,15 @@ def_package!(crate:BasicMathPackage:"Basic mathematic functions.", lib, {
             reg_functions!(lib += num_128_to_float::to_float(i128, u128));
         }
     }
+
+    #[cfg(feature = "decimal")]
+    {
+        // Decimal functions
+        combine_with_exported_module!(lib, "decimal", decimal_functions);
+
+        #[cfg(not(feature = "no_float"))]
+        combine_with_exported_module!(lib, "decimal_float", decimal_float_functions);
+    }
 });
 
 #[export_module]
@@ -242,6 +254,68 @@ mod float_functions {
     }
 }
 
+#[cfg(feature = "decimal")]
+#[export_module]
+mod decimal_functions {
+    #[rhai_fn(name = "to_decimal")]
+    pub fn int_to_decimal(x: INT) -> Decimal {
+        x.into()
+    }
+    #[rhai_fn(name = "to_int", return_raw)]
+    pub fn decimal_to_int(x: Decimal) -> Result<Dynamic, Box<EvalAltResult>> {
+        let n = x.trunc();
+        #[cfg(not(feature = "only_i32"))]
+        let n = n.to_i64();
+        #[cfg(feature = "only_i32")]
+        let n = n.to_i32();
+
+        n.map(Into::<Dynamic>::into).ok_or_else(|| {
+            EvalAltResult::ErrorArithmetic(
+                format!("Integer overflow: to_int({})", x),
+                Position::none(),
+            )
+            .into()
+        })
+    }
+    #[rhai_fn(return_raw)]
+    pub fn parse_decimal(s: &str) -> Result<Dynamic, Box<EvalAltResult>> {
+        s.trim()
+            .parse::<Decimal>()
+            .map(Into::<Dynamic>::into)
+            .map_err(|err| {
+                EvalAltResult::ErrorArithmetic(
+                    format!("Error parsing decimal number '{}': {}", s, err),
+                    Position::none(),
+                )
+                .into()
+            })
+    }
+}
+
+#[cfg(feature = "decimal")]
+#[cfg(not(feature = "no_float"))]
+#[export_module]
+mod decimal_float_functions {
+    use rust_decimal::prelude::FromPrimitive;
+
+    #[rhai_fn(name = "to_decimal", return_raw)]
+    pub fn float_to_decimal(x: FLOAT) -> Result<Dynamic, Box<EvalAltResult>> {
+        Decimal::from_f64(x)
+            .map(Into::<Dynamic>::into)
+            .ok_or_else(|| {
+                EvalAltResult::ErrorArithmetic(
+                    format!("Cannot convert to decimal: to_decimal({})", x),
+                    Position::none(),
+                )
+                .into()
+            })
+    }
+    #[rhai_fn(name = "to_float")]
+    pub fn decimal_to_float(x: Decimal) -> FLOAT {
+        x.to_f64().unwrap_or_default()
+    }
+}
+
 #[cfg(not(feature = "no_float"))]
 gen_conversion_functions!(basic_to_float => to_float (INT) -> FLOAT);
 
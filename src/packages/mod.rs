@@ -73,6 +73,16 @@ impl PackagesCollection {
             .find(|f| f.is_some())
             .flatten()
     }
+    /// Get a variadic function accepting the specified number of arguments via the hash key of its name.
+    pub fn get_variadic_fn(&self, hash_name: u64, num_args: usize) -> Option<&CallableFunction> {
+        self.0
+            .iter()
+            .find_map(|p| p.get_variadic_fn(hash_name, num_args))
+    }
+    /// Get an iterator over all the packages in the `PackagesCollection`.
+    pub fn iter(&self) -> impl Iterator<Item = &Module> {
+        self.0.iter().map(|p| p.as_ref())
+    }
     /// Does the specified TypeId iterator exist in the `PackagesCollection`?
     #[allow(dead_code)]
     pub fn contains_iter(&self, id: TypeId) -> bool {
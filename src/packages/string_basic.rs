@@ -12,6 +12,9 @@ use crate::engine::Array;
 #[cfg(not(feature = "no_object"))]
 use crate::engine::Map;
 
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
 use crate::stdlib::{
     fmt::{Debug, Display},
     format,
@@ -71,6 +74,12 @@ def_package!(crate:BasicStringPackage:"Basic string utilities, including printin
         reg_debug_functions!(lib += debug_float; f32, f64);
     }
 
+    #[cfg(feature = "decimal")]
+    {
+        reg_print_functions!(lib += print_decimal; Decimal);
+        reg_debug_functions!(lib += print_decimal; Decimal);
+    }
+
     #[cfg(not(feature = "no_index"))]
     {
         reg_print_functions!(lib += print_array; Array);
@@ -112,6 +121,9 @@ gen_functions!(print_float => to_string(f32, f64));
 #[cfg(not(feature = "no_float"))]
 gen_functions!(debug_float => to_debug(f32, f64));
 
+#[cfg(feature = "decimal")]
+gen_functions!(print_decimal => to_string(Decimal));
+
 #[cfg(not(feature = "no_index"))]
 gen_functions!(print_array => to_debug(Array));
 
@@ -10,6 +10,9 @@ use crate::StaticVec;
 #[cfg(not(feature = "unchecked"))]
 use crate::{result::EvalAltResult, token::Position};
 
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
 use crate::stdlib::{
     any::TypeId, boxed::Box, format, mem, string::String, string::ToString, vec::Vec,
 };
@@ -56,6 +59,9 @@ def_package!(crate:MoreStringPackage:"Additional string utilities, including str
     #[cfg(not(feature = "no_float"))]
     reg_functions!(lib += float; f32, f64);
 
+    #[cfg(feature = "decimal")]
+    reg_functions!(lib += decimal; Decimal);
+
     combine_with_exported_module!(lib, "string", string_functions);
 
     // Register string iterator
@@ -79,6 +85,9 @@ gen_concat_functions!(num_128 => i128, u128);
 #[cfg(not(feature = "no_float"))]
 gen_concat_functions!(float => f32, f64);
 
+#[cfg(feature = "decimal")]
+gen_concat_functions!(decimal => Decimal);
+
 #[export_module]
 mod string_functions {
     #[rhai_fn(name = "+")]
@@ -5,7 +5,7 @@ use crate::engine::{Engine, KEYWORD_THIS, MARKER_BLOCK, MARKER_EXPR, MARKER_IDEN
 use crate::error::{LexError, ParseError, ParseErrorType};
 use crate::fn_native::{FnPtr, Shared};
 use crate::module::{Module, ModuleRef};
-use crate::optimize::{optimize_into_ast, OptimizationLevel};
+use crate::optimize::{clear_var_indices_expr, optimize_into_ast, OptimizationLevel};
 use crate::scope::{EntryType as ScopeEntryType, Scope};
 use crate::syntax::{CustomSyntax, FnCustomSyntaxEval};
 use crate::token::{is_keyword_function, is_valid_identifier, Position, Token, TokenStream};
@@ -19,7 +19,10 @@ use crate::engine::Array;
 use crate::engine::{make_getter, make_setter, Map, KEYWORD_EVAL, KEYWORD_FN_PTR};
 
 #[cfg(not(feature = "no_function"))]
-use crate::engine::{FN_ANONYMOUS, KEYWORD_FN_PTR_CURRY};
+use crate::engine::{unmangle_local_fn, FN_ANONYMOUS, FN_LOCAL, KEYWORD_FN_PTR_CURRY};
+
+#[cfg(not(feature = "no_function"))]
+use crate::utils::calc_source_hash;
 
 use crate::stdlib::{
     any::TypeId,
@@ -29,25 +32,28 @@ use crate::stdlib::{
     collections::HashMap,
     fmt, format,
     hash::{Hash, Hasher},
-    iter::empty,
+    iter::{empty, once},
+    mem,
     num::NonZeroUsize,
     ops::{Add, AddAssign},
+    ptr,
     string::{String, ToString},
     vec,
     vec::Vec,
 };
 
 #[cfg(not(feature = "no_std"))]
-#[cfg(not(feature = "no_function"))]
 use crate::stdlib::collections::hash_map::DefaultHasher;
 
 #[cfg(not(feature = "no_closure"))]
 use crate::stdlib::collections::HashSet;
 
 #[cfg(feature = "no_std")]
-#[cfg(not(feature = "no_function"))]
 use ahash::AHasher;
 
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
 /// The system integer type.
 ///
 /// If the `only_i32` feature is enabled, this will be `i32` instead.
@@ -83,13 +89,142 @@ pub struct AST(
     Vec<Stmt>,
     /// Script-defined functions.
     Module,
+    /// Source text of the script, if retained.
+    Option<Shared<str>>,
+    /// Source name of the script, if any.
+    Option<ImmutableString>,
+    /// Source names of statements combined from other `AST`'s, keyed by the index of the
+    /// first statement from each source.
+    Vec<(usize, Option<ImmutableString>)>,
 );
 
 impl AST {
     /// Create a new `AST`.
     #[inline(always)]
     pub fn new(statements: Vec<Stmt>, lib: Module) -> Self {
-        Self(statements, lib)
+        Self(statements, lib, None, None, vec![])
+    }
+
+    /// Attach the source text of the script to the `AST`.
+    #[inline(always)]
+    pub(crate) fn set_source_text(&mut self, source: &str) {
+        self.2 = Some(source.into());
+    }
+
+    /// Get the source text of the script, if retained.
+    ///
+    /// Source text is only retained when enabled via `Engine::set_retain_source`.
+    #[inline(always)]
+    pub fn source_text(&self) -> Option<&str> {
+        self.2.as_deref()
+    }
+
+    /// Get the source name of the script, if any.
+    #[inline(always)]
+    pub fn source(&self) -> Option<&str> {
+        self.3.as_ref().map(|s| s.as_str())
+    }
+
+    /// Tag the `AST` with a source name (e.g. the file name of the script).
+    ///
+    /// Errors raised while evaluating the `AST` are wrapped in `EvalAltResult::ErrorInSource`
+    /// carrying the source name, which is then shown when the error is displayed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let mut ast = engine.compile("let x = 42;\nx + y")?;
+    /// ast.set_source("hello.rhai");
+    /// assert_eq!(ast.source(), Some("hello.rhai"));
+    ///
+    /// let err = engine.eval_ast::<i64>(&ast).expect_err("should error");
+    ///
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Error in 'hello.rhai': Variable not found: 'y' (line 2, position 5)"
+    /// );
+    ///
+    /// ast.clear_source();
+    /// assert_eq!(ast.source(), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn set_source(&mut self, source: impl Into<ImmutableString>) -> &mut Self {
+        self.3 = Some(source.into());
+        self
+    }
+
+    /// Remove the source name of the `AST`, if any.
+    #[inline(always)]
+    pub fn clear_source(&mut self) -> &mut Self {
+        self.3 = None;
+        self
+    }
+
+    /// Get the source name of the global statement at a particular index, if any.
+    ///
+    /// Statements combined from other `AST`'s via `AST::combine_with_source` keep their own
+    /// source names.
+    pub(crate) fn source_at(&self, index: usize) -> Option<&str> {
+        match self.4.iter().rev().find(|(start, _)| *start <= index) {
+            Some((_, source)) => source.as_ref().map(|s| s.as_str()),
+            None => self.source(),
+        }
+    }
+
+    /// Record the source name of statements appended from another `AST` at a particular index,
+    /// together with the source names of statements combined into that `AST`.
+    fn append_sources(
+        &mut self,
+        index: usize,
+        source: Option<ImmutableString>,
+        other_sources: &[(usize, Option<ImmutableString>)],
+    ) {
+        if !self.4.is_empty() || source != self.3 {
+            self.4.push((index, source));
+        }
+        self.4.extend(
+            other_sources
+                .iter()
+                .map(|(start, source)| (index + start, source.clone())),
+        );
+    }
+
+    /// Get the line of source text at a particular `Position`, without the line terminator.
+    ///
+    /// Returns `None` if the source text is not retained, or the position is out of range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, Position};
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_retain_source(true);
+    ///
+    /// let ast = engine.compile("let x = 42;\nx + y")?;
+    ///
+    /// let err = engine.eval_ast::<i64>(&ast).expect_err("should error");
+    ///
+    /// assert_eq!(ast.slice_at(err.position()), Some("x + y"));
+    /// assert_eq!(ast.slice_at(Position::new(3, 1)), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn slice_at(&self, pos: Position) -> Option<&str> {
+        let line = pos.line()?;
+
+        self.source_text()?
+            .split('\n')
+            .nth(line - 1)
+            .map(|s| s.trim_end_matches('\r'))
     }
 
     /// Get the statements.
@@ -151,14 +286,26 @@ impl AST {
     ) -> Self {
         let mut functions: Module = Default::default();
         functions.merge_filtered(&self.1, &mut filter);
-        Self(Default::default(), functions)
+        Self(
+            Default::default(),
+            functions,
+            self.2.clone(),
+            self.3.clone(),
+            vec![],
+        )
     }
 
     /// Clone the `AST`'s script statements into a new `AST`.
     /// No functions are cloned.
     #[inline(always)]
     pub fn clone_statements_only(&self) -> Self {
-        Self(self.0.clone(), Default::default())
+        Self(
+            self.0.clone(),
+            Default::default(),
+            self.2.clone(),
+            self.3.clone(),
+            self.4.clone(),
+        )
     }
 
     /// Merge two `AST` into one.  Both `AST`'s are untouched and a new, merged, version
@@ -169,6 +316,9 @@ impl AST {
     /// Of course, if the first `AST` uses a `return` statement at the end, then
     /// the second `AST` will essentially be dead code.
     ///
+    /// Source text retained in the `AST`'s, if any, is dropped.
+    /// The source name of the first `AST` (see `AST::set_source`), if any, is kept.
+    ///
     /// All script-defined functions in the second `AST` overwrite similarly-named functions
     /// in the first `AST` with the same number of parameters.
     ///
@@ -222,6 +372,9 @@ impl AST {
     /// Of course, if the first `AST` uses a `return` statement at the end, then
     /// the second `AST` will essentially be dead code.
     ///
+    /// Source text retained in the `AST`'s, if any, is dropped.
+    /// The source name of the first `AST` (see `AST::set_source`), if any, is kept.
+    ///
     /// All script-defined functions in the second `AST` overwrite similarly-named functions
     /// in the first `AST` with the same number of parameters.
     ///
@@ -276,6 +429,9 @@ impl AST {
     /// Of course, if the first `AST` uses a `return` statement at the end, then
     /// the second `AST` will essentially be dead code.
     ///
+    /// Source text retained in the `AST`'s, if any, is dropped.
+    /// The source name of the first `AST` (see `AST::set_source`), if any, is kept.
+    ///
     /// All script-defined functions in the second `AST` are first selected based on a filter
     /// predicate, then overwrite similarly-named functions in the first `AST` with the
     /// same number of parameters.
@@ -325,7 +481,7 @@ impl AST {
         other: &Self,
         mut filter: impl FnMut(FnAccess, &str, usize) -> bool,
     ) -> Self {
-        let Self(statements, functions) = self;
+        let Self(statements, functions, _, source, sources) = self;
 
         let ast = match (statements.is_empty(), other.0.is_empty()) {
             (false, false) => {
@@ -341,7 +497,13 @@ impl AST {
         let mut functions = functions.clone();
         functions.merge_filtered(&other.1, &mut filter);
 
-        Self::new(ast, functions)
+        let mut ast = Self::new(ast, functions);
+        ast.3 = source.clone();
+        ast.4 = sources.clone();
+        if !other.0.is_empty() {
+            ast.append_sources(statements.len(), source.clone(), &other.4);
+        }
+        ast
     }
 
     /// Combine one `AST` with another.  The second `AST` is consumed.
@@ -351,6 +513,9 @@ impl AST {
     /// Of course, if the first `AST` uses a `return` statement at the end, then
     /// the second `AST` will essentially be dead code.
     ///
+    /// Source text retained in the `AST`'s, if any, is dropped.
+    /// The source name of the first `AST` (see `AST::set_source`), if any, is kept.
+    ///
     /// All script-defined functions in the second `AST` are first selected based on a filter
     /// predicate, then overwrite similarly-named functions in the first `AST` with the
     /// same number of parameters.
@@ -400,9 +565,62 @@ impl AST {
         other: Self,
         mut filter: impl FnMut(FnAccess, &str, usize) -> bool,
     ) -> &mut Self {
-        let Self(ref mut statements, ref mut functions) = self;
+        if !other.0.is_empty() {
+            self.append_sources(self.0.len(), self.3.clone(), &other.4);
+        }
+        let Self(ref mut statements, ref mut functions, ref mut source, _, _) = self;
         statements.extend(other.0.into_iter());
         functions.merge_filtered(&other.1, &mut filter);
+        *source = None;
+        self
+    }
+
+    /// Combine one `AST` with another, tagging the statements in the second `AST` with a
+    /// source name.  The second `AST` is consumed.
+    ///
+    /// This is the same as `AST::combine`, except that errors raised by statements in the
+    /// second `AST` are wrapped in `EvalAltResult::ErrorInSource` carrying `source`
+    /// (instead of the source name of the first `AST`), so that they can be told apart from
+    /// errors with the same positions in the first `AST`.
+    ///
+    /// Errors raised within script-defined functions are tagged with the source name of the
+    /// statement calling them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let mut ast = engine.compile("let x = 40;")?;
+    /// ast.set_source("first.rhai");
+    ///
+    /// ast.combine_with_source(engine.compile("x + y")?, "second.rhai");
+    ///
+    /// let err = engine.eval_ast::<i64>(&ast).expect_err("should error");
+    ///
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Error in 'second.rhai': Variable not found: 'y' (line 1, position 5)"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn combine_with_source(
+        &mut self,
+        other: Self,
+        source: impl Into<ImmutableString>,
+    ) -> &mut Self {
+        if !other.0.is_empty() {
+            self.append_sources(self.0.len(), Some(source.into()), &other.4);
+        }
+        let Self(ref mut statements, ref mut functions, ref mut source_text, _, _) = self;
+        statements.extend(other.0.into_iter());
+        functions.merge_filtered(&other.1, &mut |_, _, _| true);
+        *source_text = None;
         self
     }
 
@@ -436,6 +654,16 @@ impl AST {
     }
 
     /// Iterate through all functions
+    ///
+    /// Function metadata includes:
+    /// 1) Access mode (`FnAccess::Public` or `FnAccess::Private`).
+    /// 2) Function name (as string slice).
+    /// 3) Number of parameters.
+    /// 4) Shared reference to function definition `ScriptFnDef`.
+    ///
+    /// Functions defined inside blocks or other functions (see
+    /// `Engine::set_allow_nested_functions`) are included as private functions, under decorated
+    /// names starting with `local$`.
     #[cfg(not(feature = "no_function"))]
     #[inline(always)]
     pub fn iter_functions<'a>(
@@ -444,6 +672,86 @@ impl AST {
         self.1.iter_script_fn()
     }
 
+    /// Find a script-defined function in the `AST` by name and number of parameters.
+    ///
+    /// Private functions are only returned when `include_private` is `true`.
+    /// Functions defined inside blocks or other functions are stored under decorated names,
+    /// so they are not found by the names written in the script.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # #[cfg(not(feature = "no_function"))]
+    /// # {
+    /// use rhai::Engine;
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let ast = engine.compile(r#"
+    ///                         fn foo(x) { x + 1 }
+    ///                         private fn bar() { 42 }
+    ///                     "#)?;
+    ///
+    /// assert_eq!(ast.find_function("foo", 1, false).unwrap().params.len(), 1);
+    /// assert!(ast.find_function("foo", 2, false).is_none());
+    /// assert!(ast.find_function("bar", 0, false).is_none());
+    /// assert!(ast.find_function("bar", 0, true).is_some());
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_function"))]
+    #[inline(always)]
+    pub fn find_function(
+        &self,
+        name: &str,
+        num_params: usize,
+        include_private: bool,
+    ) -> Option<Shared<ScriptFnDef>> {
+        self.1
+            .get_script_fn(name, num_params, !include_private)
+            .cloned()
+    }
+
+    /// Replace all function definitions in the `AST` with those in another `AST`.
+    /// Statements are untouched.
+    /// Source text retained in the `AST`, if any, is dropped.
+    ///
+    /// This operation is cheap because functions are shared.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # #[cfg(not(feature = "no_function"))]
+    /// # {
+    /// use rhai::Engine;
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let mut ast = engine.compile(r#"
+    ///                         fn foo() { 1 }
+    ///                         foo()
+    ///                     "#)?;
+    ///
+    /// assert_eq!(engine.eval_ast::<i64>(&ast)?, 1);
+    ///
+    /// // Hot-reload the definition of 'foo'
+    /// ast.replace_functions(&engine.compile("fn foo() { 42 }")?);
+    ///
+    /// assert_eq!(engine.eval_ast::<i64>(&ast)?, 42);
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_function"))]
+    #[inline(always)]
+    pub fn replace_functions(&mut self, other: &Self) {
+        self.1 = other.1.clone();
+        self.2 = None;
+    }
+
     /// Clear all function definitions in the `AST`.
     #[cfg(not(feature = "no_function"))]
     #[inline(always)]
@@ -455,6 +763,293 @@ impl AST {
     #[inline(always)]
     pub fn clear_statements(&mut self) {
         self.0 = vec![];
+        self.4 = vec![];
+    }
+
+    /// Calculate a hash of the global statements in the `AST`, ignoring all function definitions.
+    ///
+    /// The hash is stable within the same process for structurally equal `AST`'s, so it can be used
+    /// to detect changes to the statements (e.g. to key an incremental cache).
+    ///
+    /// Positions are included in the hash, so reformatting a script (e.g. adding white-space)
+    /// changes the hash even when its statements are otherwise the same.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # #[cfg(not(feature = "no_function"))]
+    /// # {
+    /// use rhai::Engine;
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let ast1 = engine.compile("fn foo(x) { x + 1 } foo(41)")?;
+    /// let ast2 = engine.compile("fn foo(x) { x - 1 } foo(41)")?;
+    ///
+    /// assert_eq!(ast1.statements_hash(), ast2.statements_hash());
+    /// assert_ne!(ast1.functions_hash(), ast2.functions_hash());
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn statements_hash(&self) -> u64 {
+        #[cfg(feature = "no_std")]
+        let mut s: AHasher = Default::default();
+        #[cfg(not(feature = "no_std"))]
+        let mut s = DefaultHasher::new();
+
+        self.0.hash(&mut s);
+        s.finish()
+    }
+
+    /// Calculate a hash of all script-defined functions in the `AST`, ignoring the global statements.
+    ///
+    /// The hash covers the name, access mode, parameters and body of each function, and does not
+    /// depend on the order in which the functions are defined.
+    ///
+    /// The hash is stable within the same process for structurally equal `AST`'s, so it can be used
+    /// to detect changes to the function library (e.g. to key an incremental cache).
+    ///
+    /// Positions are included in the hash, so reformatting a script (e.g. adding white-space)
+    /// changes the hash even when its functions are otherwise the same.
+    #[cfg(not(feature = "no_function"))]
+    pub fn functions_hash(&self) -> u64 {
+        let mut hashes: Vec<_> = self
+            .1
+            .iter_script_fn()
+            .map(|(_, _, _, f)| {
+                #[cfg(feature = "no_std")]
+                let mut s: AHasher = Default::default();
+                #[cfg(not(feature = "no_std"))]
+                let mut s = DefaultHasher::new();
+
+                f.name.hash(&mut s);
+                f.access.hash(&mut s);
+                f.params.hash(&mut s);
+                f.body.hash(&mut s);
+                s.finish()
+            })
+            .collect();
+
+        // Functions are not stored in any particular order
+        hashes.sort();
+
+        #[cfg(feature = "no_std")]
+        let mut s: AHasher = Default::default();
+        #[cfg(not(feature = "no_std"))]
+        let mut s = DefaultHasher::new();
+
+        hashes.hash(&mut s);
+        s.finish()
+    }
+
+    /// Collect all string literals in the `AST`, including those within script-defined functions,
+    /// together with their positions, sorted in order of appearance.
+    ///
+    /// Only string literals that survive optimization are collected, so compile the `AST`
+    /// with `OptimizationLevel::None` to see every string literal in the original script.
+    /// Property names and object map keys are not string literals and are not collected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let ast = engine.compile(r#"let x = "hello"; print(x + "world");"#)?;
+    ///
+    /// let literals: Vec<_> = ast
+    ///     .string_literals()
+    ///     .into_iter()
+    ///     .map(|(s, _)| s.to_string())
+    ///     .collect();
+    ///
+    /// assert_eq!(literals, ["hello", "world"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn string_literals(&self) -> Vec<(ImmutableString, Position)> {
+        let mut literals = Vec::new();
+
+        let mut collect = |path: &[ASTNode]| {
+            if let Some(ASTNode::Expr(Expr::StringConstant(x))) = path.last() {
+                literals.push((x.0.clone(), x.1));
+            }
+            true
+        };
+
+        self.0.iter().for_each(|stmt| {
+            walk_stmt(stmt, &mut Vec::new(), &mut collect);
+        });
+
+        #[cfg(not(feature = "no_function"))]
+        self.1.iter_script_fn().for_each(|(_, _, _, f)| {
+            walk_stmt(&f.body, &mut Vec::new(), &mut collect);
+        });
+
+        literals.sort_by_key(|(_, pos)| *pos);
+        literals
+    }
+}
+
+/// A statement or an expression within an `AST`, as visited by `walk_stmt` and `walk_expr`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ASTNode<'a> {
+    /// A statement.
+    Stmt(&'a Stmt),
+    /// An expression.
+    Expr(&'a Expr),
+}
+
+/// Visit a statement and all the statements and expressions within it, depth-first.
+///
+/// `on_node` is called on each node with the path of nodes leading to it, the node itself being
+/// the last.  The walk stops as soon as `on_node` returns `false`, in which case `false` is returned.
+pub(crate) fn walk_stmt<'a>(
+    stmt: &'a Stmt,
+    path: &mut Vec<ASTNode<'a>>,
+    on_node: &mut impl FnMut(&[ASTNode<'a>]) -> bool,
+) -> bool {
+    path.push(ASTNode::Stmt(stmt));
+
+    let result = on_node(path)
+        && match stmt {
+            Stmt::IfThenElse(condition, x, _) => {
+                walk_expr(condition, path, on_node)
+                    && walk_stmt(&x.0, path, on_node)
+                    && x.1.iter().all(|stmt| walk_stmt(stmt, path, on_node))
+            }
+            Stmt::While(condition, body, _) => {
+                walk_expr(condition, path, on_node) && walk_stmt(body, path, on_node)
+            }
+            Stmt::Loop(body, _) => walk_stmt(body, path, on_node),
+            Stmt::For(iterable, x, _) => {
+                walk_expr(iterable, path, on_node) && walk_stmt(&x.2, path, on_node)
+            }
+            Stmt::Let(_, Some(expr), _)
+            | Stmt::Const(_, Some(expr), _)
+            | Stmt::Expr(expr)
+            | Stmt::ReturnWithVal(_, Some(expr), _) => walk_expr(expr, path, on_node),
+            #[cfg(not(feature = "no_module"))]
+            Stmt::Import(expr, _, _) => walk_expr(expr, path, on_node),
+            Stmt::Assignment(x, _) => {
+                walk_expr(&x.0, path, on_node) && walk_expr(&x.2, path, on_node)
+            }
+            Stmt::Block(statements, _) => {
+                statements.iter().all(|stmt| walk_stmt(stmt, path, on_node))
+            }
+            Stmt::TryCatch(x) => {
+                walk_stmt(&(x.0).0, path, on_node) && walk_stmt(&(x.2).0, path, on_node)
+            }
+            _ => true,
+        };
+
+    path.pop();
+    result
+}
+
+/// Visit an expression and all the statements and expressions within it, depth-first.
+///
+/// See `walk_stmt` for details.
+pub(crate) fn walk_expr<'a>(
+    expr: &'a Expr,
+    path: &mut Vec<ASTNode<'a>>,
+    on_node: &mut impl FnMut(&[ASTNode<'a>]) -> bool,
+) -> bool {
+    path.push(ASTNode::Expr(expr));
+
+    let result = on_node(path)
+        && match expr {
+            Expr::Expr(x) => walk_expr(x, path, on_node),
+            Expr::Stmt(x) => walk_stmt(&x.0, path, on_node),
+            Expr::FnCall(x) => x.3.iter().all(|expr| walk_expr(expr, path, on_node)),
+            Expr::Dot(x, _) | Expr::Index(x, _) | Expr::In(x) | Expr::And(x) | Expr::Or(x) => {
+                walk_expr(&x.lhs, path, on_node) && walk_expr(&x.rhs, path, on_node)
+            }
+            Expr::Array(x) => x.0.iter().all(|expr| walk_expr(expr, path, on_node)),
+            Expr::Map(x) => x.0.iter().all(|(_, expr)| walk_expr(expr, path, on_node)),
+            Expr::ComputedMap(x) => x.0.iter().all(|(key, value)| {
+                walk_expr(key, path, on_node) && walk_expr(value, path, on_node)
+            }),
+            Expr::Custom(x) => x.keywords.iter().all(|expr| walk_expr(expr, path, on_node)),
+            _ => true,
+        };
+
+    path.pop();
+    result
+}
+
+/// Visit all the expressions within a statement mutably, depth-first.
+///
+/// `on_expr` is called on each expression before the expressions within it.
+pub(crate) fn walk_stmt_mut(stmt: &mut Stmt, on_expr: &mut impl FnMut(&mut Expr)) {
+    match stmt {
+        Stmt::IfThenElse(condition, x, _) => {
+            walk_expr_mut(condition, on_expr);
+            walk_stmt_mut(&mut x.0, on_expr);
+            x.1.iter_mut().for_each(|stmt| walk_stmt_mut(stmt, on_expr));
+        }
+        Stmt::While(condition, body, _) => {
+            walk_expr_mut(condition, on_expr);
+            walk_stmt_mut(body, on_expr);
+        }
+        Stmt::Loop(body, _) => walk_stmt_mut(body, on_expr),
+        Stmt::For(iterable, x, _) => {
+            walk_expr_mut(iterable, on_expr);
+            walk_stmt_mut(&mut x.2, on_expr);
+        }
+        Stmt::Let(_, Some(expr), _)
+        | Stmt::Const(_, Some(expr), _)
+        | Stmt::Expr(expr)
+        | Stmt::ReturnWithVal(_, Some(expr), _) => walk_expr_mut(expr, on_expr),
+        #[cfg(not(feature = "no_module"))]
+        Stmt::Import(expr, _, _) => walk_expr_mut(expr, on_expr),
+        Stmt::Assignment(x, _) => {
+            walk_expr_mut(&mut x.0, on_expr);
+            walk_expr_mut(&mut x.2, on_expr);
+        }
+        Stmt::Block(statements, _) => statements
+            .iter_mut()
+            .for_each(|stmt| walk_stmt_mut(stmt, on_expr)),
+        Stmt::TryCatch(x) => {
+            walk_stmt_mut(&mut (x.0).0, on_expr);
+            walk_stmt_mut(&mut (x.2).0, on_expr);
+        }
+        _ => (),
+    }
+}
+
+/// Visit an expression and all the expressions within it mutably, depth-first.
+///
+/// See `walk_stmt_mut` for details.
+pub(crate) fn walk_expr_mut(expr: &mut Expr, on_expr: &mut impl FnMut(&mut Expr)) {
+    on_expr(expr);
+
+    match expr {
+        Expr::Expr(x) => walk_expr_mut(x, on_expr),
+        Expr::Stmt(x) => walk_stmt_mut(&mut x.0, on_expr),
+        Expr::FnCall(x) => x.3.iter_mut().for_each(|expr| walk_expr_mut(expr, on_expr)),
+        Expr::Dot(x, _) | Expr::Index(x, _) | Expr::In(x) | Expr::And(x) | Expr::Or(x) => {
+            walk_expr_mut(&mut x.lhs, on_expr);
+            walk_expr_mut(&mut x.rhs, on_expr);
+        }
+        Expr::Array(x) => x.0.iter_mut().for_each(|expr| walk_expr_mut(expr, on_expr)),
+        Expr::Map(x) => {
+            x.0.iter_mut()
+                .for_each(|(_, expr)| walk_expr_mut(expr, on_expr))
+        }
+        Expr::ComputedMap(x) => x.0.iter_mut().for_each(|(key, value)| {
+            walk_expr_mut(key, on_expr);
+            walk_expr_mut(value, on_expr);
+        }),
+        Expr::Custom(x) => x
+            .keywords
+            .iter_mut()
+            .for_each(|expr| walk_expr_mut(expr, on_expr)),
+        _ => (),
     }
 }
 
@@ -543,6 +1138,9 @@ pub struct ScriptFnDef {
     /// Access to external variables.
     #[cfg(not(feature = "no_closure"))]
     pub externals: HashSet<String>,
+    /// Names of global variables declared via `global`.
+    #[cfg(not(feature = "no_closure"))]
+    pub globals: HashSet<String>,
     /// Function body.
     pub body: Stmt,
     /// Position of the function definition.
@@ -590,6 +1188,8 @@ struct ParseState<'e> {
     engine: &'e Engine,
     /// Encapsulates a local stack with variable names to simulate an actual runtime scope.
     stack: Vec<(String, ScopeEntryType)>,
+    /// Number of entries at the bottom of `stack` that are constants seeded from an external `Scope`.
+    scope_constants: usize,
     /// Tracks a list of external variables (variables that are not explicitly declared in the scope).
     #[cfg(not(feature = "no_closure"))]
     externals: HashMap<String, Position>,
@@ -599,9 +1199,21 @@ struct ParseState<'e> {
     /// All consequent calls to `access_var` will not be affected
     #[cfg(not(feature = "no_closure"))]
     allow_capture: bool,
+    /// Tracks a list of variables declared `global` within a function.
+    #[cfg(not(feature = "no_function"))]
+    #[cfg(not(feature = "no_closure"))]
+    globals: HashSet<String>,
     /// Encapsulates a local stack with imported module names.
     #[cfg(not(feature = "no_module"))]
     modules: Vec<String>,
+    /// Encapsulates a local stack with the functions defined inside blocks, as
+    /// (name, number of parameters, decorated name).
+    #[cfg(not(feature = "no_function"))]
+    local_fns: Vec<(String, usize, ImmutableString)>,
+    /// Hash of the text of the scripts being parsed, used to decorate the names of functions
+    /// defined inside blocks.
+    #[cfg(not(feature = "no_function"))]
+    source_hash: u64,
     /// Maximum levels of expression nesting.
     #[cfg(not(feature = "unchecked"))]
     max_expr_depth: usize,
@@ -609,6 +1221,10 @@ struct ParseState<'e> {
     #[cfg(not(feature = "unchecked"))]
     #[cfg(not(feature = "no_function"))]
     max_function_expr_depth: usize,
+    /// Current level of array/map literal nesting.
+    #[cfg(not(feature = "unchecked"))]
+    #[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
+    data_depth: usize,
 }
 
 impl<'e> ParseState<'e> {
@@ -616,6 +1232,7 @@ impl<'e> ParseState<'e> {
     #[inline(always)]
     pub fn new(
         engine: &'e Engine,
+        #[cfg(not(feature = "no_function"))] source_hash: u64,
         #[cfg(not(feature = "unchecked"))] max_expr_depth: usize,
         #[cfg(not(feature = "unchecked"))]
         #[cfg(not(feature = "no_function"))]
@@ -630,11 +1247,105 @@ impl<'e> ParseState<'e> {
             max_function_expr_depth,
             #[cfg(not(feature = "no_closure"))]
             externals: Default::default(),
+            #[cfg(not(feature = "no_function"))]
+            #[cfg(not(feature = "no_closure"))]
+            globals: Default::default(),
             #[cfg(not(feature = "no_closure"))]
             allow_capture: true,
             stack: Default::default(),
+            scope_constants: 0,
             #[cfg(not(feature = "no_module"))]
             modules: Default::default(),
+            #[cfg(not(feature = "no_function"))]
+            local_fns: Default::default(),
+            #[cfg(not(feature = "no_function"))]
+            source_hash,
+            #[cfg(not(feature = "unchecked"))]
+            #[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
+            data_depth: 0,
+        }
+    }
+
+    /// Find a function defined inside a block by name and number of parameters in the
+    /// `ParseState`, searching in reverse order.
+    ///
+    /// Returns the decorated name of the function, or `None` if it is not found.
+    #[cfg(not(feature = "no_function"))]
+    #[inline]
+    fn find_local_fn(&self, name: &str, num_params: usize) -> Option<&ImmutableString> {
+        self.local_fns
+            .iter()
+            .rev()
+            .find(|(n, p, _)| n == name && *p == num_params)
+            .map(|(_, _, fn_name)| fn_name)
+    }
+
+    /// Enter one more level of array/map literal nesting, making sure that it is within the
+    /// maximum limit.
+    #[cfg(not(feature = "unchecked"))]
+    #[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
+    #[inline]
+    pub fn enter_data_literal(&mut self, pos: Position) -> Result<(), ParseError> {
+        self.data_depth += 1;
+
+        match self.engine.max_data_depth() {
+            0 => Ok(()),
+            max if self.data_depth > max => Err(PERR::DataTooDeep.into_err(pos)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Seed the `ParseState`'s stack with the constants in an external `Scope`,
+    /// so that assignments to them are caught during parsing.
+    ///
+    /// Seeded constants are never directly indexed because the `Scope` used at
+    /// evaluation time may not be the same one.
+    fn push_scope_constants(&mut self, scope: &Scope) {
+        self.stack.extend(
+            scope
+                .iter_raw()
+                .filter(|(_, is_constant, _)| *is_constant)
+                .map(|(name, _, _)| (name.to_string(), ScopeEntryType::Constant)),
+        );
+        self.scope_constants = self.stack.len();
+    }
+
+    /// Is there a constant seeded from an external `Scope` with a particular name
+    /// that is not shadowed by a variable in the `ParseState`?
+    fn is_scope_constant(&self, name: &str) -> bool {
+        self.stack
+            .iter()
+            .rposition(|(n, _)| n == name)
+            .map_or(false, |index| index < self.scope_constants)
+    }
+
+    /// Push a new variable onto the `ParseState`'s stack.
+    ///
+    /// Returns an error if the maximum number of variables is exceeded.
+    #[inline(always)]
+    fn push_var(
+        &mut self,
+        name: String,
+        typ: ScopeEntryType,
+        _pos: Position,
+    ) -> Result<(), ParseError> {
+        #[cfg(not(feature = "unchecked"))]
+        self.ensure_vars_within_max_limit(1, _pos)?;
+
+        self.stack.push((name, typ));
+        Ok(())
+    }
+
+    /// Make sure that `num` more variables can be declared without exceeding the maximum limit.
+    #[cfg(not(feature = "unchecked"))]
+    #[inline]
+    fn ensure_vars_within_max_limit(&self, num: usize, pos: Position) -> Result<(), ParseError> {
+        let limit = self.engine.max_variables();
+
+        if limit > 0 && self.stack.len().saturating_sub(self.scope_constants) + num > limit {
+            Err(PERR::TooManyVariables.into_err(pos))
+        } else {
+            Ok(())
         }
     }
 
@@ -653,7 +1364,8 @@ impl<'e> ParseState<'e> {
             .rev()
             .enumerate()
             .find(|(_, (n, _))| *n == name)
-            .and_then(|(i, _)| NonZeroUsize::new(i + 1));
+            .and_then(|(i, _)| NonZeroUsize::new(i + 1))
+            .filter(|i| i.get() <= self.stack.len().saturating_sub(self.scope_constants));
 
         #[cfg(not(feature = "no_closure"))]
         if self.allow_capture {
@@ -748,12 +1460,16 @@ pub enum Stmt {
     While(Expr, Box<Stmt>, Position),
     /// loop { stmt }
     Loop(Box<Stmt>, Position),
-    /// for id in expr { stmt }
-    For(Expr, Box<(String, Stmt)>, Position),
+    /// for id in expr { stmt } or for (id, id) in expr { stmt }
+    For(Expr, Box<(String, Option<String>, Stmt)>, Position),
     /// let id = expr
     Let(Box<(String, Position)>, Option<Expr>, Position),
     /// const id = expr
     Const(Box<(String, Position)>, Option<Expr>, Position),
+    /// global id
+    #[cfg(not(feature = "no_function"))]
+    #[cfg(not(feature = "no_closure"))]
+    Global(Box<(String, Position)>, Position),
     /// expr op= expr
     Assignment(Box<(Expr, Cow<'static, str>, Expr)>, Position),
     /// { stmt; ... }
@@ -821,6 +1537,10 @@ impl Stmt {
             Self::Let(x, _, _) | Self::Const(x, _, _) => x.1,
             Self::TryCatch(x) => (x.0).1,
 
+            #[cfg(not(feature = "no_function"))]
+            #[cfg(not(feature = "no_closure"))]
+            Self::Global(x, _) => x.1,
+
             Self::Expr(x) => x.position(),
 
             #[cfg(not(feature = "no_module"))]
@@ -850,6 +1570,10 @@ impl Stmt {
             Self::Let(x, _, _) | Self::Const(x, _, _) => x.1 = new_pos,
             Self::TryCatch(x) => (x.0).1 = new_pos,
 
+            #[cfg(not(feature = "no_function"))]
+            #[cfg(not(feature = "no_closure"))]
+            Self::Global(x, _) => x.1 = new_pos,
+
             Self::Expr(x) => {
                 x.set_position(new_pos);
             }
@@ -887,6 +1611,10 @@ impl Stmt {
             | Self::Break(_)
             | Self::ReturnWithVal(_, _, _) => false,
 
+            #[cfg(not(feature = "no_function"))]
+            #[cfg(not(feature = "no_closure"))]
+            Self::Global(_, _) => false,
+
             #[cfg(not(feature = "no_module"))]
             Self::Import(_, _, _) | Self::Export(_, _) => false,
 
@@ -906,12 +1634,16 @@ impl Stmt {
             Self::IfThenElse(condition, x, _) => condition.is_pure() && x.0.is_pure(),
             Self::While(condition, block, _) => condition.is_pure() && block.is_pure(),
             Self::Loop(block, _) => block.is_pure(),
-            Self::For(iterable, x, _) => iterable.is_pure() && x.1.is_pure(),
+            Self::For(iterable, x, _) => iterable.is_pure() && x.2.is_pure(),
             Self::Let(_, _, _) | Self::Const(_, _, _) | Self::Assignment(_, _) => false,
             Self::Block(block, _) => block.iter().all(|stmt| stmt.is_pure()),
             Self::Continue(_) | Self::Break(_) | Self::ReturnWithVal(_, _, _) => false,
             Self::TryCatch(x) => (x.0).0.is_pure() && (x.2).0.is_pure(),
 
+            #[cfg(not(feature = "no_function"))]
+            #[cfg(not(feature = "no_closure"))]
+            Self::Global(_, _) => false,
+
             #[cfg(not(feature = "no_module"))]
             Self::Import(_, _, _) => false,
             #[cfg(not(feature = "no_module"))]
@@ -931,9 +1663,10 @@ impl Stmt {
 /// This type is volatile and may change.
 #[derive(Clone)]
 pub struct CustomExpr {
-    keywords: StaticVec<Expr>,
-    func: Shared<FnCustomSyntaxEval>,
-    pos: Position,
+    pub(crate) keywords: StaticVec<Expr>,
+    pub(crate) func: Shared<FnCustomSyntaxEval>,
+    pub(crate) pure: bool,
+    pub(crate) pos: Position,
 }
 
 impl fmt::Debug for CustomExpr {
@@ -961,6 +1694,11 @@ impl CustomExpr {
     pub fn func(&self) -> &FnCustomSyntaxEval {
         self.func.as_ref()
     }
+    /// Is this `CustomExpr` pure (i.e. free of side effects)?
+    #[inline(always)]
+    pub fn is_pure(&self) -> bool {
+        self.pure
+    }
     /// Get the position of this `CustomExpr`.
     #[inline(always)]
     pub fn position(&self) -> Position {
@@ -1013,6 +1751,9 @@ pub enum Expr {
     /// Floating-point constant.
     #[cfg(not(feature = "no_float"))]
     FloatConstant(Box<FloatWrapper>),
+    /// Fixed-precision decimal constant.
+    #[cfg(feature = "decimal")]
+    DecimalConstant(Box<(Decimal, Position)>),
     /// Character constant.
     CharConstant(Box<(char, Position)>),
     /// String constant.
@@ -1046,14 +1787,16 @@ pub enum Expr {
             Option<bool>, // Default value is `bool` in order for `Expr` to be `Hash`.
         )>,
     ),
-    /// lhs.rhs
-    Dot(Box<BinaryExpr>),
-    /// expr[expr]
-    Index(Box<BinaryExpr>),
+    /// lhs.rhs or lhs?.rhs (optional chaining, if the flag is `true`)
+    Dot(Box<BinaryExpr>, bool),
+    /// expr[expr] or expr?[expr] (optional chaining, if the flag is `true`)
+    Index(Box<BinaryExpr>, bool),
     /// [ expr, ... ]
     Array(Box<(StaticVec<Expr>, Position)>),
     /// #{ name:expr, ... }
     Map(Box<(StaticVec<((ImmutableString, Position), Expr)>, Position)>),
+    /// #{ (expr):expr, name:expr, ... }
+    ComputedMap(Box<(StaticVec<(Expr, Expr)>, Position)>),
     /// lhs in rhs
     In(Box<BinaryExpr>),
     /// lhs && rhs
@@ -1088,6 +1831,8 @@ impl Expr {
             Self::IntegerConstant(_) => TypeId::of::<INT>(),
             #[cfg(not(feature = "no_float"))]
             Self::FloatConstant(_) => TypeId::of::<FLOAT>(),
+            #[cfg(feature = "decimal")]
+            Self::DecimalConstant(_) => TypeId::of::<Decimal>(),
             Self::CharConstant(_) => TypeId::of::<char>(),
             Self::StringConstant(_) => TypeId::of::<ImmutableString>(),
             Self::FnPointer(_) => TypeId::of::<FnPtr>(),
@@ -1100,7 +1845,7 @@ impl Expr {
             Self::Array(_) => TypeId::of::<Array>(),
 
             #[cfg(not(feature = "no_object"))]
-            Self::Map(_) => TypeId::of::<Map>(),
+            Self::Map(_) | Self::ComputedMap(_) => TypeId::of::<Map>(),
 
             _ => return None,
         })
@@ -1116,6 +1861,8 @@ impl Expr {
             Self::IntegerConstant(x) => x.0.into(),
             #[cfg(not(feature = "no_float"))]
             Self::FloatConstant(x) => x.0.into(),
+            #[cfg(feature = "decimal")]
+            Self::DecimalConstant(x) => x.0.into(),
             Self::CharConstant(x) => x.0.into(),
             Self::StringConstant(x) => x.0.clone().into(),
             Self::FnPointer(x) => Dynamic(Union::FnPtr(Box::new(FnPtr::new_unchecked(
@@ -1161,6 +1908,8 @@ impl Expr {
 
             #[cfg(not(feature = "no_float"))]
             Self::FloatConstant(x) => x.1,
+            #[cfg(feature = "decimal")]
+            Self::DecimalConstant(x) => x.1,
 
             Self::IntegerConstant(x) => x.1,
             Self::CharConstant(x) => x.1,
@@ -1168,6 +1917,7 @@ impl Expr {
             Self::FnPointer(x) => x.1,
             Self::Array(x) => x.1,
             Self::Map(x) => x.1,
+            Self::ComputedMap(x) => x.1,
             Self::Property(x) => x.1,
             Self::Stmt(x) => x.1,
             Self::Variable(x) => (x.0).1,
@@ -1177,7 +1927,7 @@ impl Expr {
 
             Self::True(pos) | Self::False(pos) | Self::Unit(pos) => *pos,
 
-            Self::Dot(x) | Self::Index(x) => x.lhs.position(),
+            Self::Dot(x, _) | Self::Index(x, _) => x.lhs.position(),
 
             Self::Custom(x) => x.pos,
         }
@@ -1192,6 +1942,8 @@ impl Expr {
 
             #[cfg(not(feature = "no_float"))]
             Self::FloatConstant(x) => x.1 = new_pos,
+            #[cfg(feature = "decimal")]
+            Self::DecimalConstant(x) => x.1 = new_pos,
 
             Self::IntegerConstant(x) => x.1 = new_pos,
             Self::CharConstant(x) => x.1 = new_pos,
@@ -1199,13 +1951,14 @@ impl Expr {
             Self::FnPointer(x) => x.1 = new_pos,
             Self::Array(x) => x.1 = new_pos,
             Self::Map(x) => x.1 = new_pos,
+            Self::ComputedMap(x) => x.1 = new_pos,
             Self::Variable(x) => (x.0).1 = new_pos,
             Self::Property(x) => x.1 = new_pos,
             Self::Stmt(x) => x.1 = new_pos,
             Self::FnCall(x) => (x.0).3 = new_pos,
             Self::And(x) | Self::Or(x) | Self::In(x) => x.pos = new_pos,
             Self::True(pos) | Self::False(pos) | Self::Unit(pos) => *pos = new_pos,
-            Self::Dot(x) | Self::Index(x) => x.pos = new_pos,
+            Self::Dot(x, _) | Self::Index(x, _) => x.pos = new_pos,
             Self::Custom(x) => x.pos = new_pos,
         }
 
@@ -1221,7 +1974,9 @@ impl Expr {
 
             Self::Array(x) => x.0.iter().all(Self::is_pure),
 
-            Self::Index(x) | Self::And(x) | Self::Or(x) | Self::In(x) => {
+            Self::ComputedMap(x) => x.0.iter().all(|(k, v)| k.is_pure() && v.is_pure()),
+
+            Self::Index(x, _) | Self::And(x) | Self::Or(x) | Self::In(x) => {
                 x.lhs.is_pure() && x.rhs.is_pure()
             }
 
@@ -1249,6 +2004,8 @@ impl Expr {
 
             #[cfg(not(feature = "no_float"))]
             Self::FloatConstant(_) => true,
+            #[cfg(feature = "decimal")]
+            Self::DecimalConstant(_) => true,
 
             Self::IntegerConstant(_)
             | Self::CharConstant(_)
@@ -1282,6 +2039,8 @@ impl Expr {
 
             #[cfg(not(feature = "no_float"))]
             Self::FloatConstant(_) => true,
+            #[cfg(feature = "decimal")]
+            Self::DecimalConstant(_) => true,
 
             Self::IntegerConstant(_)
             | Self::CharConstant(_)
@@ -1315,6 +2074,8 @@ impl Expr {
 
             #[cfg(not(feature = "no_float"))]
             Self::FloatConstant(_) => false,
+            #[cfg(feature = "decimal")]
+            Self::DecimalConstant(_) => false,
 
             Self::IntegerConstant(_)
             | Self::CharConstant(_)
@@ -1329,18 +2090,19 @@ impl Expr {
             Self::StringConstant(_)
             | Self::Stmt(_)
             | Self::FnCall(_)
-            | Self::Dot(_)
-            | Self::Index(_)
+            | Self::Dot(_, _)
+            | Self::Index(_, _)
             | Self::Array(_)
-            | Self::Map(_) => match token {
+            | Self::Map(_)
+            | Self::ComputedMap(_) => match token {
                 #[cfg(not(feature = "no_index"))]
-                Token::LeftBracket => true,
+                Token::LeftBracket | Token::QuestionBracket => true,
                 _ => false,
             },
 
             Self::Variable(_) => match token {
                 #[cfg(not(feature = "no_index"))]
-                Token::LeftBracket => true,
+                Token::LeftBracket | Token::QuestionBracket => true,
                 Token::LeftParen => true,
                 Token::Bang => true,
                 Token::DoubleColon => true,
@@ -1349,7 +2111,7 @@ impl Expr {
 
             Self::Property(_) => match token {
                 #[cfg(not(feature = "no_index"))]
-                Token::LeftBracket => true,
+                Token::LeftBracket | Token::QuestionBracket => true,
                 Token::LeftParen => true,
                 _ => false,
             },
@@ -1461,6 +2223,13 @@ fn parse_fn_call(
         Token::RightParen => {
             eat_token(input, Token::RightParen);
 
+            // Calls to functions defined inside the enclosing blocks
+            #[cfg(not(feature = "no_function"))]
+            let id = match modules {
+                None => state.find_local_fn(&id, 0).map_or(id, |n| n.to_string()),
+                Some(_) => id,
+            };
+
             let hash_script = if let Some(modules) = modules.as_mut() {
                 #[cfg(not(feature = "no_module"))]
                 modules.set_index(state.find_module(&modules[0].0));
@@ -1504,6 +2273,15 @@ fn parse_fn_call(
             (Token::RightParen, _) => {
                 eat_token(input, Token::RightParen);
 
+                // Calls to functions defined inside the enclosing blocks
+                #[cfg(not(feature = "no_function"))]
+                let id = match modules {
+                    None => state
+                        .find_local_fn(&id, args.len())
+                        .map_or(id, |n| n.to_string()),
+                    Some(_) => id,
+                };
+
                 let hash_script = if let Some(modules) = modules.as_mut() {
                     #[cfg(not(feature = "no_module"))]
                     modules.set_index(state.find_module(&modules[0].0));
@@ -1563,6 +2341,7 @@ fn parse_index_chain(
     state: &mut ParseState,
     lib: &mut FunctionsLib,
     lhs: Expr,
+    is_optional: bool,
     mut settings: ParseSettings,
 ) -> Result<Expr, ParseError> {
     #[cfg(not(feature = "unchecked"))]
@@ -1573,17 +2352,10 @@ fn parse_index_chain(
     // Check type of indexing - must be integer or string
     match &idx_expr {
         // lhs[int]
-        Expr::IntegerConstant(x) if x.0 < 0 => {
-            return Err(PERR::MalformedIndexExpr(format!(
-                "Array access expects non-negative index: {} < 0",
-                x.0
-            ))
-            .into_err(x.1))
-        }
         Expr::IntegerConstant(x) => match lhs {
             Expr::Array(_) | Expr::StringConstant(_) => (),
 
-            Expr::Map(_) => {
+            Expr::Map(_) | Expr::ComputedMap(_) => {
                 return Err(PERR::MalformedIndexExpr(
                     "Object map access expects string index, not a number".into(),
                 )
@@ -1598,6 +2370,14 @@ fn parse_index_chain(
                 .into_err(lhs.position()))
             }
 
+            #[cfg(feature = "decimal")]
+            Expr::DecimalConstant(_) => {
+                return Err(PERR::MalformedIndexExpr(
+                    "Only arrays, object maps and strings can be indexed".into(),
+                )
+                .into_err(lhs.position()))
+            }
+
             Expr::CharConstant(_)
             | Expr::And(_)
             | Expr::Or(_)
@@ -1616,7 +2396,7 @@ fn parse_index_chain(
 
         // lhs[string]
         Expr::StringConstant(x) => match lhs {
-            Expr::Map(_) => (),
+            Expr::Map(_) | Expr::ComputedMap(_) => (),
 
             Expr::Array(_) | Expr::StringConstant(_) => {
                 return Err(PERR::MalformedIndexExpr(
@@ -1633,6 +2413,14 @@ fn parse_index_chain(
                 .into_err(lhs.position()))
             }
 
+            #[cfg(feature = "decimal")]
+            Expr::DecimalConstant(_) => {
+                return Err(PERR::MalformedIndexExpr(
+                    "Only arrays, object maps and strings can be indexed".into(),
+                )
+                .into_err(lhs.position()))
+            }
+
             Expr::CharConstant(_)
             | Expr::And(_)
             | Expr::Or(_)
@@ -1657,6 +2445,14 @@ fn parse_index_chain(
             )
             .into_err(x.position()))
         }
+        // lhs[decimal]
+        #[cfg(feature = "decimal")]
+        x @ Expr::DecimalConstant(_) => {
+            return Err(PERR::MalformedIndexExpr(
+                "Array access expects integer index, not a decimal".into(),
+            )
+            .into_err(x.position()))
+        }
         // lhs[char]
         x @ Expr::CharConstant(_) => {
             return Err(PERR::MalformedIndexExpr(
@@ -1697,18 +2493,29 @@ fn parse_index_chain(
             // Any more indexing following?
             match input.peek().unwrap() {
                 // If another indexing level, right-bind it
-                (Token::LeftBracket, _) => {
+                (Token::LeftBracket, _) | (Token::QuestionBracket, _) => {
                     let prev_pos = settings.pos;
-                    settings.pos = eat_token(input, Token::LeftBracket);
+                    let (token, pos) = input.next().unwrap();
+                    settings.pos = pos;
+                    let next_is_optional = token == Token::QuestionBracket;
                     // Recursively parse the indexing chain, right-binding each
-                    let idx_expr =
-                        parse_index_chain(input, state, lib, idx_expr, settings.level_up())?;
+                    let idx_expr = parse_index_chain(
+                        input,
+                        state,
+                        lib,
+                        idx_expr,
+                        next_is_optional,
+                        settings.level_up(),
+                    )?;
                     // Indexing binds to right
-                    Ok(Expr::Index(Box::new(BinaryExpr {
-                        lhs,
-                        rhs: idx_expr,
-                        pos: prev_pos,
-                    })))
+                    Ok(Expr::Index(
+                        Box::new(BinaryExpr {
+                            lhs,
+                            rhs: idx_expr,
+                            pos: prev_pos,
+                        }),
+                        is_optional,
+                    ))
                 }
                 // Otherwise terminate the indexing chain
                 _ => {
@@ -1716,18 +2523,24 @@ fn parse_index_chain(
                         // Terminate with an `Expr::Expr` wrapper to prevent the last index expression
                         // inside brackets to be mis-parsed as another level of indexing, or a
                         // dot expression/function call to be mis-parsed as following the indexing chain.
-                        Expr::Index(_) | Expr::Dot(_) | Expr::FnCall(_) => {
-                            Ok(Expr::Index(Box::new(BinaryExpr {
+                        // The wrapper only encloses the index expression itself - any `.` chain
+                        // following the closing `]` is parsed as usual by the caller.
+                        Expr::Index(_, _) | Expr::Dot(_, _) | Expr::FnCall(_) => Ok(Expr::Index(
+                            Box::new(BinaryExpr {
                                 lhs,
                                 rhs: Expr::Expr(Box::new(idx_expr)),
                                 pos: settings.pos,
-                            })))
-                        }
-                        _ => Ok(Expr::Index(Box::new(BinaryExpr {
-                            lhs,
-                            rhs: idx_expr,
-                            pos: settings.pos,
-                        }))),
+                            }),
+                            is_optional,
+                        )),
+                        _ => Ok(Expr::Index(
+                            Box::new(BinaryExpr {
+                                lhs,
+                                rhs: idx_expr,
+                                pos: settings.pos,
+                            }),
+                            is_optional,
+                        )),
                     }
                 }
             }
@@ -1751,6 +2564,8 @@ fn parse_array_literal(
 ) -> Result<Expr, ParseError> {
     #[cfg(not(feature = "unchecked"))]
     settings.ensure_level_within_max_limit(state.max_expr_depth)?;
+    #[cfg(not(feature = "unchecked"))]
+    state.enter_data_literal(settings.pos)?;
 
     let mut arr = StaticVec::new();
 
@@ -1798,6 +2613,11 @@ fn parse_array_literal(
         };
     }
 
+    #[cfg(not(feature = "unchecked"))]
+    {
+        state.data_depth -= 1;
+    }
+
     Ok(Expr::Array(Box::new((arr, settings.pos))))
 }
 
@@ -1811,8 +2631,10 @@ fn parse_map_literal(
 ) -> Result<Expr, ParseError> {
     #[cfg(not(feature = "unchecked"))]
     settings.ensure_level_within_max_limit(state.max_expr_depth)?;
+    #[cfg(not(feature = "unchecked"))]
+    state.enter_data_literal(settings.pos)?;
 
-    let mut map = StaticVec::new();
+    let mut map: StaticVec<(Expr, Expr)> = StaticVec::new();
 
     while !input.peek().unwrap().0.is_eof() {
         const MISSING_RBRACE: &str = "to end this object map literal";
@@ -1825,11 +2647,18 @@ fn parse_map_literal(
             _ => (),
         }
 
-        let (name, pos) = match input.next().unwrap() {
-            (Token::Identifier(s), pos) => (s, pos),
-            (Token::StringConstant(s), pos) => (s, pos),
+        let key = match input.next().unwrap() {
+            (Token::Identifier(s), pos) => Expr::StringConstant(Box::new((s.into(), pos))),
+            (Token::StringConstant(s), pos) => Expr::StringConstant(Box::new((s.into(), pos))),
+            // Reserved keywords are OK because property names are always strings
             (Token::Reserved(s), pos) if is_valid_identifier(s.chars()) => {
-                return Err(PERR::Reserved(s).into_err(pos));
+                Expr::StringConstant(Box::new((s.into(), pos)))
+            }
+            // Computed key: (expr)
+            (Token::LeftParen, pos) => {
+                let mut settings = settings;
+                settings.pos = pos;
+                parse_paren_expr(input, state, lib, settings.level_up())?
             }
             (Token::LexError(err), pos) => return Err(err.into_err(pos)),
             (_, pos) if map.is_empty() => {
@@ -1853,10 +2682,13 @@ fn parse_map_literal(
             (_, pos) => {
                 return Err(PERR::MissingToken(
                     Token::Colon.into(),
-                    format!(
-                        "to follow the property '{}' in this object map literal",
-                        name
-                    ),
+                    match &key {
+                        Expr::StringConstant(x) => format!(
+                            "to follow the property '{}' in this object map literal",
+                            x.0
+                        ),
+                        _ => "to follow the computed property in this object map literal".into(),
+                    },
                 )
                 .into_err(pos))
             }
@@ -1872,7 +2704,7 @@ fn parse_map_literal(
         }
 
         let expr = parse_expr(input, state, lib, settings.level_up())?;
-        map.push(((Into::<ImmutableString>::into(name), pos), expr));
+        map.push((key, expr));
 
         match input.peek().unwrap() {
             (Token::Comma, _) => {
@@ -1896,17 +2728,45 @@ fn parse_map_literal(
         }
     }
 
-    // Check for duplicating properties
+    #[cfg(not(feature = "unchecked"))]
+    {
+        state.data_depth -= 1;
+    }
+
+    // Check for duplicating properties - only literal keys can be checked
     map.iter()
         .enumerate()
-        .try_for_each(|(i, ((k1, _), _))| {
+        .filter_map(|(i, (k, _))| match k {
+            Expr::StringConstant(x) => Some((i, &x.0)),
+            _ => None,
+        })
+        .try_for_each(|(i, k1)| {
             map.iter()
                 .skip(i + 1)
-                .find(|((k2, _), _)| k2 == k1)
-                .map_or_else(|| Ok(()), |((k2, pos), _)| Err((k2, *pos)))
+                .find_map(|(k2, _)| match k2 {
+                    Expr::StringConstant(x) if &x.0 == k1 => Some(x.1),
+                    _ => None,
+                })
+                .map_or_else(|| Ok(()), |pos| Err((k1, pos)))
         })
         .map_err(|(key, pos)| PERR::DuplicatedProperty(key.to_string()).into_err(pos))?;
 
+    // Computed keys are evaluated at runtime
+    if map
+        .iter()
+        .any(|(k, _)| !matches!(k, Expr::StringConstant(_)))
+    {
+        return Ok(Expr::ComputedMap(Box::new((map, settings.pos))));
+    }
+
+    let map = map
+        .into_iter()
+        .map(|(k, expr)| match k {
+            Expr::StringConstant(x) => (*x, expr),
+            _ => unreachable!(),
+        })
+        .collect();
+
     Ok(Expr::Map(Box::new((map, settings.pos))))
 }
 
@@ -1935,10 +2795,15 @@ fn parse_primary(
 
     let (next_token, _) = input.peek().unwrap();
 
+    // Only a bare name can be called - e.g. not `(foo)(x)` or `(foo)!(x)`
+    let is_fn_name = matches!(token, Token::Identifier(_) | Token::Reserved(_));
+
     let mut root_expr = match token {
         Token::IntegerConstant(x) => Expr::IntegerConstant(Box::new((x, settings.pos))),
         #[cfg(not(feature = "no_float"))]
         Token::FloatConstant(x) => Expr::FloatConstant(Box::new(FloatWrapper(x, settings.pos))),
+        #[cfg(feature = "decimal")]
+        Token::DecimalConstant(x) => Expr::DecimalConstant(Box::new((x, settings.pos))),
         Token::CharConstant(c) => Expr::CharConstant(Box::new((c, settings.pos))),
         Token::StringConstant(s) => Expr::StringConstant(Box::new((s.into(), settings.pos))),
 
@@ -2016,6 +2881,11 @@ fn parse_primary(
             break;
         }
 
+        match token {
+            Token::LeftParen | Token::Bang if !is_fn_name => break,
+            _ => (),
+        }
+
         let (token, token_pos) = input.next().unwrap();
         settings.pos = token_pos;
 
@@ -2074,7 +2944,12 @@ fn parse_primary(
             // Indexing
             #[cfg(not(feature = "no_index"))]
             (expr, Token::LeftBracket) => {
-                parse_index_chain(input, state, lib, expr, settings.level_up())?
+                parse_index_chain(input, state, lib, expr, false, settings.level_up())?
+            }
+            // Optional indexing
+            #[cfg(not(feature = "no_index"))]
+            (expr, Token::QuestionBracket) => {
+                parse_index_chain(input, state, lib, expr, true, settings.level_up())?
             }
             // Unknown postfix operator
             (expr, token) => unreachable!(
@@ -2152,6 +3027,13 @@ fn parse_unary(
                     Ok(Expr::FloatConstant(Box::new(FloatWrapper(-x.0, x.1))))
                 }
 
+                // Negative decimal
+                #[cfg(feature = "decimal")]
+                Expr::DecimalConstant(x) => {
+                    let (num, pos) = *x;
+                    Ok(Expr::DecimalConstant(Box::new((-num, pos))))
+                }
+
                 // Call negative function
                 expr => {
                     let op = "-";
@@ -2195,10 +3077,22 @@ fn parse_unary(
         // | ...
         #[cfg(not(feature = "no_function"))]
         Token::Pipe | Token::Or if settings.allow_anonymous_fn => {
+            // The closure body continues the current level of nesting, so that nested closures
+            // cannot exceed the global limit
+            #[cfg(not(feature = "unchecked"))]
+            let max_closure_depth =
+                match (state.engine.max_expr_depth(), state.max_function_expr_depth) {
+                    (0, 0) => 0,
+                    (0, depth) => settings.level + depth,
+                    (max, 0) => max,
+                    (max, depth) => max.min(settings.level + depth),
+                };
+
             let mut new_state = ParseState::new(
                 state.engine,
+                state.source_hash,
                 #[cfg(not(feature = "unchecked"))]
-                state.max_function_expr_depth,
+                max_closure_depth,
                 #[cfg(not(feature = "unchecked"))]
                 state.max_function_expr_depth,
             );
@@ -2210,10 +3104,12 @@ fn parse_unary(
                 is_global: false,
                 is_function_scope: true,
                 is_breakable: false,
-                level: 0,
+                level: settings.level,
                 pos: *token_pos,
             };
 
+            new_state.local_fns = state.local_fns.clone();
+
             let (expr, func) = parse_anon_fn(input, &mut new_state, lib, settings)?;
 
             #[cfg(not(feature = "no_closure"))]
@@ -2243,6 +3139,11 @@ fn make_assignment_stmt<'a>(
     pos: Position,
 ) -> Result<Stmt, ParseError> {
     match &lhs {
+        // const (from external scope) = rhs
+        Expr::Variable(x) if x.3.is_none() && state.is_scope_constant(&(x.0).0) => {
+            let (name, name_pos) = &x.0;
+            Err(PERR::AssignmentToConstant(name.clone()).into_err(*name_pos))
+        }
         // var (non-indexed) = rhs
         Expr::Variable(x) if x.3.is_none() => {
             Ok(Stmt::Assignment(Box::new((lhs, fn_name.into(), rhs)), pos))
@@ -2261,7 +3162,12 @@ fn make_assignment_stmt<'a>(
             }
         }
         // xxx[???] = rhs, xxx.??? = rhs
-        Expr::Index(x) | Expr::Dot(x) => match &x.lhs {
+        Expr::Index(x, _) | Expr::Dot(x, _) => match &x.lhs {
+            // const[???] (from external scope) = rhs, const.??? (from external scope) = rhs
+            Expr::Variable(x) if x.3.is_none() && state.is_scope_constant(&(x.0).0) => {
+                let (name, name_pos) = &x.0;
+                Err(PERR::AssignmentToConstant(name.clone()).into_err(*name_pos))
+            }
             // var[???] (non-indexed) = rhs, var.??? (non-indexed) = rhs
             Expr::Variable(x) if x.3.is_none() => {
                 Ok(Stmt::Assignment(Box::new((lhs, fn_name.into(), rhs)), pos))
@@ -2322,7 +3228,8 @@ fn parse_op_assignment_stmt(
         | Token::PowerOfAssign
         | Token::AndAssign
         | Token::OrAssign
-        | Token::XOrAssign => token.syntax(),
+        | Token::XOrAssign
+        | Token::CustomAssign(_) => token.syntax(),
 
         _ => return Ok(Stmt::Expr(lhs)),
     };
@@ -2332,15 +3239,20 @@ fn parse_op_assignment_stmt(
     make_assignment_stmt(op, state, lhs, rhs, pos)
 }
 
-/// Make a dot expression.
+/// Make a dot expression, or an optional chaining expression (i.e. `?.`) if `is_optional` is `true`.
 #[cfg(not(feature = "no_object"))]
-fn make_dot_expr(lhs: Expr, rhs: Expr, op_pos: Position) -> Result<Expr, ParseError> {
+fn make_dot_expr(
+    lhs: Expr,
+    rhs: Expr,
+    op_pos: Position,
+    is_optional: bool,
+) -> Result<Expr, ParseError> {
     Ok(match (lhs, rhs) {
         // idx_lhs[idx_expr].rhs
         // Attach dot chain to the bottom level of indexing chain
-        (Expr::Index(mut x), rhs) => {
-            x.rhs = make_dot_expr(x.rhs, rhs, op_pos)?;
-            Expr::Index(x)
+        (Expr::Index(mut x, index_is_optional), rhs) => {
+            x.rhs = make_dot_expr(x.rhs, rhs, op_pos, is_optional)?;
+            Expr::Index(x, index_is_optional)
         }
         // lhs.id
         (lhs, Expr::Variable(x)) if x.1.is_none() => {
@@ -2350,47 +3262,65 @@ fn make_dot_expr(lhs: Expr, rhs: Expr, op_pos: Position) -> Result<Expr, ParseEr
             let setter = make_setter(&name);
             let rhs = Expr::Property(Box::new(((name.into(), getter, setter), pos)));
 
-            Expr::Dot(Box::new(BinaryExpr {
-                lhs,
-                rhs,
-                pos: op_pos,
-            }))
+            Expr::Dot(
+                Box::new(BinaryExpr {
+                    lhs,
+                    rhs,
+                    pos: op_pos,
+                }),
+                is_optional,
+            )
         }
         // lhs.module::id - syntax error
         (_, Expr::Variable(x)) if x.1.is_some() => {
             return Err(PERR::PropertyExpected.into_err(x.1.unwrap()[0].1));
         }
         // lhs.prop
-        (lhs, prop @ Expr::Property(_)) => Expr::Dot(Box::new(BinaryExpr {
-            lhs,
-            rhs: prop,
-            pos: op_pos,
-        })),
-        // lhs.dot_lhs.dot_rhs
-        (lhs, Expr::Dot(x)) => {
-            let rhs = Expr::Dot(Box::new(BinaryExpr {
-                lhs: x.lhs.into_property(),
-                rhs: x.rhs,
-                pos: x.pos,
-            }));
-            Expr::Dot(Box::new(BinaryExpr {
+        (lhs, prop @ Expr::Property(_)) => Expr::Dot(
+            Box::new(BinaryExpr {
                 lhs,
-                rhs,
+                rhs: prop,
                 pos: op_pos,
-            }))
+            }),
+            is_optional,
+        ),
+        // lhs.dot_lhs.dot_rhs
+        (lhs, Expr::Dot(x, dot_is_optional)) => {
+            let rhs = Expr::Dot(
+                Box::new(BinaryExpr {
+                    lhs: x.lhs.into_property(),
+                    rhs: x.rhs,
+                    pos: x.pos,
+                }),
+                dot_is_optional,
+            );
+            Expr::Dot(
+                Box::new(BinaryExpr {
+                    lhs,
+                    rhs,
+                    pos: op_pos,
+                }),
+                is_optional,
+            )
         }
         // lhs.idx_lhs[idx_rhs]
-        (lhs, Expr::Index(x)) => {
-            let rhs = Expr::Index(Box::new(BinaryExpr {
-                lhs: x.lhs.into_property(),
-                rhs: x.rhs,
-                pos: x.pos,
-            }));
-            Expr::Dot(Box::new(BinaryExpr {
-                lhs,
-                rhs,
-                pos: op_pos,
-            }))
+        (lhs, Expr::Index(x, index_is_optional)) => {
+            let rhs = Expr::Index(
+                Box::new(BinaryExpr {
+                    lhs: x.lhs.into_property(),
+                    rhs: x.rhs,
+                    pos: x.pos,
+                }),
+                index_is_optional,
+            );
+            Expr::Dot(
+                Box::new(BinaryExpr {
+                    lhs,
+                    rhs,
+                    pos: op_pos,
+                }),
+                is_optional,
+            )
         }
         // lhs.Fn() or lhs.eval()
         (_, Expr::FnCall(x))
@@ -2411,16 +3341,109 @@ fn make_dot_expr(lhs: Expr, rhs: Expr, op_pos: Position) -> Result<Expr, ParseEr
             .into_err((x.0).3))
         }
         // lhs.func(...)
-        (lhs, func @ Expr::FnCall(_)) => Expr::Dot(Box::new(BinaryExpr {
-            lhs,
-            rhs: func,
-            pos: op_pos,
-        })),
+        (lhs, func @ Expr::FnCall(_)) => Expr::Dot(
+            Box::new(BinaryExpr {
+                lhs,
+                rhs: func,
+                pos: op_pos,
+            }),
+            is_optional,
+        ),
         // lhs.rhs
         (_, rhs) => return Err(PERR::PropertyExpected.into_err(rhs.position())),
     })
 }
 
+/// Is the token a comparison operator that can be chained (e.g. `a < b < c`)?
+#[inline(always)]
+fn is_chainable_comparison(token: &Token) -> bool {
+    match token {
+        Token::LessThan
+        | Token::LessThanEqualsTo
+        | Token::GreaterThan
+        | Token::GreaterThanEqualsTo => true,
+        _ => false,
+    }
+}
+
+/// Make a comparison expression.
+fn make_comparison_expr(lhs: Expr, op_token: &Token, rhs: Expr, op_pos: Position) -> Expr {
+    let op = op_token.syntax();
+    let hash = calc_fn_hash(empty(), &op, 2, empty());
+
+    let mut args = StaticVec::new();
+    args.push(lhs);
+    args.push(rhs);
+
+    // Comparison operators default to false when passed invalid operands
+    Expr::FnCall(Box::new(((op, true, false, op_pos), None, hash, args, Some(false))))
+}
+
+/// Make a chain of comparisons (e.g. `a < b <= c`) into comparisons joined by `&&`
+/// (e.g. `a < b && b <= c`).
+///
+/// Operands between two comparisons that are not pure are evaluated only once, by binding them
+/// to temporary variables within a statement block.
+fn make_comparison_chain(lhs: Expr, chain: StaticVec<(Token, Position, Expr)>) -> Expr {
+    fn make_temp_var(index: usize, pos: Position) -> Expr {
+        Expr::Variable(Box::new(((format!("cmp${}", index), pos), None, 0, None)))
+    }
+
+    fn make_let_stmt(index: usize, expr: Expr) -> Stmt {
+        let pos = expr.position();
+        Stmt::Let(Box::new((format!("cmp${}", index), pos)), Some(expr), pos)
+    }
+
+    fn make_block_expr(statements: Vec<Stmt>, pos: Position) -> Expr {
+        Expr::Stmt(Box::new((Stmt::Block(statements, pos), pos)))
+    }
+
+    fn make_chain(lhs: Expr, mut chain: StaticVec<(Token, Position, Expr)>, index: usize) -> Expr {
+        let (op_token, op_pos, rhs) = chain.remove(0);
+
+        if chain.is_empty() {
+            return make_comparison_expr(lhs, &op_token, rhs, op_pos);
+        }
+
+        if rhs.is_pure() {
+            let comparison = make_comparison_expr(lhs, &op_token, rhs.clone(), op_pos);
+
+            return Expr::And(Box::new(BinaryExpr {
+                lhs: comparison,
+                rhs: make_chain(rhs, chain, index),
+                pos: op_pos,
+            }));
+        }
+
+        let pos = rhs.position();
+        let temp = make_temp_var(index, pos);
+        let comparisons = Expr::And(Box::new(BinaryExpr {
+            lhs: make_comparison_expr(lhs, &op_token, temp.clone(), op_pos),
+            rhs: make_chain(temp, chain, index + 1),
+            pos: op_pos,
+        }));
+        make_block_expr(vec![make_let_stmt(index, rhs), Stmt::Expr(comparisons)], pos)
+    }
+
+    let pos = lhs.position();
+    let needs_temp = chain.iter().rev().skip(1).any(|(_, _, expr)| !expr.is_pure());
+
+    let mut expr = if !lhs.is_pure() && !chain[0].2.is_pure() && chain.len() > 1 {
+        // The first operand must be evaluated before the second
+        let comparisons = make_chain(make_temp_var(0, pos), chain, 1);
+        make_block_expr(vec![make_let_stmt(0, lhs), Stmt::Expr(comparisons)], pos)
+    } else {
+        make_chain(lhs, chain, 0)
+    };
+
+    // Variables are searched by name because the temporary variables change the layout of the stack
+    if needs_temp {
+        clear_var_indices_expr(&mut expr);
+    }
+
+    expr
+}
+
 /// Make an 'in' expression.
 fn make_in_expr(lhs: Expr, rhs: Expr, op_pos: Position) -> Result<Expr, ParseError> {
     match (&lhs, &rhs) {
@@ -2445,6 +3468,14 @@ fn make_in_expr(lhs: Expr, rhs: Expr, op_pos: Position) -> Result<Expr, ParseErr
             .into_err(x.position()))
         }
 
+        #[cfg(feature = "decimal")]
+        (_, x @ Expr::DecimalConstant(_)) => {
+            return Err(PERR::MalformedInExpr(
+                "'in' expression expects a string, array or object map".into(),
+            )
+            .into_err(x.position()))
+        }
+
         // "xxx" in "xxxx", 'x' in "xxxx" - OK!
         (Expr::StringConstant(_), Expr::StringConstant(_))
         | (Expr::CharConstant(_), Expr::StringConstant(_)) => (),
@@ -2457,6 +3488,14 @@ fn make_in_expr(lhs: Expr, rhs: Expr, op_pos: Position) -> Result<Expr, ParseErr
             )
             .into_err(x.position()))
         }
+        // 123.456d in "xxxx"
+        #[cfg(feature = "decimal")]
+        (x @ Expr::DecimalConstant(_), Expr::StringConstant(_)) => {
+            return Err(PERR::MalformedInExpr(
+                "'in' expression for a string expects a string, not a decimal".into(),
+            )
+            .into_err(x.position()))
+        }
         // 123 in "xxxx"
         (x @ Expr::IntegerConstant(_), Expr::StringConstant(_)) => {
             return Err(PERR::MalformedInExpr(
@@ -2509,6 +3548,14 @@ fn make_in_expr(lhs: Expr, rhs: Expr, op_pos: Position) -> Result<Expr, ParseErr
             )
             .into_err(x.position()))
         }
+        // 123.456d in #{...}
+        #[cfg(feature = "decimal")]
+        (x @ Expr::DecimalConstant(_), Expr::Map(_)) => {
+            return Err(PERR::MalformedInExpr(
+                "'in' expression for an object map expects a string, not a decimal".into(),
+            )
+            .into_err(x.position()))
+        }
         // 123 in #{...}
         (x @ Expr::IntegerConstant(_), Expr::Map(_)) => {
             return Err(PERR::MalformedInExpr(
@@ -2575,20 +3622,23 @@ fn parse_binary_op(
     settings.ensure_level_within_max_limit(state.max_expr_depth)?;
 
     let mut root = lhs;
+    let mut chain = StaticVec::new();
 
     loop {
         let (current_op, current_pos) = input.peek().unwrap();
-        let precedence = if let Token::Custom(c) = current_op {
+        let (precedence, bind_right) = if let Token::Custom(c) = current_op {
             // Custom operators
-            if let Some(Some(p)) = state.engine.custom_keywords.get(c) {
-                *p
+            if let Some(Some((p, r))) = state.engine.custom_keywords.get(c) {
+                (*p, *r)
             } else {
                 return Err(PERR::Reserved(c.clone()).into_err(*current_pos));
             }
         } else {
-            current_op.precedence()
+            (
+                state.engine.token_precedence(current_op),
+                current_op.is_bind_right(),
+            )
         };
-        let bind_right = current_op.is_bind_right();
 
         // Bind left to the parent lhs expression if precedence is higher
         // If same precedence, then check if the operator binds right
@@ -2598,7 +3648,9 @@ fn parse_binary_op(
 
         let (op_token, pos) = input.next().unwrap();
 
-        if cfg!(not(feature = "no_object")) && op_token == Token::Period {
+        if cfg!(not(feature = "no_object"))
+            && (op_token == Token::Period || op_token == Token::QuestionDot)
+        {
             if let (Token::Identifier(_), _) = input.peek().unwrap() {
                 // prevents capturing of the object properties as vars: xxx.<var>
                 #[cfg(not(feature = "no_closure"))]
@@ -2613,19 +3665,19 @@ fn parse_binary_op(
         let (next_op, next_pos) = input.peek().unwrap();
         let next_precedence = if let Token::Custom(c) = next_op {
             // Custom operators
-            if let Some(Some(p)) = state.engine.custom_keywords.get(c) {
+            if let Some(Some((p, _))) = state.engine.custom_keywords.get(c) {
                 *p
             } else {
                 return Err(PERR::Reserved(c.clone()).into_err(*next_pos));
             }
         } else {
-            next_op.precedence()
+            state.engine.token_precedence(next_op)
         };
 
         // Bind to right if the next operator has higher precedence
         // If same precedence, then check if the operator binds right
         let rhs = if (precedence == next_precedence && bind_right) || precedence < next_precedence {
-            parse_binary_op(input, state, lib, precedence, rhs, settings)?
+            parse_binary_op(input, state, lib, precedence, rhs, settings.level_up())?
         } else {
             // Otherwise bind to left (even if next operator has the same precedence)
             rhs
@@ -2637,6 +3689,23 @@ fn parse_binary_op(
         #[cfg(not(feature = "unchecked"))]
         settings.ensure_level_within_max_limit(state.max_expr_depth)?;
 
+        // Chain of comparisons, e.g. `a < b < c`
+        if state.engine.allow_comparison_chains && is_chainable_comparison(&op_token) {
+            let (next_op, _) = input.peek().unwrap();
+            let continued = is_chainable_comparison(next_op)
+                && state.engine.token_precedence(next_op) == precedence;
+
+            if continued || !chain.is_empty() {
+                chain.push((op_token, pos, rhs));
+
+                // Keep the first operand as the root until the end of the chain
+                if !continued {
+                    root = make_comparison_chain(root, mem::take(&mut chain));
+                }
+                continue;
+            }
+        }
+
         let cmp_def = Some(false);
         let op = op_token.syntax();
         let hash = calc_fn_hash(empty(), &op, 2, empty());
@@ -2697,7 +3766,13 @@ fn parse_binary_op(
             Token::Period => {
                 let rhs = args.pop().unwrap();
                 let current_lhs = args.pop().unwrap();
-                make_dot_expr(current_lhs, rhs, pos)?
+                make_dot_expr(current_lhs, rhs, pos, false)?
+            }
+            #[cfg(not(feature = "no_object"))]
+            Token::QuestionDot => {
+                let rhs = args.pop().unwrap();
+                let current_lhs = args.pop().unwrap();
+                make_dot_expr(current_lhs, rhs, pos, true)?
             }
 
             Token::Custom(s) if state.engine.custom_keywords.contains_key(&s) => {
@@ -2721,11 +3796,17 @@ fn parse_custom_syntax(
     syntax: &CustomSyntax,
     pos: Position,
 ) -> Result<Expr, ParseError> {
+    #[cfg(not(feature = "unchecked"))]
+    settings.ensure_level_within_max_limit(state.max_expr_depth)?;
+
     let mut exprs: StaticVec<Expr> = Default::default();
 
     // Adjust the variables stack
     match syntax.scope_delta {
         delta if delta > 0 => {
+            #[cfg(not(feature = "unchecked"))]
+            state.ensure_vars_within_max_limit(delta as usize, pos)?;
+
             state.stack.resize(
                 state.stack.len() + delta as usize,
                 ("".to_string(), ScopeEntryType::Normal),
@@ -2794,6 +3875,7 @@ fn parse_custom_syntax(
     Ok(Expr::Custom(Box::new(CustomExpr {
         keywords: exprs,
         func: syntax.func.clone(),
+        pure: syntax.pure,
         pos,
     })))
 }
@@ -2816,7 +3898,10 @@ fn parse_expr(
         let token_pos = *pos;
 
         match token {
-            Token::Custom(key) | Token::Reserved(key) | Token::Identifier(key) => {
+            // Disabled custom syntax is skipped
+            Token::Custom(key) | Token::Reserved(key) | Token::Identifier(key)
+                if !state.engine.disabled_symbols.contains(key) =>
+            {
                 match state.engine.custom_syntax.get_key_value(key) {
                     Some((key, syntax)) => {
                         input.next().unwrap();
@@ -2864,7 +3949,8 @@ fn ensure_not_assignment(input: &mut TokenStream) -> Result<(), ParseError> {
         | (Token::PowerOfAssign, pos)
         | (Token::AndAssign, pos)
         | (Token::OrAssign, pos)
-        | (Token::XOrAssign, pos) => Err(PERR::BadInput(
+        | (Token::XOrAssign, pos)
+        | (Token::CustomAssign(_), pos) => Err(PERR::BadInput(
             "Expecting a boolean expression, not an assignment".to_string(),
         )
         .into_err(*pos)),
@@ -2873,6 +3959,26 @@ fn ensure_not_assignment(input: &mut TokenStream) -> Result<(), ParseError> {
     }
 }
 
+/// Make sure that a guard condition is not a constant boolean, if strict conditions are enabled.
+///
+/// `while true` is exempted if the `Engine` allows it.
+fn ensure_not_constant_condition(
+    state: &ParseState,
+    guard: &Expr,
+    is_while: bool,
+) -> Result<(), ParseError> {
+    if !state.engine.strict_conditions {
+        return Ok(());
+    }
+
+    match guard {
+        Expr::True(_) if is_while && state.engine.allow_while_true => Ok(()),
+        Expr::True(pos) => Err(PERR::ConstantCondition(true).into_err(*pos)),
+        Expr::False(pos) => Err(PERR::ConstantCondition(false).into_err(*pos)),
+        _ => Ok(()),
+    }
+}
+
 /// Parse an if statement.
 fn parse_if(
     input: &mut TokenStream,
@@ -2891,6 +3997,7 @@ fn parse_if(
     ensure_not_statement_expr(input, "a boolean")?;
     let guard = parse_expr(input, state, lib, settings.level_up())?;
     ensure_not_assignment(input)?;
+    ensure_not_constant_condition(state, &guard, false)?;
     let if_body = parse_block(input, state, lib, settings.level_up())?;
 
     // if guard { if_body } else ...
@@ -2931,6 +4038,7 @@ fn parse_while(
     ensure_not_statement_expr(input, "a boolean")?;
     let guard = parse_expr(input, state, lib, settings.level_up())?;
     ensure_not_assignment(input)?;
+    ensure_not_constant_condition(state, &guard, true)?;
 
     settings.is_breakable = true;
     let body = Box::new(parse_block(input, state, lib, settings.level_up())?);
@@ -2973,20 +4081,61 @@ fn parse_for(
     #[cfg(not(feature = "unchecked"))]
     settings.ensure_level_within_max_limit(state.max_expr_depth)?;
 
-    // for name ...
-    let name = match input.next().unwrap() {
-        // Variable name
-        (Token::Identifier(s), _) => s,
-        // Reserved keyword
-        (Token::Reserved(s), pos) if is_valid_identifier(s.chars()) => {
-            return Err(PERR::Reserved(s).into_err(pos));
+    fn parse_var_name(input: &mut TokenStream) -> Result<(String, Position), ParseError> {
+        match input.next().unwrap() {
+            // Variable name
+            (Token::Identifier(s), pos) => Ok((s, pos)),
+            // Reserved keyword
+            (Token::Reserved(s), pos) if is_valid_identifier(s.chars()) => {
+                Err(PERR::Reserved(s).into_err(pos))
+            }
+            // Bad identifier
+            (Token::LexError(err), pos) => Err(err.into_err(pos)),
+            // Not a variable name
+            (_, pos) => Err(PERR::VariableExpected.into_err(pos)),
         }
-        // Bad identifier
-        (Token::LexError(err), pos) => return Err(err.into_err(pos)),
-        // Not a variable name
-        (_, pos) => return Err(PERR::VariableExpected.into_err(pos)),
+    }
+
+    // for (name, value) ...
+    #[cfg(not(feature = "no_object"))]
+    let ((name, name_pos), value_name) = if match_token(input, Token::LeftParen).0 {
+        let name = parse_var_name(input)?;
+
+        match input.next().unwrap() {
+            (Token::Comma, _) => (),
+            (Token::LexError(err), pos) => return Err(err.into_err(pos)),
+            (_, pos) => {
+                return Err(PERR::MissingToken(
+                    Token::Comma.into(),
+                    "to separate the key and value variables".into(),
+                )
+                .into_err(pos))
+            }
+        }
+
+        let value_name = parse_var_name(input)?;
+
+        match input.next().unwrap() {
+            (Token::RightParen, _) => (),
+            (Token::LexError(err), pos) => return Err(err.into_err(pos)),
+            (_, pos) => {
+                return Err(PERR::MissingToken(
+                    Token::RightParen.into(),
+                    "to close the list of iteration variables".into(),
+                )
+                .into_err(pos))
+            }
+        }
+
+        (name, Some(value_name))
+    } else {
+        (parse_var_name(input)?, None)
     };
 
+    // for name ...
+    #[cfg(feature = "no_object")]
+    let ((name, name_pos), value_name) = (parse_var_name(input)?, None::<(String, Position)>);
+
     // for name in ...
     match input.next().unwrap() {
         (Token::In, _) => (),
@@ -3004,14 +4153,25 @@ fn parse_for(
     let expr = parse_expr(input, state, lib, settings.level_up())?;
 
     let prev_stack_len = state.stack.len();
-    state.stack.push((name.clone(), ScopeEntryType::Normal));
+    state.push_var(name.clone(), ScopeEntryType::Normal, name_pos)?;
+
+    let value_name = if let Some((value_name, value_pos)) = value_name {
+        state.push_var(value_name.clone(), ScopeEntryType::Normal, value_pos)?;
+        Some(value_name)
+    } else {
+        None
+    };
 
     settings.is_breakable = true;
     let body = parse_block(input, state, lib, settings.level_up())?;
 
     state.stack.truncate(prev_stack_len);
 
-    Ok(Stmt::For(expr, Box::new((name, body)), token_pos))
+    Ok(Stmt::For(
+        expr,
+        Box::new((name, value_name, body)),
+        token_pos,
+    ))
 }
 
 /// Parse a variable definition statement.
@@ -3026,39 +4186,109 @@ fn parse_let(
     let token_pos = input.next().unwrap().1;
     settings.pos = token_pos;
 
-    #[cfg(not(feature = "unchecked"))]
-    settings.ensure_level_within_max_limit(state.max_expr_depth)?;
+    #[cfg(not(feature = "unchecked"))]
+    settings.ensure_level_within_max_limit(state.max_expr_depth)?;
+
+    // const { ... }
+    if var_type == ScopeEntryType::Constant && match_token(input, Token::LeftBrace).0 {
+        return parse_const_group(input, state, lib, settings.level_up());
+    }
+
+    // let name ...
+    let (name, pos) = match input.next().unwrap() {
+        (Token::Identifier(s), pos) => (s, pos),
+        (Token::Reserved(s), pos) if is_valid_identifier(s.chars()) => {
+            return Err(PERR::Reserved(s).into_err(pos));
+        }
+        (Token::LexError(err), pos) => return Err(err.into_err(pos)),
+        (_, pos) => return Err(PERR::VariableExpected.into_err(pos)),
+    };
+
+    // let name = ...
+    let init_value = if match_token(input, Token::Equals).0 {
+        // let name = expr
+        Some(parse_expr(input, state, lib, settings.level_up())?)
+    } else {
+        None
+    };
+
+    match var_type {
+        // let name = expr
+        ScopeEntryType::Normal => {
+            state.push_var(name.clone(), ScopeEntryType::Normal, pos)?;
+            Ok(Stmt::Let(Box::new((name, pos)), init_value, token_pos))
+        }
+        // const name = { expr:constant }
+        ScopeEntryType::Constant => {
+            state.push_var(name.clone(), ScopeEntryType::Constant, pos)?;
+            Ok(Stmt::Const(Box::new((name, pos)), init_value, token_pos))
+        }
+    }
+}
+
+/// Parse a group of constants, i.e. `const { X = 1; Y = 2; }`, after the opening `{`.
+/// The constants are returned in a `Stmt::Block`, which the caller flattens into the enclosing scope.
+fn parse_const_group(
+    input: &mut TokenStream,
+    state: &mut ParseState,
+    lib: &mut FunctionsLib,
+    mut settings: ParseSettings,
+) -> Result<Stmt, ParseError> {
+    #[cfg(not(feature = "unchecked"))]
+    settings.ensure_level_within_max_limit(state.max_expr_depth)?;
+
+    let mut statements = Vec::new();
+
+    loop {
+        // const { ... name ...
+        let (name, pos) = match input.next().unwrap() {
+            (Token::RightBrace, _) => break,
+            (Token::Identifier(s), pos) => (s, pos),
+            (Token::Reserved(s), pos) if is_valid_identifier(s.chars()) => {
+                return Err(PERR::Reserved(s).into_err(pos));
+            }
+            (Token::LexError(err), pos) => return Err(err.into_err(pos)),
+            (Token::EOF, pos) => {
+                return Err(PERR::MissingToken(
+                    Token::RightBrace.into(),
+                    "to end this group of constants".into(),
+                )
+                .into_err(pos))
+            }
+            (_, pos) => return Err(PERR::VariableExpected.into_err(pos)),
+        };
+
+        settings.pos = pos;
 
-    // let name ...
-    let (name, pos) = match input.next().unwrap() {
-        (Token::Identifier(s), pos) => (s, pos),
-        (Token::Reserved(s), pos) if is_valid_identifier(s.chars()) => {
-            return Err(PERR::Reserved(s).into_err(pos));
-        }
-        (Token::LexError(err), pos) => return Err(err.into_err(pos)),
-        (_, pos) => return Err(PERR::VariableExpected.into_err(pos)),
-    };
+        // const { ... name = expr ...
+        let init_value = if match_token(input, Token::Equals).0 {
+            Some(parse_expr(input, state, lib, settings.level_up())?)
+        } else {
+            None
+        };
 
-    // let name = ...
-    let init_value = if match_token(input, Token::Equals).0 {
-        // let name = expr
-        Some(parse_expr(input, state, lib, settings.level_up())?)
-    } else {
-        None
-    };
+        state.push_var(name.clone(), ScopeEntryType::Constant, pos)?;
+        statements.push(Stmt::Const(Box::new((name, pos)), init_value, pos));
 
-    match var_type {
-        // let name = expr
-        ScopeEntryType::Normal => {
-            state.stack.push((name.clone(), ScopeEntryType::Normal));
-            Ok(Stmt::Let(Box::new((name, pos)), init_value, token_pos))
-        }
-        // const name = { expr:constant }
-        ScopeEntryType::Constant => {
-            state.stack.push((name.clone(), ScopeEntryType::Constant));
-            Ok(Stmt::Const(Box::new((name, pos)), init_value, token_pos))
+        match input.peek().unwrap() {
+            // const { ... name = expr; ...
+            (Token::SemiColon, _) => {
+                eat_token(input, Token::SemiColon);
+            }
+            // const { ... name = expr }
+            (Token::RightBrace, _) => (),
+            (Token::LexError(err), pos) => return Err(err.into_err(*pos)),
+            (_, pos) => {
+                return Err(PERR::MissingToken(
+                    Token::SemiColon.into(),
+                    "to terminate this constant declaration".into(),
+                )
+                .into_err(*pos))
+            }
         }
     }
+
+    Ok(Stmt::Block(statements, settings.pos))
 }
 
 /// Parse an import statement.
@@ -3198,16 +4428,43 @@ fn parse_block(
     #[cfg(not(feature = "unchecked"))]
     settings.ensure_level_within_max_limit(state.max_expr_depth)?;
 
-    let mut statements = Vec::new();
     let prev_stack_len = state.stack.len();
 
     #[cfg(not(feature = "no_module"))]
     let prev_mods_len = state.modules.len();
 
-    while !match_token(input, Token::RightBrace).0 {
+    #[cfg(not(feature = "no_function"))]
+    let prev_fns_len = state.local_fns.len();
+
+    let statements = parse_statements(input, state, lib, settings, Token::RightBrace)?;
+
+    state.stack.truncate(prev_stack_len);
+
+    #[cfg(not(feature = "no_module"))]
+    state.modules.truncate(prev_mods_len);
+
+    #[cfg(not(feature = "no_function"))]
+    state.local_fns.truncate(prev_fns_len);
+
+    Ok(Stmt::Block(statements, settings.pos))
+}
+
+/// Parse a list of statements up to (and including) the `end` token.
+fn parse_statements(
+    input: &mut TokenStream,
+    state: &mut ParseState,
+    lib: &mut FunctionsLib,
+    mut settings: ParseSettings,
+    end: Token,
+) -> Result<Vec<Stmt>, ParseError> {
+    let mut statements = Vec::new();
+
+    while !match_token(input, end.clone()).0 {
         // Parse statements inside the block
         settings.is_global = false;
 
+        let is_const = input.peek().unwrap().0 == Token::Const;
+
         let stmt = match parse_stmt(input, state, lib, settings.level_up())? {
             Some(s) => s,
             None => continue,
@@ -3216,18 +4473,22 @@ fn parse_block(
         // See if it needs a terminating semicolon
         let need_semicolon = !stmt.is_self_terminated();
 
-        statements.push(stmt);
+        push_stmt(&mut statements, stmt, is_const);
 
         match input.peek().unwrap() {
             // { ... stmt }
-            (Token::RightBrace, _) => {
-                eat_token(input, Token::RightBrace);
+            (token, _) if *token == end => {
+                eat_token(input, end);
                 break;
             }
             // { ... stmt;
             (Token::SemiColon, _) if need_semicolon => {
                 eat_token(input, Token::SemiColon);
             }
+            // { ... { stmt } ; - under strict semicolons, treat it as the terminator
+            (Token::SemiColon, _) if state.engine.strict_semicolons => {
+                eat_token(input, Token::SemiColon);
+            }
             // { ... { stmt } ;
             (Token::SemiColon, _) if !need_semicolon => (),
             // { ... { stmt } ???
@@ -3246,12 +4507,50 @@ fn parse_block(
         }
     }
 
-    state.stack.truncate(prev_stack_len);
+    Ok(statements)
+}
 
-    #[cfg(not(feature = "no_module"))]
-    state.modules.truncate(prev_mods_len);
+/// Add a statement to a list of statements.
+/// A group of constants (i.e. a `Stmt::Block` parsed from `const { ... }`) is flattened into the list,
+/// so that the constants are declared in the enclosing scope.
+#[inline]
+fn push_stmt(statements: &mut Vec<Stmt>, stmt: Stmt, is_const: bool) {
+    match stmt {
+        Stmt::Block(constants, _) if is_const => statements.extend(constants),
+        stmt => statements.push(stmt),
+    }
+}
 
-    Ok(Stmt::Block(statements, settings.pos))
+/// Parse a global variable declaration statement.
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_closure"))]
+fn parse_global(
+    input: &mut TokenStream,
+    state: &mut ParseState,
+    mut settings: ParseSettings,
+) -> Result<Stmt, ParseError> {
+    // global ...
+    let token_pos = eat_token(input, Token::Global);
+    settings.pos = token_pos;
+
+    #[cfg(not(feature = "unchecked"))]
+    settings.ensure_level_within_max_limit(state.max_expr_depth)?;
+
+    // global name
+    let (name, pos) = match input.next().unwrap() {
+        (Token::Identifier(s), pos) => (s, pos),
+        (Token::Reserved(s), pos) if is_valid_identifier(s.chars()) => {
+            return Err(PERR::Reserved(s).into_err(pos));
+        }
+        (Token::LexError(err), pos) => return Err(err.into_err(pos)),
+        (_, pos) => return Err(PERR::VariableExpected.into_err(pos)),
+    };
+
+    // Whether the variable is a constant is only known at runtime
+    state.push_var(name.clone(), ScopeEntryType::Normal, pos)?;
+    state.globals.insert(name.clone());
+
+    Ok(Stmt::Global(Box::new((name, pos)), token_pos))
 }
 
 /// Parse an expression as a statement.
@@ -3291,13 +4590,18 @@ fn parse_stmt(
 
     match token {
         // Semicolon - empty statement
+        Token::SemiColon if state.engine.strict_semicolons => {
+            Err(PERR::EmptyStatement.into_err(settings.pos))
+        }
         Token::SemiColon => Ok(Some(Stmt::Noop(settings.pos))),
 
         Token::LeftBrace => parse_block(input, state, lib, settings.level_up()).map(Some),
 
         // fn ...
         #[cfg(not(feature = "no_function"))]
-        Token::Fn if !settings.is_global => Err(PERR::WrongFnDefinition.into_err(settings.pos)),
+        Token::Fn if !settings.is_global && !state.engine.allow_nested_functions => {
+            Err(PERR::WrongFnDefinition.into_err(settings.pos))
+        }
 
         #[cfg(not(feature = "no_function"))]
         Token::Fn | Token::Private => {
@@ -3308,15 +4612,20 @@ fn parse_stmt(
                 FnAccess::Public
             };
 
+            // Functions defined inside blocks are local to the block
+            let is_local = !settings.is_global;
+
             match input.next().unwrap() {
                 (Token::Fn, pos) => {
                     let mut new_state = ParseState::new(
                         state.engine,
+                        state.source_hash,
                         #[cfg(not(feature = "unchecked"))]
                         state.max_function_expr_depth,
                         #[cfg(not(feature = "unchecked"))]
                         state.max_function_expr_depth,
                     );
+                    new_state.local_fns = state.local_fns.clone();
 
                     let settings = ParseSettings {
                         allow_if_expr: true,
@@ -3329,7 +4638,15 @@ fn parse_stmt(
                         pos: pos,
                     };
 
-                    let func = parse_fn(input, &mut new_state, lib, access, settings)?;
+                    let func = parse_fn(input, &mut new_state, lib, access, is_local, settings)?;
+
+                    if is_local {
+                        state.local_fns.push((
+                            unmangle_local_fn(&func.name).into(),
+                            func.params.len(),
+                            func.name.clone(),
+                        ));
+                    }
 
                     // Qualifiers (none) + function name + number of arguments.
                     let hash = calc_fn_hash(empty(), &func.name, func.params.len(), empty());
@@ -3408,6 +4725,16 @@ fn parse_stmt(
         Token::Let => parse_let(input, state, lib, Normal, settings.level_up()).map(Some),
         Token::Const => parse_let(input, state, lib, Constant, settings.level_up()).map(Some),
 
+        #[cfg(not(feature = "no_function"))]
+        #[cfg(not(feature = "no_closure"))]
+        Token::Global if !settings.is_function_scope => {
+            Err(PERR::WrongGlobal.into_err(settings.pos))
+        }
+
+        #[cfg(not(feature = "no_function"))]
+        #[cfg(not(feature = "no_closure"))]
+        Token::Global => parse_global(input, state, settings.level_up()).map(Some),
+
         #[cfg(not(feature = "no_module"))]
         Token::Import => parse_import(input, state, lib, settings.level_up()).map(Some),
 
@@ -3487,6 +4814,7 @@ fn parse_fn(
     state: &mut ParseState,
     lib: &mut FunctionsLib,
     access: FnAccess,
+    is_local: bool,
     mut settings: ParseSettings,
 ) -> Result<ScriptFnDef, ParseError> {
     #[cfg(not(feature = "unchecked"))]
@@ -3515,7 +4843,7 @@ fn parse_fn(
             match input.next().unwrap() {
                 (Token::RightParen, _) => break,
                 (Token::Identifier(s), pos) => {
-                    state.stack.push((s.clone(), ScopeEntryType::Normal));
+                    state.push_var(s.clone(), ScopeEntryType::Normal, pos)?;
                     params.push((s, pos))
                 }
                 (Token::LexError(err), pos) => return Err(err.into_err(pos)),
@@ -3554,6 +4882,27 @@ fn parse_fn(
             PERR::FnDuplicatedParam(name.to_string(), p.to_string()).into_err(pos)
         })?;
 
+    // Decorate the name of a local function to keep it apart from other functions with the
+    // same name, and make it visible within its own body to allow recursion.
+    // The decoration includes a hash of the script text because `AST`'s can be merged,
+    // and is otherwise stable so that the same script always produces the same `AST`.
+    let (fn_name, access): (ImmutableString, _) = if is_local {
+        let fn_name = format!(
+            "{}{}${:x}_{}_{}",
+            FN_LOCAL,
+            name,
+            state.source_hash,
+            settings.pos.line().unwrap_or(0),
+            settings.pos.position().unwrap_or(0)
+        );
+        state
+            .local_fns
+            .push((name.clone(), params.len(), fn_name.clone().into()));
+        (fn_name.into(), FnAccess::Private)
+    } else {
+        (name.clone().into(), access)
+    };
+
     // Parse function body
     let body = match input.peek().unwrap() {
         (Token::LeftBrace, _) => {
@@ -3575,11 +4924,13 @@ fn parse_fn(
         .collect();
 
     Ok(ScriptFnDef {
-        name: name.into(),
+        name: fn_name,
         access,
         params,
         #[cfg(not(feature = "no_closure"))]
         externals,
+        #[cfg(not(feature = "no_closure"))]
+        globals: state.globals.clone(),
         body,
         pos: settings.pos,
         lib: None,
@@ -3667,7 +5018,7 @@ fn parse_anon_fn(
                 match input.next().unwrap() {
                     (Token::Pipe, _) => break,
                     (Token::Identifier(s), pos) => {
-                        state.stack.push((s.clone(), ScopeEntryType::Normal));
+                        state.push_var(s.clone(), ScopeEntryType::Normal, pos)?;
                         params.push((s, pos))
                     }
                     (Token::LexError(err), pos) => return Err(err.into_err(pos)),
@@ -3762,6 +5113,8 @@ fn parse_anon_fn(
         params,
         #[cfg(not(feature = "no_closure"))]
         externals: Default::default(),
+        #[cfg(not(feature = "no_closure"))]
+        globals: state.globals.clone(),
         body,
         pos: settings.pos,
         lib: None,
@@ -3782,22 +5135,26 @@ impl Engine {
     pub(crate) fn parse_global_expr(
         &self,
         input: &mut TokenStream,
+        #[cfg(not(feature = "no_function"))] source_hash: u64,
         scope: &Scope,
         optimization_level: OptimizationLevel,
     ) -> Result<AST, ParseError> {
         let mut functions = Default::default();
         let mut state = ParseState::new(
             self,
+            #[cfg(not(feature = "no_function"))]
+            source_hash,
             #[cfg(not(feature = "unchecked"))]
             self.max_expr_depth(),
             #[cfg(not(feature = "unchecked"))]
             #[cfg(not(feature = "no_function"))]
             self.max_function_expr_depth(),
         );
+        state.push_scope_constants(scope);
 
         let settings = ParseSettings {
             allow_if_expr: false,
-            allow_stmt_expr: false,
+            allow_stmt_expr: self.allow_statement_expression,
             allow_anonymous_fn: false,
             is_global: true,
             is_function_scope: false,
@@ -3821,27 +5178,100 @@ impl Engine {
 
         let expr = vec![Stmt::Expr(expr)];
 
+        if self.strict_function_calls {
+            self.check_fn_call_arities(&expr, &[])?;
+        }
+
         Ok(
             // Optimize AST
             optimize_into_ast(self, scope, expr, Default::default(), optimization_level),
         )
     }
 
+    /// Parse a single global level statement, optionally terminated by a semicolon.
+    pub(crate) fn parse_global_stmt(
+        &self,
+        input: &mut TokenStream,
+        #[cfg(not(feature = "no_function"))] source_hash: u64,
+        scope: &Scope,
+        optimization_level: OptimizationLevel,
+    ) -> Result<AST, ParseError> {
+        let mut functions = Default::default();
+        let mut state = ParseState::new(
+            self,
+            #[cfg(not(feature = "no_function"))]
+            source_hash,
+            #[cfg(not(feature = "unchecked"))]
+            self.max_expr_depth(),
+            #[cfg(not(feature = "unchecked"))]
+            #[cfg(not(feature = "no_function"))]
+            self.max_function_expr_depth(),
+        );
+        state.push_scope_constants(scope);
+
+        let settings = ParseSettings {
+            allow_if_expr: true,
+            allow_stmt_expr: true,
+            allow_anonymous_fn: true,
+            is_global: true,
+            is_function_scope: false,
+            is_breakable: false,
+            level: 0,
+            pos: Position::none(),
+        };
+
+        let is_const = input.peek().unwrap().0 == Token::Const;
+        let mut statements = Vec::new();
+
+        if let Some(stmt) = parse_stmt(input, &mut state, &mut functions, settings)? {
+            push_stmt(&mut statements, stmt, is_const);
+        }
+
+        match_token(input, Token::SemiColon);
+
+        match input.peek().unwrap() {
+            (Token::EOF, _) => (),
+            (Token::LexError(err), pos) => return Err(err.into_err(*pos)),
+            // Return error if there is more than one statement
+            (token, pos) => {
+                return Err(
+                    PERR::BadInput(format!("Unexpected '{}'", token.syntax())).into_err(*pos)
+                )
+            }
+        }
+
+        let lib: Vec<_> = functions.into_iter().map(|(_, v)| v).collect();
+
+        if self.strict_function_calls {
+            self.check_fn_call_arities(&statements, &lib)?;
+        }
+
+        Ok(
+            // Optimize AST
+            optimize_into_ast(self, scope, statements, lib, optimization_level),
+        )
+    }
+
     /// Parse the global level statements.
     fn parse_global_level(
         &self,
         input: &mut TokenStream,
+        #[cfg(not(feature = "no_function"))] source_hash: u64,
+        scope: &Scope,
     ) -> Result<(Vec<Stmt>, Vec<ScriptFnDef>), ParseError> {
         let mut statements: Vec<Stmt> = Default::default();
         let mut functions = Default::default();
         let mut state = ParseState::new(
             self,
+            #[cfg(not(feature = "no_function"))]
+            source_hash,
             #[cfg(not(feature = "unchecked"))]
             self.max_expr_depth(),
             #[cfg(not(feature = "unchecked"))]
             #[cfg(not(feature = "no_function"))]
             self.max_function_expr_depth(),
         );
+        state.push_scope_constants(scope);
 
         while !input.peek().unwrap().0.is_eof() {
             let settings = ParseSettings {
@@ -3855,14 +5285,22 @@ impl Engine {
                 pos: Position::none(),
             };
 
+            let is_const = input.peek().unwrap().0 == Token::Const;
+
             let stmt = match parse_stmt(input, &mut state, &mut functions, settings)? {
                 Some(s) => s,
-                None => continue,
+                None => {
+                    // fn ... { ... } ; - under strict semicolons, treat it as the terminator
+                    if self.strict_semicolons {
+                        match_token(input, Token::SemiColon);
+                    }
+                    continue;
+                }
             };
 
             let need_semicolon = !stmt.is_self_terminated();
 
-            statements.push(stmt);
+            push_stmt(&mut statements, stmt, is_const);
 
             match input.peek().unwrap() {
                 // EOF
@@ -3871,6 +5309,10 @@ impl Engine {
                 (Token::SemiColon, _) if need_semicolon => {
                     eat_token(input, Token::SemiColon);
                 }
+                // { stmt } ; - under strict semicolons, treat it as the terminator
+                (Token::SemiColon, _) if self.strict_semicolons => {
+                    eat_token(input, Token::SemiColon);
+                }
                 // stmt ;
                 (Token::SemiColon, _) if !need_semicolon => (),
                 // { stmt } ???
@@ -3897,16 +5339,250 @@ impl Engine {
     pub(crate) fn parse(
         &self,
         input: &mut TokenStream,
+        #[cfg(not(feature = "no_function"))] source_hash: u64,
         scope: &Scope,
         optimization_level: OptimizationLevel,
     ) -> Result<AST, ParseError> {
-        let (statements, lib) = self.parse_global_level(input)?;
+        let (statements, lib) = self.parse_global_level(
+            input,
+            #[cfg(not(feature = "no_function"))]
+            source_hash,
+            scope,
+        )?;
+
+        if self.strict_function_calls {
+            self.check_fn_call_arities(&statements, &lib)?;
+        }
 
         Ok(
             // Optimize AST
             optimize_into_ast(self, scope, statements, lib, optimization_level),
         )
     }
+
+    /// Parse the body of a script-defined function with the specified name and parameters,
+    /// returning an `AST` containing the function (plus any anonymous functions defined within it).
+    #[cfg(not(feature = "no_function"))]
+    pub(crate) fn parse_script_fn(
+        &self,
+        name: &str,
+        access: FnAccess,
+        params: &[&str],
+        body: &str,
+        optimization_level: OptimizationLevel,
+    ) -> Result<AST, ParseError> {
+        // The function name and parameters must be single tokens, just as in a script
+        let lex_single = |text: &str| {
+            let text = [text];
+            let mut tokens = self.lex(&text, None);
+            match (tokens.next(), tokens.next()) {
+                (Some((token, _)), Some((Token::EOF, _))) if token.syntax() == text[0] => token,
+                _ => Token::EOF,
+            }
+        };
+
+        let name = lex_single(name)
+            .into_function_name_for_override()
+            .map_err(|t| match t {
+                Token::Reserved(s) => PERR::Reserved(s).into_err(Position::none()),
+                _ => PERR::FnMissingName.into_err(Position::none()),
+            })?;
+
+        let mut functions = Default::default();
+        let mut state = ParseState::new(
+            self,
+            calc_source_hash(&[body]),
+            #[cfg(not(feature = "unchecked"))]
+            self.max_function_expr_depth(),
+            #[cfg(not(feature = "unchecked"))]
+            self.max_function_expr_depth(),
+        );
+
+        let mut fn_params = StaticVec::<String>::new();
+
+        for &param in params {
+            let param = match lex_single(param) {
+                Token::Identifier(s) => s,
+                Token::Reserved(s) => return Err(PERR::Reserved(s).into_err(Position::none())),
+                _ => return Err(PERR::VariableExpected.into_err(Position::none())),
+            };
+
+            if fn_params.contains(&param) {
+                return Err(PERR::FnDuplicatedParam(name, param).into_err(Position::none()));
+            }
+
+            state.push_var(param.clone(), ScopeEntryType::Normal, Position::none())?;
+            fn_params.push(param);
+        }
+
+        let settings = ParseSettings {
+            allow_if_expr: true,
+            allow_stmt_expr: true,
+            allow_anonymous_fn: true,
+            is_global: false,
+            is_function_scope: true,
+            is_breakable: false,
+            level: 1,
+            pos: Position::none(),
+        };
+
+        let body = [body];
+        let input = &mut self.lex(&body, None).peekable();
+        let statements = parse_statements(input, &mut state, &mut functions, settings, Token::EOF)?;
+
+        #[cfg(not(feature = "no_closure"))]
+        let externals = state
+            .externals
+            .keys()
+            .filter(|name| !fn_params.contains(name))
+            .cloned()
+            .collect();
+
+        // Qualifiers (none) + function name + number of arguments.
+        let hash = calc_fn_hash(empty(), &name, fn_params.len(), empty());
+
+        functions.insert(
+            hash,
+            ScriptFnDef {
+                name: name.into(),
+                access,
+                params: fn_params,
+                #[cfg(not(feature = "no_closure"))]
+                externals,
+                #[cfg(not(feature = "no_closure"))]
+                globals: state.globals.clone(),
+                body: Stmt::Block(statements, Position::none()),
+                pos: Position::none(),
+                lib: None,
+            },
+        );
+
+        let lib = functions.into_iter().map(|(_, v)| v).collect();
+
+        Ok(
+            // Optimize AST
+            optimize_into_ast(self, &Default::default(), vec![], lib, optimization_level),
+        )
+    }
+}
+
+impl Engine {
+    /// Check that every non-qualified call to a function in normal function-call style matches
+    /// the number of parameters of at least one script-defined or registered native function
+    /// of the same name.
+    ///
+    /// Calls to functions that are not known at all are not checked, as they may still be
+    /// resolved at run-time.  Method calls are not checked because they may be calls to
+    /// function pointers held in object map properties.
+    fn check_fn_call_arities(
+        &self,
+        statements: &[Stmt],
+        lib: &[ScriptFnDef],
+    ) -> Result<(), ParseError> {
+        let mut arities: HashMap<&str, StaticVec<(usize, usize)>> = Default::default();
+
+        lib.iter().for_each(|f| {
+            let num_params = f.params.len();
+            arities
+                .entry(&f.name)
+                .or_default()
+                .push((num_params, num_params));
+        });
+
+        once(&self.global_module)
+            .chain(self.packages.iter())
+            .for_each(|m| {
+                m.iter_fn().for_each(|(name, _, num_params, _, _)| {
+                    arities
+                        .entry(name)
+                        .or_default()
+                        .push((*num_params, *num_params));
+                });
+                m.iter_variadic_fn()
+                    .for_each(|(name, min_args, max_args, _)| {
+                        arities
+                            .entry(name)
+                            .or_default()
+                            .push((*min_args, *max_args));
+                    });
+            });
+
+        let check = |name: &str, num_args: usize, pos: Position| {
+            if is_keyword_function(name)
+                || self.scope_functions.contains_key(&calc_fn_hash(
+                    empty(),
+                    name,
+                    num_args,
+                    empty(),
+                ))
+            {
+                return Ok(());
+            }
+
+            match arities.get(name) {
+                Some(ranges)
+                    if !ranges.iter().any(|&(min_args, max_args)| {
+                        num_args >= min_args && num_args <= max_args
+                    }) =>
+                {
+                    Err(PERR::FnWrongArgsCount(name.to_string(), num_args).into_err(pos))
+                }
+                _ => Ok(()),
+            }
+        };
+
+        let mut result = Ok(());
+        let mut path = Vec::new();
+
+        statements
+            .iter()
+            .chain(lib.iter().map(|f| &f.body))
+            .all(|stmt| {
+                walk_stmt(stmt, &mut path, &mut |path| match path.last() {
+                    // Method calls are skipped, but their arguments are checked
+                    Some(ASTNode::Expr(Expr::FnCall(x))) if !is_method_call(path) => {
+                        let ((name, native, _, pos), modules, _, args, _) = x.as_ref();
+
+                        if !*native && modules.is_none() {
+                            result = check(name, args.len(), *pos);
+                        }
+                        result.is_ok()
+                    }
+                    _ => true,
+                })
+            });
+
+        result
+    }
+}
+
+/// Is the expression at the end of a path a method call (or property) in a dot chain?
+///
+/// Dot/index chains nest to the right (e.g. `a.b().c` is `a.(b().c)`), so an expression is a
+/// method call if it is on the right of a dot, or on the left of a dot/index that is itself
+/// part of the method chain.
+fn is_method_call(path: &[ASTNode]) -> bool {
+    let mut nodes = path.iter().rev();
+
+    let mut child = match nodes.next() {
+        Some(ASTNode::Expr(expr)) => *expr,
+        _ => return false,
+    };
+
+    for node in nodes {
+        let parent = match node {
+            ASTNode::Expr(expr) => *expr,
+            ASTNode::Stmt(_) => return false,
+        };
+
+        match parent {
+            Expr::Dot(x, _) if ptr::eq(&x.rhs, child) => return true,
+            Expr::Dot(x, _) | Expr::Index(x, _) if ptr::eq(&x.lhs, child) => child = parent,
+            _ => return false,
+        }
+    }
+
+    false
 }
 
 /// Map a `Dynamic` value to an expression.
@@ -3916,6 +5592,8 @@ pub fn map_dynamic_to_expr(value: Dynamic, pos: Position) -> Option<Expr> {
     match value.0 {
         #[cfg(not(feature = "no_float"))]
         Union::Float(value) => Some(Expr::FloatConstant(Box::new(FloatWrapper(value, pos)))),
+        #[cfg(feature = "decimal")]
+        Union::Decimal(value) => Some(Expr::DecimalConstant(Box::new((*value, pos)))),
 
         Union::Unit(_) => Some(Expr::Unit(pos)),
         Union::Int(value) => Some(Expr::IntegerConstant(Box::new((value, pos)))),
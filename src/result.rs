@@ -7,7 +7,7 @@ use crate::token::Position;
 use crate::utils::ImmutableString;
 
 #[cfg(not(feature = "no_function"))]
-use crate::engine::is_anonymous_fn;
+use crate::engine::{is_anonymous_fn, unmangle_local_fn};
 
 use crate::stdlib::{
     boxed::Box,
@@ -48,6 +48,10 @@ pub enum EvalAltResult {
     /// An error has occurred while loading a module.
     /// Wrapped value are the module name and the interior error.
     ErrorInModule(String, Box<EvalAltResult>, Position),
+    /// An error has occurred while evaluating an `AST` tagged with a source name via `AST::set_source`.
+    /// Wrapped values are the source name and the interior error.
+    /// The position is that of the interior error.
+    ErrorInSource(String, Box<EvalAltResult>, Position),
     /// Access to `this` that is not bound.
     ErrorUnboundThis(Position),
     /// Data is not of the required type.
@@ -107,20 +111,15 @@ impl EvalAltResult {
             Self::ErrorParsing(p, _) => p.desc(),
             Self::ErrorInFunctionCall(_, _, _) => "Error in called function",
             Self::ErrorInModule(_, _, _) => "Error in module",
+            Self::ErrorInSource(_, _, _) => "Error in script",
             Self::ErrorFunctionNotFound(_, _) => "Function not found",
             Self::ErrorUnboundThis(_) => "'this' is not bound",
             Self::ErrorMismatchDataType(_, _, _) => "Data type is incorrect",
             Self::ErrorIndexingType(_, _) => {
                 "Indexing can only be performed on an array, an object map, a string, or a type with an indexer function defined"
             }
-            Self::ErrorArrayBounds(_, index, _) if *index < 0 => {
-                "Array access expects non-negative index"
-            }
             Self::ErrorArrayBounds(0, _, _) => "Empty array has nothing to access",
             Self::ErrorArrayBounds(_, _, _) => "Array index out of bounds",
-            Self::ErrorStringBounds(_, index, _) if *index < 0 => {
-                "Indexing a string expects a non-negative index"
-            }
             Self::ErrorStringBounds(0, _, _) => "Empty string has nothing to index",
             Self::ErrorStringBounds(_, _, _) => "String index out of bounds",
             Self::ErrorFor(_) => "For loop expects an array, object map, or range",
@@ -162,6 +161,14 @@ impl fmt::Display for EvalAltResult {
             Self::ErrorInFunctionCall(s, err, _) if is_anonymous_fn(s) => {
                 write!(f, "Error in call to closure: {}", err)?
             }
+            #[cfg(not(feature = "no_function"))]
+            Self::ErrorInFunctionCall(s, err, _) => write!(
+                f,
+                "Error in call to function '{}': {}",
+                unmangle_local_fn(s),
+                err
+            )?,
+            #[cfg(feature = "no_function")]
             Self::ErrorInFunctionCall(s, err, _) => {
                 write!(f, "Error in call to function '{}': {}", s, err)?
             }
@@ -170,6 +177,9 @@ impl fmt::Display for EvalAltResult {
             }
             Self::ErrorInModule(s, err, _) => write!(f, "Error in module '{}': {}", s, err)?,
 
+            // The interior error already includes the position
+            Self::ErrorInSource(s, err, _) => return write!(f, "Error in '{}': {}", s, err),
+
             Self::ErrorFunctionNotFound(s, _)
             | Self::ErrorVariableNotFound(s, _)
             | Self::ErrorDataRace(s, _)
@@ -196,7 +206,7 @@ impl fmt::Display for EvalAltResult {
 
             Self::ErrorAssignmentToConstant(s, _) => write!(f, "{}: '{}'", desc, s)?,
             Self::ErrorMismatchOutputType(r, s, _) => {
-                write!(f, "Output type is incorrect: {} (expecting {})", r, s)?
+                write!(f, "Output type is incorrect: {} (expecting {})", s, r)?
             }
             Self::ErrorMismatchDataType(r, s, _) if r.is_empty() => {
                 write!(f, "Data type is incorrect, expecting {}", s)?
@@ -209,9 +219,6 @@ impl fmt::Display for EvalAltResult {
             Self::LoopBreak(_, _) => f.write_str(desc)?,
             Self::Return(_, _) => f.write_str(desc)?,
 
-            Self::ErrorArrayBounds(_, index, _) if *index < 0 => {
-                write!(f, "{}: {} < 0", desc, index)?
-            }
             Self::ErrorArrayBounds(0, _, _) => f.write_str(desc)?,
             Self::ErrorArrayBounds(1, index, _) => write!(
                 f,
@@ -223,9 +230,6 @@ impl fmt::Display for EvalAltResult {
                 "Array index {} is out of bounds: only {} elements in the array",
                 index, max
             )?,
-            Self::ErrorStringBounds(_, index, _) if *index < 0 => {
-                write!(f, "{}: {} < 0", desc, index)?
-            }
             Self::ErrorStringBounds(0, _, _) => f.write_str(desc)?,
             Self::ErrorStringBounds(1, index, _) => write!(
                 f,
@@ -274,6 +278,7 @@ impl EvalAltResult {
         match self {
             Self::ErrorSystem(_, _) => false,
             Self::ErrorParsing(_, _) => unreachable!(),
+            Self::ErrorInSource(_, err, _) => err.is_catchable(),
 
             Self::ErrorFunctionNotFound(_, _)
             | Self::ErrorInFunctionCall(_, _, _)
@@ -309,6 +314,7 @@ impl EvalAltResult {
         match self {
             Self::ErrorSystem(_, _) => true,
             Self::ErrorParsing(_, _) => unreachable!(),
+            Self::ErrorInSource(_, err, _) => err.is_system_exception(),
 
             Self::ErrorTooManyOperations(_)
             | Self::ErrorTooManyModules(_)
@@ -332,6 +338,7 @@ impl EvalAltResult {
             | Self::ErrorFunctionNotFound(_, pos)
             | Self::ErrorInFunctionCall(_, _, pos)
             | Self::ErrorInModule(_, _, pos)
+            | Self::ErrorInSource(_, _, pos)
             | Self::ErrorUnboundThis(pos)
             | Self::ErrorMismatchDataType(_, _, pos)
             | Self::ErrorArrayBounds(_, _, pos)
@@ -366,6 +373,7 @@ impl EvalAltResult {
             | Self::ErrorFunctionNotFound(_, pos)
             | Self::ErrorInFunctionCall(_, _, pos)
             | Self::ErrorInModule(_, _, pos)
+            | Self::ErrorInSource(_, _, pos)
             | Self::ErrorUnboundThis(pos)
             | Self::ErrorMismatchDataType(_, _, pos)
             | Self::ErrorArrayBounds(_, _, pos)
@@ -391,6 +399,64 @@ impl EvalAltResult {
         }
     }
 
+    /// Get the script call stack of this error, innermost frame first.
+    ///
+    /// Each frame holds the name of the function in which the position lies (empty for the
+    /// main script) and the position - either where the error occurred or where the next
+    /// function was called.
+    ///
+    /// Nested function calls are only kept separate when `Engine::set_capture_call_stack`
+    /// is enabled; otherwise they are collapsed into a single frame (e.g. `a > b`).
+    pub fn call_stack(&self) -> Vec<(&str, Position)> {
+        let mut frames = Vec::new();
+        let mut fn_name = "";
+        let mut err = self;
+
+        loop {
+            match err {
+                Self::ErrorInSource(_, inner, _) => err = inner,
+                Self::ErrorInFunctionCall(name, inner, pos) => {
+                    frames.push((fn_name, *pos));
+                    fn_name = name;
+                    err = inner;
+                }
+                _ => {
+                    frames.push((fn_name, err.position()));
+                    break;
+                }
+            }
+        }
+
+        frames.reverse();
+        frames
+    }
+
+    /// Get the value thrown via a `throw` statement, if this error is (or wraps) one.
+    ///
+    /// Errors raised inside called functions are wrapped in `ErrorInFunctionCall`,
+    /// so the original thrown value is found by unwrapping all such layers.
+    pub(crate) fn thrown_value(&self) -> Option<&Dynamic> {
+        match self {
+            Self::ErrorRuntime(value, _) => Some(value),
+            Self::ErrorInFunctionCall(_, inner, _) | Self::ErrorInSource(_, inner, _) => {
+                inner.thrown_value()
+            }
+            _ => None,
+        }
+    }
+
+    /// Consume the current `EvalAltResult` and wrap it in `ErrorInSource` if there is a source name.
+    #[inline(always)]
+    pub(crate) fn in_source(self: Box<Self>, source: Option<&str>) -> Box<Self> {
+        match source {
+            Some(source) => {
+                let pos = self.position();
+                Box::new(Self::ErrorInSource(source.to_string(), self, pos))
+            }
+            None => self,
+        }
+    }
+
     /// Consume the current `EvalAltResult` and return a new one with the specified `Position`
     /// if the current position is `Position::None`.
     #[inline(always)]
@@ -1,6 +1,8 @@
 //! Module that defines the `Scope` type representing a function call-stack scope.
 
 use crate::any::{Dynamic, Variant};
+#[cfg(not(feature = "no_optimize"))]
+use crate::optimize::SHARED_LITERAL_PREFIX;
 use crate::parser::{map_dynamic_to_expr, Expr};
 use crate::token::Position;
 
@@ -325,6 +327,26 @@ impl<'a> Scope<'a> {
             .any(|Entry { name: key, .. }| name == key)
     }
 
+    /// Is the entry a constant?  Returns `None` if the scope does not contain the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Scope;
+    ///
+    /// let mut my_scope = Scope::new();
+    ///
+    /// my_scope.push("x", 42_i64);
+    /// my_scope.push_constant("y", 42_i64);
+    /// assert_eq!(my_scope.is_constant("x"), Some(false));
+    /// assert_eq!(my_scope.is_constant("y"), Some(true));
+    /// assert_eq!(my_scope.is_constant("z"), None);
+    /// ```
+    #[inline(always)]
+    pub fn is_constant(&self, name: &str) -> Option<bool> {
+        self.get_index(name).map(|(_, typ)| typ.is_constant())
+    }
+
     /// Find an entry in the Scope, starting from the last.
     #[inline(always)]
     pub(crate) fn get_index(&self, name: &str) -> Option<(usize, EntryType)> {
@@ -403,6 +425,50 @@ impl<'a> Scope<'a> {
         self
     }
 
+    /// Flatten all shared values in the Scope into independent copies.
+    ///
+    /// Variables captured by closures are kept as shared values in the Scope.
+    /// After flattening, each entry holds its own copy of the value, so changing it no longer
+    /// affects any closure that captured it (and vice versa).
+    ///
+    /// This is a no-op under the `no_closure` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Scope;
+    ///
+    /// let mut my_scope = Scope::new();
+    ///
+    /// my_scope.push("x", 42_i64);
+    /// my_scope.flatten();
+    /// assert_eq!(my_scope.get_value::<i64>("x").unwrap(), 42);
+    /// ```
+    #[inline]
+    pub fn flatten(&mut self) -> &mut Self {
+        #[cfg(not(feature = "no_closure"))]
+        self.0.iter_mut().for_each(|Entry { value, .. }| {
+            if value.is_shared() {
+                *value = value.flatten_clone();
+            }
+        });
+        self
+    }
+
+    /// Remove the constants synthesized by the optimizer to share literals
+    /// from the entries beyond the specified size.
+    #[cfg(not(feature = "no_optimize"))]
+    #[inline]
+    pub(crate) fn remove_shared_literals(&mut self, size: usize) -> &mut Self {
+        let mut index = 0;
+
+        self.0.retain(|Entry { name, .. }| {
+            index += 1;
+            index <= size || !name.starts_with(SHARED_LITERAL_PREFIX)
+        });
+        self
+    }
+
     /// Get a mutable reference to an entry in the Scope.
     #[inline(always)]
     pub(crate) fn get_mut(&mut self, index: usize) -> (&mut Dynamic, EntryType) {
@@ -154,6 +154,8 @@ impl<'de> Deserializer<'de> for &mut DynamicDeserializer<'de> {
             Union::FnPtr(_) => self.type_error(),
             #[cfg(not(feature = "no_std"))]
             Union::TimeStamp(_) => self.type_error(),
+            #[cfg(feature = "decimal")]
+            Union::Decimal(_) => self.type_error(),
 
             Union::Variant(value) if value.is::<i8>() => self.deserialize_i8(visitor),
             Union::Variant(value) if value.is::<i16>() => self.deserialize_i16(visitor),
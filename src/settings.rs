@@ -5,14 +5,21 @@ use crate::packages::PackageLibrary;
 use crate::token::{is_valid_identifier, Token};
 
 #[cfg(not(feature = "no_module"))]
-use crate::module::ModuleResolver;
+use crate::module::{Module, ModuleResolver};
 
 #[cfg(not(feature = "no_optimize"))]
 use crate::optimize::OptimizationLevel;
 
-use crate::stdlib::{format, string::String};
+use crate::stdlib::{format, string::String, vec::Vec};
 
-#[cfg(not(feature = "no_module"))]
+#[cfg(feature = "internals")]
+use crate::{fn_native::SendSync, token::Position};
+
+#[cfg(feature = "internals")]
+#[cfg(not(feature = "no_optimize"))]
+use crate::{optimize::OptimizeContext, parser::Expr};
+
+#[cfg(any(not(feature = "no_module"), feature = "internals"))]
 use crate::stdlib::boxed::Box;
 
 impl Engine {
@@ -25,6 +32,7 @@ impl Engine {
     pub fn load_package(&mut self, package: impl Into<PackageLibrary>) -> &mut Self {
         // Push the package to the top - packages are searched in reverse order
         self.packages.push(package.into());
+        self.compile_cache_generation += 1;
         self
     }
 
@@ -48,6 +56,285 @@ impl Engine {
         self.optimization_level
     }
 
+    /// _[INTERNALS]_ Register a custom optimization pass that is run on each expression,
+    /// after the built-in optimizations are applied to it.
+    /// Exported under the `internals` feature only.
+    ///
+    /// The pass returns the expression to use in place of the original.  If it changes the
+    /// expression, it should call `OptimizeContext::set_dirty` so that the optimizer runs again
+    /// over the `AST` and the built-in optimizations are applied to the result.
+    ///
+    /// Optimization passes are run in the order they are registered.  They are not run when the
+    /// optimization level is `OptimizationLevel::None`.
+    ///
+    /// Not available under the `no_optimize` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #![allow(deprecated)]
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, Expr};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// // Replace calls to 'answer()' with the constant 42
+    /// engine.register_optimizer_pass(|expr, context| match expr {
+    ///     Expr::FnCall(x) if (x.0).0 == "answer" && x.3.is_empty() => {
+    ///         context.set_dirty();
+    ///         Expr::IntegerConstant(Box::new((42, (x.0).3)))
+    ///     }
+    ///     _ => expr,
+    /// });
+    ///
+    /// assert_eq!(engine.eval::<i64>("answer() + 1")?, 43);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "internals")]
+    #[cfg(not(feature = "no_optimize"))]
+    #[deprecated(note = "this method is volatile and may change")]
+    #[inline(always)]
+    pub fn register_optimizer_pass(
+        &mut self,
+        pass: impl Fn(Expr, &OptimizeContext) -> Expr + SendSync + 'static,
+    ) -> &mut Self {
+        self.optimizer_passes.push(Box::new(pass));
+        self.compile_cache_generation += 1;
+        self
+    }
+
+    /// Control whether the `Engine` raises a parse error when the guard condition of an `if` or
+    /// `while` statement is a constant boolean (e.g. `if true { ... }`), which is usually a mistake.
+    ///
+    /// `while true` is still allowed unless disabled via `set_allow_while_true`.
+    #[inline(always)]
+    pub fn set_strict_conditions(&mut self, enable: bool) -> &mut Self {
+        self.strict_conditions = enable;
+        self
+    }
+
+    /// Are constant `if` and `while` guard conditions parse errors?
+    #[inline(always)]
+    pub fn strict_conditions(&self) -> bool {
+        self.strict_conditions
+    }
+
+    /// Control whether `while true` is allowed when strict conditions are enabled.
+    /// Default is `true`.
+    ///
+    /// Disabling this requires intentional infinite loops to be written with `loop` instead.
+    #[inline(always)]
+    pub fn set_allow_while_true(&mut self, enable: bool) -> &mut Self {
+        self.allow_while_true = enable;
+        self
+    }
+
+    /// Is `while true` allowed when strict conditions are enabled?
+    #[inline(always)]
+    pub fn allow_while_true(&self) -> bool {
+        self.allow_while_true
+    }
+
+    /// Control whether the `Engine` raises a parse error on empty statements, i.e. a semicolon
+    /// that does not terminate a statement (e.g. the second `;` in `let x = 1;; x`).
+    /// Default is `false`.
+    ///
+    /// A single semicolon following a statement block (e.g. `if x { ... };`) is still allowed.
+    #[inline(always)]
+    pub fn set_strict_semicolons(&mut self, enable: bool) -> &mut Self {
+        self.strict_semicolons = enable;
+        self
+    }
+
+    /// Are empty statements parse errors?
+    #[inline(always)]
+    pub fn strict_semicolons(&self) -> bool {
+        self.strict_semicolons
+    }
+
+    /// Control whether the `Engine` raises a parse error when a function is called (in normal
+    /// function-call style) with a number of arguments that does not match any script-defined
+    /// or registered native function of the same name.
+    /// Default is `false`.
+    ///
+    /// Calls to functions that are not defined at all, method calls and calls via `call`
+    /// are not checked, as they may still be resolved at run-time.
+    #[inline(always)]
+    pub fn set_strict_function_calls(&mut self, enable: bool) -> &mut Self {
+        self.strict_function_calls = enable;
+        self
+    }
+
+    /// Are calls to known functions with the wrong number of arguments parse errors?
+    #[inline(always)]
+    pub fn strict_function_calls(&self) -> bool {
+        self.strict_function_calls
+    }
+
+    /// Control whether compiled `AST`'s retain the source text of their scripts.
+    /// Default is `false` to avoid the memory cost.
+    ///
+    /// Retained source text can be used via `AST::slice_at` to render the lines of source
+    /// referred to by error positions.
+    #[inline(always)]
+    pub fn set_retain_source(&mut self, enable: bool) -> &mut Self {
+        self.retain_source = enable;
+        self
+    }
+
+    /// Do compiled `AST`'s retain the source text of their scripts?
+    #[inline(always)]
+    pub fn retain_source(&self) -> bool {
+        self.retain_source
+    }
+
+    /// Control whether errors raised inside script-defined functions capture the script call stack.
+    /// Default is `false` for performance.
+    ///
+    /// When enabled, nested function calls are kept as nested `EvalAltResult::ErrorInFunctionCall`
+    /// errors (each with its own call-site position), available via `EvalAltResult::call_stack`.
+    #[inline(always)]
+    pub fn set_capture_call_stack(&mut self, enable: bool) -> &mut Self {
+        self.capture_call_stack = enable;
+        self
+    }
+
+    /// Do errors raised inside script-defined functions capture the script call stack?
+    #[inline(always)]
+    pub fn capture_call_stack(&self) -> bool {
+        self.capture_call_stack
+    }
+
+    /// Control whether a method call with no matching method falls back to a script-defined
+    /// function taking the object as the first argument, i.e. `x.foo(y)` calls `foo(x, y)`.
+    /// Default is `false` to avoid surprising overload resolution.
+    ///
+    /// Native Rust functions can always be called in method style.
+    /// The object is passed by value to the free function, so it is never modified.
+    #[inline(always)]
+    pub fn set_fallback_to_free_fn(&mut self, enable: bool) -> &mut Self {
+        self.fallback_to_free_fn = enable;
+        self
+    }
+
+    /// Do method calls with no matching method fall back to script-defined free functions?
+    #[inline(always)]
+    pub fn fallback_to_free_fn(&self) -> bool {
+        self.fallback_to_free_fn
+    }
+
+    /// Control whether functions can be defined inside a statement block or the body of
+    /// another function.  Default is `false`, requiring all functions to be defined at global level.
+    ///
+    /// A nested function is only visible to the statements following its definition within the
+    /// enclosing block (including any functions and closures defined there), and shadows any
+    /// function of the same name and number of parameters defined outside.
+    /// Like any other function, it cannot access variables of the enclosing scope.
+    #[inline(always)]
+    pub fn set_allow_nested_functions(&mut self, enable: bool) -> &mut Self {
+        self.allow_nested_functions = enable;
+        self
+    }
+
+    /// Are functions allowed to be defined inside blocks and other functions?
+    #[inline(always)]
+    pub fn allow_nested_functions(&self) -> bool {
+        self.allow_nested_functions
+    }
+
+    /// Control whether a chain of comparison operators (i.e. `<`, `<=`, `>` and `>=`) is parsed
+    /// as in mathematical notation, so that `a < b < c` means `a < b && b < c`.
+    /// Default is `false`, parsing `a < b < c` as `(a < b) < c`.
+    ///
+    /// Each operand in the chain is evaluated at most once, in order from left to right, and the
+    /// comparisons short-circuit.  Operators of different directions can be mixed (e.g. `a < b > c`).
+    #[inline(always)]
+    pub fn set_allow_comparison_chains(&mut self, enable: bool) -> &mut Self {
+        self.allow_comparison_chains = enable;
+        self
+    }
+
+    /// Are chains of comparison operators parsed as in mathematical notation?
+    #[inline(always)]
+    pub fn allow_comparison_chains(&self) -> bool {
+        self.allow_comparison_chains
+    }
+
+    /// Control whether statement blocks (e.g. `{ let t = 1; t + 1 }`) are allowed as expressions
+    /// in contexts where they are normally disallowed, such as in expressions evaluated via
+    /// `Engine::eval_expression` or compiled via `Engine::compile_expression`.
+    /// Default is `false`.
+    ///
+    /// Statement blocks are always allowed as expressions within full scripts.
+    #[inline(always)]
+    pub fn set_allow_statement_expression(&mut self, enable: bool) -> &mut Self {
+        self.allow_statement_expression = enable;
+        self
+    }
+
+    /// Are statement blocks allowed as expressions everywhere?
+    #[inline(always)]
+    pub fn allow_statement_expression(&self) -> bool {
+        self.allow_statement_expression
+    }
+
+    /// Enable a cache of compiled `AST`'s holding up to `capacity` entries, evicting the
+    /// least-recently-used entry when full.  A capacity of zero disables the cache.
+    /// Default is disabled.
+    ///
+    /// Compiling the same script again (with the same optimization level, custom syntax,
+    /// custom operators and disabled symbols) returns a clone of the cached `AST`.
+    /// Scripts compiled with a non-empty `Scope` are never cached because the optimizer may
+    /// fold constants in the `Scope` into the `AST`.
+    ///
+    /// Registering functions, loading packages or changing how modules are resolved
+    /// invalidates all cached `AST`'s, because the optimizer may have folded calls to functions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.enable_compile_cache(100);
+    ///
+    /// engine.compile("40 + 2")?;
+    /// engine.compile("40 + 2")?;
+    ///
+    /// assert_eq!(engine.compile_cache_stats(), (1, 1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline(always)]
+    pub fn enable_compile_cache(&mut self, capacity: usize) -> &mut Self {
+        #[cfg(not(feature = "sync"))]
+        self.compile_cache.borrow_mut().set_capacity(capacity);
+        #[cfg(feature = "sync")]
+        self.compile_cache.write().unwrap().set_capacity(capacity);
+        self
+    }
+
+    /// Get the number of hits and misses, in that order, of the compile cache.
+    #[inline(always)]
+    pub fn compile_cache_stats(&self) -> (u64, u64) {
+        #[cfg(not(feature = "sync"))]
+        return self.compile_cache.borrow().stats();
+        #[cfg(feature = "sync")]
+        return self.compile_cache.read().unwrap().stats();
+    }
+
+    /// Remove all `AST`'s from the compile cache and reset its hit/miss counters.
+    #[inline(always)]
+    pub fn clear_compile_cache(&self) {
+        #[cfg(not(feature = "sync"))]
+        self.compile_cache.borrow_mut().clear();
+        #[cfg(feature = "sync")]
+        self.compile_cache.write().unwrap().clear();
+    }
+
     /// Set the maximum levels of function calls allowed for a script in order to avoid
     /// infinite recursion and stack overflows.
     #[cfg(not(feature = "unchecked"))]
@@ -140,6 +427,73 @@ impl Engine {
         self.limits_set.max_function_expr_depth
     }
 
+    /// Set the maximum number of variables that can be declared within a scope (0 for unlimited).
+    ///
+    /// This limit is enforced during parsing.
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub fn set_max_variables(&mut self, variables: usize) -> &mut Self {
+        self.limits_set.max_variables = if variables == usize::MAX {
+            0
+        } else {
+            variables
+        };
+        self
+    }
+
+    /// The maximum number of variables that can be declared within a scope (0 for unlimited).
+    #[cfg(not(feature = "unchecked"))]
+    #[inline(always)]
+    pub fn max_variables(&self) -> usize {
+        self.limits_set.max_variables
+    }
+
+    /// Set the maximum levels of nesting of array/map literals (0 for unlimited).
+    ///
+    /// This limit is independent of the maximum levels of expression nesting (see
+    /// `Engine::set_max_expr_depths`), so deeply-nested code can be allowed while keeping
+    /// data literals (e.g. JSON text passed to `Engine::parse_json`) shallow.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// # #[cfg(not(feature = "no_index"))]
+    /// # {
+    /// use rhai::{Engine, ParseErrorType};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.set_max_data_depth(2);
+    ///
+    /// engine.compile("let x = [[1, 2], [3]];")?;
+    ///
+    /// let err = engine.compile("let x = [[[1]]];").expect_err("should error");
+    /// assert_eq!(*err.0, ParseErrorType::DataTooDeep);
+    /// # }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "unchecked"))]
+    #[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
+    #[inline(always)]
+    pub fn set_max_data_depth(&mut self, max_depth: usize) -> &mut Self {
+        self.limits_set.max_data_depth = if max_depth == usize::MAX {
+            0
+        } else {
+            max_depth
+        };
+        self
+    }
+
+    /// The maximum levels of nesting of array/map literals (0 for unlimited).
+    #[cfg(not(feature = "unchecked"))]
+    #[cfg(any(not(feature = "no_index"), not(feature = "no_object")))]
+    #[inline(always)]
+    pub fn max_data_depth(&self) -> usize {
+        self.limits_set.max_data_depth
+    }
+
     /// Set the maximum length of strings (0 for unlimited).
     #[cfg(not(feature = "unchecked"))]
     #[inline(always)]
@@ -199,6 +553,44 @@ impl Engine {
         resolver: Option<impl ModuleResolver + 'static>,
     ) -> &mut Self {
         self.module_resolver = resolver.map(|f| Box::new(f) as Box<dyn ModuleResolver>);
+        self.compile_cache_generation += 1;
+        self
+    }
+
+    /// Register a module under a path so that it can be imported by scripts via `import`.
+    ///
+    /// Registering another module under the same path replaces the previous one.
+    ///
+    /// Modules registered this way take precedence over the module resolution service
+    /// set via `set_module_resolver`, which is still consulted for all other paths.
+    ///
+    /// Not available under the `no_module` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, Module};
+    ///
+    /// let mut module = Module::new();
+    /// module.set_var("answer", 42_i64);
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.register_static_module("hello", module);
+    ///
+    /// assert_eq!(engine.eval::<i64>(r#"import "hello" as h; h::answer"#)?, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "no_module"))]
+    #[inline(always)]
+    pub fn register_static_module<S: Into<String>>(
+        &mut self,
+        path: S,
+        module: Module,
+    ) -> &mut Self {
+        self.static_modules.insert(path, module);
+        self.compile_cache_generation += 1;
         self
     }
 
@@ -244,9 +636,31 @@ impl Engine {
         self
     }
 
+    /// Get all keywords and operators disabled via `Engine::disable_symbol`, sorted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.disable_symbol("while").disable_symbol("+=");
+    ///
+    /// assert_eq!(engine.disabled_symbols(), vec!["+=", "while"]);
+    /// ```
+    pub fn disabled_symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<_> = self.disabled_symbols.iter().cloned().collect();
+        symbols.sort();
+        symbols
+    }
+
     /// Register a custom operator into the language.
     ///
-    /// The operator must be a valid identifier (i.e. it cannot be a symbol).
+    /// The operator must be a valid identifier (i.e. it cannot be a symbol), unless it is a
+    /// built-in binary operator (e.g. `+`) that has been disabled via `Engine::disable_symbol`.
+    /// In that case, the operator is parsed as a custom operator calling the function registered
+    /// under the same name, which still falls back to built-in implementations for standard types.
     ///
     /// # Example
     ///
@@ -270,28 +684,251 @@ impl Engine {
     /// # Ok(())
     /// # }
     /// ```
+    #[inline(always)]
     pub fn register_custom_operator(
         &mut self,
         keyword: &str,
         precedence: u8,
     ) -> Result<&mut Self, String> {
-        if !is_valid_identifier(keyword.chars()) {
-            return Err(format!("not a valid identifier: '{}'", keyword).into());
-        }
+        self.register_custom_operator_with_assoc(keyword, precedence, false)
+    }
 
+    /// Register a custom operator into the language, specifying its associativity.
+    ///
+    /// The operator must be a valid identifier (i.e. it cannot be a symbol).
+    ///
+    /// A right-associative operator groups from the right, i.e. `a op b op c` is parsed as
+    /// `a op (b op c)` instead of `(a op b) op c`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, RegisterFn};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// // Register a right-associative custom operator called 'pow'
+    /// engine.register_custom_operator_with_assoc("pow", 180, true).unwrap();
+    ///
+    /// // Register a binary function named 'pow'
+    /// engine.register_fn("pow", |x: i64, y: i64| x.pow(y as u32));
+    ///
+    /// // 2 pow (3 pow 2) == 2 pow 9 == 512
+    /// assert_eq!(engine.eval_expression::<i64>("2 pow 3 pow 2")?, 512);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_custom_operator_with_assoc(
+        &mut self,
+        keyword: &str,
+        precedence: u8,
+        right_associative: bool,
+    ) -> Result<&mut Self, String> {
         match Token::lookup_from_syntax(keyword) {
+            // Disabled built-in binary operators can be redefined
+            Some(token)
+                if !is_valid_identifier(keyword.chars())
+                    && token.precedence() > 0
+                    && self.disabled_symbols.contains(keyword) => {}
+            // Otherwise the operator must be a valid identifier
+            _ if !is_valid_identifier(keyword.chars()) => {
+                return Err(format!("not a valid identifier: '{}'", keyword).into())
+            }
             // Standard identifiers, reserved keywords and custom keywords are OK
             None | Some(Token::Reserved(_)) | Some(Token::Custom(_)) => (),
             // Disabled keywords are also OK
-            Some(token) if !self.disabled_symbols.contains(token.syntax().as_ref()) => (),
+            Some(token) if self.disabled_symbols.contains(token.syntax().as_ref()) => (),
             // Active standard keywords cannot be made custom
             Some(_) => return Err(format!("'{}' is a reserved keyword", keyword).into()),
         }
 
         // Add to custom keywords
         self.custom_keywords
-            .insert(keyword.into(), Some(precedence));
+            .insert(keyword.into(), Some((precedence, right_associative)));
 
         Ok(self)
     }
+
+    /// Get all custom operators registered via `Engine::register_custom_operator`, together with
+    /// their precedence, sorted by name.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.register_custom_operator("foo", 160).unwrap();
+    /// engine.register_custom_operator("bar", 120).unwrap();
+    ///
+    /// assert_eq!(
+    ///     engine.custom_operators(),
+    ///     vec![("bar".to_string(), 120), ("foo".to_string(), 160)]
+    /// );
+    /// ```
+    pub fn custom_operators(&self) -> Vec<(String, u8)> {
+        let mut operators: Vec<_> = self
+            .custom_keywords
+            .iter()
+            .filter_map(|(keyword, op)| op.map(|(precedence, _)| (keyword.clone(), precedence)))
+            .collect();
+        operators.sort();
+        operators
+    }
+
+    /// Get the precedence of a binary operator (built-in or custom), or `None` if the operator
+    /// is not a binary operator or has been disabled.
+    ///
+    /// Assignment operators have a precedence of zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// assert_eq!(engine.operator_precedence("+"), Some(150));
+    /// assert_eq!(engine.operator_precedence("*"), Some(180));
+    /// assert_eq!(engine.operator_precedence("="), Some(0));
+    /// assert_eq!(engine.operator_precedence("!"), None);
+    ///
+    /// engine.register_custom_operator("foo", 160).unwrap();
+    ///
+    /// assert_eq!(engine.operator_precedence("foo"), Some(160));
+    /// ```
+    pub fn operator_precedence(&self, op: &str) -> Option<u8> {
+        if let Some(Some((precedence, _))) = self.custom_keywords.get(op) {
+            return Some(*precedence);
+        }
+
+        if self.disabled_symbols.contains(op) {
+            return None;
+        }
+
+        match Token::lookup_from_syntax(op) {
+            Some(token) if token.precedence() > 0 || token.is_bind_right() => {
+                Some(self.token_precedence(&token))
+            }
+            _ => None,
+        }
+    }
+
+    /// Override the precedence of a built-in binary operator.
+    ///
+    /// The precedence must be between 1 and 239 (inclusive), so that the operator still binds
+    /// tighter than assignments (precedence 0) and looser than property access (precedence 240).
+    /// The precedence of assignment operators and property access cannot be changed.
+    ///
+    /// Use `register_custom_operator` to set the precedence of custom operators.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// assert_eq!(engine.eval_expression::<i64>("1 + 2 * 3")?, 7);
+    ///
+    /// // Make '+' bind tighter than '*'
+    /// engine.set_operator_precedence("+", 200)?;
+    ///
+    /// assert_eq!(engine.eval_expression::<i64>("1 + 2 * 3")?, 9);
+    ///
+    /// // Assignments must bind loosest
+    /// assert!(engine.set_operator_precedence("=", 100).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_operator_precedence(
+        &mut self,
+        op: &str,
+        precedence: u8,
+    ) -> Result<&mut Self, String> {
+        let max_precedence = Token::Period.precedence() - 1;
+
+        match Token::lookup_from_syntax(op) {
+            Some(Token::Period) | Some(Token::QuestionDot) => {
+                return Err(format!("the precedence of '{}' cannot be changed", op));
+            }
+            Some(token) if token.precedence() > 0 => (),
+            Some(token) if token.is_bind_right() => {
+                return Err(format!(
+                    "the precedence of the assignment operator '{}' cannot be changed",
+                    op
+                ));
+            }
+            _ => return Err(format!("'{}' is not a built-in binary operator", op)),
+        }
+
+        if precedence == 0 || precedence > max_precedence {
+            return Err(format!(
+                "the precedence of '{}' must be between 1 and {}",
+                op, max_precedence
+            ));
+        }
+
+        self.precedence_overrides.insert(op.into(), precedence);
+
+        Ok(self)
+    }
+
+    /// _[INTERNALS]_ Provide a callback that is invoked on each token, together with its position,
+    /// before it is consumed by the parser.
+    /// Exported under the `internals` feature only.
+    ///
+    /// The callback returns the token to use in place of the original.  This allows rewriting
+    /// tokens (e.g. turning an identifier into a custom keyword or an operator) or rejecting them
+    /// by returning `Token::LexError`.
+    ///
+    /// The callback is consulted after disabled symbols and custom keywords/operators are resolved.
+    ///
+    /// ## Contract
+    ///
+    /// * The end of the script (`Token::EOF`) and lexer errors (`Token::LexError`) are never
+    ///   passed to the callback.
+    /// * The callback cannot end the script early - mapping a token into `Token::EOF` raises
+    ///   a lexer error instead.
+    /// * Tokens that are out of place (e.g. a custom keyword that is not registered) are
+    ///   reported as parse errors, as if they were typed into the script.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #![allow(deprecated)]
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::{Engine, LexError, Token};
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.on_parse_token(|token, _| match token {
+    ///     // 'is' is an alias to '=='
+    ///     Token::Identifier(s) if s == "is" => Token::EqualsTo,
+    ///     // 'loop' is not allowed
+    ///     Token::Loop => Token::LexError(Box::new(LexError::ImproperSymbol(
+    ///         "'loop' is not allowed".to_string(),
+    ///     ))),
+    ///     _ => token,
+    /// });
+    ///
+    /// assert!(engine.eval::<bool>("let x = 42; x is 42")?);
+    /// assert!(engine.compile("loop { break; }").is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "internals")]
+    #[deprecated(note = "this method is volatile and may change")]
+    #[inline(always)]
+    pub fn on_parse_token(
+        &mut self,
+        callback: impl Fn(Token, Position) -> Token + SendSync + 'static,
+    ) -> &mut Self {
+        self.token_mapper = Some(Box::new(callback));
+        self.compile_cache_generation += 1;
+        self
+    }
 }
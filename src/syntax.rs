@@ -14,6 +14,7 @@ use crate::stdlib::{
     boxed::Box,
     format,
     string::{String, ToString},
+    vec::Vec,
 };
 
 /// A general expression evaluation trait object.
@@ -89,6 +90,9 @@ pub struct CustomSyntax {
     pub parse: Box<FnCustomSyntaxParse>,
     pub func: Shared<FnCustomSyntaxEval>,
     pub scope_delta: isize,
+    pub pure: bool,
+    /// Custom keywords/symbols used by this custom syntax.
+    pub keywords: StaticVec<String>,
 }
 
 impl Engine {
@@ -97,6 +101,7 @@ impl Engine {
     /// * `keywords` holds a slice of strings that define the custom syntax.  
     /// * `new_vars` is the number of new variables declared by this custom syntax, or the number of variables removed (if negative).  
     /// * `func` is the implementation function.
+    #[inline(always)]
     pub fn register_custom_syntax<S: AsRef<str> + ToString>(
         &mut self,
         keywords: impl AsRef<[S]>,
@@ -104,10 +109,45 @@ impl Engine {
         func: impl Fn(&mut EvalContext, &[Expression]) -> Result<Dynamic, Box<EvalAltResult>>
             + SendSync
             + 'static,
+    ) -> Result<&mut Self, ParseError> {
+        self.register_custom_syntax_internal(keywords, new_vars, false, func)
+    }
+
+    /// Register a pure custom syntax with the `Engine`.
+    ///
+    /// A pure custom syntax has no side effects and always returns the same result
+    /// for the same inputs.  When all the inputs are constants, the optimizer (under
+    /// `OptimizationLevel::Full`) evaluates it eagerly and replaces it with the result.
+    ///
+    /// * `keywords` holds a slice of strings that define the custom syntax.  
+    /// * `new_vars` is the number of new variables declared by this custom syntax, or the number of variables removed (if negative).  
+    /// * `func` is the implementation function.
+    #[inline(always)]
+    pub fn register_custom_syntax_pure<S: AsRef<str> + ToString>(
+        &mut self,
+        keywords: impl AsRef<[S]>,
+        new_vars: isize,
+        func: impl Fn(&mut EvalContext, &[Expression]) -> Result<Dynamic, Box<EvalAltResult>>
+            + SendSync
+            + 'static,
+    ) -> Result<&mut Self, ParseError> {
+        self.register_custom_syntax_internal(keywords, new_vars, true, func)
+    }
+
+    /// Register a custom syntax with the `Engine`, specifying whether it is pure.
+    fn register_custom_syntax_internal<S: AsRef<str> + ToString>(
+        &mut self,
+        keywords: impl AsRef<[S]>,
+        new_vars: isize,
+        pure: bool,
+        func: impl Fn(&mut EvalContext, &[Expression]) -> Result<Dynamic, Box<EvalAltResult>>
+            + SendSync
+            + 'static,
     ) -> Result<&mut Self, ParseError> {
         let keywords = keywords.as_ref();
 
         let mut segments: StaticVec<_> = Default::default();
+        let mut custom_keywords: StaticVec<String> = Default::default();
 
         for s in keywords {
             let s = s.as_ref().trim();
@@ -126,6 +166,7 @@ impl Engine {
                     if !self.custom_keywords.contains_key(s) {
                         self.custom_keywords.insert(s.into(), None);
                     }
+                    custom_keywords.push(s.into());
                     s.into()
                 }
                 // Standard keyword in first position
@@ -142,11 +183,24 @@ impl Engine {
                     .into_err(Position::none())
                     .into());
                 }
+                // Custom operator in first position
+                s if segments.is_empty()
+                    && matches!(self.custom_keywords.get(s), Some(Some(_))) =>
+                {
+                    return Err(LexError::ImproperSymbol(format!(
+                        "Improper symbol for custom syntax at position #{}: '{}' is a custom operator",
+                        segments.len() + 1,
+                        s
+                    ))
+                    .into_err(Position::none())
+                    .into());
+                }
                 // Identifier in first position
                 s if segments.is_empty() && is_valid_identifier(s.chars()) => {
                     if !self.custom_keywords.contains_key(s) {
                         self.custom_keywords.insert(s.into(), None);
                     }
+                    custom_keywords.push(s.into());
                     s.into()
                 }
                 // Anything else is an error
@@ -172,8 +226,8 @@ impl Engine {
         // The first keyword is the discriminator
         let key = segments[0].clone();
 
-        self.register_custom_syntax_raw(
-            key,
+        self.register_custom_syntax_raw_internal(
+            key.clone(),
             // Construct the parsing function
             move |stream| {
                 if stream.len() >= segments.len() {
@@ -183,9 +237,13 @@ impl Engine {
                 }
             },
             new_vars,
+            pure,
             func,
         );
 
+        // Remember the custom keywords so that they can be released upon removal
+        self.custom_syntax.get_mut(key.as_str()).unwrap().keywords = custom_keywords;
+
         Ok(self)
     }
 
@@ -201,6 +259,7 @@ impl Engine {
     ///
     /// All custom keywords must be manually registered via `Engine::register_custom_operator`.
     /// Otherwise, custom keywords won't be recognized.
+    #[inline(always)]
     pub fn register_custom_syntax_raw(
         &mut self,
         key: impl Into<ImmutableString>,
@@ -209,14 +268,116 @@ impl Engine {
         func: impl Fn(&mut EvalContext, &[Expression]) -> Result<Dynamic, Box<EvalAltResult>>
             + SendSync
             + 'static,
+    ) -> &mut Self {
+        self.register_custom_syntax_raw_internal(key, parse, new_vars, false, func)
+    }
+
+    /// Register a custom syntax with the `Engine`, specifying whether it is pure.
+    fn register_custom_syntax_raw_internal(
+        &mut self,
+        key: impl Into<ImmutableString>,
+        parse: impl Fn(&[String]) -> Result<Option<String>, ParseError> + SendSync + 'static,
+        new_vars: isize,
+        pure: bool,
+        func: impl Fn(&mut EvalContext, &[Expression]) -> Result<Dynamic, Box<EvalAltResult>>
+            + SendSync
+            + 'static,
     ) -> &mut Self {
         let syntax = CustomSyntax {
             parse: Box::new(parse),
             func: (Box::new(func) as Box<FnCustomSyntaxEval>).into(),
             scope_delta: new_vars,
+            pure,
+            keywords: Default::default(),
         };
 
         self.custom_syntax.insert(key.into(), syntax);
+        self.compile_cache_generation += 1;
         self
     }
+
+    /// Remove a custom syntax from the `Engine`, given its leading keyword.
+    ///
+    /// Custom keywords that were introduced by the custom syntax and are not used by any other
+    /// custom syntax (nor registered as custom operators) are released, so they can be used
+    /// as normal identifiers again.
+    ///
+    /// Returns `true` if the custom syntax existed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.register_custom_syntax(&["do_twice", "$expr$"], 0, |context, inputs| {
+    ///     let value = context.eval_expression_tree(&inputs[0])?;
+    ///     Ok((value.as_int().unwrap() * 2).into())
+    /// })?;
+    ///
+    /// assert_eq!(engine.eval::<i64>("do_twice 21")?, 42);
+    ///
+    /// assert!(engine.remove_custom_syntax("do_twice"));
+    ///
+    /// // 'do_twice' is a normal identifier again
+    /// assert_eq!(engine.eval::<i64>("let do_twice = 42; do_twice")?, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn remove_custom_syntax(&mut self, key: &str) -> bool {
+        let syntax = match self.custom_syntax.remove(key) {
+            Some(syntax) => syntax,
+            None => return false,
+        };
+
+        self.compile_cache_generation += 1;
+
+        for keyword in syntax.keywords.iter() {
+            let in_use = self
+                .custom_syntax
+                .values()
+                .any(|syntax| syntax.keywords.contains(keyword));
+
+            if !in_use && matches!(self.custom_keywords.get(keyword), Some(None)) {
+                self.custom_keywords.remove(keyword);
+            }
+        }
+
+        true
+    }
+
+    /// Get the leading keywords of all custom syntax registered with the `Engine`, together with
+    /// all custom keywords introduced by them, sorted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn main() -> Result<(), Box<rhai::EvalAltResult>> {
+    /// use rhai::Engine;
+    ///
+    /// let mut engine = Engine::new();
+    ///
+    /// engine.register_custom_syntax(&["exec", "->", "$block$"], 0, |context, inputs| {
+    ///     context.eval_expression_tree(&inputs[0])
+    /// })?;
+    ///
+    /// assert_eq!(engine.custom_syntax_keywords(), vec!["->", "exec"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn custom_syntax_keywords(&self) -> Vec<String> {
+        let mut keywords: Vec<String> = self
+            .custom_keywords
+            .iter()
+            .filter(|(_, precedence)| precedence.is_none())
+            .map(|(keyword, _)| keyword.clone())
+            .chain(self.custom_syntax.keys().map(|key| key.to_string()))
+            .collect();
+
+        keywords.sort();
+        keywords.dedup();
+        keywords
+    }
 }
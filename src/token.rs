@@ -15,6 +15,9 @@ use crate::StaticVec;
 #[cfg(not(feature = "no_float"))]
 use crate::parser::FLOAT;
 
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
 use crate::stdlib::{
     borrow::Cow,
     boxed::Box,
@@ -50,9 +53,24 @@ impl Position {
     /// `line` must not be zero.
     /// If `position` is zero, then it is at the beginning of a line.
     ///
+    /// Line zero is reserved for `Position::none()`, which is distinct from any position
+    /// created via `Position::new`.
+    ///
     /// # Panics
     ///
     /// Panics if `line` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Position;
+    ///
+    /// let pos = Position::new(3, 10);
+    ///
+    /// assert_eq!(pos.line(), Some(3));
+    /// assert_eq!(pos.position(), Some(10));
+    /// assert_ne!(Position::new(1, 0), Position::none());
+    /// ```
     #[inline(always)]
     pub fn new(line: u16, position: u16) -> Self {
         assert!(line != 0, "line cannot be zero");
@@ -106,9 +124,43 @@ impl Position {
         self.pos -= 1;
     }
 
-    /// Advance to the next line.
+    /// Advance by a number of character positions, up to the maximum position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is `Position::none()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rhai::Position;
+    ///
+    /// let mut pos = Position::new(1, 0);
+    /// pos.advance_column(5);
+    ///
+    /// assert_eq!(pos, Position::new(1, 5));
+    ///
+    /// pos.new_line();
+    ///
+    /// assert_eq!(pos, Position::new(2, 0));
+    /// ```
+    #[inline(always)]
+    pub fn advance_column(&mut self, columns: usize) {
+        assert!(!self.is_none(), "cannot advance Position::none");
+
+        // Advance up to maximum position
+        self.pos = (self.pos as usize)
+            .saturating_add(columns)
+            .min(u16::MAX as usize) as u16;
+    }
+
+    /// Advance to the beginning of the next line, up to the maximum line number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is `Position::none()`.
     #[inline(always)]
-    pub(crate) fn new_line(&mut self) {
+    pub fn new_line(&mut self) {
         assert!(!self.is_none(), "cannot advance Position::none");
 
         // Advance up to maximum position
@@ -156,6 +208,41 @@ impl fmt::Debug for Position {
     }
 }
 
+/// A span of text in the input script, from the position of its first character
+/// to the position of its last character (both inclusive).
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Default)]
+pub struct Span {
+    /// Position of the first character.
+    start: Position,
+    /// Position of the last character.
+    end: Position,
+}
+
+impl Span {
+    /// Create a new `Span`.
+    #[inline(always)]
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+    /// Position of the first character of the `Span`.
+    #[inline(always)]
+    pub fn start(&self) -> Position {
+        self.start
+    }
+    /// Position of the last character of the `Span`.
+    #[inline(always)]
+    pub fn end(&self) -> Position {
+        self.end
+    }
+}
+
+impl fmt::Display for Span {
+    #[inline(always)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} to {}", self.start, self.end)
+    }
+}
+
 /// _[INTERNALS]_ A Rhai language token.
 /// Exported under the `internals` feature only.
 ///
@@ -171,6 +258,11 @@ pub enum Token {
     /// Reserved under the `no_float` feature.
     #[cfg(not(feature = "no_float"))]
     FloatConstant(FLOAT),
+    /// A `Decimal` constant.
+    ///
+    /// Exported under the `decimal` feature only.
+    #[cfg(feature = "decimal")]
+    DecimalConstant(Decimal),
     /// An identifier.
     Identifier(String),
     /// A character constant.
@@ -219,6 +311,10 @@ pub enum Token {
     Comma,
     /// `.`
     Period,
+    /// `?.`
+    QuestionDot,
+    /// `?[`
+    QuestionBracket,
     /// `#{`
     MapStart,
     /// `=`
@@ -311,6 +407,12 @@ pub enum Token {
     /// Reserved under the `no_function` feature.
     #[cfg(not(feature = "no_function"))]
     Private,
+    /// `global`
+    ///
+    /// Reserved under the `no_function` and `no_closure` features.
+    #[cfg(not(feature = "no_function"))]
+    #[cfg(not(feature = "no_closure"))]
+    Global,
     /// `import`
     ///
     /// Reserved under the `no_module` feature.
@@ -328,12 +430,18 @@ pub enum Token {
     As,
     /// A lexer error.
     LexError(Box<LexError>),
-    /// A comment block.
-    Comment(String),
+    /// A line comment (`// ...`).
+    LineComment(String),
+    /// A block comment (`/* ... */`).
+    BlockComment(String),
+    /// A doc comment (`/// ...` or `/** ... */`).
+    DocComment(String),
     /// A reserved symbol.
     Reserved(String),
     /// A custom keyword.
     Custom(String),
+    /// A compound assignment with a custom operator (i.e. `op=`). Wrapped value is the custom operator.
+    CustomAssign(String),
     /// End of the input stream.
     EOF,
 }
@@ -347,12 +455,16 @@ impl Token {
             IntegerConstant(i) => i.to_string().into(),
             #[cfg(not(feature = "no_float"))]
             FloatConstant(f) => f.to_string().into(),
+            #[cfg(feature = "decimal")]
+            DecimalConstant(d) => format!("{}{}", d, DECIMAL_SUFFIX).into(),
             StringConstant(_) => "string".into(),
             CharConstant(c) => c.to_string().into(),
             Identifier(s) => s.clone().into(),
             Reserved(s) => s.clone().into(),
             Custom(s) => s.clone().into(),
+            CustomAssign(s) => format!("{}=", s).into(),
             LexError(err) => err.to_string().into(),
+            LineComment(s) | BlockComment(s) | DocComment(s) => s.clone().into(),
 
             token => match token {
                 LeftBrace => "{",
@@ -372,6 +484,8 @@ impl Token {
                 DoubleColon => "::",
                 Comma => ",",
                 Period => ".",
+                QuestionDot => "?.",
+                QuestionBracket => "?[",
                 MapStart => "#{",
                 Equals => "=",
                 True => "true",
@@ -422,6 +536,9 @@ impl Token {
                 Fn => "fn",
                 #[cfg(not(feature = "no_function"))]
                 Private => "private",
+                #[cfg(not(feature = "no_function"))]
+                #[cfg(not(feature = "no_closure"))]
+                Global => "global",
 
                 #[cfg(not(feature = "no_module"))]
                 Import => "import",
@@ -456,6 +573,8 @@ impl Token {
             "::" => DoubleColon,
             "," => Comma,
             "." => Period,
+            "?." => QuestionDot,
+            "?[" => QuestionBracket,
             "#{" => MapStart,
             "=" => Equals,
             "true" => True,
@@ -506,6 +625,9 @@ impl Token {
             "fn" => Fn,
             #[cfg(not(feature = "no_function"))]
             "private" => Private,
+            #[cfg(not(feature = "no_function"))]
+            #[cfg(not(feature = "no_closure"))]
+            "global" => Global,
 
             #[cfg(not(feature = "no_module"))]
             "import" => Import,
@@ -517,6 +639,9 @@ impl Token {
             #[cfg(feature = "no_function")]
             "fn" | "private" => Reserved(syntax.into()),
 
+            #[cfg(any(feature = "no_function", feature = "no_closure"))]
+            "global" => Reserved(syntax.into()),
+
             #[cfg(feature = "no_module")]
             "import" | "export" | "as" => Reserved(syntax.into()),
 
@@ -548,6 +673,35 @@ impl Token {
         }
     }
 
+    /// Is this token a comment?
+    #[inline]
+    pub fn is_comment(&self) -> bool {
+        use Token::*;
+
+        match self {
+            LineComment(_) | BlockComment(_) | DocComment(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Classify the text of a comment (including the leading `//` or `/*`) into a comment token.
+    ///
+    /// Doc comments start with exactly three slashes (`///`), or with `/**` (but not `/***` or `/**/`).
+    fn from_comment(comment: String) -> Self {
+        if comment.starts_with("///") && !comment.starts_with("////") {
+            Token::DocComment(comment)
+        } else if comment.starts_with("//") {
+            Token::LineComment(comment)
+        } else if comment.starts_with("/**")
+            && !comment.starts_with("/***")
+            && !comment.starts_with("/**/")
+        {
+            Token::DocComment(comment)
+        } else {
+            Token::BlockComment(comment)
+        }
+    }
+
     // If another operator is after these, it's probably an unary operator
     // (not sure about fn name).
     pub fn is_next_unary(&self) -> bool {
@@ -569,6 +723,8 @@ impl Token {
             Divide           |
             Comma            |
             Period           |
+            QuestionDot      |
+            QuestionBracket  | // ?[-expr] - is unary
             Equals           |
             LessThan         |
             GreaterThan      |
@@ -601,7 +757,8 @@ impl Token {
             Throw            |
             PowerOf          |
             In               |
-            PowerOfAssign    => true,
+            PowerOfAssign    |
+            CustomAssign(_)  => true,
 
             _ => false,
         }
@@ -615,7 +772,7 @@ impl Token {
             // Assignments are not considered expressions - set to zero
             Equals | PlusAssign | MinusAssign | MultiplyAssign | DivideAssign | LeftShiftAssign
             | RightShiftAssign | AndAssign | OrAssign | XOrAssign | ModuloAssign
-            | PowerOfAssign => 0,
+            | PowerOfAssign | CustomAssign(_) => 0,
 
             Or | XOr | Pipe => 30,
 
@@ -635,7 +792,7 @@ impl Token {
 
             LeftShift | RightShift => 210,
 
-            Period => 240,
+            Period | QuestionDot => 240,
 
             _ => 0,
         }
@@ -649,10 +806,10 @@ impl Token {
             // Assignments bind to the right
             Equals | PlusAssign | MinusAssign | MultiplyAssign | DivideAssign | LeftShiftAssign
             | RightShiftAssign | AndAssign | OrAssign | XOrAssign | ModuloAssign
-            | PowerOfAssign => true,
+            | PowerOfAssign | CustomAssign(_) => true,
 
             // Property access binds to the right
-            Period => true,
+            Period | QuestionDot => true,
 
             _ => false,
         }
@@ -665,11 +822,12 @@ impl Token {
         match self {
             LeftBrace | RightBrace | LeftParen | RightParen | LeftBracket | RightBracket | Plus
             | UnaryPlus | Minus | UnaryMinus | Multiply | Divide | Modulo | PowerOf | LeftShift
-            | RightShift | SemiColon | Colon | DoubleColon | Comma | Period | MapStart | Equals
-            | LessThan | GreaterThan | LessThanEqualsTo | GreaterThanEqualsTo | EqualsTo
-            | NotEqualsTo | Bang | Pipe | Or | XOr | Ampersand | And | PlusAssign | MinusAssign
-            | MultiplyAssign | DivideAssign | LeftShiftAssign | RightShiftAssign | AndAssign
-            | OrAssign | XOrAssign | ModuloAssign | PowerOfAssign => true,
+            | RightShift | SemiColon | Colon | DoubleColon | Comma | Period | QuestionDot
+            | QuestionBracket | MapStart | Equals | LessThan | GreaterThan | LessThanEqualsTo
+            | GreaterThanEqualsTo | EqualsTo | NotEqualsTo | Bang | Pipe | Or | XOr | Ampersand
+            | And | PlusAssign | MinusAssign | MultiplyAssign | DivideAssign | LeftShiftAssign
+            | RightShiftAssign | AndAssign | OrAssign | XOrAssign | ModuloAssign
+            | PowerOfAssign | CustomAssign(_) => true,
 
             _ => false,
         }
@@ -683,6 +841,10 @@ impl Token {
             #[cfg(not(feature = "no_function"))]
             Fn | Private => true,
 
+            #[cfg(not(feature = "no_function"))]
+            #[cfg(not(feature = "no_closure"))]
+            Global => true,
+
             #[cfg(not(feature = "no_module"))]
             Import | Export | As => true,
 
@@ -745,8 +907,9 @@ pub struct TokenizeState {
     pub comment_level: usize,
     /// Return `None` at the end of the stream instead of `Some(Token::EOF)`?
     pub end_with_none: bool,
-    /// Include comments?
-    pub include_comments: bool,
+    /// Keep comments (as `Token::LineComment`, `Token::BlockComment` or `Token::DocComment`)
+    /// instead of discarding them?
+    pub keep_comments: bool,
 }
 
 /// _[INTERNALS]_ Trait that encapsulates a peekable character input stream.
@@ -915,14 +1078,14 @@ fn scan_comment(
     while let Some(c) = stream.get_next() {
         pos.advance();
 
-        if state.include_comments {
+        if state.keep_comments {
             comment.push(c);
         }
 
         match c {
             '/' => {
                 if let Some(c2) = stream.get_next() {
-                    if state.include_comments {
+                    if state.keep_comments {
                         comment.push(c2);
                     }
                     if c2 == '*' {
@@ -933,7 +1096,7 @@ fn scan_comment(
             }
             '*' => {
                 if let Some(c2) = stream.get_next() {
-                    if state.include_comments {
+                    if state.keep_comments {
                         comment.push(c2);
                     }
                     if c2 == '/' {
@@ -966,9 +1129,11 @@ pub fn get_next_token(
 ) -> Option<(Token, Position)> {
     let result = get_next_token_inner(stream, state, pos);
 
-    // Save the last token's state
+    // Save the last token's state (comments are transparent)
     if let Some((ref token, _)) = result {
-        state.non_unary = !token.is_next_unary();
+        if !token.is_comment() {
+            state.non_unary = !token.is_next_unary();
+        }
     }
 
     result
@@ -1015,8 +1180,8 @@ fn get_next_token_inner(
         let mut comment = String::new();
         scan_comment(stream, state, pos, &mut comment);
 
-        if state.include_comments {
-            return Some((Token::Comment(comment), start_pos));
+        if state.keep_comments {
+            return Some((Token::BlockComment(comment), start_pos));
         }
     }
 
@@ -1043,7 +1208,7 @@ fn get_next_token_inner(
                             result.push(next_char);
                             eat_next(stream, pos);
                         }
-                        #[cfg(not(feature = "no_float"))]
+                        #[cfg(any(not(feature = "no_float"), feature = "decimal"))]
                         '.' => {
                             stream.get_next().unwrap();
 
@@ -1105,10 +1270,54 @@ fn get_next_token_inner(
                     }
                 }
 
+                // Type suffix, e.g. 42i64 or 1.5f64
+                let mut suffix = String::new();
+
+                match stream.peek_next() {
+                    Some(ch @ 'i') | Some(ch @ 'f') => {
+                        stream.get_next().unwrap();
+
+                        if stream.peek_next().map_or(false, |c| c.is_ascii_digit()) {
+                            suffix.push(ch);
+                            pos.advance();
+
+                            while let Some(next_char) = stream.peek_next() {
+                                if !is_id_continue(next_char) {
+                                    break;
+                                }
+                                suffix.push(next_char);
+                                eat_next(stream, pos);
+                            }
+                        } else {
+                            stream.unread(ch);
+                        }
+                    }
+                    // Decimal suffix, e.g. 1.50d
+                    #[cfg(feature = "decimal")]
+                    Some(ch @ 'd') if radix_base.is_none() => {
+                        stream.get_next().unwrap();
+
+                        if stream.peek_next().map_or(true, |c| !is_id_continue(c)) {
+                            suffix.push(ch);
+                            pos.advance();
+                        } else {
+                            stream.unread(ch);
+                        }
+                    }
+                    _ => (),
+                }
+
                 if negated {
                     result.insert(0, '-');
                 }
 
+                if !suffix.is_empty() {
+                    return Some((
+                        parse_number_with_suffix(&result, radix_base, &suffix),
+                        start_pos,
+                    ));
+                }
+
                 // Parse number
                 if let Some(radix) = radix_base {
                     let out: String = result.iter().skip(2).filter(|&&c| c != '_').collect();
@@ -1131,6 +1340,11 @@ fn get_next_token_inner(
                     #[cfg(not(feature = "no_float"))]
                     let num = num.or_else(|_| FLOAT::from_str(&out).map(Token::FloatConstant));
 
+                    // Without floating-point, numbers with fractions are decimals
+                    #[cfg(feature = "no_float")]
+                    #[cfg(feature = "decimal")]
+                    let num = num.or_else(|_| Decimal::from_str(&out).map(Token::DecimalConstant));
+
                     return Some((
                         num.unwrap_or_else(|_| {
                             Token::LexError(Box::new(LERR::MalformedNumber(
@@ -1252,7 +1466,7 @@ fn get_next_token_inner(
             ('/', '/') => {
                 eat_next(stream, pos);
 
-                let mut comment = if state.include_comments {
+                let mut comment = if state.keep_comments {
                     "//".to_string()
                 } else {
                     String::new()
@@ -1264,14 +1478,14 @@ fn get_next_token_inner(
                         break;
                     }
 
-                    if state.include_comments {
+                    if state.keep_comments {
                         comment.push(c);
                     }
                     pos.advance();
                 }
 
-                if state.include_comments {
-                    return Some((Token::Comment(comment), start_pos));
+                if state.keep_comments {
+                    return Some((Token::from_comment(comment), start_pos));
                 }
             }
             ('/', '*') => {
@@ -1279,15 +1493,23 @@ fn get_next_token_inner(
 
                 eat_next(stream, pos);
 
-                let mut comment = if state.include_comments {
+                let mut comment = if state.keep_comments {
                     "/*".to_string()
                 } else {
                     String::new()
                 };
                 scan_comment(stream, state, pos, &mut comment);
 
-                if state.include_comments {
-                    return Some((Token::Comment(comment), start_pos));
+                if state.keep_comments {
+                    return Some((Token::from_comment(comment), start_pos));
+                }
+
+                // Reached the end of the stream while still inside the comment
+                if state.comment_level > 0 {
+                    return Some((
+                        Token::LexError(Box::new(LERR::UnterminatedComment)),
+                        start_pos,
+                    ));
                 }
             }
 
@@ -1312,6 +1534,18 @@ fn get_next_token_inner(
             }
             ('.', _) => return Some((Token::Period, start_pos)),
 
+            // Optional chaining
+            #[cfg(not(feature = "no_object"))]
+            ('?', '.') => {
+                eat_next(stream, pos);
+                return Some((Token::QuestionDot, start_pos));
+            }
+            #[cfg(not(feature = "no_index"))]
+            ('?', '[') => {
+                eat_next(stream, pos);
+                return Some((Token::QuestionBracket, start_pos));
+            }
+
             ('=', '=') => {
                 eat_next(stream, pos);
 
@@ -1567,6 +1801,67 @@ fn is_id_continue(x: char) -> bool {
     x.is_ascii_alphanumeric() || x == '_'
 }
 
+/// Type suffix for integer literals matching `INT`.
+#[cfg(not(feature = "only_i32"))]
+const INT_SUFFIX: &str = "i64";
+/// Type suffix for integer literals matching `INT`.
+#[cfg(feature = "only_i32")]
+const INT_SUFFIX: &str = "i32";
+
+/// Type suffix for floating-point literals matching `FLOAT`.
+#[cfg(not(feature = "no_float"))]
+const FLOAT_SUFFIX: &str = "f64";
+
+/// Type suffix for `Decimal` literals.
+#[cfg(feature = "decimal")]
+const DECIMAL_SUFFIX: &str = "d";
+
+/// Parse a numeric literal with a type suffix.
+///
+/// Only suffixes matching the active `INT` and `FLOAT` types, plus `d` for `Decimal`
+/// (under the `decimal` feature), are supported.
+/// All other suffixes are errors.
+fn parse_number_with_suffix(digits: &[char], radix_base: Option<u32>, suffix: &str) -> Token {
+    let malformed = || {
+        let mut text: String = digits.iter().collect();
+        text.push_str(suffix);
+        Token::LexError(Box::new(LERR::MalformedNumber(text)))
+    };
+
+    let out: String = digits.iter().filter(|&&c| c != '_').collect();
+
+    match radix_base {
+        // Integer suffix on a hex/octal/binary literal
+        Some(radix) if suffix == INT_SUFFIX => {
+            let (sign, digits) = match out.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", out.as_str()),
+            };
+            INT::from_str_radix(&format!("{}{}", sign, &digits[2..]), radix)
+                .map(Token::IntegerConstant)
+                .unwrap_or_else(|_| malformed())
+        }
+        // Float suffix (or any other suffix) on a hex/octal/binary literal
+        Some(_) => malformed(),
+        // Integer suffix on a decimal literal
+        None if suffix == INT_SUFFIX => INT::from_str(&out)
+            .map(Token::IntegerConstant)
+            .unwrap_or_else(|_| malformed()),
+        // Float suffix on a decimal literal
+        #[cfg(not(feature = "no_float"))]
+        None if suffix == FLOAT_SUFFIX => FLOAT::from_str(&out)
+            .map(Token::FloatConstant)
+            .unwrap_or_else(|_| malformed()),
+        // Decimal suffix on a decimal literal
+        #[cfg(feature = "decimal")]
+        None if suffix == DECIMAL_SUFFIX => Decimal::from_str(&out)
+            .map(Token::DecimalConstant)
+            .unwrap_or_else(|_| malformed()),
+        // Unsupported suffix
+        None => malformed(),
+    }
+}
+
 /// A type that implements the `InputStream` trait.
 /// Multiple character streams are jointed together to form one single stream.
 pub struct MultiInputsStream<'a> {
@@ -1638,6 +1933,32 @@ pub struct TokenIterator<'a, 'e> {
     map: Option<Box<dyn Fn(Token) -> Token>>,
 }
 
+impl TokenIterator<'_, '_> {
+    /// Keep comments in the token stream as `Token::LineComment`, `Token::BlockComment`
+    /// or `Token::DocComment` instead of skipping them.
+    #[inline(always)]
+    pub(crate) fn keep_comments(mut self) -> Self {
+        self.state.keep_comments = true;
+        self
+    }
+
+    /// Skip the rest of a malformed token, up to the next whitespace or delimiter,
+    /// so that tokenizing can resume after a lexer error.
+    #[cfg(feature = "internals")]
+    fn skip_to_boundary(&mut self) {
+        while let Some(ch) = self.stream.peek_next() {
+            match ch {
+                ';' | ',' | '(' | ')' | '{' | '}' | '[' | ']' => break,
+                ch if ch.is_whitespace() => break,
+                _ => {
+                    self.stream.get_next();
+                    self.pos.advance();
+                }
+            }
+        }
+    }
+}
+
 impl<'a> Iterator for TokenIterator<'a, '_> {
     type Item = (Token, Position);
 
@@ -1688,8 +2009,23 @@ impl<'a> Iterator for TokenIterator<'a, '_> {
                 // Reserved keyword/operator that is not custom.
                 (_, false) => Token::Reserved(s),
             }, pos)),
-            // Custom keyword
-            Some((Token::Identifier(s), pos)) if self.engine.custom_keywords.contains_key(&s) => {
+            // Custom operator followed immediately by `=` (but not `==`) - compound assignment
+            Some((Token::Identifier(s), pos)) if matches!(self.engine.custom_keywords.get(&s), Some(Some(_)))
+                                                && self.stream.peek_next() == Some('=') => {
+                self.stream.get_next();
+
+                if self.stream.peek_next() == Some('=') {
+                    self.stream.unread('=');
+                    Some((Token::Custom(s), pos))
+                } else {
+                    self.pos.advance();
+                    Some((Token::CustomAssign(s), pos))
+                }
+            }
+            // Custom keyword (custom syntax keywords can be disabled)
+            Some((Token::Identifier(s), pos)) if matches!(self.engine.custom_keywords.get(&s), Some(Some(_)))
+                                                || (self.engine.custom_keywords.contains_key(&s)
+                                                    && !self.engine.disabled_symbols.contains(&s)) => {
                 Some((Token::Custom(s), pos))
             }
             // Custom standard keyword - must be disabled
@@ -1702,6 +2038,12 @@ impl<'a> Iterator for TokenIterator<'a, '_> {
                     unreachable!()
                 }
             }
+            // Disabled binary operator redefined as a custom operator
+            Some((token, pos)) if token.is_operator() && !matches!(token, Token::UnaryPlus | Token::UnaryMinus)
+                                                      && self.engine.disabled_symbols.contains(token.syntax().as_ref())
+                                                      && matches!(self.engine.custom_keywords.get(token.syntax().as_ref()), Some(Some(_))) => {
+                Some((Token::Custom(token.syntax().into()), pos))
+            }
             // Disabled operator
             Some((token, pos)) if token.is_operator() && self.engine.disabled_symbols.contains(token.syntax().as_ref()) => {
                 Some((
@@ -1719,6 +2061,20 @@ impl<'a> Iterator for TokenIterator<'a, '_> {
         match token {
             None => None,
             Some((token, pos)) => {
+                // The end of the script and lexer errors are never remapped
+                let token = match self.engine.token_mapper {
+                    Some(ref mapper) if !token.is_eof() && !matches!(token, Token::LexError(_)) => {
+                        match mapper(token, pos) {
+                            // Cannot cut the script short
+                            Token::EOF => Token::LexError(Box::new(LERR::ImproperSymbol(
+                                "the end of the script cannot be mapped from a token".to_string(),
+                            ))),
+                            token => token,
+                        }
+                    }
+                    _ => token,
+                };
+
                 if let Some(ref map) = self.map {
                     Some((map(token), pos))
                 } else {
@@ -1730,6 +2086,33 @@ impl<'a> Iterator for TokenIterator<'a, '_> {
 }
 
 impl Engine {
+    /// Get the precedence of a binary operator token, taking into account any overrides
+    /// set via `Engine::set_operator_precedence`.
+    #[inline]
+    pub(crate) fn token_precedence(&self, token: &Token) -> u8 {
+        if self.precedence_overrides.is_empty() {
+            return token.precedence();
+        }
+
+        self.precedence_overrides
+            .get(token.syntax().as_ref())
+            .cloned()
+            .unwrap_or_else(|| token.precedence())
+    }
+
+    /// Is a name a binary operator (built-in or custom) that is evaluated via a function call?
+    #[inline]
+    pub(crate) fn is_binary_operator(&self, name: &str) -> bool {
+        match Token::lookup_from_syntax(name) {
+            Some(Token::Period) | Some(Token::QuestionDot) => false,
+            Some(token) if token.precedence() > 0 => true,
+            _ => self
+                .custom_keywords
+                .get(name)
+                .map_or(false, Option::is_some),
+        }
+    }
+
     /// Tokenize an input text stream.
     #[inline]
     pub fn lex<'a, 'e>(
@@ -1747,7 +2130,7 @@ impl Engine {
                 non_unary: false,
                 comment_level: 0,
                 end_with_none: false,
-                include_comments: false,
+                keep_comments: false,
             },
             pos: Position::new(1, 0),
             stream: MultiInputsStream {
@@ -1758,4 +2141,71 @@ impl Engine {
             map,
         }
     }
+
+    /// _[INTERNALS]_ Tokenize a script into tokens, each with the `Span` of text it covers.
+    /// The end of the script is not included.
+    /// Exported under the `internals` feature only.
+    ///
+    /// If `keep_comments` is `true`, comments are returned as `Token::LineComment`,
+    /// `Token::BlockComment` or `Token::DocComment` (for `/// ...` and `/** ... */`).
+    /// Otherwise they are discarded.
+    ///
+    /// Lexer errors are returned as `Token::LexError` tokens covering the malformed text.
+    /// If `tolerant` is `false`, tokenizing stops after the first error.
+    /// Otherwise, the malformed text is skipped up to the next whitespace or delimiter
+    /// (e.g. `;` or `)`) and tokenizing resumes from there, which is useful for highlighting
+    /// scripts that may contain errors.
+    ///
+    /// ## WARNING
+    ///
+    /// The `Token` type is volatile and may change.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #![allow(deprecated)]
+    /// use rhai::{Engine, Token};
+    ///
+    /// let engine = Engine::new();
+    ///
+    /// let tokens = engine.tokenize("let x = 1 ¤ 2;", true, false);
+    ///
+    /// assert_eq!(tokens.len(), 7);
+    /// assert!(matches!(tokens[4].0, Token::LexError(_)));
+    /// assert_eq!(tokens[6].0, Token::SemiColon);
+    /// ```
+    #[cfg(feature = "internals")]
+    #[deprecated(note = "this method is volatile and may change")]
+    pub fn tokenize(
+        &self,
+        script: &str,
+        tolerant: bool,
+        keep_comments: bool,
+    ) -> Vec<(Token, Span)> {
+        let scripts = [script];
+        let mut stream = self.lex(&scripts, None);
+        stream.state.keep_comments = keep_comments;
+        let mut tokens = Vec::new();
+
+        loop {
+            let (token, start) = match stream.next() {
+                None | Some((Token::EOF, _)) => break,
+                Some(r) => r,
+            };
+
+            let is_error = matches!(token, Token::LexError(_));
+
+            if is_error && tolerant {
+                stream.skip_to_boundary();
+            }
+
+            tokens.push((token, Span::new(start, stream.pos)));
+
+            if is_error && !tolerant {
+                break;
+            }
+        }
+
+        tokens
+    }
 }
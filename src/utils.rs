@@ -1,12 +1,14 @@
 //! Module containing various utility types and functions.
 
 use crate::fn_native::{shared_make_mut, shared_take, Shared};
+use crate::parser::AST;
 
 use crate::stdlib::{
     any::TypeId,
     borrow::Borrow,
     boxed::Box,
     cmp::Ordering,
+    collections::{HashMap, VecDeque},
     fmt,
     hash::{BuildHasher, Hash, Hasher},
     iter::FromIterator,
@@ -91,6 +93,116 @@ pub fn calc_fn_hash<'a>(
     s.finish()
 }
 
+/// Calculate a `u64` hash key from the text of a list of scripts.
+#[cfg(not(feature = "no_function"))]
+pub(crate) fn calc_source_hash(scripts: &[&str]) -> u64 {
+    #[cfg(feature = "no_std")]
+    let mut s: AHasher = Default::default();
+    #[cfg(not(feature = "no_std"))]
+    let mut s = DefaultHasher::new();
+
+    scripts.iter().for_each(|script| script.hash(&mut s));
+    s.finish()
+}
+
+/// A least-recently-used cache of compiled `AST`'s, keyed on a `u64` hash of the source text
+/// together with the `Engine` settings that affect compilation.
+///
+/// The source text is kept alongside each `AST` and compared on lookup, so that a hash
+/// collision never returns the `AST` of a different script.
+///
+/// A capacity of zero disables the cache.
+#[derive(Debug, Clone, Default)]
+pub struct CompileCache {
+    /// Maximum number of `AST`'s to keep.
+    capacity: usize,
+    /// Cached `AST`'s, together with the source text they were compiled from.
+    entries: HashMap<u64, (Box<[String]>, AST), StraightHasherBuilder>,
+    /// Keys of the cached `AST`'s, from the least-recently-used to the most-recently-used.
+    order: VecDeque<u64>,
+    /// Number of cache hits.
+    hits: u64,
+    /// Number of cache misses.
+    misses: u64,
+}
+
+impl CompileCache {
+    /// Is the cache enabled?
+    #[inline(always)]
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+    /// Set the capacity of the cache, evicting the least-recently-used entries if necessary.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+
+        while self.entries.len() > capacity {
+            self.evict();
+        }
+    }
+    /// Get a clone of the `AST` cached for a list of scripts, if any.
+    pub fn get(&mut self, key: u64, scripts: &[&str]) -> Option<AST> {
+        match self.entries.get(&key) {
+            Some((text, ast)) if text.iter().map(String::as_str).eq(scripts.iter().copied()) => {
+                let ast = ast.clone();
+                self.touch(key);
+                self.hits += 1;
+                Some(ast)
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+    /// Put the `AST` of a list of scripts into the cache, evicting the least-recently-used entry
+    /// if it is full.
+    ///
+    /// An entry with the same key is replaced.
+    pub fn insert(&mut self, key: u64, scripts: &[&str], ast: AST) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let text = scripts.iter().map(|&s| s.to_string()).collect();
+
+        if self.entries.insert(key, (text, ast)).is_some() {
+            self.touch(key);
+            return;
+        }
+        if self.entries.len() > self.capacity {
+            self.evict();
+        }
+        self.order.push_back(key);
+    }
+    /// Mark an entry as the most-recently-used.
+    fn touch(&mut self, key: u64) {
+        // Recently-used entries are near the back, so search from there
+        if let Some(index) = self.order.iter().rposition(|&k| k == key) {
+            self.order.remove(index);
+        }
+        self.order.push_back(key);
+    }
+    /// Remove the least-recently-used entry.
+    fn evict(&mut self) {
+        if let Some(key) = self.order.pop_front() {
+            self.entries.remove(&key);
+        }
+    }
+    /// Remove all cached `AST`'s and reset the hit/miss counters.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+    /// Get the number of cache hits and misses.
+    #[inline(always)]
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+}
+
 /// The system immutable string type.
 ///
 /// An `ImmutableString` wraps an `Rc<String>` (or `Arc<String>` under the `sync` feature)
@@ -1,5 +1,5 @@
 #![cfg(not(feature = "no_index"))]
-use rhai::{Array, Engine, EvalAltResult, RegisterFn, INT};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Position, RegisterFn, INT};
 
 #[test]
 fn test_arrays() -> Result<(), Box<EvalAltResult>> {
@@ -8,6 +8,14 @@ fn test_arrays() -> Result<(), Box<EvalAltResult>> {
     assert_eq!(engine.eval::<INT>("let x = [1, 2, 3]; x[1]")?, 2);
     assert_eq!(engine.eval::<INT>("let x = [1, 2, 3,]; x[1]")?, 2);
     assert_eq!(engine.eval::<INT>("let y = [1, 2, 3]; y[1] = 5; y[1]")?, 5);
+    assert_eq!(engine.eval::<INT>("[1, 2, 3][-1]")?, 3);
+    assert_eq!(engine.eval::<INT>("let y = [1, 2, 3]; y[-3] = 5; y[0]")?, 5);
+    assert!(matches!(
+        *engine
+            .eval::<INT>("let y = [1, 2, 3]; y[-4]")
+            .expect_err("should error"),
+        EvalAltResult::ErrorArrayBounds(3, -4, _)
+    ));
     assert_eq!(
         engine.eval::<char>(r#"let y = [1, [ 42, 88, "93" ], 3]; y[1][2][1]"#)?,
         '3'
@@ -59,6 +67,17 @@ fn test_arrays() -> Result<(), Box<EvalAltResult>> {
         5
     );
 
+    assert_eq!(
+        engine
+            .eval::<Array>("[1, 2] * 3")?
+            .into_iter()
+            .map(|v| v.cast::<INT>())
+            .collect::<Vec<_>>(),
+        [1, 2, 1, 2, 1, 2]
+    );
+    assert!(engine.eval::<Array>("[1, 2] * 0")?.is_empty());
+    assert!(engine.eval::<Array>("[1, 2] * -1")?.is_empty());
+
     Ok(())
 }
 
@@ -234,3 +253,103 @@ fn test_arrays_map_reduce() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(not(feature = "no_object"))]
+fn test_arrays_custom_indexer() -> Result<(), Box<EvalAltResult>> {
+    #[derive(Debug, Clone)]
+    struct Grid(Vec<INT>);
+
+    let mut engine = Engine::new();
+
+    engine.register_type::<Grid>();
+    engine.register_fn("new_grid", || Grid(vec![1, 2, 3]));
+    engine.register_indexer_get_result(|grid: &mut Grid, index: INT| {
+        grid.0
+            .get(index as usize)
+            .map(|&v| v.into())
+            .ok_or_else(|| "out of range".into())
+    });
+    engine.register_indexer_set_result(|grid: &mut Grid, index: INT, value: INT| {
+        match grid.0.get_mut(index as usize) {
+            Some(v) => {
+                *v = value;
+                Ok(())
+            }
+            None => Err("out of range".into()),
+        }
+    });
+
+    assert_eq!(engine.eval::<INT>("let g = new_grid(); g[1]")?, 2);
+    assert_eq!(
+        engine.eval::<INT>("let g = new_grid(); g[1] = 42; g[1]")?,
+        42
+    );
+
+    // Errors raised by indexers carry the position of the index expression
+    let err = engine
+        .eval::<INT>("let g = new_grid();\ng[  10]")
+        .expect_err("should error");
+    assert!(matches!(*err, EvalAltResult::ErrorRuntime(_, _)));
+    assert_eq!(err.position(), Position::new(2, 5));
+
+    let err = engine
+        .eval::<()>("let g = new_grid();\ng[  10] = 42;")
+        .expect_err("should error");
+    assert!(matches!(*err, EvalAltResult::ErrorRuntime(_, _)));
+    assert_eq!(err.position(), Position::new(2, 5));
+
+    Ok(())
+}
+
+#[test]
+fn test_arrays_vec_args() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_fn("sum", |xs: Vec<INT>| xs.iter().sum::<INT>());
+    engine.register_fn("join", |xs: Vec<String>, sep: &str| xs.join(sep));
+
+    assert_eq!(engine.eval::<INT>("sum([1, 2, 3])")?, 6);
+    assert_eq!(engine.eval::<INT>("sum([])")?, 0);
+    assert_eq!(
+        engine.eval::<String>(r#"join(["a", "b", "c"], "-")"#)?,
+        "a-b-c"
+    );
+
+    assert!(matches!(
+        *engine.eval::<INT>(r#"sum([1, "x"])"#).expect_err("should error"),
+        EvalAltResult::ErrorMismatchDataType(err, typ, pos)
+            if err == "array element 1 is string" && typ == std::any::type_name::<INT>() && pos.position() == Some(1)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_arrays_typed() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let values: Vec<INT> = vec![1, 2, 3];
+    let array: Dynamic = values.iter().copied().collect();
+
+    assert!(array.is::<Array>());
+    assert_eq!(array.clone().into_typed_array::<INT>()?, values);
+
+    let array = engine.eval::<Dynamic>("[1, 2, 3]")?;
+    assert_eq!(array.into_typed_array::<INT>()?, values);
+
+    let array = engine.eval::<Dynamic>(r#"[1, "x", 3]"#)?;
+    assert!(matches!(
+        *array.into_typed_array::<INT>().expect_err("should error"),
+        EvalAltResult::ErrorMismatchDataType(err, typ, _)
+            if err == "array element 1 is string" && typ == std::any::type_name::<INT>()
+    ));
+
+    assert!(matches!(
+        *Dynamic::from(42 as INT).into_typed_array::<INT>().expect_err("should error"),
+        EvalAltResult::ErrorMismatchDataType(err, typ, _)
+            if err == std::any::type_name::<INT>() && typ == "array"
+    ));
+
+    Ok(())
+}
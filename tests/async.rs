@@ -0,0 +1,102 @@
+#![cfg(feature = "async")]
+
+use rhai::{
+    Dynamic, Engine, EvalAltResult, FnFuture, ImmutableString, RegisterAsyncFn,
+    RegisterAsyncResultFn, INT,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+/// A minimal executor that polls a future until it completes.
+fn block_on(mut fut: FnFuture) -> Result<Dynamic, Box<EvalAltResult>> {
+    let mut cx = Context::from_waker(Waker::noop());
+
+    loop {
+        if let Poll::Ready(result) = fut.as_mut().poll(&mut cx) {
+            return result;
+        }
+    }
+}
+
+/// A future that is pending for a number of polls before completing.
+struct Delay(usize);
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 == 0 {
+            Poll::Ready(())
+        } else {
+            self.0 -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[test]
+fn test_async_fn() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.on_block_on(block_on);
+
+    engine.register_async_fn("answer", || async { 42 as INT });
+    engine.register_async_fn("add", |x: INT, y: INT| async move {
+        Delay(3).await;
+        x + y
+    });
+    engine.register_async_fn("greet", |name: ImmutableString| async move {
+        Delay(1).await;
+        format!("hello, {}!", name)
+    });
+
+    assert_eq!(engine.eval::<INT>("answer()")?, 42);
+    assert_eq!(engine.eval::<INT>("add(40, 2)")?, 42);
+    assert_eq!(
+        engine.eval::<INT>("let x = 0; for i in range(0, 10) { x = add(x, i); } x")?,
+        45
+    );
+    assert_eq!(engine.eval::<String>(r#"greet("world")"#)?, "hello, world!");
+
+    Ok(())
+}
+
+#[test]
+fn test_async_result_fn() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.on_block_on(block_on);
+
+    engine.register_async_result_fn("div", |x: INT, y: INT| async move {
+        Delay(2).await;
+        if y == 0 {
+            Err("division by zero!".into())
+        } else {
+            Ok((x / y).into())
+        }
+    });
+
+    assert_eq!(engine.eval::<INT>("div(84, 2)")?, 42);
+
+    assert!(matches!(
+        *engine.eval::<INT>("let x = 1;\ndiv(x, 0)").expect_err("should error"),
+        EvalAltResult::ErrorRuntime(msg, pos)
+            if msg.as_str().unwrap() == "division by zero!" && pos.line() == Some(2)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_async_fn_no_executor() {
+    let mut engine = Engine::new();
+
+    engine.register_async_fn("answer", || async { 42 as INT });
+
+    assert!(matches!(
+        *engine.eval::<INT>("answer()").expect_err("should error"),
+        EvalAltResult::ErrorRuntime(_, _)
+    ));
+}
@@ -20,3 +20,52 @@ fn test_binary_ops() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_binary_ops_comparison_chains() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    // Without chaining, 'a < b < c' compares a boolean with a number
+    assert_eq!(engine.eval::<bool>("let x = 5; 0 < x < 10")?, false);
+
+    engine.set_allow_comparison_chains(true);
+
+    assert_eq!(engine.eval::<bool>("let x = 5; 0 < x < 10")?, true);
+    assert_eq!(engine.eval::<bool>("let x = 15; 0 < x < 10")?, false);
+    assert_eq!(engine.eval::<bool>("let x = 5; 0 <= x <= 5 < 6")?, true);
+    assert_eq!(engine.eval::<bool>("let x = 5; 1 < x > 2")?, true);
+    assert_eq!(engine.eval::<bool>("let x = 5; 1 < x > 6")?, false);
+    assert_eq!(engine.eval::<bool>("1 < 2 + 3 < 4 * 2 && 2 > 1")?, true);
+    assert_eq!(engine.eval::<bool>("(1 < 2) == (3 > 2)")?, true);
+    assert_eq!(
+        engine.eval::<bool>("let a = 1; let b = 2; let c = 3; a < b * 1 < c && c > b - 0 > a")?,
+        true
+    );
+
+    // Each operand is evaluated once, in order, and comparisons short-circuit
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                let log = 0;
+                let x = 0;
+                if 0 < { log = log * 10 + 1; x += 1; x } < { log = log * 10 + 2; 10 } {
+                    log = log * 10 + 3;
+                }
+                log
+            "#
+        )?,
+        123
+    );
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                let count = 0;
+                let x = 10 > { count += 1; 20 } > { count += 10; 0 };
+                count
+            "#
+        )?,
+        1
+    );
+
+    Ok(())
+}
@@ -142,7 +142,7 @@ fn test_fn_ptr_raw() -> Result<(), Box<EvalAltResult>> {
                 x
             "#
         ).expect_err("should error"),
-        EvalAltResult::ErrorFunctionNotFound(x, _) if x.starts_with("foo (")
+        EvalAltResult::ErrorFunctionNotFound(x, _) if x.starts_with("foo(")
     ));
 
     assert_eq!(
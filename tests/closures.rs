@@ -1,5 +1,5 @@
 #![cfg(not(feature = "no_function"))]
-use rhai::{Engine, EvalAltResult, FnPtr, ParseErrorType, RegisterFn, Scope, INT};
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, ParseErrorType, RegisterFn, Scope, INT};
 use std::any::TypeId;
 use std::cell::RefCell;
 use std::mem::take;
@@ -276,3 +276,108 @@ fn test_closures_external() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(not(feature = "no_closure"))]
+#[cfg(not(feature = "no_object"))]
+fn test_closures_scope_flatten() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+    let mut scope = Scope::new();
+
+    let ast = engine.compile("let x = 1; let f = || x;")?;
+    engine.consume_ast_with_scope(&mut scope, &ast)?;
+
+    assert!(scope.iter_raw().any(|(name, _, value)| name == "x" && value.is_shared()));
+
+    scope.flatten();
+
+    assert!(scope.iter_raw().all(|(_, _, value)| !value.is_shared()));
+
+    engine.eval_with_scope::<()>(&mut scope, "x = 42;")?;
+
+    assert_eq!(scope.get_value::<INT>("x").unwrap(), 42);
+
+    // The closure keeps its own copy of the captured variable
+    let ast = ast.clone_functions_only().merge(&engine.compile("f.call()")?);
+    assert_eq!(engine.eval_ast_with_scope::<INT>(&mut scope, &ast)?, 1);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "no_closure"))]
+fn test_closures_capture_syntax() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    assert_eq!(engine.eval::<bool>("let a = 1; let b = 2; a!=b")?, true);
+    assert_eq!(engine.eval::<bool>("let a = 1; let b = 1; a!=b")?, false);
+
+    assert_eq!(
+        engine.eval::<INT>("fn foo(y) { x + y } let x = 40; foo!(2)")?,
+        42
+    );
+
+    // Only a bare function name can be followed by '!'
+    assert!(matches!(
+        *engine
+            .compile("fn foo(y) { x + y } let x = 40; (foo)!(2)")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::MissingToken(_, _)
+    ));
+
+    #[cfg(not(feature = "no_object"))]
+    assert!(matches!(
+        *engine
+            .compile("let obj = #{ foo: 1 }; obj.foo!(2)")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::MalformedCapture(_)
+    ));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "no_closure"))]
+#[cfg(not(feature = "no_index"))]
+#[cfg(not(feature = "no_object"))]
+fn test_closures_deep_clone() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                let x = [1, 2, 3];
+                let f = || x.len();
+                let y = clone(x);
+                y.push(4);
+                f.call() + y.len() * 10
+            "#
+        )?,
+        43
+    );
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                let x = 1;
+                let f = |y| x + y;
+                let g = f.clone();
+                x = 40;
+                g.call(2) + f.call(0)
+            "#
+        )?,
+        43
+    );
+
+    let x = Dynamic::from(vec![Dynamic::from(1 as INT)]).into_shared();
+    let mut y = x.deep_clone();
+    assert!(!y.is_shared());
+    y.write_lock::<rhai::Array>()
+        .unwrap()
+        .push(Dynamic::from(2 as INT));
+    assert_eq!(x.read_lock::<rhai::Array>().unwrap().len(), 1);
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use rhai::{Engine, INT};
+use rhai::{Engine, ParseErrorType, INT};
 
 #[test]
 fn test_comments() {
@@ -12,3 +12,101 @@ fn test_comments() {
         .eval::<INT>("let /* I am a multi-line comment, yay! */ x = 5; x")
         .is_ok());
 }
+
+#[test]
+fn test_comments_unterminated() {
+    let engine = Engine::new();
+
+    let err = engine
+        .compile("let x = 5;\nlet /* I am /* nested */ and never closed\nx = 5;")
+        .expect_err("should error");
+
+    assert_eq!(
+        *err.0,
+        ParseErrorType::BadInput("Open block comment is not terminated".to_string())
+    );
+    assert_eq!(err.1.line(), Some(2));
+    assert_eq!(err.1.position(), Some(5));
+}
+
+#[test]
+#[cfg(feature = "internals")]
+#[cfg(not(feature = "no_function"))]
+#[allow(deprecated)]
+fn test_comments_doc() {
+    use rhai::Token;
+
+    let engine = Engine::new();
+
+    let script = r"
+        /// This is a doc comment
+        /// spanning two lines.
+        fn foo(x) { x + 1 }
+
+        // Not a doc comment
+        //// Not a doc comment either
+        fn bar() { /* inline */ -1 }
+
+        /** Block doc comment */
+        fn baz() { 0 }
+
+        /**/ /*** Not a doc comment */
+    ";
+
+    // Without the flag, comments are discarded
+    let tokens = engine.tokenize(script, false, false);
+    assert!(tokens.iter().all(|(token, _)| !token.is_comment()));
+
+    let tokens = engine.tokenize(script, false, true);
+
+    // Collect the doc comments preceding each function
+    let mut docs: Vec<(String, Vec<String>)> = Vec::new();
+    let mut pending = Vec::new();
+
+    for window in tokens.windows(2) {
+        match (&window[0].0, &window[1].0) {
+            (Token::DocComment(s), _) => pending.push(s.clone()),
+            (Token::Fn, Token::Identifier(name)) => {
+                docs.push((name.clone(), pending.drain(..).collect()))
+            }
+            (token, _) if token.is_comment() => (),
+            _ => pending.clear(),
+        }
+    }
+
+    assert_eq!(
+        docs,
+        vec![
+            (
+                "foo".to_string(),
+                vec![
+                    "/// This is a doc comment".to_string(),
+                    "/// spanning two lines.".to_string()
+                ]
+            ),
+            ("bar".to_string(), vec![]),
+            (
+                "baz".to_string(),
+                vec!["/** Block doc comment */".to_string()]
+            ),
+        ]
+    );
+
+    assert!(tokens.iter().any(
+        |(token, _)| matches!(token, Token::LineComment(s) if s == "//// Not a doc comment either")
+    ));
+    assert!(tokens
+        .iter()
+        .any(|(token, _)| matches!(token, Token::BlockComment(s) if s == "/* inline */")));
+    assert!(tokens
+        .iter()
+        .any(|(token, _)| matches!(token, Token::BlockComment(s) if s == "/**/")));
+    assert!(tokens.iter().any(
+        |(token, _)| matches!(token, Token::BlockComment(s) if s == "/*** Not a doc comment */")
+    ));
+
+    // Comments do not affect unary operators
+    assert!(!tokens
+        .iter()
+        .any(|(token, _)| matches!(token, Token::Minus)));
+}
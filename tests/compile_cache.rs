@@ -0,0 +1,135 @@
+use rhai::{Engine, EvalAltResult, RegisterFn, Scope, INT};
+
+#[test]
+fn test_compile_cache() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    // Disabled by default
+    engine.compile("40 + 2")?;
+    engine.compile("40 + 2")?;
+    assert_eq!(engine.compile_cache_stats(), (0, 0));
+
+    engine.enable_compile_cache(2);
+
+    let ast = engine.compile("let x = 40; x + 2")?;
+    assert_eq!(engine.compile_cache_stats(), (0, 1));
+    let ast2 = engine.compile("let x = 40; x + 2")?;
+    assert_eq!(engine.compile_cache_stats(), (1, 1));
+    assert_eq!(format!("{:?}", ast), format!("{:?}", ast2));
+    assert_eq!(engine.eval_ast::<INT>(&ast2)?, 42);
+
+    // Evaluating a script also goes through the cache
+    assert_eq!(engine.eval::<INT>("let x = 40; x + 2")?, 42);
+    assert_eq!(engine.compile_cache_stats(), (2, 1));
+
+    // Scripts compiled with a non-empty scope are not cached
+    let mut scope = Scope::new();
+    scope.push_constant("x", 42 as INT);
+    engine.compile_with_scope(&scope, "x")?;
+    engine.compile_with_scope(&scope, "x")?;
+    assert_eq!(engine.compile_cache_stats(), (2, 1));
+
+    // The least-recently-used script is evicted
+    engine.compile("1")?;
+    engine.compile("2")?;
+    engine.compile("1")?;
+    assert_eq!(engine.compile_cache_stats(), (3, 3));
+    engine.compile("let x = 40; x + 2")?;
+    assert_eq!(engine.compile_cache_stats(), (3, 4));
+
+    // Shrinking the cache keeps the most-recently-used script
+    engine.enable_compile_cache(1);
+    engine.compile("let x = 40; x + 2")?;
+    assert_eq!(engine.compile_cache_stats(), (4, 4));
+    engine.compile("1")?;
+    assert_eq!(engine.compile_cache_stats(), (4, 5));
+    engine.enable_compile_cache(2);
+
+    engine.clear_compile_cache();
+    assert_eq!(engine.compile_cache_stats(), (0, 0));
+    engine.compile("1")?;
+    assert_eq!(engine.compile_cache_stats(), (0, 1));
+
+    // Disabling the cache
+    engine.enable_compile_cache(0);
+    engine.compile("1")?;
+    assert_eq!(engine.compile_cache_stats(), (0, 1));
+
+    Ok(())
+}
+
+#[test]
+fn test_compile_cache_engine_settings() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.enable_compile_cache(10);
+
+    engine.compile("1 + 2")?;
+    engine.compile("1 + 2")?;
+    assert_eq!(engine.compile_cache_stats(), (1, 1));
+
+    #[cfg(not(feature = "no_optimize"))]
+    {
+        engine.set_optimization_level(rhai::OptimizationLevel::None);
+        let ast = engine.compile("1 + 2")?;
+        assert_eq!(engine.compile_cache_stats(), (1, 2));
+        assert!(format!("{:?}", ast).contains("FnCall"));
+    }
+
+    // Registered functions
+    #[cfg(not(feature = "no_optimize"))]
+    engine.set_optimization_level(rhai::OptimizationLevel::Simple);
+    assert_eq!(engine.eval::<INT>("40 + 2")?, 42);
+    engine.register_fn("+", |x: INT, y: INT| x * y);
+    assert_eq!(engine.eval::<INT>("40 + 2")?, 80);
+
+    // Custom operators
+    assert!(engine.compile("1 foo 2").is_err());
+    engine
+        .register_custom_operator("foo", 160)?
+        .register_fn("foo", |x: INT, y: INT| x * y);
+    assert_eq!(engine.eval::<INT>("1 foo 2")?, 2);
+
+    let (_, misses) = engine.compile_cache_stats();
+    engine.compile("1 + 2")?;
+    assert_eq!(engine.compile_cache_stats().1, misses + 1);
+
+    // Token mappers (zero-sized closures must not be mistaken for one another)
+    #[cfg(feature = "internals")]
+    #[allow(deprecated)]
+    {
+        use rhai::Token;
+
+        engine.on_parse_token(|token, _| match token {
+            Token::Identifier(s) if s == "is" => Token::EqualsTo,
+            _ => token,
+        });
+        assert!(engine.eval::<bool>("42 is 42")?);
+        engine.on_parse_token(|token, _| token);
+        assert!(engine.compile("42 is 42").is_err());
+    }
+
+    // Optimizer passes
+    #[cfg(feature = "internals")]
+    #[cfg(not(feature = "no_optimize"))]
+    #[allow(deprecated)]
+    {
+        use rhai::{Expr, OptimizationLevel};
+
+        engine.set_optimization_level(OptimizationLevel::Simple);
+        assert!(engine.eval::<INT>("answer()").is_err());
+        engine.register_optimizer_pass(|expr, context| match expr {
+            Expr::FnCall(x) if (x.0).0 == "answer" => {
+                context.set_dirty();
+                Expr::IntegerConstant(Box::new((42, (x.0).3)))
+            }
+            _ => expr,
+        });
+        assert_eq!(engine.eval::<INT>("answer()")?, 42);
+    }
+
+    // Disabled symbols
+    engine.disable_symbol("+");
+    assert!(engine.compile("1 + 2").is_err());
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use rhai::{Engine, EvalAltResult, ParseErrorType, INT};
+use rhai::{Engine, EvalAltResult, ParseErrorType, Scope, INT};
 
 #[test]
 fn test_constant() -> Result<(), Box<EvalAltResult>> {
@@ -22,6 +22,76 @@ fn test_constant() -> Result<(), Box<EvalAltResult>> {
     Ok(())
 }
 
+#[test]
+fn test_constant_scope() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let mut scope = Scope::new();
+    scope.push_constant("x", 42 as INT);
+
+    assert!(matches!(
+        *engine.compile_with_scope(&scope, "x = 1").expect_err("expects error").0,
+        ParseErrorType::AssignmentToConstant(x) if x == "x"
+    ));
+
+    #[cfg(not(feature = "no_object"))]
+    assert!(matches!(
+        *engine.compile_with_scope(&scope, "x.foo = 1").expect_err("expects error").0,
+        ParseErrorType::AssignmentToConstant(x) if x == "x"
+    ));
+
+    // Shadowing the constant makes it assignable again
+    assert_eq!(
+        engine.eval_with_scope::<INT>(&mut scope, "let x = 0; x = 1; x")?,
+        1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_constant_group() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    assert_eq!(engine.eval::<INT>("const { X = 40; Y = 2; } X + Y")?, 42);
+    assert_eq!(engine.eval::<INT>("const { X = 40; Y = 2 }; X + Y")?, 42);
+    assert_eq!(
+        engine.eval::<INT>("let r = 0; { const { X = 40; Y = 2; } r = X + Y; } r")?,
+        42
+    );
+    assert_eq!(engine.eval::<INT>("const { } 42")?, 42);
+
+    assert!(matches!(
+        *engine
+            .eval::<INT>("const { X = 40; Y = 2; } X = 42;")
+            .expect_err("expects error"),
+        EvalAltResult::ErrorParsing(ParseErrorType::AssignmentToConstant(x), _) if x == "X"
+    ));
+    assert!(matches!(
+        *engine
+            .compile("const { X = 40; let y = 2; }")
+            .expect_err("expects error")
+            .0,
+        ParseErrorType::VariableExpected
+    ));
+    assert!(matches!(
+        *engine
+            .compile("const { X = 40 Y = 2 }")
+            .expect_err("expects error")
+            .0,
+        ParseErrorType::MissingToken(_, _)
+    ));
+
+    // Constants in the group are folded by the optimizer
+    #[cfg(not(feature = "no_optimize"))]
+    {
+        let ast = engine.compile("const { X = 40; Y = 2; } X + Y")?;
+        assert!(format!("{:?}", ast).contains("IntegerConstant((42,"));
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_var_is_def() -> Result<(), Box<EvalAltResult>> {
     let engine = Engine::new();
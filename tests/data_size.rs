@@ -273,3 +273,52 @@ fn test_max_map_size() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(not(feature = "no_index"))]
+fn test_max_data_depth() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.compile("let x = [[[[[1]]]]];")?;
+
+    engine.set_max_data_depth(3);
+
+    engine.compile("let x = [[[1], [2]], [3]];")?;
+    engine.compile("let x = [[1], [[2]], fn_call([[3]])];")?;
+
+    assert_eq!(
+        *engine
+            .compile("let x = [[1], [[[2]]]];")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::DataTooDeep
+    );
+
+    #[cfg(not(feature = "no_object"))]
+    {
+        engine.compile("let x = #{a: [#{b: 1}]};")?;
+
+        assert_eq!(
+            *engine
+                .compile("let x = #{a: [#{b: [1]}]};")
+                .expect_err("should error")
+                .0,
+            ParseErrorType::DataTooDeep
+        );
+
+        engine.parse_json(r#"{"a": {"b": {"c": 1}}}"#, false)?;
+
+        assert!(matches!(
+            *engine
+                .parse_json(r#"{"a": {"b": {"c": {"d": 1}}}}"#, false)
+                .expect_err("should error"),
+            EvalAltResult::ErrorParsing(ParseErrorType::DataTooDeep, _)
+        ));
+    }
+
+    engine.set_max_data_depth(0);
+
+    engine.compile("let x = [[[[[1]]]]];")?;
+
+    Ok(())
+}
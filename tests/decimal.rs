@@ -0,0 +1,78 @@
+#![cfg(feature = "decimal")]
+use rhai::{Decimal, Engine, EvalAltResult, INT};
+use std::str::FromStr;
+
+#[test]
+fn test_decimal() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    assert_eq!(
+        engine.eval::<Decimal>("1.50d")?,
+        Decimal::from_str("1.50").unwrap()
+    );
+    assert_eq!(engine.eval::<Decimal>("-2d")?, Decimal::from(-2));
+    assert_eq!(
+        engine.eval::<Decimal>("let x = 2d; let y = -x; y")?,
+        Decimal::from(-2)
+    );
+    assert!(engine.eval::<bool>("0.1d + 0.2d == 0.3d")?);
+    assert!(engine.eval::<bool>("1.10d > 1.09d")?);
+    assert_eq!(engine.eval::<String>("type_of(1.50d)")?, "decimal");
+    assert_eq!(engine.eval::<String>("(1.50d).to_string()")?, "1.50");
+    assert_eq!(engine.eval::<String>(r#""$" + 19.99d"#)?, "$19.99");
+    assert_eq!(
+        engine.eval::<Decimal>("let x = 10d; x /= 4d; x")?,
+        Decimal::from_str("2.5").unwrap()
+    );
+
+    #[cfg(not(feature = "unchecked"))]
+    assert!(matches!(
+        *engine
+            .eval::<Decimal>("1d / 0d")
+            .expect_err("expects error"),
+        EvalAltResult::ErrorArithmetic(_, _)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_decimal_mixed() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    // Integers are promoted to decimals
+    assert_eq!(
+        engine.eval::<Decimal>("1.25d * 4")?,
+        Decimal::from_str("5.00").unwrap()
+    );
+    assert_eq!(
+        engine.eval::<Decimal>("let x = 1; x += 0.5d; x")?,
+        Decimal::from_str("1.5").unwrap()
+    );
+    assert!(engine.eval::<bool>("3 == 3.0d")?);
+
+    // Floating-point numbers must be converted explicitly
+    #[cfg(not(feature = "no_float"))]
+    {
+        assert!(matches!(
+            *engine
+                .eval::<Decimal>("1.5d + 1.5")
+                .expect_err("expects error"),
+            EvalAltResult::ErrorFunctionNotFound(_, _)
+        ));
+        assert_eq!(
+            engine.eval::<Decimal>("1.5d + to_decimal(1.5)")?,
+            Decimal::from(3)
+        );
+        assert_eq!(engine.eval::<rhai::FLOAT>("to_float(2.5d)")?, 2.5);
+    }
+
+    assert_eq!(engine.eval::<INT>("to_int(-2.75d)")?, -2);
+    assert_eq!(engine.eval::<Decimal>("to_decimal(42)")?, Decimal::from(42));
+    assert_eq!(
+        engine.eval::<Decimal>(r#"parse_decimal("0.001")"#)?,
+        Decimal::from_str("0.001").unwrap()
+    );
+
+    Ok(())
+}
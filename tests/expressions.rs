@@ -26,6 +26,27 @@ fn test_expressions() -> Result<(), Box<EvalAltResult>> {
     Ok(())
 }
 
+#[test]
+fn test_expressions_statement_blocks() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    assert!(engine
+        .eval_expression::<INT>("40 + { let t = 1; t + 1 }")
+        .is_err());
+
+    engine.set_allow_statement_expression(true);
+
+    assert_eq!(
+        engine.eval_expression::<INT>("40 + { let t = 1; t + 1 }")?,
+        42
+    );
+    assert!(engine.compile_expression("{ let t = 1; t + 1 }").is_ok());
+    assert!(engine.eval_expression::<()>("let x = 42").is_err());
+    assert_eq!(engine.eval::<INT>("let x = { let t = 1; t + 1 }; x")?, 2);
+
+    Ok(())
+}
+
 /// This example taken from https://github.com/jonathandturner/rhai/issues/115
 #[test]
 #[cfg(not(feature = "no_object"))]
@@ -1,4 +1,4 @@
-use rhai::{Engine, EvalAltResult, RegisterFn, INT};
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, RegisterFn, Scope, INT};
 
 #[test]
 fn test_fn_ptr() -> Result<(), Box<EvalAltResult>> {
@@ -110,3 +110,31 @@ fn test_fn_ptr_curry() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_fn_ptr_curry_from_rust() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_fn("foo", |x: INT, y: INT, z: INT| x * 100 + y * 10 + z);
+
+    let mut fn_ptr = FnPtr::new("foo", vec![Dynamic::from(1 as INT)])?;
+    fn_ptr.add_curry(Dynamic::from(2 as INT));
+
+    assert_eq!(fn_ptr.curry().len(), 2);
+
+    let mut scope = Scope::new();
+    scope.push("f", fn_ptr);
+
+    // Curried arguments are passed before call arguments
+    assert_eq!(
+        engine.eval_with_scope::<INT>(&mut scope, "call(f, 3)")?,
+        123
+    );
+
+    assert!(matches!(
+        *FnPtr::new("hello world", vec![]).expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(f, _) if f == "hello world"
+    ));
+
+    Ok(())
+}
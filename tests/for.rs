@@ -30,6 +30,48 @@ fn test_for_array() -> Result<(), Box<EvalAltResult>> {
     Ok(())
 }
 
+#[test]
+fn test_for_range_step() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let script = r#"
+        let s = "";
+        for i in range(FROM, TO, STEP) { s += i; s += ","; }
+        s
+    "#;
+
+    let run = |from: INT, to: INT, step: INT| {
+        engine.eval::<String>(
+            &script
+                .replace("FROM", &from.to_string())
+                .replace("TO", &to.to_string())
+                .replace("STEP", &step.to_string()),
+        )
+    };
+
+    // Ascending
+    assert_eq!(run(0, 5, 1)?, "0,1,2,3,4,");
+    assert_eq!(run(5, 0, 1)?, "");
+
+    // Step > 1
+    assert_eq!(run(0, 10, 3)?, "0,3,6,9,");
+    assert_eq!(run(1, 10, 3)?, "1,4,7,");
+
+    // Descending
+    assert_eq!(run(10, 0, -1)?, "10,9,8,7,6,5,4,3,2,1,");
+    assert_eq!(run(10, 0, -4)?, "10,6,2,");
+    assert_eq!(run(-1, -10, -3)?, "-1,-4,-7,");
+    assert_eq!(run(0, 5, -1)?, "");
+
+    // Zero step
+    assert!(matches!(
+        *run(0, 5, 0).expect_err("should error"),
+        EvalAltResult::ErrorArithmetic(_, _)
+    ));
+
+    Ok(())
+}
+
 #[test]
 fn test_for_string() -> Result<(), Box<EvalAltResult>> {
     let engine = Engine::new();
@@ -75,3 +117,61 @@ fn test_for_object() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_for_object_entries() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let script = r#"
+        let sum = 0;
+        let keys = "";
+        let map = #{a: 1, b: 2, c: 3};
+
+        for (key, value) in map {
+            keys += key;
+            sum += value;
+        }
+
+        keys.len + sum
+    "#;
+
+    assert_eq!(engine.eval::<INT>(script)?, 9);
+
+    // The single-variable form iterates through the keys
+    let script = r#"
+        let keys = "";
+        let map = #{a: 1, b: 2, c: 3};
+
+        for key in map {
+            keys += key;
+        }
+
+        keys.len
+    "#;
+
+    assert_eq!(engine.eval::<INT>(script)?, 3);
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                let x = 0;
+                for (k, v) in #{a: 1, b: 2} { if k == "a" { x = v; } }
+                x
+            "#
+        )?,
+        1
+    );
+
+    assert!(matches!(
+        *engine
+            .eval::<()>("for (k, v) in 42 {}")
+            .expect_err("should error"),
+        EvalAltResult::ErrorMismatchDataType(_, _, _)
+    ));
+
+    assert!(engine.compile("for (k v) in #{} {}").is_err());
+    assert!(engine.compile("for (k, v in #{} {}").is_err());
+
+    Ok(())
+}
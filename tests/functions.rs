@@ -1,5 +1,5 @@
 #![cfg(not(feature = "no_function"))]
-use rhai::{Engine, EvalAltResult, ParseErrorType, INT};
+use rhai::{Engine, EvalAltResult, ParseErrorType, RegisterFn, Scope, INT};
 
 #[test]
 fn test_functions() -> Result<(), Box<EvalAltResult>> {
@@ -88,7 +88,7 @@ fn test_function_pointers() -> Result<(), Box<EvalAltResult>> {
     #[cfg(not(feature = "no_object"))]
     assert!(matches!(
         *engine.eval::<INT>(r#"let f = Fn("abc"); f.call(0)"#).expect_err("should error"),
-        EvalAltResult::ErrorFunctionNotFound(f, _) if f.starts_with("abc (")
+        EvalAltResult::ErrorFunctionNotFound(f, _) if f.starts_with("abc(")
     ));
 
     #[cfg(not(feature = "no_object"))]
@@ -199,3 +199,412 @@ fn test_function_is_def() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_function_replace() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let mut ast = engine.compile(
+        r"
+            fn foo(x) { x + 1 }
+            fn bar() { 0 }
+            foo(41)
+        ",
+    )?;
+
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    ast.replace_functions(&engine.compile("fn foo(x) { x * 2 }")?);
+
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 82);
+    assert_eq!(ast.iter_functions().count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_function_find() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let ast = engine.compile(
+        r"
+            fn foo() { 0 }
+            fn foo(x) { x + 1 }
+            private fn bar(x, y) { x + y }
+        ",
+    )?;
+
+    let f = ast
+        .find_function("foo", 1, false)
+        .expect("foo/1 should exist");
+    assert_eq!(f.name.as_str(), "foo");
+    assert_eq!(f.params.len(), 1);
+    assert_eq!(f.params[0], "x");
+
+    assert_eq!(ast.find_function("foo", 0, false).unwrap().params.len(), 0);
+    assert!(ast.find_function("foo", 2, false).is_none());
+    assert!(ast.find_function("baz", 0, true).is_none());
+
+    // Private functions
+    assert!(ast.find_function("bar", 2, false).is_none());
+    assert_eq!(ast.find_function("bar", 2, true).unwrap().params.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_function_ast_hash() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let ast = engine.compile("fn foo(x) { x + 1 } fn bar() { 0 } foo(41)")?;
+
+    // Structurally equal
+    let ast2 = engine.compile("fn foo(x) { x + 1 } fn bar() { 0 } foo(41)")?;
+    assert_eq!(ast.statements_hash(), ast2.statements_hash());
+    assert_eq!(ast.functions_hash(), ast2.functions_hash());
+
+    // Only the functions changed
+    let ast2 = engine.compile("fn foo(x) { x * 2 } fn bar() { 0 } foo(41)")?;
+    assert_eq!(ast.statements_hash(), ast2.statements_hash());
+    assert_ne!(ast.functions_hash(), ast2.functions_hash());
+
+    // Only the statements changed
+    let ast2 = engine.compile("fn foo(x) { x + 1 } fn bar() { 0 } foo(42)")?;
+    assert_ne!(ast.statements_hash(), ast2.statements_hash());
+    assert_eq!(ast.functions_hash(), ast2.functions_hash());
+
+    // Access mode is significant
+    let ast2 = engine.compile("fn foo(x) { x + 1 } private fn bar() { 0 }")?;
+    assert_ne!(ast.functions_hash(), ast2.functions_hash());
+
+    assert_eq!(
+        ast.clone_functions_only().functions_hash(),
+        ast.functions_hash()
+    );
+    assert_eq!(
+        ast.clone_statements_only().statements_hash(),
+        ast.statements_hash()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_function_on_call() -> Result<(), Box<EvalAltResult>> {
+    use std::sync::{Arc, RwLock};
+
+    let calls = Arc::new(RwLock::new(Vec::new()));
+    let logger = calls.clone();
+
+    let mut engine = Engine::new();
+
+    engine.on_fn_call(move |name, num_params, enter| {
+        logger
+            .write()
+            .unwrap()
+            .push((name.to_string(), num_params, enter))
+    });
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                fn fact(n) { if n <= 1 { 1 } else { n * fact(n - 1) } }
+                fact(2)
+            "
+        )?,
+        2
+    );
+
+    assert_eq!(
+        *calls.read().unwrap(),
+        [
+            ("fact".to_string(), 1, true),
+            ("fact".to_string(), 1, true),
+            ("fact".to_string(), 1, false),
+            ("fact".to_string(), 1, false),
+        ]
+    );
+
+    calls.write().unwrap().clear();
+
+    // Exit is reported even when the function fails
+    assert!(engine
+        .eval::<INT>(r#"fn foo() { throw "oops"; } foo()"#)
+        .is_err());
+
+    assert_eq!(
+        *calls.read().unwrap(),
+        [("foo".to_string(), 0, true), ("foo".to_string(), 0, false)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_function_hash() -> Result<(), Box<EvalAltResult>> {
+    use rhai::StraightHasherBuilder;
+    use std::collections::HashMap;
+
+    let hash = Engine::calc_fn_hash("foo", 2);
+
+    assert_eq!(hash, Engine::calc_fn_hash("foo", 2));
+    assert_ne!(hash, Engine::calc_fn_hash("foo", 1));
+    assert_ne!(hash, Engine::calc_fn_hash("bar", 2));
+
+    let mut cache: HashMap<u64, INT, StraightHasherBuilder> = Default::default();
+    cache.insert(hash, 42);
+    assert_eq!(cache.get(&Engine::calc_fn_hash("foo", 2)), Some(&42));
+
+    #[cfg(not(feature = "no_module"))]
+    {
+        use rhai::Module;
+
+        let engine = Engine::new();
+        let ast = engine.compile("fn foo(x, y) { x + y }")?;
+        let module = Module::eval_ast_as_new(Default::default(), &ast, &engine)?;
+
+        assert!(module.contains_fn(hash, true));
+        assert!(!module.contains_fn(Engine::calc_fn_hash("foo", 1), true));
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "no_closure"))]
+fn test_function_global() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                let x = 40;
+                fn inc(n) { global x; x += n; }
+                inc(1);
+                inc(1);
+                x
+            "
+        )?,
+        42
+    );
+
+    assert_eq!(
+        engine.eval::<INT>("let x = 41; fn get() { global x; x + 1 } get()")?,
+        42
+    );
+
+    // Global variables are re-bound when shadowed at global level
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                let x = 1;
+                fn set(v) { global x; x = v; }
+                let x = 2;
+                set(42);
+                x
+            "
+        )?,
+        42
+    );
+
+    // Nested function calls
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                let x = 1;
+                fn foo() { global x; x += 1; bar(); }
+                fn bar() { global x; x *= 21; }
+                foo();
+                x
+            "
+        )?,
+        42
+    );
+
+    // Function scopes are isolated without `global`
+    assert!(matches!(
+        *engine.eval::<INT>("let x = 42; fn get() { x } get()").expect_err("should error"),
+        EvalAltResult::ErrorInFunctionCall(fn_name, err, _)
+            if fn_name == "get" && matches!(*err, EvalAltResult::ErrorVariableNotFound(_, _))
+    ));
+
+    // Global variable must exist
+    assert!(matches!(
+        *engine.eval::<INT>("fn get() { global x; x } get()").expect_err("should error"),
+        EvalAltResult::ErrorInFunctionCall(fn_name, err, _)
+            if fn_name == "get" && matches!(*err, EvalAltResult::ErrorVariableNotFound(ref v, _) if v == "x")
+    ));
+
+    // Constants stay constant
+    assert!(matches!(
+        *engine.eval::<INT>("const X = 42; fn set() { global X; X = 0; } set(); X").expect_err("should error"),
+        EvalAltResult::ErrorInFunctionCall(fn_name, err, _)
+            if fn_name == "set" && matches!(*err, EvalAltResult::ErrorAssignmentToConstant(_, _))
+    ));
+
+    // Only valid within functions
+    assert_eq!(
+        *engine
+            .compile("let x = 42; global x;")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::WrongGlobal
+    );
+
+    // Variables in the global scope
+    let mut scope = rhai::Scope::new();
+    scope.push("counter", 0 as INT);
+
+    engine.consume_with_scope(
+        &mut scope,
+        "fn bump() { global counter; counter += 1; } bump(); bump();",
+    )?;
+
+    assert_eq!(scope.get_value::<INT>("counter").unwrap(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_function_strict_calls() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let script = "fn foo(x) { x } foo(1, 2)";
+
+    // Not checked by default
+    assert!(engine.compile(script).is_ok());
+
+    engine.set_strict_function_calls(true);
+    engine.register_fn("bar", |x: INT, y: INT| x + y);
+
+    assert_eq!(
+        *engine.compile(script).expect_err("should error").0,
+        ParseErrorType::FnWrongArgsCount("foo".to_string(), 2)
+    );
+    assert_eq!(
+        *engine
+            .compile("fn foo(x) { bar(x) }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::FnWrongArgsCount("bar".to_string(), 1)
+    );
+    assert!(engine
+        .compile("let x = 1; if x > 0 { foo(x, x) } fn foo(x) { x }")
+        .is_err());
+
+    // Any overload with a matching number of parameters is accepted
+    assert_eq!(
+        engine.eval::<INT>(
+            "fn foo(x) { x } fn foo(x, y, z) { x + y + z } foo(1, 2, 3) + bar(1, 2)"
+        )?,
+        9
+    );
+
+    // Calls that may be resolved at run-time are not checked
+    assert!(engine.compile("fn foo(x) { x } baz(1, 2)").is_ok());
+    assert!(engine
+        .compile(r#"fn foo(x) { x } call(Fn("foo"), 1, 2)"#)
+        .is_ok());
+    #[cfg(not(feature = "no_object"))]
+    assert!(engine
+        .compile("fn foo(x) { x } let x = 1; x.foo(2)")
+        .is_ok());
+    #[cfg(not(feature = "no_object"))]
+    assert!(engine
+        .compile("fn foo(x) { x } let x = #{a: 1}; x.a.foo(2).foo(3)")
+        .is_ok());
+
+    // Arguments of method calls and indices in dot chains are checked
+    #[cfg(not(feature = "no_object"))]
+    assert!(matches!(
+        *engine
+            .compile("fn foo(x) { x } let x = 1; x.foo(foo(1, 2))")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::FnWrongArgsCount(f, 2) if f == "foo"
+    ));
+    #[cfg(not(feature = "no_object"))]
+    #[cfg(not(feature = "no_index"))]
+    assert!(matches!(
+        *engine
+            .compile("fn foo(x) { x } let x = #{a: [1]}; x.a[foo(1, 2)]")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::FnWrongArgsCount(f, 2) if f == "foo"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_function_call_stack() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let script = r#"
+        fn a() { b() }
+        fn b() {
+            throw "boom";
+        }
+        a()
+    "#;
+
+    let err = engine.eval::<()>(script).expect_err("should error");
+    assert_eq!(err.call_stack().len(), 2);
+    assert_eq!(err.call_stack()[0].0, "a > b");
+
+    engine.set_capture_call_stack(true);
+
+    let err = engine.eval::<()>(script).expect_err("should error");
+    let frames: Vec<_> = err
+        .call_stack()
+        .into_iter()
+        .map(|(name, pos)| (name.to_string(), pos.line().unwrap()))
+        .collect();
+
+    assert_eq!(
+        frames,
+        vec![
+            ("b".to_string(), 4),
+            ("a".to_string(), 2),
+            ("".to_string(), 6)
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_function_eval_with_functions() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let lib = engine.compile(
+        r#"
+            fn add(x, y) { x + y }
+            fn triple(x) { add(x, add(x, x)) }
+            fn answer() { 0 }
+            let unused = 1;
+        "#,
+    )?;
+
+    let mut scope = Scope::new();
+    scope.push("x", 14 as INT);
+
+    let ast = engine.compile("triple(x)")?;
+    assert_eq!(
+        engine.eval_ast_with_functions::<INT>(&ast, &lib, &mut scope)?,
+        42
+    );
+    assert!(engine.eval_ast_with_scope::<INT>(&mut scope, &ast).is_err());
+
+    // Functions in the statements AST take precedence
+    let ast = engine.compile("fn answer() { 42 } answer()")?;
+    assert_eq!(
+        engine.eval_ast_with_functions::<INT>(&ast, &lib, &mut scope)?,
+        42
+    );
+
+    // Statements in the functions AST are not run
+    assert!(scope.get_value::<INT>("unused").is_none());
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 #![cfg(not(feature = "no_object"))]
 
-use rhai::{Engine, EvalAltResult, ImmutableString, RegisterFn, INT};
+use rhai::{Dynamic, Engine, EvalAltResult, ImmutableString, RegisterFn, INT};
 
 #[test]
 fn test_get_set() -> Result<(), Box<EvalAltResult>> {
@@ -128,3 +128,124 @@ fn test_get_set_chain() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_get_set_result_position() -> Result<(), Box<EvalAltResult>> {
+    #[derive(Clone)]
+    struct Person {
+        age: INT,
+    }
+
+    #[derive(Clone)]
+    struct Family {
+        parent: Person,
+    }
+
+    let mut engine = Engine::new();
+
+    engine.register_type::<Person>();
+    engine.register_type::<Family>();
+    engine.register_fn("new_person", || Person { age: 1 });
+    engine.register_fn("new_family", || Family {
+        parent: Person { age: 30 },
+    });
+    engine.register_get_set(
+        "parent",
+        |f: &mut Family| f.parent.clone(),
+        |f: &mut Family, p: Person| f.parent = p,
+    );
+    engine.register_get("age", |p: &mut Person| p.age);
+    engine.register_set_result("age", |p: &mut Person, age: INT| {
+        if age < 0 {
+            Err("age cannot be negative".into())
+        } else {
+            p.age = age;
+            Ok(())
+        }
+    });
+
+    assert_eq!(
+        engine.eval::<INT>("let p = new_person(); p.age = 42; p.age")?,
+        42
+    );
+
+    let err = engine
+        .eval::<()>("let p = new_person();\np.age = -1;")
+        .expect_err("should error");
+    assert_eq!(
+        err.to_string(),
+        "Runtime error: age cannot be negative (line 2, position 7)"
+    );
+
+    let err = engine
+        .eval::<()>("let p = new_person();\np.age -= 100;")
+        .expect_err("should error");
+    assert_eq!(
+        err.to_string(),
+        "Runtime error: age cannot be negative (line 2, position 7)"
+    );
+
+    let err = engine
+        .eval::<()>("let f = new_family();\nf.parent.age = -1;")
+        .expect_err("should error");
+    assert_eq!(
+        err.to_string(),
+        "Runtime error: age cannot be negative (line 2, position 14)"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_get_set_fallback() -> Result<(), Box<EvalAltResult>> {
+    #[derive(Clone)]
+    struct Record {
+        id: INT,
+    }
+
+    let mut engine = Engine::new();
+
+    engine.register_type::<Record>();
+    engine.register_fn("new_record", || Record { id: 7 });
+    engine.register_get("id", |r: &mut Record| r.id * 100);
+    engine.register_type_getter_fallback(
+        |r: &mut Record, prop: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            match prop {
+                "name" => Ok(format!("record-{}", r.id).into()),
+                "len" => Ok((prop.len() as INT).into()),
+                _ if prop.starts_with("double_") => Ok((r.id * 2).into()),
+                _ => Err(format!("no property '{}'", prop).into()),
+            }
+        },
+    );
+
+    // Specific getters take precedence
+    assert_eq!(engine.eval::<INT>("let r = new_record(); r.id")?, 700);
+
+    assert_eq!(
+        engine.eval::<String>("let r = new_record(); r.name")?,
+        "record-7"
+    );
+    assert_eq!(engine.eval::<INT>("new_record().len")?, 3);
+    assert_eq!(
+        engine.eval::<INT>("let r = new_record(); r.double_x + r.double_y")?,
+        28
+    );
+    assert_eq!(engine.eval::<INT>("let r = new_record(); r.name.len()")?, 8);
+
+    assert!(matches!(
+        *engine.eval::<INT>("let r = new_record();\nr.missing").expect_err("should error"),
+        EvalAltResult::ErrorRuntime(msg, pos)
+            if msg.as_str().unwrap() == "no property 'missing'" && pos.line() == Some(2)
+    ));
+
+    // Other types are not affected
+    assert!(matches!(
+        *engine
+            .eval::<INT>("let x = 42; x.name")
+            .expect_err("should error"),
+        EvalAltResult::ErrorDotExpr(_, _)
+    ));
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use rhai::{Engine, EvalAltResult, INT};
+use rhai::{Engine, EvalAltResult, ParseErrorType, INT};
 
 #[test]
 fn test_if() -> Result<(), Box<EvalAltResult>> {
@@ -45,3 +45,51 @@ fn test_if_expr() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_if_strict_conditions() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    assert_eq!(engine.eval::<INT>("if true { 42 } else { 123 }")?, 42);
+
+    engine.set_strict_conditions(true);
+
+    let err = engine
+        .compile("if true { 42 } else { 123 }")
+        .expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::ConstantCondition(true));
+    assert_eq!(err.1.position(), Some(4));
+
+    assert_eq!(
+        *engine
+            .compile("let x = 1; if x > 0 { 42 } else if false { 123 }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::ConstantCondition(false)
+    );
+
+    assert_eq!(
+        *engine
+            .compile("while false { }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::ConstantCondition(false)
+    );
+
+    // 'while true' is allowed by default
+    engine.compile("while true { break; }")?;
+    assert_eq!(engine.eval::<INT>("let x = 1; if x > 0 { 42 } else { 123 }")?, 42);
+
+    engine.set_allow_while_true(false);
+
+    assert_eq!(
+        *engine
+            .compile("while true { break; }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::ConstantCondition(true)
+    );
+    engine.compile("loop { break; }")?;
+
+    Ok(())
+}
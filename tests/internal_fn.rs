@@ -1,6 +1,6 @@
 #![cfg(not(feature = "no_function"))]
 
-use rhai::{Engine, EvalAltResult, INT};
+use rhai::{Engine, EvalAltResult, ParseErrorType, Scope, INT};
 
 #[test]
 fn test_internal_fn() -> Result<(), Box<EvalAltResult>> {
@@ -61,3 +61,79 @@ fn test_internal_fn_overloading() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_internal_fn_nested() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    assert!(matches!(
+        *engine
+            .compile("fn foo(x) { fn bar(y) { y } bar(x) }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::WrongFnDefinition
+    ));
+
+    engine.set_allow_nested_functions(true);
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                fn foo(x) {
+                    fn bar(y) { y * 2 }
+                    bar(x) + 1
+                }
+                foo(20)
+            "#
+        )?,
+        41
+    );
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                fn foo(x) { x + 1 }
+                let a = {
+                    fn foo(x) { x + 100 }
+                    fn fact(n) { if n <= 1 { 1 } else { n * fact(n - 1) } }
+                    foo(fact(3))
+                };
+                a + foo(1)
+            "#
+        )?,
+        108
+    );
+
+    assert!(matches!(
+        *engine
+            .eval::<INT>("{ fn bar() { 42 } } bar()")
+            .expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(f, _) if f.starts_with("bar")
+    ));
+
+    assert!(engine
+        .eval::<INT>("let x = 1; { fn bar() { x } bar() }")
+        .is_err());
+
+    // Local functions at the same position in different scripts are kept apart
+    let ast1 = engine.compile("fn outer() { fn foo() { 1 } foo() }")?;
+    let ast2 = engine.compile("fn other() { fn foo() { 2 } foo() }")?;
+    let ast = ast1.merge(&ast2);
+
+    let mut scope = Scope::new();
+
+    assert_eq!(engine.call_fn::<_, INT>(&mut scope, &ast, "outer", ())?, 1);
+    assert_eq!(engine.call_fn::<_, INT>(&mut scope, &ast, "other", ())?, 2);
+    assert!(ast.find_function("foo", 0, true).is_none());
+    assert_eq!(ast.iter_functions().count(), 4);
+
+    // Compiling the same script always produces the same local functions
+    let script = "fn outer() { fn foo() { 1 } foo() } { fn bar() { 2 } bar() }";
+    let ast1 = engine.compile(script)?;
+    let ast2 = engine.compile(script)?;
+
+    assert_eq!(ast1.functions_hash(), ast2.functions_hash());
+    assert_eq!(ast1.statements_hash(), ast2.statements_hash());
+
+    Ok(())
+}
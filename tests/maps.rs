@@ -101,6 +101,73 @@ fn test_map_assign() -> Result<(), Box<EvalAltResult>> {
     Ok(())
 }
 
+#[test]
+fn test_map_computed_keys() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let x = engine.eval::<Map>(
+        r#"
+            let k = "hello";
+            #{a: 1, (k): 2, (k + "!"): 3, (40 + 2): 4, ('x'): 5}
+        "#,
+    )?;
+
+    assert_eq!(x.len(), 5);
+    assert_eq!(x["a"].clone().cast::<INT>(), 1);
+    assert_eq!(x["hello"].clone().cast::<INT>(), 2);
+    assert_eq!(x["hello!"].clone().cast::<INT>(), 3);
+    assert_eq!(x["42"].clone().cast::<INT>(), 4);
+    assert_eq!(x["x"].clone().cast::<INT>(), 5);
+
+    // Colliding computed keys overwrite earlier values
+    assert_eq!(
+        engine.eval::<INT>(r#"let k = "a"; let x = #{a: 1, (k): 2}; x.a"#)?,
+        2
+    );
+    assert_eq!(
+        engine.eval::<INT>(r#"let k = "a"; let x = #{(k): 1, a: 2}; x.a + x.len()"#)?,
+        3
+    );
+
+    // Literal keys are still checked for duplicates
+    assert!(matches!(
+        *engine
+            .compile(r#"let k = "b"; #{a: 1, (k): 2, a: 3}"#)
+            .expect_err("should error")
+            .0,
+        ParseErrorType::DuplicatedProperty(p) if p == "a"
+    ));
+
+    assert!(matches!(
+        *engine
+            .compile("#{(1 + 2 3): 1}")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::MissingToken(_, _)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_map_reserved_keys() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let x = engine.eval::<Map>("#{var: 1, print: 2, this: 3, static: 4}")?;
+
+    assert_eq!(x.len(), 4);
+    assert_eq!(x["var"].clone().cast::<INT>(), 1);
+    assert_eq!(x["print"].clone().cast::<INT>(), 2);
+    assert_eq!(x["this"].clone().cast::<INT>(), 3);
+    #[cfg(not(feature = "no_index"))]
+    assert_eq!(
+        engine.eval::<INT>(r#"let x = #{var: 40, static: 2}; x["var"] + x["static"]"#)?,
+        42
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_map_return() -> Result<(), Box<EvalAltResult>> {
     let engine = Engine::new();
@@ -190,6 +257,44 @@ fn test_map_json() -> Result<(), Box<EvalAltResult>> {
             if token == "{" && pos.position() == Some(4)
     ));
 
+    let json = r#"
+        {
+            "a": { "b": { "c": 42 }, "d": null },   // nested objects
+            /* comment */ "e": true
+        }
+    "#;
+
+    let map = engine.parse_json(json, true)?;
+
+    let a = map["a"].read_lock::<Map>().unwrap();
+    let b = a["b"].read_lock::<Map>().unwrap();
+    assert_eq!(b["c"].clone().cast::<INT>(), 42);
+    assert_eq!(a["d"].clone().cast::<()>(), ());
+    assert_eq!(map["e"].clone().cast::<bool>(), true);
+
+    #[cfg(not(feature = "no_index"))]
+    {
+        let map = engine.parse_json(r#"{ "e": [1, { "f": true }, null] }"#, false)?;
+
+        let e = map["e"].read_lock::<rhai::Array>().unwrap();
+        assert_eq!(e.len(), 3);
+        assert_eq!(e[0].clone().cast::<INT>(), 1);
+        assert!(e[1].read_lock::<Map>().unwrap()["f"].clone().cast::<bool>());
+        assert_eq!(e[2].clone().cast::<()>(), ());
+    }
+
+    assert!(matches!(
+        *engine.parse_json(json, false).expect_err("should error"),
+        EvalAltResult::ErrorParsing(ParseErrorType::BadInput(_), pos)
+            if pos.line() == Some(3) && pos.position() == Some(53)
+    ));
+
+    assert!(matches!(
+        *engine.parse_json("{\n  \"a\": 1,\n  \"b\" 2\n}", false).expect_err("should error"),
+        EvalAltResult::ErrorParsing(ParseErrorType::MissingToken(token, _), pos)
+            if token == ":" && pos.line() == Some(3) && pos.position() == Some(7)
+    ));
+
     Ok(())
 }
 
@@ -119,3 +119,43 @@ fn test_math_parse() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(not(feature = "unchecked"))]
+fn test_math_division_by_zero() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    for script in &[
+        "10 / 0",
+        "let x = 10; x / 0",
+        "let x = 10; x /= 0; x",
+        "10 % 0",
+        "let x = 10; x % 0",
+        "let x = 10; x %= 0; x",
+    ] {
+        let (msg, pos) = match *engine.eval::<INT>(script).expect_err("should error") {
+            EvalAltResult::ErrorArithmetic(msg, pos) => (msg, pos),
+            err => panic!("wrong error: {}", err),
+        };
+
+        // The error is reported at the position of the operator
+        let op = if script.contains('/') { '/' } else { '%' };
+        assert_eq!(pos.position(), Some(script.rfind(op).unwrap() + 1));
+        assert!(msg.to_lowercase().contains("division by zero"), "{}", msg);
+    }
+
+    // Division by zero is never folded into a compile-time error
+    #[cfg(not(feature = "no_optimize"))]
+    {
+        use rhai::OptimizationLevel;
+
+        engine.set_optimization_level(OptimizationLevel::Simple);
+        let ast = engine.compile("10 / 0")?;
+        assert!(matches!(
+            *engine.eval_ast::<INT>(&ast).expect_err("should error"),
+            EvalAltResult::ErrorArithmetic(_, _)
+        ));
+    }
+
+    Ok(())
+}
@@ -2,6 +2,9 @@
 
 use rhai::{Engine, EvalAltResult, RegisterFn, INT};
 
+#[cfg(not(feature = "no_index"))]
+use rhai::{Array, Dynamic};
+
 #[test]
 fn test_method_call() -> Result<(), Box<EvalAltResult>> {
     #[derive(Debug, Clone, Eq, PartialEq)]
@@ -47,3 +50,114 @@ fn test_method_call_style() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(not(feature = "no_index"))]
+fn test_method_call_after_index() -> Result<(), Box<EvalAltResult>> {
+    #[derive(Debug, Clone)]
+    struct TestStruct {
+        values: Vec<INT>,
+    }
+
+    impl TestStruct {
+        fn foo(&mut self) -> Self {
+            self.clone()
+        }
+
+        fn get_bar(&mut self) -> Array {
+            self.values.iter().cloned().map(Dynamic::from).collect()
+        }
+
+        fn new() -> Self {
+            Self {
+                values: vec![1, 2, 3],
+            }
+        }
+    }
+
+    let mut engine = Engine::new();
+
+    engine
+        .register_type::<TestStruct>()
+        .register_fn("foo", TestStruct::foo)
+        .register_fn("bar", TestStruct::get_bar)
+        .register_get("bar", TestStruct::get_bar)
+        .register_fn("new_ts", TestStruct::new);
+
+    assert_eq!(
+        engine.eval::<INT>("let a = [new_ts()]; a[0].foo().bar[1]")?,
+        2
+    );
+    assert_eq!(engine.eval::<INT>("let a = [new_ts()]; a[0].bar()[2]")?, 3);
+    assert_eq!(
+        engine.eval::<INT>("let a = [new_ts()]; a[0].foo().bar().len()")?,
+        3
+    );
+    assert_eq!(
+        engine.eval::<INT>("let a = [[new_ts()]]; a[0][0].foo().bar[1] + 1")?,
+        3
+    );
+    assert_eq!(
+        engine.eval::<INT>("let a = [new_ts()]; let i = [0]; a[i[0]].foo().bar[2]")?,
+        3
+    );
+    assert_eq!(
+        engine.eval::<INT>("let a = [new_ts()]; let m = #{ i: 0 }; a[m.i].bar()[0]")?,
+        1
+    );
+    assert_eq!(
+        engine.eval::<INT>("let a = [new_ts()]; a[0].foo().bar[1].abs()")?,
+        2
+    );
+
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "no_function"))]
+fn test_method_call_fallback_to_free_fn() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let script = r"
+        fn add(x, y) { x += y; x }
+        let x = 40;
+        x.add(2)
+    ";
+
+    assert!(matches!(
+        *engine.eval::<INT>(script).expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(_, _)
+    ));
+
+    engine.set_fallback_to_free_fn(true);
+
+    assert_eq!(engine.eval::<INT>(script)?, 42);
+
+    // The object is passed by value
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                fn add(x, y) { x += y; x }
+                let x = 40;
+                x.add(2);
+                x
+            "
+        )?,
+        40
+    );
+
+    // Methods take precedence
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                fn add(x, y) { x + y }
+                fn add(y) { this * y }
+                let x = 40;
+                x.add(2)
+            "
+        )?,
+        80
+    );
+
+    Ok(())
+}
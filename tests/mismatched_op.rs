@@ -1,4 +1,4 @@
-use rhai::{Engine, EvalAltResult, RegisterFn, INT};
+use rhai::{Dynamic, Engine, EvalAltResult, RegisterFn, INT};
 
 #[test]
 fn test_mismatched_op() {
@@ -8,6 +8,17 @@ fn test_mismatched_op() {
         *engine.eval::<INT>(r#""hello, " + "world!""#).expect_err("expects error"),
         EvalAltResult::ErrorMismatchOutputType(need, actual, _) if need == std::any::type_name::<INT>() && actual == "string"
     ));
+
+    assert_eq!(
+        engine
+            .eval::<INT>(r#""hi""#)
+            .expect_err("expects error")
+            .to_string(),
+        format!(
+            "Output type is incorrect: string (expecting {})",
+            std::any::type_name::<INT>()
+        )
+    );
 }
 
 #[test]
@@ -32,7 +43,7 @@ fn test_mismatched_op_custom_type() {
 
     assert!(matches!(
         *engine.eval::<INT>("60 + new_ts()").expect_err("should error"),
-        EvalAltResult::ErrorFunctionNotFound(err, _) if err == format!("+ ({}, TestStruct)", std::any::type_name::<INT>())
+        EvalAltResult::ErrorFunctionNotFound(err, _) if err == format!("+({}, TestStruct)", std::any::type_name::<INT>())
     ));
 
     assert!(matches!(
@@ -40,4 +51,70 @@ fn test_mismatched_op_custom_type() {
         EvalAltResult::ErrorMismatchOutputType(need, actual, _)
             if need == "TestStruct" && actual == std::any::type_name::<INT>()
     ));
+
+    assert_eq!(
+        engine
+            .eval::<INT>("new_ts()")
+            .expect_err("should error")
+            .to_string(),
+        format!(
+            "Output type is incorrect: TestStruct (expecting {})",
+            std::any::type_name::<INT>()
+        )
+    );
+}
+
+#[test]
+#[cfg(not(feature = "no_object"))]
+fn test_mismatched_op_undefined_operator() -> Result<(), Box<EvalAltResult>> {
+    #[derive(Debug, Clone)]
+    struct TestStruct {
+        x: INT,
+    }
+
+    let mut engine = Engine::new();
+
+    engine
+        .register_type_with_name::<TestStruct>("TestStruct")
+        .register_fn("new_ts", |x: INT| TestStruct { x })
+        .register_get("x", |ts: &mut TestStruct| ts.x);
+
+    engine.on_undefined_operator(|op, x, y| {
+        let (x, y) = match (x.read_lock::<TestStruct>(), y.read_lock::<TestStruct>()) {
+            (Some(x), Some(y)) => (x.x, y.x),
+            _ => return Ok(None),
+        };
+
+        Ok(match op {
+            "+" => Some(Dynamic::from(TestStruct { x: x + y })),
+            "-" => Some(Dynamic::from(TestStruct { x: x - y })),
+            "==" => Some((x == y).into()),
+            "dot" => Some((x * y).into()),
+            _ => None,
+        })
+    });
+
+    assert_eq!(engine.eval::<INT>("(new_ts(40) + new_ts(2)).x")?, 42);
+    assert_eq!(engine.eval::<INT>("let a = new_ts(50); a -= new_ts(8); a.x")?, 42);
+    assert_eq!(engine.eval::<bool>("new_ts(42) == new_ts(42)")?, true);
+
+    // Built-in operators are unchanged
+    assert_eq!(engine.eval::<INT>("40 + 2")?, 42);
+    assert_eq!(engine.eval::<bool>("true == 42")?, false);
+
+    // Custom operators
+    engine.register_custom_operator("dot", 160)?;
+    assert_eq!(engine.eval::<INT>("new_ts(6) dot new_ts(7)")?, 42);
+
+    // Falling through to the standard error
+    assert!(matches!(
+        *engine.eval::<INT>("new_ts(6) * new_ts(7)").expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(err, _) if err == "*(TestStruct, TestStruct)"
+    ));
+    assert!(matches!(
+        *engine.eval::<INT>("60 + new_ts(1)").expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(err, _) if err == format!("+({}, TestStruct)", std::any::type_name::<INT>())
+    ));
+
+    Ok(())
 }
@@ -1,7 +1,7 @@
 #![cfg(not(feature = "no_module"))]
 use rhai::{
-    module_resolvers::StaticModuleResolver, Dynamic, Engine, EvalAltResult, ImmutableString,
-    Module, ParseError, ParseErrorType, Scope, INT,
+    module_resolvers::StaticModuleResolver, Dynamic, Engine, EvalAltResult, FnAccess,
+    ImmutableString, Module, ParseError, ParseErrorType, Scope, INT,
 };
 
 #[test]
@@ -39,6 +39,21 @@ fn test_module_sub_module() -> Result<(), Box<EvalAltResult>> {
 
     assert_eq!(m2.get_var_value::<INT>("answer").unwrap(), 41);
 
+    let mut vars: Vec<_> = module.iter_var().map(|(name, _)| name).collect();
+    vars.sort();
+    assert_eq!(vars, vec!["MYSTIC_NUMBER"]);
+
+    let sub_modules: Vec<_> = module.iter_sub_module().map(|(name, _)| name).collect();
+    assert_eq!(sub_modules, vec!["life"]);
+
+    let (_, m) = m.iter_sub_module().next().unwrap();
+    let vars: Vec<_> = m
+        .iter_var()
+        .map(|(name, value)| (name, value.as_int().unwrap()))
+        .collect();
+    assert_eq!(vars, vec![("answer", 41)]);
+    assert_eq!(m.iter_sub_module().count(), 0);
+
     let mut resolver = StaticModuleResolver::new();
     resolver.insert("question", module);
 
@@ -176,6 +191,17 @@ fn test_module_resolver() -> Result<(), Box<EvalAltResult>> {
             EvalAltResult::ErrorTooManyModules(_)
         ));
 
+        // The module count is reset for each evaluation
+        for _ in 0..3 {
+            engine.eval::<()>(
+                r#"
+                    for x in range(0, 4) {
+                        import "hello" as h;
+                    }
+                "#,
+            )?;
+        }
+
         #[cfg(not(feature = "no_function"))]
         assert!(matches!(
             *engine
@@ -218,6 +244,117 @@ fn test_module_resolver() -> Result<(), Box<EvalAltResult>> {
     Ok(())
 }
 
+#[test]
+fn test_module_variadic_fn() -> Result<(), Box<EvalAltResult>> {
+    let mut module = Module::new();
+    module.set_variadic_fn("sum", 2, 5, |args| {
+        args.iter()
+            .map(|v| v.as_int())
+            .sum::<Result<INT, _>>()
+            .map_err(|typ| format!("cannot sum {}", typ).into())
+    });
+    // Exact-arity functions are matched first
+    module.set_fn_2("sum", |x: INT, y: INT| Ok(x * 1000 + y));
+
+    assert!(module.contains_variadic_fn("sum", 5));
+    assert!(!module.contains_variadic_fn("sum", 6));
+
+    let mut engine = Engine::new();
+    engine.load_package(module.clone());
+
+    assert_eq!(engine.eval::<INT>("sum(1, 2, 3, 4, 5)")?, 15);
+    assert_eq!(engine.eval::<INT>("sum(1, 2, 3)")?, 6);
+    assert_eq!(engine.eval::<INT>("sum(1, 2)")?, 1002);
+    #[cfg(not(feature = "no_object"))]
+    {
+        assert_eq!(engine.eval::<INT>("let x = 40; x.sum(1, 1)")?, 42);
+        assert_eq!(engine.eval::<INT>("let x = 40; x.sum(1, 1); x")?, 40);
+    }
+    assert!(matches!(
+        *engine.eval::<INT>("sum(1, 2, 3, 4, 5, 6)").expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(f, _) if f.starts_with("sum(")
+    ));
+    assert!(matches!(
+        *engine.eval::<INT>("sum(1)").expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(f, _) if f.starts_with("sum(")
+    ));
+    assert!(matches!(
+        *engine
+            .eval::<INT>(r#"sum(1, "x", 3)"#)
+            .expect_err("should error"),
+        EvalAltResult::ErrorRuntime(_, _)
+    ));
+
+    // Module-qualified calls
+    let mut parent = Module::new();
+    parent.set_sub_module("math", module);
+
+    let mut resolver = StaticModuleResolver::new();
+    resolver.insert("hello", parent);
+
+    engine.set_module_resolver(Some(resolver));
+
+    assert_eq!(
+        engine.eval::<INT>(r#"import "hello" as h; h::math::sum(1, 2, 3, 4, 5)"#)?,
+        15
+    );
+    assert_eq!(
+        engine.eval::<INT>(r#"import "hello" as h; h::math::sum(1, 2)"#)?,
+        1002
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_module_register_static() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let mut module = Module::new();
+    module.set_var("answer", 42 as INT);
+    engine.register_static_module("hello", module);
+
+    let mut module = Module::new();
+    module.set_var("answer", 1 as INT);
+    engine.register_static_module("world", module);
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                import "hello" as h;
+                import "world" as w;
+                h::answer + w::answer
+            "#
+        )?,
+        43
+    );
+
+    // Registered modules are consulted before the module resolver
+    let mut resolver = StaticModuleResolver::new();
+    let mut module = Module::new();
+    module.set_var("answer", 0 as INT);
+    resolver.insert("hello", module);
+    let mut module = Module::new();
+    module.set_var("answer", 123 as INT);
+    resolver.insert("other", module);
+    engine.set_module_resolver(Some(resolver));
+
+    assert_eq!(engine.eval::<INT>(r#"import "hello" as h; h::answer"#)?, 42);
+    assert_eq!(
+        engine.eval::<INT>(r#"import "other" as o; o::answer"#)?,
+        123
+    );
+
+    // Registering under the same path replaces the module
+    let mut module = Module::new();
+    module.set_var("answer", 2 as INT);
+    engine.register_static_module("hello", module);
+
+    assert_eq!(engine.eval::<INT>(r#"import "hello" as h; h::answer"#)?, 2);
+
+    Ok(())
+}
+
 #[test]
 #[cfg(not(feature = "no_function"))]
 fn test_module_from_ast() -> Result<(), Box<EvalAltResult>> {
@@ -300,7 +437,30 @@ fn test_module_from_ast() -> Result<(), Box<EvalAltResult>> {
         *engine
             .consume(r#"import "testing" as ttt; ttt::hidden()"#)
             .expect_err("should error"),
-        EvalAltResult::ErrorFunctionNotFound(fn_name, _) if fn_name == "ttt::hidden ()"
+        EvalAltResult::ErrorFunctionNotFound(fn_name, _) if fn_name == "ttt::hidden()"
+    ));
+    assert_eq!(
+        engine
+            .consume(r#"import "testing" as ttt; ttt::hidden(42, "x")"#)
+            .expect_err("should error")
+            .to_string(),
+        format!(
+            "Function not found: 'ttt::hidden({}, &str | ImmutableString | String)' (line 1, position 31)",
+            std::any::type_name::<INT>()
+        )
+    );
+    assert!(matches!(
+        *engine
+            .consume(r#"import "testing" as ttt; ttt::extra::missing(1)"#)
+            .expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(fn_name, _)
+            if fn_name == format!("ttt::extra::missing({})", std::any::type_name::<INT>())
+    ));
+    assert!(matches!(
+        *engine
+            .consume(r#"import "testing" as t2; t2::extra::missing()"#)
+            .expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(fn_name, _) if fn_name == "t2::extra::missing()"
     ));
 
     Ok(())
@@ -432,3 +592,208 @@ fn test_module_ast_namespace2() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_module_add_script_fn() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let mut module = Module::new();
+    let hash = module.add_script_fn("add", FnAccess::Public, &["x", "y"], "x + y", &engine)?;
+    assert!(module.contains_fn(hash, true));
+
+    module.add_script_fn("secret", FnAccess::Private, &[], "42", &engine)?;
+    assert!(module.get_script_fn("secret", 0, true).is_none());
+    assert!(module.get_script_fn("secret", 0, false).is_some());
+
+    #[cfg(not(feature = "no_closure"))]
+    module.add_script_fn(
+        "add_with_closure",
+        FnAccess::Public,
+        &["x"],
+        "let f = |y| x + y; call(f, 2)",
+        &engine,
+    )?;
+
+    let mut resolver = StaticModuleResolver::new();
+    resolver.insert("hello", module);
+    engine.set_module_resolver(Some(resolver));
+
+    assert_eq!(
+        engine.eval::<INT>(r#"import "hello" as h; h::add(40, 2)"#)?,
+        42
+    );
+    assert!(matches!(
+        *engine
+            .eval::<INT>(r#"import "hello" as h; h::secret()"#)
+            .expect_err("should error"),
+        EvalAltResult::ErrorFunctionNotFound(fn_name, _) if fn_name.starts_with("h::secret")
+    ));
+
+    #[cfg(not(feature = "no_closure"))]
+    assert_eq!(
+        engine.eval::<INT>(r#"import "hello" as h; h::add_with_closure(40)"#)?,
+        42
+    );
+
+    // The name, parameters and body are parsed just as in a script
+    let mut module = Module::new();
+
+    assert_eq!(
+        *module
+            .add_script_fn("add(x)", FnAccess::Public, &[], "x", &engine)
+            .expect_err("should error")
+            .0,
+        ParseErrorType::FnMissingName
+    );
+    assert_eq!(
+        *module
+            .add_script_fn("add", FnAccess::Public, &["x", "x"], "x", &engine)
+            .expect_err("should error")
+            .0,
+        ParseErrorType::FnDuplicatedParam("add".into(), "x".into())
+    );
+    assert!(matches!(
+        module
+            .add_script_fn("add", FnAccess::Public, &["x"], "let y = ;", &engine)
+            .expect_err("should error"),
+        ParseError(err, pos) if matches!(*err, ParseErrorType::BadInput(_)) && pos.position() == Some(9)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_module_import_computed_path() -> Result<(), Box<EvalAltResult>> {
+    let mut resolver = StaticModuleResolver::new();
+
+    for (path, answer) in [("mods/foo", 1 as INT), ("mods/bar", 41)].iter() {
+        let mut module = Module::new();
+        module.set_var("answer", *answer);
+        resolver.insert(*path, module);
+    }
+
+    let mut engine = Engine::new();
+    engine.set_module_resolver(Some(resolver));
+
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                let sum = 0;
+                let name = "foo";
+
+                for i in range(0, 2) {
+                    import ("mods/" + name) as m;
+                    sum += m::answer;
+                    name = "bar";
+                }
+
+                sum
+            "#
+        )?,
+        42
+    );
+
+    assert!(matches!(
+        *engine
+            .eval::<()>(
+                r#"
+                    let name = "baz";
+                    import "mods/" + name as m;
+                "#
+            )
+            .expect_err("should error"),
+        EvalAltResult::ErrorModuleNotFound(path, pos) if path == "mods/baz" && pos.line() == Some(3)
+    ));
+
+    let err = engine
+        .eval::<()>(
+            r#"
+                let name = 42;
+                import name as m;
+            "#,
+        )
+        .expect_err("should error");
+
+    assert!(matches!(
+        *err,
+        EvalAltResult::ErrorMismatchDataType(ref actual, ref expected, pos)
+            if actual == std::any::type_name::<INT>() && expected == "string" && pos.position() == Some(24)
+    ));
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Data type is incorrect: {} (expecting string) (line 3, position 24)",
+            std::any::type_name::<INT>()
+        )
+    );
+
+    #[cfg(not(feature = "unchecked"))]
+    {
+        engine.set_max_modules(1);
+
+        assert!(matches!(
+            *engine
+                .eval::<()>(
+                    r#"
+                        let name = "foo";
+                        for i in range(0, 2) {
+                            import "mods/" + name;
+                            name = "bar";
+                        }
+                    "#
+                )
+                .expect_err("should error"),
+            EvalAltResult::ErrorTooManyModules(pos) if pos.line() == Some(4)
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_module_import_not_retained() -> Result<(), Box<EvalAltResult>> {
+    let mut resolver = StaticModuleResolver::new();
+
+    let mut module = Module::new();
+    module.set_var("answer", 42 as INT);
+    resolver.insert("hello", module);
+
+    let mut engine = Engine::new();
+    engine.set_module_resolver(Some(resolver));
+
+    let mut scope = Scope::new();
+
+    assert_eq!(
+        engine.eval_with_scope::<INT>(&mut scope, r#"import "hello" as h; h::answer"#)?,
+        42
+    );
+
+    assert!(matches!(
+        *engine
+            .eval_with_scope::<INT>(&mut scope, "h::answer")
+            .expect_err("should error"),
+        EvalAltResult::ErrorModuleNotFound(name, _) if name == "h"
+    ));
+
+    engine.consume_with_scope(&mut scope, r#"import "hello" as h;"#)?;
+
+    assert!(matches!(
+        *engine
+            .eval_with_scope::<INT>(&mut scope, "h::answer")
+            .expect_err("should error"),
+        EvalAltResult::ErrorModuleNotFound(name, _) if name == "h"
+    ));
+
+    let ast = engine.compile(r#"import "hello" as h;"#)?;
+    engine.consume_ast_with_scope(&mut scope, &ast)?;
+
+    assert!(matches!(
+        *engine
+            .eval_with_scope::<INT>(&mut scope, "h::answer")
+            .expect_err("should error"),
+        EvalAltResult::ErrorModuleNotFound(name, _) if name == "h"
+    ));
+
+    Ok(())
+}
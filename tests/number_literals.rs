@@ -54,3 +54,36 @@ fn test_binary_literal() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_number_literal_suffix() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    #[cfg(not(feature = "only_i32"))]
+    {
+        assert_eq!(engine.eval::<INT>("42i64")?, 42);
+        assert_eq!(engine.eval::<INT>("1_000_i64")?, 1000);
+        assert_eq!(engine.eval::<INT>("0xffi64")?, 255);
+        assert_eq!(engine.eval::<INT>("0b1010i64 + 1")?, 11);
+        assert!(engine.compile("42i32").is_err());
+    }
+
+    #[cfg(feature = "only_i32")]
+    {
+        assert_eq!(engine.eval::<INT>("42i32")?, 42);
+        assert_eq!(engine.eval::<INT>("0xffi32")?, 255);
+        assert!(engine.compile("42i64").is_err());
+    }
+
+    #[cfg(not(feature = "no_float"))]
+    {
+        assert_eq!(engine.eval::<rhai::FLOAT>("1.5f64")?, 1.5);
+        assert_eq!(engine.eval::<rhai::FLOAT>("2f64")?, 2.0);
+        assert!(engine.compile("1.5f32").is_err());
+        assert!(engine.compile("1.5i64").is_err());
+    }
+
+    assert!(engine.compile("42i8").is_err());
+
+    Ok(())
+}
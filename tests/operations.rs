@@ -1,5 +1,5 @@
 #![cfg(not(feature = "unchecked"))]
-use rhai::{Engine, EvalAltResult};
+use rhai::{Engine, EvalAltResult, Scope, INT};
 
 #[test]
 fn test_max_operations() -> Result<(), Box<EvalAltResult>> {
@@ -128,3 +128,98 @@ fn test_max_operations_progress() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(not(feature = "no_function"))]
+fn test_max_operations_call_fn() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    let mut scope = Scope::new();
+
+    let ast = engine.compile(
+        r"
+            fn count(n) { let x = 0; while x < n { x += 1; } x }
+        ",
+    )?;
+
+    assert_eq!(
+        engine.call_fn_with_max_operations::<_, INT>(
+            &mut scope,
+            &ast,
+            "count",
+            500,
+            (10 as INT,)
+        )?,
+        10
+    );
+
+    assert!(matches!(
+        *engine
+            .call_fn_with_max_operations::<_, INT>(&mut scope, &ast, "count", 500, (1000 as INT,))
+            .expect_err("should error"),
+        EvalAltResult::ErrorTooManyOperations(_)
+    ));
+
+    // The global limit still applies
+    engine.set_max_operations(100);
+
+    assert!(matches!(
+        *engine
+            .call_fn_with_max_operations::<_, INT>(&mut scope, &ast, "count", 0, (1000 as INT,))
+            .expect_err("should error"),
+        EvalAltResult::ErrorTooManyOperations(_)
+    ));
+
+    engine.set_max_operations(0);
+
+    assert_eq!(
+        engine
+            .call_fn_dynamic_with_max_operations(
+                &mut scope,
+                &ast,
+                "count",
+                None,
+                0,
+                [(1000 as INT).into()]
+            )?
+            .cast::<INT>(),
+        1000
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_max_operations_reset() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_max_operations(500);
+
+    let script = "let x = 0; while x < 60 { x += 1; }";
+
+    // Each evaluation nearly exhausts the budget
+    assert!(engine
+        .eval::<()>("let x = 0; while x < 80 { x += 1; }")
+        .is_err());
+
+    for _ in 0..5 {
+        engine.eval::<()>(script)?;
+    }
+
+    let ast = engine.compile(script)?;
+
+    for _ in 0..5 {
+        engine.consume_ast(&ast)?;
+    }
+
+    #[cfg(not(feature = "no_function"))]
+    {
+        engine.set_max_call_levels(10);
+
+        let ast = engine.compile("fn f(n) { if n > 0 { f(n - 1) } } f(8);")?;
+
+        for _ in 0..5 {
+            engine.consume_ast(&ast)?;
+        }
+    }
+
+    Ok(())
+}
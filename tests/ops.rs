@@ -1,4 +1,4 @@
-use rhai::{Engine, EvalAltResult, INT};
+use rhai::{Engine, EvalAltResult, RegisterFn, INT};
 
 #[test]
 fn test_ops() -> Result<(), Box<EvalAltResult>> {
@@ -21,3 +21,26 @@ fn test_op_precedence() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_ops_commutative() -> Result<(), Box<EvalAltResult>> {
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    struct Money(INT);
+
+    let mut engine = Engine::new();
+
+    engine
+        .register_fn("money", Money)
+        .register_fn("value", |m: &mut Money| m.0)
+        .register_commutative_fn("+", |m: Money, x: INT| Money(m.0 + x))
+        .register_commutative_fn("-", |x: INT, y: INT| x * 100 + y);
+
+    assert_eq!(engine.eval::<Money>("let x = money(40); x + 2")?, Money(42));
+    assert_eq!(engine.eval::<Money>("let x = money(40); 2 + x")?, Money(42));
+    assert_eq!(engine.eval::<INT>("value(1 + money(41))")?, 42);
+
+    // Arguments of the same type are not swapped
+    assert_eq!(engine.eval::<INT>("let x = 4; x - 2")?, 402);
+
+    Ok(())
+}
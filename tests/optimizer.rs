@@ -1,6 +1,6 @@
 #![cfg(not(feature = "no_optimize"))]
 
-use rhai::{Engine, EvalAltResult, OptimizationLevel, RegisterFn, INT};
+use rhai::{Engine, EvalAltResult, ImmutableString, OptimizationLevel, RegisterFn, Scope, INT};
 
 #[test]
 fn test_optimizer_run() -> Result<(), Box<EvalAltResult>> {
@@ -65,3 +65,705 @@ fn test_optimizer_parse() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_optimizer_if_comparison() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    let ast = engine.compile("if 1 < 2 { 42 } else { 123 }")?;
+    assert!(format!("{:?}", ast).starts_with("AST([Expr(IntegerConstant((42,"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    let ast = engine.compile("if 1 > 2 { 42 } else { 123 }")?;
+    assert!(format!("{:?}", ast).starts_with("AST([Expr(IntegerConstant((123,"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 123);
+
+    let ast = engine.compile("let x = 0; if 1 >= 2 { x = 42; } x")?;
+    assert!(!format!("{:?}", ast).contains("IfThenElse"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 0);
+
+    let ast = engine.compile(r#"if "a" == "a" { if 2 != 2 { 1 } else { 2 } } else { 3 }"#)?;
+    assert!(format!("{:?}", ast).starts_with("AST([Expr(IntegerConstant((2,"));
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_type_of() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    let ast = engine.compile("type_of(42)")?;
+    assert!(!format!("{:?}", ast).contains("FnCall"));
+    assert_eq!(
+        engine.eval_ast::<String>(&ast)?,
+        std::any::type_name::<INT>()
+    );
+
+    let ast = engine.compile(r#"type_of("hello")"#)?;
+    assert!(!format!("{:?}", ast).contains("FnCall"));
+    assert_eq!(engine.eval_ast::<String>(&ast)?, "string");
+
+    // Variables are not folded
+    let ast = engine.compile("let x = 42; type_of(x)")?;
+    assert!(format!("{:?}", ast).contains("FnCall"));
+
+    // Overrides take precedence
+    engine.register_fn("type_of", |_x: INT| "number".to_string());
+
+    let ast = engine.compile("type_of(42)")?;
+    assert!(format!("{:?}", ast).contains("FnCall"));
+    assert_eq!(engine.eval_ast::<String>(&ast)?, "number");
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_positions() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    // Folded constants take the position of the expression they replace
+    let ast = engine.compile(r#"let x = "a" in "abc";"#)?;
+    assert!(format!("{:?}", ast).contains("True(1:13)"));
+
+    let ast = engine.compile("let x = 1; let y = false && x;")?;
+    assert!(format!("{:?}", ast).contains("False(1:26)"));
+
+    let ast = engine.compile("let x = 1; let y = true || x;")?;
+    assert!(format!("{:?}", ast).contains("True(1:25)"));
+
+    let ast = engine.compile("let x = 1 + 2;")?;
+    assert!(format!("{:?}", ast).contains("IntegerConstant((3, 1:11))"));
+
+    let ast = engine.compile("let x = { 42 };")?;
+    assert!(format!("{:?}", ast).contains("IntegerConstant((42, 1:9))"));
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_hoist_loop_invariants() -> Result<(), Box<EvalAltResult>> {
+    use std::sync::{Arc, RwLock};
+
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Full);
+
+    let calls = Arc::new(RwLock::new(0));
+    let counter = calls.clone();
+
+    // Pretend to be an expensive, pure function
+    engine.register_fn("expensive", move |x: INT| {
+        *counter.write().unwrap() += 1;
+        x * 2
+    });
+
+    let mut run = |script: &str| -> Result<(INT, INT), Box<EvalAltResult>> {
+        *calls.write().unwrap() = 0;
+        let result = engine.eval::<INT>(script)?;
+        let count = *calls.read().unwrap();
+        Ok((result, count))
+    };
+
+    // loop
+    assert_eq!(
+        run("let a = 21; let x = 0; loop { let y = expensive(a); x += y; if x >= 420 { break; } } x")?,
+        (420, 1)
+    );
+
+    // while
+    assert_eq!(
+        run("let a = 21; let x = 0; while x < 420 { let y = expensive(a); let z = y + 1; x += z - 1; } x")?,
+        (420, 1)
+    );
+    assert_eq!(
+        run("let a = 21; let x = 0; while x < 0 { let y = expensive(a); x += y; } x")?,
+        (0, 0)
+    );
+
+    // for
+    assert_eq!(
+        run(
+            "let a = 21; let x = 0; for i in range(0, 10) { let y = expensive(a); x += y + i; } x"
+        )?,
+        (465, 1)
+    );
+    #[cfg(not(feature = "no_index"))]
+    assert_eq!(
+        run("let a = 21; let x = 0; for i in [1, 2, 3] { let y = expensive(a); x += y * i; } x")?,
+        (252, 1)
+    );
+
+    // Dependent on the loop variable
+    assert_eq!(
+        run("let x = 0; for i in range(0, 10) { let y = expensive(i); x += y; } x")?,
+        (90, 10)
+    );
+
+    // Dependent on variables changed within the loop
+    assert_eq!(
+        run("let a = 0; let x = 0; while a < 10 { let y = expensive(a); x += y; a += 1; } x")?,
+        (90, 10)
+    );
+    #[cfg(not(feature = "no_index"))]
+    assert_eq!(
+        run("let a = []; let x = 0; while x < 100 { let y = expensive(len(a)); x += y + 1; push(a, 1); } x")?,
+        (100, 10)
+    );
+
+    // The loop body may not run
+    #[cfg(not(feature = "no_index"))]
+    assert_eq!(
+        run(
+            "let a = 21; let x = 0; let arr = []; for i in arr { let y = expensive(a); x += y; } x"
+        )?,
+        (0, 0)
+    );
+
+    // Global variables may be changed by functions
+    #[cfg(not(feature = "no_function"))]
+    #[cfg(not(feature = "no_closure"))]
+    assert_eq!(
+        run(r"
+            fn bump() { global a; a += 1; }
+            let a = 1; let x = 0; let i = 0;
+            while i < 3 { let y = expensive(a); x += y; bump(); i += 1; }
+            x
+        ")?,
+        (12, 3)
+    );
+
+    // Condition with side effects
+    #[cfg(not(feature = "no_index"))]
+    assert_eq!(
+        run("let a = 21; let x = 0; let arr = [1, 2, 3]; while pop(arr) > 0 { let y = expensive(a); x += y; } x")?,
+        (126, 3)
+    );
+
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "no_function"))]
+#[cfg(not(feature = "no_closure"))]
+fn test_optimizer_hoist_loop_closures() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    #[cfg(not(feature = "unchecked"))]
+    engine.set_max_operations(1000);
+
+    for &level in &[OptimizationLevel::Simple, OptimizationLevel::Full] {
+        engine.set_optimization_level(level);
+
+        // Captured variables may be changed by calling the closure
+        #[cfg(not(feature = "no_object"))]
+        assert_eq!(
+            engine.eval::<INT>(
+                "let x = 0; let f = || x += 1; loop { let y = x; if y > 3 { break; } f.call(); } x"
+            )?,
+            4
+        );
+        assert_eq!(
+            engine.eval::<INT>(
+                "let x = 0; let f = || x += 1; loop { let y = x; if y > 3 { break; } call(f); } x"
+            )?,
+            4
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_loop_blocks() -> Result<(), Box<EvalAltResult>> {
+    use std::sync::{Arc, RwLock};
+
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    // Redundant inner blocks in loop bodies are collapsed
+    let ast = engine.compile("let c = true; let x = 0; while c { { x } }")?;
+    assert!(!format!("{:?}", ast).contains("Block"));
+
+    let ast = engine.compile("let c = true; let x = 0; while c { { x += 1 } }")?;
+    assert!(!format!("{:?}", ast).contains("Block"));
+
+    let ast = engine.compile("let x = 0; loop { { { x += 1; } } }")?;
+    assert!(!format!("{:?}", ast).contains("Block"));
+
+    let ast = engine.compile("let x = 0; for i in range(0, 10) { { x += i; } }")?;
+    assert!(!format!("{:?}", ast).contains("Block"));
+
+    // A trailing let statement with side effects is kept as an expression
+    let calls = Arc::new(RwLock::new(0 as INT));
+    let counter = calls.clone();
+
+    engine.register_fn("tick", move || {
+        *counter.write().unwrap() += 1;
+        42 as INT
+    });
+
+    let ast = engine.compile("let x = 0; while x < 3 { { let y = tick(); } x += 1; }")?;
+    assert!(!format!("{:?}", ast).contains("Let((\"y\""));
+    engine.consume_ast(&ast)?;
+    assert_eq!(*calls.read().unwrap(), 3);
+
+    assert_eq!(engine.eval::<()>("{ let y = tick(); }")?, ());
+    assert_eq!(*calls.read().unwrap(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_string_concat() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    let script = r#"let x = "hello"; let y = "world"; "<" + x + ", " + y + "!>""#;
+
+    let ast = engine.compile(script)?;
+    assert!(format!("{:?}", ast).contains(r#""concat$""#));
+    assert_eq!(engine.eval_ast::<String>(&ast)?, "<hello, world!>");
+
+    // Operands that are not strings are added in turn
+    assert_eq!(
+        engine.eval::<String>(r#"let x = 40; let y = 'a'; "x=" + x + 2 + ", y=" + y"#)?,
+        "x=402, y=a"
+    );
+    assert!(engine
+        .eval::<String>(r#"let x = (); "x=" + x + 1 + "!" + ()"#)
+        .is_ok());
+
+    // Chains not starting with a string constant are not rewritten
+    let ast = engine.compile("let x = 1; x + 2 + 3 + 4")?;
+    assert!(!format!("{:?}", ast).contains("concat$"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 10);
+
+    // Chains with side effects are not rewritten
+    #[cfg(not(feature = "no_function"))]
+    {
+        let ast = engine.compile(r#"fn f(x) { x } let x = "a"; "<" + f(x) + x + ">""#)?;
+        assert!(!format!("{:?}", ast).contains("concat$"));
+        assert_eq!(engine.eval_ast::<String>(&ast)?, "<aa>");
+    }
+
+    // Overloaded operators are respected
+    engine.register_fn("+", |x: ImmutableString, y: ImmutableString| {
+        format!("{}|{}", x, y)
+    });
+
+    let ast = engine.compile(r#"let x = "a"; "<" + x + "b" + ">""#)?;
+    assert!(!format!("{:?}", ast).contains("concat$"));
+    assert_eq!(engine.eval_ast::<String>(&ast)?, "<|a|b|>");
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_specialize_ast() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::None);
+
+    let ast = engine.compile(
+        r#"
+            let greeting = if formal { "Good day, " } else { "Hi, " };
+            greeting + name + suffix
+        "#,
+    )?;
+
+    let mut bindings = Scope::new();
+    bindings.push("formal", false);
+    bindings.push("suffix", "!");
+
+    let specialized = engine.specialize_ast(&ast, &bindings);
+    let text = format!("{:?}", specialized);
+    assert!(!text.contains(r#""formal""#));
+    assert!(!text.contains(r#""suffix""#));
+    assert!(!text.contains("Good day"));
+    assert!(text.contains(r#""name""#));
+
+    // The original AST is untouched
+    assert!(format!("{:?}", ast).contains(r#""formal""#));
+
+    let mut scope = Scope::new();
+    scope.push("name", "Bob");
+    assert_eq!(
+        engine.eval_ast_with_scope::<String>(&mut scope, &specialized)?,
+        "Hi, Bob!"
+    );
+
+    // Variables declared by the script shadow bindings
+    let ast = engine
+        .compile("let r = x; { let x = 2; r += x; } for x in range(0, 3) { r += x; } r + x")?;
+    let mut bindings = Scope::new();
+    bindings.push("x", 40 as INT);
+    let specialized = engine.specialize_ast(&ast, &bindings);
+    assert_eq!(engine.eval_ast::<INT>(&specialized)?, 85);
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_fold_unary_and_comparisons() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    let ast = engine.compile("let x = !true;")?;
+    assert!(format!("{:?}", ast).contains("False(1:9)"));
+
+    let ast = engine.compile("const X = 42; let y = -X;")?;
+    assert!(format!("{:?}", ast).contains("IntegerConstant((-42, 1:23))"));
+
+    let ast = engine.compile(r#"let x = "a" < "b"; let y = 'x' == 'y';"#)?;
+    assert!(format!("{:?}", ast).contains("True("));
+    assert!(format!("{:?}", ast).contains("False("));
+    assert!(!format!("{:?}", ast).contains("FnCall"));
+
+    // Folded comparisons feed dead-branch elimination
+    let ast = engine.compile("const X = 5; if !(X > 3) { print(42); }")?;
+    assert!(!format!("{:?}", ast).contains("FnCall"));
+
+    // Overflow is left for run-time
+    #[cfg(not(feature = "unchecked"))]
+    {
+        let ast = engine.compile(&format!("const X = {}; let y = -X;", INT::MIN))?;
+        assert!(format!("{:?}", ast).contains("FnCall"));
+    }
+
+    // Overrides take precedence
+    engine.register_fn("!", |x: bool| x);
+
+    let ast = engine.compile("let x = !true;")?;
+    assert!(format!("{:?}", ast).contains("FnCall"));
+    assert!(engine.eval_ast::<()>(&ast).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_keep_uninitialized_let() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    for level in &[OptimizationLevel::Simple, OptimizationLevel::Full] {
+        engine.set_optimization_level(*level);
+
+        // A 'let' without initial value that is assigned later is kept
+        let ast = engine.compile("let x; let y = 1; x = 42; x")?;
+        assert!(format!("{:?}", ast).contains(r#"Let(("x""#));
+        assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+        assert_eq!(
+            engine.eval::<INT>(
+                r"
+                    let sum = 0;
+
+                    for i in range(0, 4) {
+                        let x;
+                        if i % 2 == 0 { x = 10; } else { x = 1; }
+                        sum += x;
+                    }
+
+                    sum
+                "
+            )?,
+            22
+        );
+
+        // A 'let' at the end of a block is still removed
+        let ast = engine.compile("{ let y = 1; let x; }")?;
+        assert!(!format!("{:?}", ast).contains("Let("));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_index"))]
+#[test]
+fn test_optimizer_fold_array_ops() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    let ast = engine.compile("let x = [1, 2] + [3, 4];")?;
+    assert!(!format!("{:?}", ast).contains("FnCall"));
+    assert_eq!(format!("{:?}", ast).matches("IntegerConstant").count(), 4);
+    assert_eq!(
+        engine
+            .eval_ast::<rhai::Array>(&engine.compile("[1, 2] + [3, 4]")?)?
+            .len(),
+        4
+    );
+
+    let ast = engine.compile("let x = [0] * 3;")?;
+    assert!(!format!("{:?}", ast).contains("FnCall"));
+    assert_eq!(format!("{:?}", ast).matches("IntegerConstant").count(), 3);
+
+    // Results over the size limit are left for run-time
+    #[cfg(not(feature = "unchecked"))]
+    {
+        engine.set_max_array_size(3);
+
+        let ast = engine.compile("let x = [1, 2] + [3, 4];")?;
+        assert!(format!("{:?}", ast).contains("FnCall"));
+
+        let ast = engine.compile("[0] * 10")?;
+        assert!(format!("{:?}", ast).contains("FnCall"));
+        assert!(matches!(
+            *engine.eval_ast::<()>(&ast).expect_err("should error"),
+            EvalAltResult::ErrorDataTooLarge(_, 3, 10, _)
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_index"))]
+#[test]
+fn test_optimizer_share_literals() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Full);
+
+    let script = "let a = [1, 2, 3]; let b = [1, 2, 3]; a[0] = 42; b[0] + a[0]";
+
+    let ast = engine.compile(script)?;
+    assert_eq!(format!("{:?}", ast).matches("Array(").count(), 1);
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 43);
+
+    // The shared constants do not leak into the scope
+    let mut scope = Scope::new();
+    assert_eq!(engine.eval_ast_with_scope::<INT>(&mut scope, &ast)?, 43);
+    assert_eq!(scope.len(), 2);
+    assert_eq!(engine.eval_ast_with_scope::<INT>(&mut scope, &ast)?, 43);
+    assert_eq!(scope.len(), 4);
+
+    // Shared literals are never modified in place
+    assert_eq!(
+        engine.eval::<INT>(
+            r"
+                let a = [1, 2]; push(a, 3); a += [1, 2];
+                let b = [1, 2]; len(b)
+            "
+        )?,
+        2
+    );
+    assert_eq!(
+        engine.eval::<INT>("let a = [1, 2]; push([1, 2], 3); len(a)")?,
+        2
+    );
+
+    #[cfg(not(feature = "no_object"))]
+    {
+        let ast = engine.compile("let a = #{x: 1, y: 2}; let b = #{y: 2, x: 1}; a.x + b.y")?;
+        assert_eq!(format!("{:?}", ast).matches("Map(").count(), 1);
+        assert_eq!(engine.eval_ast::<INT>(&ast)?, 3);
+    }
+
+    // Literals occurring only once are left alone
+    let ast = engine.compile("let a = [1, 2, 3]; let b = [1, 2]; len(a) + len(b)")?;
+    assert!(!format!("{:?}", ast).contains("lit$"));
+
+    // Only at `OptimizationLevel::Full`
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    let ast = engine.compile(script)?;
+    assert_eq!(format!("{:?}", ast).matches("Array(").count(), 2);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_optimizer_is_def_fn() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Full);
+
+    let ast = engine.compile(
+        r#"
+            fn foo(x) { x }
+            private fn bar() { is_def_fn("foo", 1) }
+            if is_def_fn("foo", 1) && bar() { 42 } else { 0 }
+        "#,
+    )?;
+    assert!(!format!("{:?}", ast).contains("is_def_fn"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    // Functions not defined in the script may be registered later
+    let ast = engine.compile(r#"is_def_fn("foo", 2) || is_def_fn("baz", 0)"#)?;
+    assert_eq!(format!("{:?}", ast).matches("is_def_fn").count(), 2);
+
+    // Variables are never folded
+    let ast = engine.compile(r#"let x = 1; is_def_var("x")"#)?;
+    assert!(format!("{:?}", ast).contains("is_def_var"));
+
+    // Only at `OptimizationLevel::Full`
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    let ast = engine.compile(r#"fn foo(x) { x } is_def_fn("foo", 1)"#)?;
+    assert!(format!("{:?}", ast).contains("is_def_fn"));
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_scope_constants() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Full);
+
+    let mut scope = Scope::new();
+    scope.push_constant("x", 21 as INT);
+
+    let ast = engine.compile_with_scope(&scope, "x * 2")?;
+    assert!(format!("{:?}", ast).contains("IntegerConstant((42, "));
+    assert!(!format!("{:?}", ast).contains("Variable"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    // Non-constant variables in the scope are not folded
+    let mut scope = Scope::new();
+    scope.push("x", 21 as INT);
+
+    let ast = engine.compile_with_scope(&scope, "x * 2")?;
+    assert!(format!("{:?}", ast).contains("Variable"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "no_index"))]
+fn test_optimizer_try_catch() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    // The try block has side effects but can never throw
+    let ast = engine.compile("let x = 1; try { let y = [x, 2]; x = 42; } catch { x = 0; } x")?;
+    assert!(!format!("{:?}", ast).contains("TryCatch"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    // Function calls, indexing and undeclared variables may throw
+    let ast = engine.compile("try { print(1); } catch { }")?;
+    assert!(format!("{:?}", ast).contains("TryCatch"));
+
+    let ast = engine.compile("let x = [1]; let r = 0; try { x[10] } catch { r = 42; } r")?;
+    assert!(format!("{:?}", ast).contains("TryCatch"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    let ast = engine.compile("let x = [1]; let r = 0; try { let y = x[10]; } catch { r = 42; } r")?;
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    let ast = engine.compile("let r = 0; try { x; } catch { r = 42; } r")?;
+    assert!(format!("{:?}", ast).contains("TryCatch"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    Ok(())
+}
+
+#[test]
+fn test_optimizer_op_assignment() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    let ast = engine.compile("let x = 40; x = x + 2; x")?;
+    assert!(format!("{:?}", ast).contains(r#""+=""#));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    let ast = engine.compile("let x = 2; let y = 4; x = x * (y + 3) * 3; x")?;
+    assert!(!format!("{:?}", ast).contains(r#""*=""#));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    let ast = engine.compile("let x = 6; let y = 1; x = x << y; x")?;
+    assert!(format!("{:?}", ast).contains(r#""<<=""#));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 12);
+
+    // The operand is not pure and may modify the variable before it is read
+    let ast = engine.compile("let x = 1; x = x + { x = 40; 1 }; x")?;
+    assert!(!format!("{:?}", ast).contains(r#""+=""#));
+
+    // Different variables
+    let ast = engine.compile("let x = 1; let y = 2; x = y + 40; x")?;
+    assert!(!format!("{:?}", ast).contains(r#""+=""#));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    engine.set_optimization_level(OptimizationLevel::None);
+
+    let ast = engine.compile("let x = 40; x = x + 2; x")?;
+    assert!(!format!("{:?}", ast).contains(r#""+=""#));
+
+    Ok(())
+}
+
+#[cfg(feature = "internals")]
+#[test]
+#[allow(deprecated)]
+fn test_optimizer_custom_pass() -> Result<(), Box<EvalAltResult>> {
+    use rhai::Expr;
+
+    let mut engine = Engine::new();
+
+    // Fold 'double' on constant integers - 'double' is not registered,
+    // so the script fails to run if the pass does not fire
+    engine.register_optimizer_pass(|expr, context| match expr {
+        Expr::FnCall(x) if (x.0).0 == "double" && x.3.len() == 1 => match x.3[0] {
+            Expr::IntegerConstant(ref v) => {
+                context.set_dirty();
+                Expr::IntegerConstant(Box::new((v.0 * 2, v.1)))
+            }
+            _ => Expr::FnCall(x),
+        },
+        _ => expr,
+    });
+
+    let ast = engine.compile("double(2)")?;
+    assert!(format!("{:?}", ast).starts_with("AST([Expr(IntegerConstant((4,"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 4);
+
+    // Constants are propagated into the argument, and the folded result into the addition
+    let ast = engine.compile("const X = 2; double(X) + 1")?;
+    assert!(format!("{:?}", ast).contains("Expr(IntegerConstant((5,"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 5);
+
+    engine.set_optimization_level(OptimizationLevel::None);
+    assert!(engine.eval::<INT>("double(2)").is_err());
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_object"))]
+#[test]
+fn test_optimizer_map_literal_side_effects() -> Result<(), Box<EvalAltResult>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+
+    let mut engine = Engine::new();
+    engine.set_optimization_level(OptimizationLevel::Simple);
+    engine.register_fn("side_effect", move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+        0 as INT
+    });
+
+    // Map literals with only pure values are folded
+    let ast = engine.compile("#{ a: 1, b: 2 }.b")?;
+    assert!(format!("{:?}", ast).starts_with("AST([Expr(IntegerConstant((2,"));
+
+    #[cfg(not(feature = "no_index"))]
+    {
+        let ast = engine.compile(r#"#{ a: 1, b: 2 }["b"]"#)?;
+        assert!(format!("{:?}", ast).starts_with("AST([Expr(IntegerConstant((2,"));
+    }
+
+    // Impure sibling values are kept
+    let ast = engine.compile("#{ a: side_effect(), b: 1 }.b")?;
+    assert!(format!("{:?}", ast).contains("side_effect"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 1);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    #[cfg(not(feature = "no_index"))]
+    {
+        let ast = engine.compile(r#"#{ a: side_effect(), b: 1 }["b"]"#)?;
+        assert!(format!("{:?}", ast).contains("side_effect"));
+        assert_eq!(engine.eval_ast::<INT>(&ast)?, 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    Ok(())
+}
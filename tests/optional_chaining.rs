@@ -0,0 +1,90 @@
+#![cfg(not(feature = "no_object"))]
+#![cfg(not(feature = "no_index"))]
+use rhai::{Engine, EvalAltResult, INT};
+
+#[cfg(feature = "internals")]
+#[cfg(not(feature = "no_optimize"))]
+use rhai::OptimizationLevel;
+
+/// Get the optional flags of the links in the dot/index chain of the last statement in a script.
+#[cfg(feature = "internals")]
+#[allow(deprecated)]
+fn chain_links(engine: &Engine, script: &str) -> Result<Vec<bool>, Box<EvalAltResult>> {
+    use rhai::{Expr, Stmt};
+
+    let ast = engine.compile(script)?;
+
+    let mut expr = match ast.statements().last() {
+        Some(Stmt::Expr(expr)) => expr,
+        stmt => panic!("expects an expression statement, not {:?}", stmt),
+    };
+    let mut links = Vec::new();
+
+    loop {
+        match expr {
+            Expr::Dot(x, is_optional) | Expr::Index(x, is_optional) => {
+                links.push(*is_optional);
+                expr = &x.rhs;
+            }
+            _ => return Ok(links),
+        }
+    }
+}
+
+#[cfg(feature = "internals")]
+#[test]
+fn test_optional_chaining_parse() -> Result<(), Box<EvalAltResult>> {
+    #[allow(unused_mut)]
+    let mut engine = Engine::new();
+    #[cfg(not(feature = "no_optimize"))]
+    engine.set_optimization_level(OptimizationLevel::None);
+
+    assert_eq!(chain_links(&engine, "let x; x.a")?, [false]);
+    assert_eq!(chain_links(&engine, "let x; x[0]")?, [false]);
+
+    // Variables
+    assert_eq!(chain_links(&engine, "let x; x?.a")?, [true]);
+    assert_eq!(chain_links(&engine, "let x; x?[0]")?, [true]);
+
+    // Properties
+    assert_eq!(chain_links(&engine, "let x; x.a?.b")?, [false, true]);
+    assert_eq!(chain_links(&engine, "let x; x?.a?.b")?, [true, true]);
+    assert_eq!(chain_links(&engine, "let x; x.a?[0]")?, [false, true]);
+
+    // Function call results
+    #[cfg(not(feature = "no_function"))]
+    assert_eq!(chain_links(&engine, "fn foo() { 42 } foo()?.a")?, [true]);
+    assert_eq!(chain_links(&engine, "let x; x?.foo()")?, [true]);
+
+    // Index results
+    assert_eq!(chain_links(&engine, "let x; x[0]?[1]")?, [false, true]);
+    assert_eq!(chain_links(&engine, "let x; x?[0][1]")?, [true, false]);
+    assert_eq!(chain_links(&engine, "let x; x[0]?.a")?, [false, true]);
+
+    // Literals
+    assert_eq!(chain_links(&engine, "[1, 2, 3]?[0]")?, [true]);
+    assert_eq!(chain_links(&engine, "#{a: 1}?.a")?, [true]);
+    assert_eq!(chain_links(&engine, r#"#{a: 1}?["a"]"#)?, [true]);
+
+    Ok(())
+}
+
+#[test]
+fn test_optional_chaining_parse_errors() {
+    let engine = Engine::new();
+
+    assert!(engine.compile("let x; x?.").is_err());
+    assert!(engine.compile("let x; x?[0").is_err());
+    assert!(engine.compile("42?[0]").is_err());
+}
+
+#[test]
+fn test_optional_chaining_eval() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    // Optional links evaluate like normal links
+    assert_eq!(engine.eval::<INT>("let x = #{a: #{b: 42}}; x?.a?.b")?, 42);
+    assert_eq!(engine.eval::<INT>("let x = [1, [2, 42]]; x?[1]?[1]")?, 42);
+
+    Ok(())
+}
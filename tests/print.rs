@@ -1,4 +1,4 @@
-use rhai::{Engine, EvalAltResult};
+use rhai::{Engine, EvalAltResult, ImmutableString, INT};
 use std::sync::{Arc, RwLock};
 
 #[test]
@@ -30,3 +30,50 @@ fn test_print() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_print_captured() -> Result<(), Box<EvalAltResult>> {
+    let logbook = Arc::new(RwLock::new(Vec::<String>::new()));
+    let log = logbook.clone();
+
+    let mut engine = Engine::new();
+
+    engine.on_print(move |s| log.write().unwrap().push(s.to_string()));
+
+    assert_eq!(
+        engine.eval_captured::<INT>(r#"print("a"); 42"#)?,
+        (42, vec!["a".to_string()], vec![])
+    );
+
+    let (_, prints, debugs) = engine.eval_captured::<()>(r#"print(1); debug("x"); print("b")"#)?;
+    assert_eq!(prints, ["1", "b"]);
+    assert_eq!(debugs, [r#""x""#]);
+
+    // Errors still end the capture
+    assert!(engine
+        .eval_captured::<INT>(r#"print("c"); throw 42;"#)
+        .is_err());
+
+    // Nested captures only capture their own output
+    engine.register_raw_fn(
+        "inner",
+        &[std::any::TypeId::of::<ImmutableString>()],
+        |context, args| {
+            let script = args[0].clone().cast::<ImmutableString>();
+            let (_, prints, _) = context.engine().eval_captured::<()>(&script)?;
+            Ok(prints.len() as INT)
+        },
+    );
+
+    let (result, prints, _) = engine.eval_captured::<INT>(
+        r#"print("outer"); let n = inner("print(1); print(2);"); print("done"); n"#,
+    )?;
+    assert_eq!(result, 2);
+    assert_eq!(prints, ["outer", "done"]);
+
+    // Not captured
+    engine.eval::<()>(r#"print("d")"#)?;
+    assert_eq!(*logbook.read().unwrap(), ["d"]);
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use rhai::{Engine, EvalAltResult, Position, AST, INT};
+
+#[test]
+fn test_source_slice_at() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    let ast = engine.compile("let x = 42;\nx + y")?;
+    assert!(ast.source_text().is_none());
+    assert_eq!(ast.slice_at(Position::new(1, 1)), None);
+
+    engine.set_retain_source(true);
+
+    let ast = engine.compile("let x = 42;\r\nlet y = x + z;\r\ny")?;
+    assert_eq!(
+        ast.source_text(),
+        Some("let x = 42;\r\nlet y = x + z;\r\ny")
+    );
+
+    let err = engine.eval_ast::<INT>(&ast).expect_err("should error");
+    let pos = err.position();
+    assert_eq!(pos, Position::new(2, 13));
+    assert_eq!(ast.slice_at(pos), Some("let y = x + z;"));
+
+    assert_eq!(ast.slice_at(Position::new(3, 1)), Some("y"));
+    assert_eq!(ast.slice_at(Position::new(4, 1)), None);
+    assert_eq!(ast.slice_at(Position::none()), None);
+
+    let ast = engine.compile_expression("40 + 2")?;
+    assert_eq!(ast.slice_at(Position::new(1, 4)), Some("40 + 2"));
+
+    // Merged ASTs do not retain source text
+    let ast = ast.merge(&engine.compile("42")?);
+    assert!(ast.source_text().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_source_name() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let mut ast = engine.compile("let x = 42;\nx + y")?;
+    assert_eq!(ast.source(), None);
+    assert!(matches!(
+        *engine.eval_ast::<INT>(&ast).expect_err("should error"),
+        EvalAltResult::ErrorVariableNotFound(_, _)
+    ));
+
+    ast.set_source("hello.rhai");
+    assert_eq!(ast.source(), Some("hello.rhai"));
+
+    let err = engine.eval_ast::<INT>(&ast).expect_err("should error");
+    assert_eq!(err.position(), Position::new(2, 5));
+    assert_eq!(
+        err.to_string(),
+        "Error in 'hello.rhai': Variable not found: 'y' (line 2, position 5)"
+    );
+    match *err {
+        EvalAltResult::ErrorInSource(source, err, _) => {
+            assert_eq!(source, "hello.rhai");
+            assert!(matches!(*err, EvalAltResult::ErrorVariableNotFound(v, _) if v == "y"));
+        }
+        err => panic!("wrong error: {}", err),
+    }
+    assert!(matches!(
+        *engine.consume_ast(&ast).expect_err("should error"),
+        EvalAltResult::ErrorInSource(_, _, _)
+    ));
+
+    // The source name of the first AST is kept when merging
+    let mut other = engine.compile("let y = 0;")?;
+    other.set_source("other.rhai");
+    assert_eq!(ast.merge(&other).source(), Some("hello.rhai"));
+    assert_eq!(other.merge(&ast).source(), Some("other.rhai"));
+
+    ast.clear_source();
+    assert_eq!(ast.source(), None);
+    assert!(matches!(
+        *engine.eval_ast::<INT>(&ast).expect_err("should error"),
+        EvalAltResult::ErrorVariableNotFound(_, _)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_source_combine_with_source() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    let source_of = |ast: &AST| match *engine.eval_ast::<INT>(ast).expect_err("should error") {
+        EvalAltResult::ErrorInSource(source, err, _) => (source, err.position()),
+        err => panic!("wrong error: {}", err),
+    };
+
+    let mut first = engine.compile("let x = 40;\nlet y = x;")?;
+    first.set_source("first.rhai");
+
+    // Error in the second script at the same position as a statement in the first
+    let mut ast = first.clone();
+    ast.combine_with_source(engine.compile("let z = 1;\nx + w")?, "second.rhai");
+    assert_eq!(ast.source(), Some("first.rhai"));
+    assert_eq!(
+        source_of(&ast),
+        ("second.rhai".to_string(), Position::new(2, 5))
+    );
+
+    // Error in the first script
+    let mut ast = engine.compile("let x = 40;\nx + w")?;
+    ast.set_source("first.rhai");
+    ast.combine_with_source(engine.compile("x + 2")?, "second.rhai");
+    assert_eq!(
+        source_of(&ast),
+        ("first.rhai".to_string(), Position::new(2, 5))
+    );
+
+    // Statements combined afterwards without a source name belong to the first script
+    let mut ast = first.clone();
+    ast.combine_with_source(engine.compile("x + 2")?, "second.rhai");
+    ast.combine(engine.compile("x + w")?);
+    assert_eq!(
+        source_of(&ast),
+        ("first.rhai".to_string(), Position::new(1, 5))
+    );
+
+    // Source names are kept when combining combined scripts
+    let mut second = engine.compile("let z = 1;")?;
+    second.combine_with_source(engine.compile("x + w")?, "third.rhai");
+    let ast = first.merge(&second);
+    assert_eq!(
+        source_of(&ast),
+        ("third.rhai".to_string(), Position::new(1, 5))
+    );
+
+    Ok(())
+}
+
+#[cfg(not(feature = "no_function"))]
+#[test]
+fn test_source_string_literals() -> Result<(), Box<EvalAltResult>> {
+    fn string_literals(ast: &AST) -> Vec<(String, Position)> {
+        ast.string_literals()
+            .into_iter()
+            .map(|(s, pos)| (s.to_string(), pos))
+            .collect()
+    }
+
+    #[allow(unused_mut)]
+    let mut engine = Engine::new();
+    #[cfg(not(feature = "no_optimize"))]
+    engine.set_optimization_level(rhai::OptimizationLevel::None);
+
+    let ast = engine.compile(
+        r#"
+            let x = "hello";
+            fn greet(name) { "hi, " + name }
+            if x == "hello" { greet("world") }
+        "#,
+    )?;
+
+    assert_eq!(
+        string_literals(&ast),
+        [
+            ("hello".to_string(), Position::new(2, 21)),
+            ("hi, ".to_string(), Position::new(3, 30)),
+            ("hello".to_string(), Position::new(4, 21)),
+            ("world".to_string(), Position::new(4, 37)),
+        ]
+    );
+
+    // Literals nested inside arrays and object maps
+    #[cfg(not(feature = "no_index"))]
+    #[cfg(not(feature = "no_object"))]
+    {
+        let ast = engine.compile(r#"let y = [1, "foo", #{ a: "bar" }];"#)?;
+
+        assert_eq!(
+            string_literals(&ast),
+            [
+                ("foo".to_string(), Position::new(1, 13)),
+                ("bar".to_string(), Position::new(1, 26)),
+            ]
+        );
+    }
+
+    Ok(())
+}
@@ -91,3 +91,73 @@ fn test_stack_overflow_parsing() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_stack_overflow_parsing_nested() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+    let n = 10000;
+
+    let mut scripts = vec![
+        format!("{}1{}", "(".repeat(n), ")".repeat(n)),
+        format!("1{}", " ~ 1".repeat(n)),
+        format!("{}1", "- ".repeat(n)),
+    ];
+
+    #[cfg(not(feature = "no_object"))]
+    scripts.push(format!("x{}", ".a".repeat(n)));
+
+    #[cfg(not(feature = "no_function"))]
+    scripts.push(format!("{}1", "|| ".repeat(n)));
+
+    for script in scripts {
+        assert_eq!(
+            *engine.compile(&script).expect_err("should error").0,
+            ParseErrorType::ExprTooDeep
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_stack_too_many_variables() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.compile("let a = 1; let b = 2; let c = 3; let d = 4;")?;
+
+    engine.set_max_variables(3);
+
+    engine.compile("let a = 1; let b = 2; let c = 3;")?;
+    engine.compile("let a = 1; let b = 2; { let c = 3; } let d = 4;")?;
+
+    assert_eq!(
+        *engine
+            .compile("let a = 1; let b = 2; let c = 3; let d = 4;")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::TooManyVariables
+    );
+
+    assert_eq!(
+        *engine
+            .compile("let a = 1; let b = 2; let c = 3; for x in range(0, 1) {}")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::TooManyVariables
+    );
+
+    #[cfg(not(feature = "no_function"))]
+    assert_eq!(
+        *engine
+            .compile("fn foo(a, b, c, d) { a }")
+            .expect_err("should error")
+            .0,
+        ParseErrorType::TooManyVariables
+    );
+
+    engine.set_max_variables(0);
+
+    engine.compile("let a = 1; let b = 2; let c = 3; let d = 4;")?;
+
+    Ok(())
+}
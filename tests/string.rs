@@ -27,6 +27,8 @@ fn test_string() -> Result<(), Box<EvalAltResult>> {
 
     #[cfg(not(feature = "no_index"))]
     assert_eq!(engine.eval::<char>(r#"let y = "hello"; y[1]"#)?, 'e');
+    #[cfg(not(feature = "no_index"))]
+    assert_eq!(engine.eval::<char>(r#"let y = "hello"; y[-1]"#)?, 'o');
 
     #[cfg(not(feature = "no_object"))]
     assert_eq!(engine.eval::<INT>(r#"let y = "hello"; y.len"#)?, 5);
@@ -114,3 +114,71 @@ fn test_custom_syntax_raw() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[cfg(not(feature = "no_optimize"))]
+#[test]
+fn test_custom_syntax_pure() -> Result<(), Box<EvalAltResult>> {
+    use rhai::OptimizationLevel;
+
+    let mut engine = Engine::new();
+
+    engine.register_custom_syntax_pure(&["double", "$expr$"], 0, |context, inputs| {
+        let value = context.eval_expression_tree(&inputs[0])?.as_int().unwrap();
+        Ok((value * 2).into())
+    })?;
+
+    engine.set_optimization_level(OptimizationLevel::Full);
+
+    let ast = engine.compile("double 21")?;
+    assert!(!format!("{:?}", ast).contains("Custom"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    let ast = engine.compile("let x = 21; double x")?;
+    assert!(format!("{:?}", ast).contains("Custom"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    engine.set_optimization_level(OptimizationLevel::Simple);
+
+    let ast = engine.compile("double 21")?;
+    assert!(format!("{:?}", ast).contains("Custom"));
+    assert_eq!(engine.eval_ast::<INT>(&ast)?, 42);
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_syntax_remove() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_custom_syntax(&["do_twice", "$expr$"], 0, |context, inputs| {
+        let value = context.eval_expression_tree(&inputs[0])?.as_int().unwrap();
+        Ok((value * 2).into())
+    })?;
+
+    assert_eq!(engine.eval::<INT>("do_twice 21")?, 42);
+    assert!(engine.compile("let do_twice = 1;").is_err());
+
+    // Disabled custom syntax is parsed as a normal identifier
+    engine.disable_symbol("do_twice");
+    assert_eq!(engine.eval::<INT>("let do_twice = 21; do_twice")?, 21);
+
+    let mut engine = Engine::new();
+
+    engine.register_custom_syntax(&["do_twice", "$expr$"], 0, |context, inputs| {
+        let value = context.eval_expression_tree(&inputs[0])?.as_int().unwrap();
+        Ok((value * 2).into())
+    })?;
+
+    assert!(engine.remove_custom_syntax("do_twice"));
+    assert!(!engine.remove_custom_syntax("do_twice"));
+    assert_eq!(engine.eval::<INT>("let do_twice = 21; do_twice")?, 21);
+
+    // Custom syntax cannot start with a custom operator
+    engine.register_custom_operator("foo", 160)?;
+
+    assert!(engine
+        .register_custom_syntax(&["foo", "$expr$"], 0, |_, _| Ok(().into()))
+        .is_err());
+
+    Ok(())
+}
@@ -15,6 +15,35 @@ fn test_throw() {
     ));
 }
 
+#[test]
+fn test_throw_return_global() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    // 'return' at global level stops the script and yields the value as the result
+    assert_eq!(engine.eval::<INT>("return 42; 123")?, 42);
+    assert_eq!(
+        engine.eval::<INT>("let x = 0; loop { x += 1; if x >= 42 { return x; } } 123")?,
+        42
+    );
+    assert_eq!(engine.eval::<INT>("let x = { return 42; }; 123")?, 42);
+    engine.eval::<()>("return; 123")?;
+    engine.consume("return 42; throw 123;")?;
+
+    // 'return' inside 'eval' only stops the evaluated script
+    assert_eq!(
+        engine.eval::<INT>(r#"let x = eval("return 41;"); x + 1"#)?,
+        42
+    );
+
+    // 'throw' at global level is still an error
+    assert!(matches!(
+        *engine.eval::<INT>("throw 42; return 123;").expect_err("expects error"),
+        EvalAltResult::ErrorRuntime(s, _) if s.as_int().unwrap() == 42
+    ));
+
+    Ok(())
+}
+
 #[test]
 fn test_try_catch() -> Result<(), Box<EvalAltResult>> {
     let engine = Engine::new();
@@ -39,3 +68,61 @@ fn test_try_catch() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_try_catch_structured() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    #[cfg(not(feature = "no_object"))]
+    assert_eq!(
+        engine.eval::<INT>(
+            r#"
+                try {
+                    throw #{ code: 404, msg: "not found" };
+                } catch (e) {
+                    if e.msg == "not found" { return e.code; }
+                }
+            "#
+        )?,
+        404
+    );
+
+    #[cfg(not(feature = "no_index"))]
+    assert_eq!(
+        engine.eval::<INT>("try { throw [1, 2, 42]; } catch (e) { return e[2]; }")?,
+        42
+    );
+
+    // Values thrown inside functions are caught unchanged
+    #[cfg(not(feature = "no_function"))]
+    {
+        #[cfg(not(feature = "no_object"))]
+        assert_eq!(
+            engine.eval::<INT>(
+                r#"
+                    fn lookup(x) { throw #{ code: x }; }
+                    fn find(x) { lookup(x) }
+
+                    try { find(404); } catch (e) { return e.code; }
+                "#
+            )?,
+            404
+        );
+
+        assert_eq!(
+            engine.eval::<INT>("fn foo() { throw 42; } try { foo(); } catch (e) { return e; }")?,
+            42
+        );
+
+        // Rethrowing keeps the original error
+        assert!(matches!(
+            *engine
+                .eval::<()>("fn foo() { throw 42; } try { foo(); } catch { throw; }")
+                .expect_err("expects error"),
+            EvalAltResult::ErrorInFunctionCall(f, err, _)
+                if f == "foo" && matches!(*err, EvalAltResult::ErrorRuntime(ref v, _) if v.as_int().unwrap() == 42)
+        ));
+    }
+
+    Ok(())
+}
@@ -1,13 +1,12 @@
 #![cfg(not(feature = "no_std"))]
 #![cfg(not(target_arch = "wasm32"))]
 
-use rhai::{Engine, EvalAltResult};
+use rhai::{Engine, EvalAltResult, INT};
 
 #[cfg(not(feature = "no_float"))]
 use rhai::FLOAT;
 
-#[cfg(feature = "no_float")]
-use rhai::INT;
+use std::time::{Duration, Instant};
 
 #[test]
 fn test_timestamp() -> Result<(), Box<EvalAltResult>> {
@@ -107,3 +106,40 @@ fn test_timestamp_op() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_eval_with_timeout() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+
+    assert_eq!(
+        engine.eval_with_timeout::<INT>("40 + 2", Duration::from_secs(5))?,
+        42
+    );
+
+    let start = Instant::now();
+
+    assert!(matches!(
+        *engine
+            .eval_with_timeout::<()>("loop {}", Duration::from_millis(50))
+            .expect_err("should time out"),
+        EvalAltResult::ErrorTerminated(_)
+    ));
+
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    // Nested `eval` calls share the same deadline
+    assert!(matches!(
+        *engine
+            .eval_with_timeout::<()>(r#"eval("loop {}")"#, Duration::from_millis(50))
+            .expect_err("should time out"),
+        EvalAltResult::ErrorTerminated(_)
+    ));
+
+    // The timeout does not carry over to later evaluations
+    assert_eq!(
+        engine.eval::<INT>("let x = 0; while x < 1000 { x += 1; } x")?,
+        1000
+    );
+
+    Ok(())
+}
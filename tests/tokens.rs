@@ -22,6 +22,21 @@ fn test_tokens_disabled() {
     ));
 }
 
+#[test]
+fn test_tokens_reserved() {
+    let engine = Engine::new();
+
+    let err = engine.compile("let x = print;").expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::Reserved("print".to_string()));
+    assert!(err
+        .to_string()
+        .contains("did you mean to call it as a function?"));
+
+    let err = engine.compile("let var = 42;").expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::Reserved("var".to_string()));
+    assert!(err.to_string().contains("cannot be used as a name"));
+}
+
 #[test]
 fn test_tokens_custom_operator() -> Result<(), Box<EvalAltResult>> {
     let mut engine = Engine::new();
@@ -52,6 +67,191 @@ fn test_tokens_custom_operator() -> Result<(), Box<EvalAltResult>> {
     Ok(())
 }
 
+#[test]
+fn test_tokens_custom_operator_assoc() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_fn("minus", |x: INT, y: INT| x - y);
+    engine.register_fn("rminus", |x: INT, y: INT| x - y);
+
+    engine.register_custom_operator("minus", 160).unwrap();
+    engine
+        .register_custom_operator_with_assoc("rminus", 160, true)
+        .unwrap();
+
+    // (10 - 4) - 3
+    assert_eq!(engine.eval_expression::<INT>("10 minus 4 minus 3")?, 3);
+    // 10 - (4 - 3)
+    assert_eq!(engine.eval_expression::<INT>("10 rminus 4 rminus 3")?, 9);
+
+    Ok(())
+}
+
+#[test]
+fn test_tokens_custom_operator_assignment() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_custom_operator("foo", 160).unwrap();
+    engine.register_fn("foo", |x: INT, y: INT| (x * y) - (x + y));
+
+    // x foo= y is the same as x = x foo y
+    assert_eq!(engine.eval::<INT>("let x = 3; x foo= 4; x")?, 5);
+    assert_eq!(engine.eval::<INT>("let x = 3; x foo= 2 + 2; x")?, 5);
+
+    #[cfg(not(feature = "no_index"))]
+    assert_eq!(engine.eval::<INT>("let x = [1, 3]; x[1] foo= 4; x[1]")?, 5);
+
+    #[cfg(not(feature = "no_object"))]
+    assert_eq!(engine.eval::<INT>("let x = #{a: 3}; x.a foo= 4; x.a")?, 5);
+
+    // Built-in compound assignments are unaffected
+    assert_eq!(engine.eval::<INT>("let x = 3; x += 4; x")?, 7);
+
+    assert!(engine.compile("let x = 3; if x foo= 4 {}").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_tokens_custom_operator_redefined() -> Result<(), Box<EvalAltResult>> {
+    #[derive(Debug, Clone)]
+    struct Money(INT);
+
+    let mut engine = Engine::new();
+
+    engine
+        .register_fn("money", Money)
+        .register_fn("value", |m: &mut Money| m.0)
+        .register_fn("+", |x: Money, y: Money| Money(x.0 + y.0));
+
+    // Built-in operators cannot be made custom unless disabled
+    assert!(engine.register_custom_operator("+", 150).is_err());
+    assert!(engine.register_custom_operator("if", 150).is_err());
+
+    engine.disable_symbol("+");
+
+    assert!(engine.compile("money(1) + money(2)").is_err());
+
+    // Redefine '+' as a custom operator binding tighter than '*'
+    engine.register_custom_operator("+", 190).unwrap();
+
+    assert_eq!(engine.operator_precedence("+"), Some(190));
+    assert_eq!(
+        engine.eval_expression::<INT>("value(money(1) + money(2) + money(39))")?,
+        42
+    );
+    assert_eq!(engine.eval_expression::<INT>("2 * 3 + 4")?, 14);
+
+    // Unary '+' remains disabled
+    assert!(engine.compile("let x = +1;").is_err());
+
+    // Compound assignment is a separate symbol and is unaffected
+    assert_eq!(engine.eval::<INT>("let x = 40; x += 2; x")?, 42);
+
+    Ok(())
+}
+
+#[test]
+fn test_tokens_operator_precedence() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    assert_eq!(engine.operator_precedence("+"), Some(150));
+    assert_eq!(engine.operator_precedence("+="), Some(0));
+    assert_eq!(engine.operator_precedence("("), None);
+    assert_eq!(engine.operator_precedence("foo"), None);
+
+    // Make '+' bind tighter than '*'
+    engine.set_operator_precedence("+", 200)?;
+    assert_eq!(engine.operator_precedence("+"), Some(200));
+    assert_eq!(engine.eval_expression::<INT>("2 * 3 + 4")?, 14);
+    assert_eq!(engine.eval_expression::<INT>("1 + 2 * 3 + 4")?, 21);
+    assert_eq!(engine.eval::<INT>("let x = 1; x += 2 * 3 + 4; x")?, 15);
+
+    // Restore
+    engine.set_operator_precedence("+", 150)?;
+    assert_eq!(engine.eval_expression::<INT>("1 + 2 * 3 + 4")?, 11);
+
+    // Changes that break the grammar are rejected
+    assert!(engine.set_operator_precedence("=", 160).is_err());
+    assert!(engine.set_operator_precedence("*=", 160).is_err());
+    assert!(engine.set_operator_precedence(".", 100).is_err());
+    assert!(engine.set_operator_precedence("+", 0).is_err());
+    assert!(engine.set_operator_precedence("+", 240).is_err());
+    assert!(engine.set_operator_precedence("!", 100).is_err());
+    assert!(engine.set_operator_precedence("foo", 100).is_err());
+
+    // Disabled operators have no precedence
+    engine.disable_symbol("-");
+    assert_eq!(engine.operator_precedence("-"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_tokens_introspection() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    assert!(engine.custom_operators().is_empty());
+    assert!(engine.disabled_symbols().is_empty());
+    assert!(engine.custom_syntax_keywords().is_empty());
+
+    engine.register_custom_operator("foo", 160).unwrap();
+    engine
+        .register_custom_operator_with_assoc("bar", 120, true)
+        .unwrap();
+    engine.disable_symbol("while").disable_symbol("+=");
+    engine.register_custom_syntax(&["twice", "|", "$expr$", "|"], 0, |context, inputs| {
+        context.eval_expression_tree(&inputs[0])
+    })?;
+
+    assert_eq!(
+        engine.custom_operators(),
+        vec![("bar".to_string(), 120), ("foo".to_string(), 160)]
+    );
+    assert_eq!(engine.disabled_symbols(), vec!["+=", "while"]);
+    assert_eq!(engine.custom_syntax_keywords(), vec!["twice", "|"]);
+
+    engine.remove_custom_syntax("twice");
+    assert!(engine.custom_syntax_keywords().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_tokens_strict_semicolons() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    // Lenient by default
+    assert_eq!(engine.eval::<INT>("let x = 1;; x")?, 1);
+
+    engine.set_strict_semicolons(true);
+    assert!(engine.strict_semicolons());
+
+    let err = engine.compile("let x = 1;; x").expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::EmptyStatement);
+    assert_eq!(err.1.position(), Some(11));
+
+    let err = engine.compile("; 42").expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::EmptyStatement);
+
+    let err = engine.compile("{ let x = 1; ; x }").expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::EmptyStatement);
+    assert_eq!(err.1.position(), Some(14));
+
+    let err = engine.compile("if true { 1 };;").expect_err("should error");
+    assert_eq!(*err.0, ParseErrorType::EmptyStatement);
+
+    // Genuine statement terminators are allowed
+    assert_eq!(engine.eval::<INT>("let x = 1; x;")?, 1);
+    assert_eq!(engine.eval::<INT>("let x = 1; { x += 1; }; x")?, 2);
+    assert_eq!(engine.eval::<INT>("let x = 1; if x > 0 { x = 42; }; x")?, 42);
+    assert_eq!(engine.eval::<INT>("let x = 0; while x < 42 { x += 1; }; x")?, 42);
+    #[cfg(not(feature = "no_function"))]
+    assert_eq!(engine.eval::<INT>("fn foo() { 42 }; foo()")?, 42);
+
+    Ok(())
+}
+
 #[test]
 fn test_tokens_unicode_xid_ident() -> Result<(), Box<EvalAltResult>> {
     let engine = Engine::new();
@@ -77,3 +277,83 @@ fn test_tokens_unicode_xid_ident() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "internals")]
+#[allow(deprecated)]
+fn test_tokens_on_parse_token() -> Result<(), Box<EvalAltResult>> {
+    use rhai::{LexError, Token};
+
+    let mut engine = Engine::new();
+
+    engine.register_custom_operator("foo", 160).unwrap();
+    engine.register_fn("foo", |x: INT, y: INT| (x * y) - (x + y));
+
+    engine.on_parse_token(|token, _| match token {
+        // 'bar' is an alias to the custom operator 'foo'
+        Token::Identifier(s) if s == "bar" => Token::Custom("foo".into()),
+        // 'is' is an alias to '=='
+        Token::Identifier(s) if s == "is" => Token::EqualsTo,
+        // 'loop' is not allowed
+        Token::Loop => Token::LexError(Box::new(LexError::ImproperSymbol(
+            "'loop' is not allowed".to_string(),
+        ))),
+        // Misuse
+        Token::Identifier(s) if s == "stop" => Token::EOF,
+        Token::Identifier(s) if s == "baz" => Token::Custom(s),
+        _ => token,
+    });
+
+    assert_eq!(engine.eval::<INT>("let x = 3; x bar 4")?, 5);
+    assert!(engine.eval::<bool>("let x = 42; x is 42")?);
+
+    assert!(matches!(
+        *engine.compile("loop { break; }").expect_err("should error").0,
+        ParseErrorType::BadInput(ref s) if s == "'loop' is not allowed"
+    ));
+    assert!(matches!(
+        *engine.compile("let x = 42; stop; x = 0;").expect_err("should error").0,
+        ParseErrorType::BadInput(_)
+    ));
+    assert!(engine.compile("let x = 1 baz 2;").is_err());
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "internals")]
+#[allow(deprecated)]
+fn test_tokens_tokenize_tolerant() {
+    use rhai::{Position, Token};
+
+    let engine = Engine::new();
+
+    let script = "let x = 12 ¤¤¤ * y; // done";
+
+    let tokens = engine.tokenize(script, true, true);
+
+    assert!(matches!(tokens[0].0, Token::Let));
+    assert!(matches!(tokens[1].0, Token::Identifier(ref s) if s == "x"));
+    assert_eq!(tokens[3].0, Token::IntegerConstant(12));
+    assert_eq!(tokens[3].1.start(), Position::new(1, 9));
+    assert_eq!(tokens[3].1.end(), Position::new(1, 10));
+
+    // The error token covers all the illegal characters
+    assert!(matches!(tokens[4].0, Token::LexError(_)));
+    assert_eq!(tokens[4].1.start(), Position::new(1, 12));
+    assert_eq!(tokens[4].1.end(), Position::new(1, 14));
+
+    // Tokenizing resumes after the error
+    assert_eq!(tokens[5].0, Token::Multiply);
+    assert!(matches!(tokens[6].0, Token::Identifier(ref s) if s == "y"));
+    assert_eq!(tokens[7].0, Token::SemiColon);
+    assert!(matches!(tokens[8].0, Token::LineComment(ref s) if s == "// done"));
+    assert_eq!(tokens.len(), 9);
+
+    // Without tolerance, tokenizing stops at the first error
+    let tokens = engine.tokenize(script, false, true);
+
+    assert_eq!(tokens.len(), 5);
+    assert!(matches!(tokens[4].0, Token::LexError(_)));
+    assert_eq!(tokens[4].1.end(), Position::new(1, 12));
+}
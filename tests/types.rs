@@ -1,4 +1,7 @@
-use rhai::{Engine, EvalAltResult, RegisterFn, INT};
+use rhai::{
+    Dynamic, Engine, EvalAltResult, ImmutableString, RegisterErrorFn, RegisterFn, RegisterResultFn,
+    RegisterTupleFn, INT,
+};
 
 #[test]
 fn test_type_of() -> Result<(), Box<EvalAltResult>> {
@@ -48,3 +51,171 @@ fn test_type_of() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_type_dynamic_return() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_dynamic_fn("get", |x: INT| {
+        Ok(if x > 0 {
+            Dynamic::from(x)
+        } else {
+            Dynamic::from("none")
+        })
+    });
+    engine.register_fn("get2", |x: INT| -> Dynamic {
+        if x > 0 {
+            x.into()
+        } else {
+            "none".into()
+        }
+    });
+
+    assert_eq!(engine.eval::<INT>("get(42)")?, 42);
+    assert_eq!(engine.eval::<String>("get(0)")?, "none");
+    assert_eq!(engine.eval::<INT>("get2(42)")?, 42);
+    assert_eq!(engine.eval::<String>("get2(0)")?, "none");
+
+    let script = r#"
+        let x = get(N);
+        if type_of(x) == "string" { len(x) } else { x * 2 }
+    "#;
+
+    assert_eq!(engine.eval::<INT>(&script.replace("N", "21"))?, 42);
+    assert_eq!(engine.eval::<INT>(&script.replace("N", "0"))?, 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_type_error_return() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    fn parse_num(s: &str) -> Result<i64, std::num::ParseIntError> {
+        s.parse::<i64>()
+    }
+
+    engine.register_error_fn("parse_num", parse_num);
+    engine.register_error_fn("check", |x: INT| -> Result<(), Box<EvalAltResult>> {
+        if x > 0 {
+            Ok(())
+        } else {
+            Err(EvalAltResult::ErrorRuntime(x.into(), Default::default()).into())
+        }
+    });
+
+    assert_eq!(engine.eval::<i64>(r#"parse_num("42")"#)?, 42);
+
+    assert!(matches!(
+        *engine.eval::<i64>(r#"parse_num("hello")"#).expect_err("expects error"),
+        EvalAltResult::ErrorRuntime(msg, pos)
+            if msg.as_str().unwrap() == "invalid digit found in string" && pos.line() == Some(1)
+    ));
+
+    // `Box<EvalAltResult>` errors are passed through unchanged
+    engine.eval::<()>("check(1)")?;
+
+    assert!(matches!(
+        *engine.eval::<()>("check(-1)").expect_err("expects error"),
+        EvalAltResult::ErrorRuntime(x, _) if x.as_int().unwrap() == -1
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_type_try_cast_result() -> Result<(), Box<EvalAltResult>> {
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestStruct {
+        x: INT,
+    }
+
+    let value = Dynamic::from(42 as INT);
+
+    let value = value.try_cast_result::<String>().unwrap_err();
+    let value = value.try_cast_result::<bool>().unwrap_err();
+    assert_eq!(value.try_cast_result::<INT>().unwrap(), 42);
+
+    assert_eq!(
+        Dynamic::from("hello").try_cast_result::<String>().unwrap(),
+        "hello"
+    );
+    assert_eq!(
+        Dynamic::from("hello")
+            .try_cast_result::<ImmutableString>()
+            .unwrap(),
+        "hello"
+    );
+    assert_eq!(Dynamic::from('x').try_cast_result::<char>().unwrap(), 'x');
+    assert_eq!(Dynamic::from(()).try_cast_result::<()>().unwrap(), ());
+
+    #[cfg(not(feature = "no_float"))]
+    assert_eq!(
+        Dynamic::from(1.5 as rhai::FLOAT)
+            .try_cast_result::<rhai::FLOAT>()
+            .unwrap(),
+        1.5
+    );
+
+    #[cfg(not(feature = "no_index"))]
+    assert_eq!(
+        Dynamic::from(vec![Dynamic::from(42 as INT)])
+            .try_cast_result::<rhai::Array>()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    #[cfg(not(feature = "no_object"))]
+    assert!(Dynamic::from(rhai::Map::new())
+        .try_cast_result::<rhai::Map>()
+        .unwrap()
+        .is_empty());
+
+    let value = Dynamic::from(TestStruct { x: 1 });
+    let value = value.try_cast_result::<INT>().unwrap_err();
+    let value = value.try_cast_result::<Dynamic>().unwrap();
+    assert_eq!(
+        value.try_cast_result::<TestStruct>().unwrap(),
+        TestStruct { x: 1 }
+    );
+
+    // Shared values are cast on top of their internal values
+    #[cfg(not(feature = "no_closure"))]
+    {
+        let value = Dynamic::from(42 as INT).into_shared();
+        let value = value.try_cast_result::<String>().unwrap_err();
+        assert!(value.is_shared());
+        assert_eq!(value.try_cast_result::<INT>().unwrap(), 42);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_type_tuple_args() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_tuple_fn("describe", |(n, s, b): (INT, String, bool)| {
+        format!("{} {} {}", n, s, b)
+    });
+
+    assert_eq!(
+        engine.eval::<String>(r#"describe(1, "x", true)"#)?,
+        "1 x true"
+    );
+
+    let err = engine
+        .eval::<String>(r#"describe(1, 2, true)"#)
+        .expect_err("should error");
+
+    // Argument types are listed in the error
+    let expected = format!("describe({0}, {0}, bool)", std::any::type_name::<INT>());
+
+    assert!(matches!(
+        *err,
+        EvalAltResult::ErrorFunctionNotFound(ref sig, _) if *sig == expected
+    ));
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use rhai::{Engine, EvalAltResult};
+use rhai::{Engine, EvalAltResult, RegisterFn, INT};
 
 #[test]
 fn test_unit() -> Result<(), Box<EvalAltResult>> {
@@ -20,3 +20,26 @@ fn test_unit_with_spaces() -> Result<(), Box<EvalAltResult>> {
     engine.eval::<()>("let x = ( ); x")?;
     Ok(())
 }
+
+#[test]
+fn test_unit_option_return() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_fn("find", |x: INT| if x > 0 { Some(x * 2) } else { None });
+    engine.register_fn("name", |x: INT| {
+        if x > 0 {
+            Some("hello".to_string())
+        } else {
+            None
+        }
+    });
+
+    assert_eq!(engine.eval::<INT>("find(21)")?, 42);
+    engine.eval::<()>("find(0)")?;
+    assert_eq!(engine.eval::<bool>("find(0) == ()")?, true);
+
+    assert_eq!(engine.eval::<String>("name(1)")?, "hello");
+    engine.eval::<()>("name(0)")?;
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use rhai::{Engine, EvalAltResult, Position, Scope, INT};
+use rhai::{Dynamic, Engine, EvalAltResult, Position, Scope, INT};
 
 #[test]
 fn test_var_scope() -> Result<(), Box<EvalAltResult>> {
@@ -19,6 +19,27 @@ fn test_var_scope() -> Result<(), Box<EvalAltResult>> {
     Ok(())
 }
 
+#[test]
+fn test_var_scope_is_constant() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+    let mut scope = Scope::new();
+
+    engine.eval_with_scope::<()>(&mut scope, "const x = 1; let y = 2;")?;
+
+    assert!(scope.contains("x"));
+    assert!(scope.contains("y"));
+    assert!(!scope.contains("z"));
+    assert_eq!(scope.is_constant("x"), Some(true));
+    assert_eq!(scope.is_constant("y"), Some(false));
+    assert_eq!(scope.is_constant("z"), None);
+
+    // Shadowing uses the latest entry
+    scope.push("x", 3 as INT);
+    assert_eq!(scope.is_constant("x"), Some(false));
+
+    Ok(())
+}
+
 #[test]
 fn test_scope_eval() -> Result<(), Box<EvalAltResult>> {
     let engine = Engine::new();
@@ -94,3 +115,80 @@ fn test_var_resolver() -> Result<(), Box<EvalAltResult>> {
 
     Ok(())
 }
+
+#[test]
+fn test_var_scope_eval_stmt() -> Result<(), Box<EvalAltResult>> {
+    let engine = Engine::new();
+    let mut scope = Scope::new();
+
+    // Variables persist across statements
+    assert!(engine
+        .eval_stmt_with_scope(&mut scope, "let x = 1")?
+        .is::<()>());
+    assert_eq!(
+        engine
+            .eval_stmt_with_scope(&mut scope, "x + 1")?
+            .cast::<INT>(),
+        2
+    );
+    engine.eval_stmt_with_scope(&mut scope, "const Y = 40;")?;
+    engine.eval_stmt_with_scope(&mut scope, "x += 1;")?;
+    engine.eval_stmt_with_scope(&mut scope, "if x > 1 { x = x + Y }")?;
+    assert_eq!(scope.get_value::<INT>("x").unwrap(), 42);
+    assert_eq!(scope.get_value::<INT>("Y").unwrap(), 40);
+
+    // Variables in inner blocks do not leak
+    engine.eval_stmt_with_scope(&mut scope, "{ let z = 1; }")?;
+    assert!(!scope.contains("z"));
+
+    // Only a single statement is allowed
+    assert!(matches!(
+        *engine.eval_stmt_with_scope(&mut scope, "let a = 1; let b = 2;").expect_err("should error"),
+        EvalAltResult::ErrorParsing(_, pos) if pos.position() == Some(12)
+    ));
+    assert!(!scope.contains("a"));
+
+    Ok(())
+}
+
+#[test]
+fn test_var_scope_register_scope_fn() -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+
+    engine.register_scope_fn("get_var", 1, |context, args| {
+        let name = args[0].as_str().map_err(|typ| typ.to_string())?;
+
+        Ok(context
+            .scope()
+            .and_then(|scope| scope.get_value::<Dynamic>(name))
+            .unwrap_or_default())
+    });
+
+    let mut scope = Scope::new();
+    scope.push("y", 40 as INT);
+
+    assert_eq!(
+        engine.eval_with_scope::<INT>(&mut scope, r#"let x = 2; get_var("x") + get_var("y")"#)?,
+        42
+    );
+    assert!(engine
+        .eval_with_scope::<()>(&mut scope, r#"get_var("z")"#)
+        .is_ok());
+
+    #[cfg(not(feature = "no_function"))]
+    {
+        // Functions only see their own scope
+        assert_eq!(
+            engine.eval::<INT>(r#"fn foo(x) { get_var("x") } let x = 1; foo(42)"#)?,
+            42
+        );
+
+        // Script-defined functions take precedence
+        assert_eq!(
+            engine.eval::<INT>(r#"fn get_var(name) { 0 } let x = 1; get_var("x")"#)?,
+            0
+        );
+    }
+
+    Ok(())
+}